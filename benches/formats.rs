@@ -0,0 +1,55 @@
+use brain_core::testing::{GenerateSpec, generate};
+use brain_core::{opml, storage, xmind};
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+fn bench_formats(c: &mut Criterion) {
+    let spec = GenerateSpec {
+        node_count: 10_000,
+        ..Default::default()
+    };
+    let map = generate(1, &spec);
+
+    let xml = storage::to_xml(&map).unwrap();
+    c.bench_function("freemind_export_10k", |b| {
+        b.iter(|| storage::to_xml(black_box(&map)).unwrap())
+    });
+    c.bench_function("freemind_import_10k", |b| {
+        b.iter(|| storage::from_xml(black_box(&xml)).unwrap())
+    });
+
+    let opml_text = opml::to_opml(&map).unwrap();
+    c.bench_function("opml_import_10k", |b| {
+        b.iter(|| opml::from_opml(black_box(&opml_text)).unwrap())
+    });
+
+    let xmind_data = xmind::to_xmind(&map).unwrap();
+    c.bench_function("xmind_import_10k", |b| {
+        b.iter(|| xmind::from_xmind(black_box(&xmind_data)).unwrap())
+    });
+}
+
+/// The FreeMind importer builds nodes straight from parser events. For
+/// comparison, `freemind_tree_100k` only deserializes the document into the
+/// nested `XmlMap` structs, which the importer used to do before building a
+/// single node, and which holds a second copy of the whole map in memory.
+fn bench_large_freemind(c: &mut Criterion) {
+    let spec = GenerateSpec {
+        node_count: 100_000,
+        ..Default::default()
+    };
+    let xml = storage::to_xml(&generate(2, &spec)).unwrap();
+
+    let mut group = c.benchmark_group("freemind_100k");
+    group.sample_size(10);
+    group.bench_function("streaming_import", |b| {
+        b.iter(|| storage::read_xml(black_box(xml.as_bytes())).unwrap())
+    });
+    group.bench_function("tree_deserialize", |b| {
+        b.iter(|| quick_xml::de::from_str::<storage::XmlMap>(black_box(&xml)).unwrap())
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_formats, bench_large_freemind);
+criterion_main!(benches);
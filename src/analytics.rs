@@ -0,0 +1,183 @@
+use crate::{BrainCoreError, MindMap, Node};
+use arrow_array::builder::{ListBuilder, StringBuilder};
+use arrow_array::{ArrayRef, BooleanArray, RecordBatch, StringArray, UInt32Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+use std::collections::HashMap;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Schema of the node table produced by [`to_record_batch`].
+pub fn node_schema() -> Schema {
+    Schema::new(vec![
+        Field::new("id", DataType::Utf8, false),
+        Field::new("parent", DataType::Utf8, true),
+        Field::new("depth", DataType::UInt32, false),
+        Field::new("content_length", DataType::UInt64, false),
+        Field::new("created", DataType::UInt64, false),
+        Field::new("modified", DataType::UInt64, false),
+        Field::new("time_spent_ms", DataType::UInt64, false),
+        Field::new("subtree_time_ms", DataType::UInt64, false),
+        Field::new("archived", DataType::Boolean, false),
+        Field::new(
+            "icons",
+            DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))),
+            false,
+        ),
+        Field::new(
+            "tags",
+            DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))),
+            false,
+        ),
+    ])
+}
+
+/// Flattens the map into one row per node, depth-first from the root and
+/// then from each floating node, whose trees count depth from their top.
+/// Archived nodes are included and flagged in the `archived` column.
+pub fn to_record_batch(map: &MindMap) -> Result<RecordBatch, BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+
+    let mut rows: Vec<(&Node, u32)> = Vec::with_capacity(map.nodes.len());
+    let mut stack: Vec<(&Node, u32)> = map
+        .floating_roots
+        .iter()
+        .rev()
+        .filter_map(|id| map.nodes.get(id))
+        .chain([root])
+        .map(|node| (node, 0))
+        .collect();
+    while let Some((node, depth)) = stack.pop() {
+        rows.push((node, depth));
+        for child_id in node.children.iter().rev() {
+            if let Some(child) = map.nodes.get(child_id) {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+
+    // Children follow their parent in `rows`, so going backwards finishes
+    // each subtree's total before its top node needs it.
+    let mut totals: HashMap<&str, u64> = HashMap::with_capacity(rows.len());
+    for (node, _) in rows.iter().rev() {
+        let children: u64 = node
+            .children
+            .iter()
+            .filter_map(|c| totals.get(c.as_str()))
+            .sum();
+        totals.insert(&node.id, map.time_spent(&node.id) + children);
+    }
+
+    let mut ids = Vec::new();
+    let mut parents = Vec::new();
+    let mut depths = Vec::new();
+    let mut lengths = Vec::new();
+    let mut created = Vec::new();
+    let mut modified = Vec::new();
+    let mut time_spent = Vec::new();
+    let mut subtree_time = Vec::new();
+    let mut archived = Vec::new();
+    let mut icons = ListBuilder::new(StringBuilder::new());
+    let mut tags = ListBuilder::new(StringBuilder::new());
+
+    for (node, depth) in rows {
+        ids.push(node.id.as_str());
+        parents.push(node.parent.as_deref());
+        depths.push(depth);
+        lengths.push(node.content.chars().count() as u64);
+        created.push(node.created);
+        modified.push(node.modified);
+        time_spent.push(map.time_spent(&node.id));
+        subtree_time.push(totals[node.id.as_str()]);
+        archived.push(map.is_archived(&node.id));
+        for icon in &node.icons {
+            icons.values().append_value(icon);
+        }
+        icons.append(true);
+        for tag in &node.tags {
+            tags.values().append_value(tag);
+        }
+        tags.append(true);
+    }
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(StringArray::from(ids)),
+        Arc::new(StringArray::from(parents)),
+        Arc::new(UInt32Array::from(depths)),
+        Arc::new(UInt64Array::from(lengths)),
+        Arc::new(UInt64Array::from(created)),
+        Arc::new(UInt64Array::from(modified)),
+        Arc::new(UInt64Array::from(time_spent)),
+        Arc::new(UInt64Array::from(subtree_time)),
+        Arc::new(BooleanArray::from(archived)),
+        Arc::new(icons.finish()),
+        Arc::new(tags.finish()),
+    ];
+
+    RecordBatch::try_new(Arc::new(node_schema()), columns).map_err(BrainCoreError::export)
+}
+
+/// Writes the node table as a single-row-group Parquet file.
+pub fn write_parquet<W: Write + Send>(map: &MindMap, writer: W) -> Result<(), BrainCoreError> {
+    let batch = to_record_batch(map)?;
+    let mut parquet =
+        ArrowWriter::try_new(writer, batch.schema(), None).map_err(BrainCoreError::export)?;
+    parquet.write(&batch).map_err(BrainCoreError::export)?;
+    parquet.close().map_err(BrainCoreError::export)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{Array, ListArray};
+
+    #[test]
+    fn test_record_batch_and_parquet() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let mut child = map.nodes[&root_id].clone();
+        child.id = "child".to_string();
+        child.content = "Child".to_string();
+        child.parent = Some(root_id.clone());
+        child.icons = vec!["idea".to_string(), "flag".to_string()];
+        map.nodes.insert(child.id.clone(), child);
+        map.nodes
+            .get_mut(&root_id)
+            .unwrap()
+            .children
+            .push("child".to_string());
+        map.tag("child", "urgent").unwrap();
+        map.log_time(&root_id, 1_000).unwrap();
+        map.log_time("child", 2_000).unwrap();
+        let idea = map.add_floating_node("Idea", 0.0, 0.0).unwrap();
+        map.log_time(&idea, 500).unwrap();
+
+        let batch = to_record_batch(&map).unwrap();
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.num_columns(), 11);
+        let column = |name: &str| batch.column_by_name(name).unwrap().clone();
+        let ids = column("id");
+        let ids = ids.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(ids.value(2), idea);
+        let depths = column("depth");
+        let depths = depths.as_any().downcast_ref::<UInt32Array>().unwrap();
+        assert_eq!((depths.value(1), depths.value(2)), (1, 0));
+        let totals = column("subtree_time_ms");
+        let totals = totals.as_any().downcast_ref::<UInt64Array>().unwrap();
+        assert_eq!(totals.values(), &[3_000, 2_000, 500]);
+        let tags = column("tags");
+        let tags = tags.as_any().downcast_ref::<ListArray>().unwrap();
+        assert_eq!(tags.value_length(0), 0);
+        let child_tags = tags.value(1);
+        let child_tags = child_tags.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(child_tags.value(0), "urgent");
+
+        let mut buf = Vec::new();
+        write_parquet(&map, &mut buf).unwrap();
+        assert_eq!(&buf[..4], b"PAR1");
+    }
+}
@@ -0,0 +1,104 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// Hides a node and its subtree from exports, views and search without
+    /// deleting it. If the selection was inside the subtree, the parent
+    /// becomes selected.
+    pub fn archive(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        self.set_archived(node_id, true)?;
+        if self.is_archived(&self.selection.primary) {
+            let parent = self.nodes[node_id].parent.clone();
+            self.set_selected(parent.unwrap_or_else(|| self.root_id.clone()));
+        }
+        self.prune_selection();
+        Ok(())
+    }
+
+    pub fn unarchive(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        self.set_archived(node_id, false)
+    }
+
+    /// Whether the node is archived itself or sits inside an archived subtree.
+    pub fn is_archived(&self, node_id: &str) -> bool {
+        let mut current = self.nodes.get(node_id);
+        while let Some(node) = current {
+            if node.archived {
+                return true;
+            }
+            current = node.parent.as_ref().and_then(|p| self.nodes.get(p));
+        }
+        false
+    }
+
+    fn set_archived(&mut self, node_id: &str, archived: bool) -> Result<(), BrainCoreError> {
+        if node_id == self.root_id {
+            return Err(BrainCoreError::RootImmutable);
+        }
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.archived != archived {
+            node.archived = archived;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+    use crate::export::ExportOptions;
+    use crate::storage::{from_xml, to_xml, to_xml_with};
+
+    fn add_child_for_test(map: &mut MindMap, parent_id: &str, content: &str) -> String {
+        let id = format!("node-{}", map.nodes.len());
+        let node = Node {
+            id: id.clone(),
+            content: content.to_string(),
+            parent: Some(parent_id.to_string()),
+            ..Default::default()
+        };
+        map.nodes.insert(id.clone(), node);
+        if let Some(parent) = map.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        id
+    }
+
+    #[test]
+    fn test_archive_hides_subtree() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let done = add_child_for_test(&mut map, &root_id, "Done project");
+        let task = add_child_for_test(&mut map, &done, "Old task");
+        add_child_for_test(&mut map, &root_id, "Active project");
+        map.selection.primary = task.clone();
+
+        map.archive(&done).unwrap();
+        assert!(map.is_archived(&task));
+        assert_eq!(map.selection.primary, root_id);
+        assert!(map.fuzzy_search("old", 10).is_empty());
+
+        let exported = from_xml(&to_xml_with(&map, &ExportOptions::default()).unwrap()).unwrap();
+        assert_eq!(exported.nodes.len(), 2);
+
+        // Native saves keep archived work, flag included.
+        let xml = to_xml(&map).unwrap();
+        assert!(xml.contains("ARCHIVED=\"true\""));
+        let saved = from_xml(&xml).unwrap();
+        assert_eq!(saved.nodes.len(), 4);
+        assert!(saved.nodes[&done].archived);
+        assert!(saved.is_archived(&task));
+
+        map.unarchive(&done).unwrap();
+        assert!(!map.is_archived(&task));
+        assert_eq!(map.fuzzy_search("old", 10).len(), 1);
+        assert!(map.archive(&root_id).is_err());
+    }
+}
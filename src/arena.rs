@@ -0,0 +1,544 @@
+use crate::events::MapEvent;
+use crate::timestamp::now_millis;
+use crate::validation::Change;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use uuid::Uuid;
+
+// Compact storage for bulk work on large maps. `MindMap` keys nodes by their
+// UUID strings and every link between nodes is another string, so walking or
+// rebuilding a big tree clones strings at each step. The arena keeps nodes in
+// a vector and links them through `NodeId` handles; UUIDs are only looked up
+// when entering or leaving the arena.
+
+/// Handle to a node in a [`NodeArena`]. Handles of removed nodes are reused
+/// by later insertions, so don't keep one past the removal of its node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    node: Node,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+
+/// Nodes of a map stored by integer handle. Build one with
+/// [`MindMap::to_arena`] and turn it back with [`NodeArena::to_map`]; the
+/// rest of the map, such as its trash, relationships and selection, is
+/// kept aside and comes back with it.
+///
+/// The `children` and `parent` fields of the stored nodes are left empty;
+/// the tree lives in the handles returned by [`NodeArena::children`] and
+/// [`NodeArena::parent`].
+///
+/// Edits go through the map's validators and limits, stamp its author and
+/// bump revisions and tell observers as the [`MindMap`] methods of the same
+/// name do. Validators see the map without its nodes.
+#[derive(Debug, Clone)]
+pub struct NodeArena {
+    slots: Vec<Option<Slot>>,
+    free: Vec<NodeId>,
+    ids: HashMap<String, NodeId>,
+    root: NodeId,
+    floating: Vec<NodeId>,
+    /// The map without its nodes.
+    document: MindMap,
+    /// UUIDs of the nodes removed since the arena was built.
+    removed: HashSet<String>,
+}
+
+impl NodeArena {
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    /// The tops of the floating trees; see [`MindMap::floating_roots`].
+    pub fn floating_roots(&self) -> &[NodeId] {
+        &self.floating
+    }
+
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Handle of the node with the given UUID.
+    pub fn id_of(&self, uuid: &str) -> Option<NodeId> {
+        self.ids.get(uuid).copied()
+    }
+
+    /// UUID of the node behind a handle.
+    pub fn uuid(&self, id: NodeId) -> Option<&str> {
+        self.get(id).map(|n| n.id.as_str())
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<&Node> {
+        self.slot(id).map(|s| &s.node)
+    }
+
+    /// Changes made through this skip the validators, limits and revision
+    /// bookkeeping of the editing methods.
+    pub fn get_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.slot_mut(id).map(|s| &mut s.node)
+    }
+
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.slot(id).and_then(|s| s.parent)
+    }
+
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.slot(id).map_or(&[], |s| &s.children)
+    }
+
+    /// Appends a new node under `parent` and returns its handle.
+    pub fn add_child(&mut self, parent: NodeId, content: &str) -> Result<NodeId, BrainCoreError> {
+        let parent_uuid = self
+            .uuid(parent)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(parent.to_string()))?;
+        let content = self.document.validate_content(
+            &Change {
+                map: &self.document,
+                node_id: None,
+                parent_id: Some(parent_uuid),
+            },
+            content,
+        )?;
+        let limits = self.document.limits;
+        limits
+            .check_node_count(self.len() + 1)
+            .and_then(|_| limits.check_depth(self.depth(parent) + 1))
+            .and_then(|_| limits.check_content(&content))?;
+
+        let now = now_millis();
+        let node = Node {
+            id: Uuid::new_v4().to_string(),
+            content,
+            created: now,
+            modified: now,
+            created_by: self.document.author.clone(),
+            modified_by: self.document.author.clone(),
+            ..Default::default()
+        };
+        let id = self.insert(node, Some(parent));
+        if let Some(slot) = self.slot_mut(parent) {
+            slot.children.push(id);
+        }
+        self.bump_revision(parent);
+        self.emit(|uuid| MapEvent::NodeAdded {
+            node_id: uuid(id),
+            parent_id: Some(uuid(parent)),
+        });
+        Ok(id)
+    }
+
+    /// Replaces a node's content after running it through the validators.
+    pub fn change_node(&mut self, id: NodeId, content: &str) -> Result<(), BrainCoreError> {
+        let node = self
+            .get(id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(id.to_string()))?;
+        let content = self.document.validate_content(
+            &Change {
+                map: &self.document,
+                node_id: Some(&node.id),
+                parent_id: self.parent(id).and_then(|p| self.uuid(p)),
+            },
+            content,
+        )?;
+        self.document.limits.check_content(&content)?;
+
+        let author = self.document.author.clone();
+        if let Some(node) = self.get_mut(id)
+            && node.content != content
+        {
+            node.content = content;
+            node.modified = now_millis();
+            node.modified_by = author;
+            self.bump_revision(id);
+        }
+        Ok(())
+    }
+
+    /// Moves a node and its subtree among the children of `new_parent` at
+    /// `position`, as [`MindMap::move_node`] does. Positions past the end
+    /// append.
+    pub fn move_node(
+        &mut self,
+        id: NodeId,
+        new_parent: NodeId,
+        position: usize,
+    ) -> Result<(), BrainCoreError> {
+        if id == self.root {
+            return Err(BrainCoreError::RootImmutable);
+        }
+        let parent = self
+            .slot(id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(id.to_string()))?
+            .parent
+            .ok_or_else(|| BrainCoreError::InvalidOperation("Node has no parent".to_string()))?;
+        if self.slot(new_parent).is_none() {
+            return Err(BrainCoreError::NodeNotFound(new_parent.to_string()));
+        }
+        let mut ancestor = Some(new_parent);
+        while let Some(current) = ancestor {
+            if current == id {
+                return Err(BrainCoreError::InvalidOperation(
+                    "Cannot move a node under itself or its descendants".to_string(),
+                ));
+            }
+            ancestor = self.parent(current);
+        }
+        self.document
+            .limits
+            .check_depth(self.depth(new_parent) + self.height(id))?;
+
+        if let Some(slot) = self.slot_mut(parent) {
+            slot.children.retain(|c| *c != id);
+        }
+        if let Some(slot) = self.slot_mut(new_parent) {
+            let position = position.min(slot.children.len());
+            slot.children.insert(position, id);
+        }
+        let author = self.document.author.clone();
+        if let Some(slot) = self.slot_mut(id) {
+            slot.parent = Some(new_parent);
+            slot.node.modified = now_millis();
+            slot.node.modified_by = author;
+        }
+
+        self.bump_revision(id);
+        self.bump_revision(parent);
+        if parent != new_parent {
+            self.bump_revision(new_parent);
+        }
+        self.emit(|uuid| MapEvent::NodeMoved {
+            node_id: uuid(id),
+            old_parent_id: uuid(parent),
+            new_parent_id: uuid(new_parent),
+        });
+        Ok(())
+    }
+
+    /// Removes a node and its subtree, returning how many nodes were
+    /// dropped. Their handles become free for reuse.
+    pub fn remove_node(&mut self, id: NodeId) -> Result<usize, BrainCoreError> {
+        if id == self.root {
+            return Err(BrainCoreError::RootImmutable);
+        }
+        let slot = self
+            .slot(id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(id.to_string()))?;
+        let (parent, uuid) = (slot.parent, slot.node.id.clone());
+        match parent.and_then(|p| self.slot_mut(p)) {
+            Some(slot) => slot.children.retain(|c| *c != id),
+            None => self.floating.retain(|f| *f != id),
+        }
+
+        let mut removed = 0;
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if let Some(slot) = self.slots.get_mut(current.index()).and_then(Option::take) {
+                self.ids.remove(&slot.node.id);
+                self.removed.insert(slot.node.id);
+                self.free.push(current);
+                stack.extend(slot.children);
+                removed += 1;
+            }
+        }
+        match parent {
+            Some(parent) => self.bump_revision(parent),
+            None => self.document.revision += 1,
+        }
+        self.document.emit(MapEvent::NodeRemoved { node_id: uuid });
+        Ok(removed)
+    }
+
+    /// Rebuilds a [`MindMap`] with string links from the arena. Whatever
+    /// of the rest of the map pointed at removed nodes, such as their
+    /// relationships and groups, is dropped. Revisions are those the edits
+    /// left, as if they had been made on the map.
+    pub fn to_map(&self) -> MindMap {
+        let uuid = |id: NodeId| self.slots[id.index()].as_ref().map(|s| s.node.id.clone());
+        let nodes = self
+            .slots
+            .iter()
+            .flatten()
+            .map(|slot| {
+                let mut node = slot.node.clone();
+                node.parent = slot.parent.and_then(uuid);
+                node.children = slot.children.iter().filter_map(|c| uuid(*c)).collect();
+                (node.id.clone(), node)
+            })
+            .collect();
+        let mut map = self.document.clone();
+        map.nodes = nodes;
+        map.floating_roots = self.floating.iter().filter_map(|f| uuid(*f)).collect();
+        map.drop_references(&self.removed);
+        if !map.nodes.contains_key(&map.selection.primary) {
+            map.selection.primary = map.root_id.clone();
+        }
+        map.prune_selection();
+        map
+    }
+
+    /// Records a modification of the node as [`MindMap`] does: its
+    /// revision and the map's go up and observers are told.
+    fn bump_revision(&mut self, id: NodeId) {
+        self.document.revision += 1;
+        if let Some(node) = self.get_mut(id) {
+            node.revision += 1;
+            let node_id = node.id.clone();
+            self.document.emit(MapEvent::NodeChanged { node_id });
+        }
+    }
+
+    /// Tells observers about an event built from the UUIDs of handles.
+    fn emit(&self, event: impl FnOnce(&dyn Fn(NodeId) -> String) -> MapEvent) {
+        let uuid = |id: NodeId| self.uuid(id).unwrap_or_default().to_string();
+        self.document.emit(event(&uuid));
+    }
+
+    /// Number of ancestors, as for [`MindMap`] nodes.
+    fn depth(&self, id: NodeId) -> usize {
+        std::iter::successors(self.parent(id), |p| self.parent(*p)).count()
+    }
+
+    /// Number of levels in the node's subtree, itself included.
+    fn height(&self, id: NodeId) -> usize {
+        let mut tallest = 0;
+        let mut stack = vec![(id, 1)];
+        while let Some((current, height)) = stack.pop() {
+            tallest = tallest.max(height);
+            stack.extend(self.children(current).iter().map(|c| (*c, height + 1)));
+        }
+        tallest
+    }
+
+    fn insert(&mut self, node: Node, parent: Option<NodeId>) -> NodeId {
+        let uuid = node.id.clone();
+        let slot = Slot {
+            node,
+            parent,
+            children: Vec::new(),
+        };
+        let id = match self.free.pop() {
+            Some(id) => {
+                self.slots[id.index()] = Some(slot);
+                id
+            }
+            None => {
+                self.slots.push(Some(slot));
+                NodeId((self.slots.len() - 1) as u32)
+            }
+        };
+        self.ids.insert(uuid, id);
+        id
+    }
+
+    fn slot(&self, id: NodeId) -> Option<&Slot> {
+        self.slots.get(id.index()).and_then(Option::as_ref)
+    }
+
+    fn slot_mut(&mut self, id: NodeId) -> Option<&mut Slot> {
+        self.slots.get_mut(id.index()).and_then(Option::as_mut)
+    }
+}
+
+impl MindMap {
+    /// Copies the map's nodes into a [`NodeArena`], numbering them in
+    /// depth-first order from the root, then from each floating node.
+    /// Nodes reachable from neither are left out.
+    pub fn to_arena(&self) -> NodeArena {
+        let document = MindMap {
+            nodes: HashMap::new(),
+            root_id: self.root_id.clone(),
+            selection: self.selection.clone(),
+            trash: self.trash.clone(),
+            relationships: self.relationships.clone(),
+            groups: self.groups.clone(),
+            summaries: self.summaries.clone(),
+            floating_roots: Vec::new(),
+            revision: self.revision,
+            resources: self.resources.clone(),
+            format_version: self.format_version.clone(),
+            passthrough: self.passthrough.clone(),
+            validators: self.validators.clone(),
+            observers: self.observers.clone(),
+            limits: self.limits,
+            layout: self.layout,
+            history: self.history.clone(),
+            author: self.author.clone(),
+        };
+        let mut arena = NodeArena {
+            slots: Vec::with_capacity(self.nodes.len()),
+            free: Vec::new(),
+            ids: HashMap::with_capacity(self.nodes.len()),
+            root: NodeId(0),
+            floating: Vec::new(),
+            document,
+            removed: HashSet::new(),
+        };
+        let mut stack: Vec<(&str, Option<NodeId>)> = self
+            .floating_roots
+            .iter()
+            .rev()
+            .map(|f| (f.as_str(), None))
+            .chain([(self.root_id.as_str(), None)])
+            .collect();
+        while let Some((uuid, parent)) = stack.pop() {
+            let Some(node) = self.nodes.get(uuid) else {
+                continue;
+            };
+            if arena.ids.contains_key(uuid) {
+                continue;
+            }
+            let mut copy = node.clone();
+            copy.children = Vec::new();
+            copy.parent = None;
+            let id = arena.insert(copy, parent);
+            match parent.and_then(|p| arena.slot_mut(p)) {
+                Some(slot) => slot.children.push(id),
+                None if uuid != self.root_id => arena.floating.push(id),
+                None => {}
+            }
+            stack.extend(node.children.iter().rev().map(|c| (c.as_str(), Some(id))));
+        }
+        arena
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validation::Validation;
+
+    #[test]
+    fn test_arena_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let gone = map.add_child(&b, "Gone").unwrap();
+        map.trash_node(&gone).unwrap();
+        let idea = map.add_floating_node("Idea", 300.0, 0.0).unwrap();
+        map.add_relationship(&a1, &b, Some("see")).unwrap();
+        map.add_relationship(&idea, &b, None).unwrap();
+        map.add_group(&a, Some("First")).unwrap();
+        map.select(&a1).unwrap();
+
+        // Nothing but the node links changes on the way through.
+        let unchanged = map.to_arena().to_map();
+        assert_eq!(
+            serde_json::to_value(&unchanged).unwrap(),
+            serde_json::to_value(&map).unwrap()
+        );
+
+        let mut arena = map.to_arena();
+        assert_eq!(arena.len(), 5);
+        assert_eq!(arena.floating_roots(), &[arena.id_of(&idea).unwrap()]);
+        let root = arena.root();
+        assert_eq!(arena.uuid(root), Some(root_id.as_str()));
+        let a_id = arena.id_of(&a).unwrap();
+        assert_eq!(arena.children(root), &[a_id, arena.id_of(&b).unwrap()]);
+        assert_eq!(arena.parent(arena.id_of(&a1).unwrap()), Some(a_id));
+
+        let b_id = arena.id_of(&b).unwrap();
+        assert!(arena.move_node(b_id, a_id, 0).is_ok());
+        assert!(arena.move_node(a_id, b_id, 0).is_err());
+        arena.move_node(b_id, root, 1).unwrap();
+        assert_eq!(arena.remove_node(a_id).unwrap(), 2);
+        assert!(arena.id_of(&a1).is_none());
+        let c = arena.add_child(root, "C").unwrap();
+        arena.change_node(c, "C2").unwrap();
+        assert!(matches!(
+            arena.remove_node(root),
+            Err(BrainCoreError::RootImmutable)
+        ));
+
+        let rebuilt = arena.to_map();
+        assert_eq!(rebuilt.root_id, root_id);
+        let contents: Vec<&str> = rebuilt.nodes[&root_id]
+            .children
+            .iter()
+            .map(|id| rebuilt.nodes[id].content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["B", "C2"]);
+        assert_eq!(rebuilt.nodes[&b].parent.as_deref(), Some(root_id.as_str()));
+        assert_eq!(rebuilt.floating_roots, vec![idea.clone()]);
+        assert_eq!(rebuilt.relationships.len(), 1);
+        assert_eq!(rebuilt.relationships[0].from, idea);
+        assert!(rebuilt.groups.is_empty());
+        assert_eq!(rebuilt.trash, map.trash);
+        assert_eq!(rebuilt.selection.primary, root_id);
+        assert!(rebuilt.revision > map.revision);
+        assert!(rebuilt.nodes[&b].revision > map.nodes[&b].revision);
+    }
+
+    #[test]
+    fn test_arena_edits_are_checked() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        map.set_author(Some("ada".to_string()));
+        map.set_limits(crate::limits::MapLimits {
+            max_depth: Some(2),
+            ..Default::default()
+        });
+        map.add_validator(|_: &Change, content: &str| {
+            if content.contains('!') {
+                Validation::Invalid("No shouting".to_string())
+            } else {
+                Validation::Valid
+            }
+        });
+        let (sender, events) = std::sync::mpsc::channel();
+        map.add_observer(sender);
+
+        let mut arena = map.to_arena();
+        let root = arena.root();
+        let a_id = arena.id_of(&a).unwrap();
+        assert!(matches!(
+            arena.change_node(a_id, "A!"),
+            Err(BrainCoreError::Validation(_))
+        ));
+        assert!(arena.add_child(root, "B!").is_err());
+        let a1 = arena.add_child(a_id, "A1").unwrap();
+        assert!(matches!(
+            arena.add_child(a1, "Too deep"),
+            Err(BrainCoreError::Limit(_))
+        ));
+        arena.change_node(a1, "A1 done").unwrap();
+        let node = arena.get(a1).unwrap();
+        assert_eq!(node.created_by.as_deref(), Some("ada"));
+        assert_eq!(node.revision, 1);
+        assert_eq!(
+            arena.get(a_id).unwrap().revision,
+            map.nodes[&a].revision + 1
+        );
+        assert!(matches!(
+            events.try_iter().nth(1),
+            Some(MapEvent::NodeAdded { parent_id, .. }) if parent_id.as_deref() == Some(a.as_str())
+        ));
+
+        let rebuilt = arena.to_map();
+        assert_eq!(rebuilt.revision, map.revision + 2);
+        assert_eq!(rebuilt.nodes[&a].children.len(), 1);
+    }
+}
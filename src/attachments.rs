@@ -0,0 +1,378 @@
+use crate::export::ExportOptions;
+use crate::fingerprint::fnv64;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{Read, Seek, Write};
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// Folder of a zip package that attached files are written to. XMind keeps
+/// images there; files in its older `attachments` folder are read too.
+pub(crate) const PACKAGE_FOLDER: &str = "resources";
+
+/// A file attached to a node, such as an embedded image. The bytes live in
+/// the map's [`ResourceStore`] under `data_ref`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attachment {
+    /// File name shown to the user.
+    pub name: String,
+    pub mime: String,
+    pub data_ref: String,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.mime.starts_with("image/")
+    }
+}
+
+/// The bytes of a map's attachments. Entries are keyed by a hash of their
+/// contents, so attaching the same file twice stores it once; a different
+/// file whose hash collides gets a numbered key. Serialized as base64
+/// text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceStore {
+    data: BTreeMap<String, Vec<u8>>,
+}
+
+impl ResourceStore {
+    /// Stores `data` and returns its reference. The reference keeps the
+    /// extension of `name`, so it can double as a file name in packages.
+    pub fn insert(&mut self, name: &str, data: Vec<u8>) -> String {
+        let hash = fnv64(&data);
+        let extension = extension(name).map(str::to_ascii_lowercase);
+        for n in 0.. {
+            let stem = match n {
+                0 => format!("{hash:016x}"),
+                n => format!("{hash:016x}-{n}"),
+            };
+            let data_ref = match &extension {
+                Some(extension) => format!("{stem}.{extension}"),
+                None => stem,
+            };
+            match self.data.get(&data_ref) {
+                Some(existing) if *existing != data => continue,
+                Some(_) => {}
+                None => {
+                    self.data.insert(data_ref.clone(), data);
+                }
+            }
+            return data_ref;
+        }
+        unreachable!()
+    }
+
+    /// Stores `data` under a reference it was given before, as when
+    /// loading a saved store.
+    pub fn restore(&mut self, data_ref: String, data: Vec<u8>) {
+        self.data.insert(data_ref, data);
+    }
+
+    pub fn get(&self, data_ref: &str) -> Option<&[u8]> {
+        self.data.get(data_ref).map(Vec::as_slice)
+    }
+
+    pub fn remove(&mut self, data_ref: &str) -> Option<Vec<u8>> {
+        self.data.remove(data_ref)
+    }
+
+    /// References and bytes, in reference order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.data.iter().map(|(r, d)| (r.as_str(), d.as_slice()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Copies in the entries of `other` this store lacks. An entry whose
+    /// reference is taken here by different bytes is stored under a new
+    /// one; the returned map goes from `other`'s reference to that.
+    pub fn extend_from(&mut self, other: &ResourceStore) -> HashMap<String, String> {
+        let mut renamed = HashMap::new();
+        for (data_ref, data) in &other.data {
+            match self.data.get(data_ref) {
+                Some(existing) if existing != data => {
+                    renamed.insert(data_ref.clone(), self.insert(data_ref, data.clone()));
+                }
+                Some(_) => {}
+                None => {
+                    self.data.insert(data_ref.clone(), data.clone());
+                }
+            }
+        }
+        renamed
+    }
+}
+
+impl Serialize for ResourceStore {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.data.iter().map(|(r, d)| (r, STANDARD.encode(d))))
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceStore {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let encoded = BTreeMap::<String, String>::deserialize(deserializer)?;
+        let data = encoded
+            .into_iter()
+            .map(|(r, text)| Ok((r, STANDARD.decode(text).map_err(D::Error::custom)?)))
+            .collect::<Result<_, D::Error>>()?;
+        Ok(ResourceStore { data })
+    }
+}
+
+impl MindMap {
+    /// Attaches a file to a node and returns its reference in
+    /// [`MindMap::resources`]. The MIME type is guessed from the name.
+    pub fn attach(
+        &mut self,
+        node_id: &str,
+        name: &str,
+        data: Vec<u8>,
+    ) -> Result<String, BrainCoreError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(BrainCoreError::NodeNotFound(node_id.to_string()));
+        }
+        let data_ref = self.resources.insert(name, data);
+        let attachment = Attachment {
+            name: name.to_string(),
+            mime: mime_type(name).to_string(),
+            data_ref: data_ref.clone(),
+        };
+        self.update_attachments(node_id, |attachments| attachments.push(attachment))?;
+        Ok(data_ref)
+    }
+
+    /// Removes the node's attachments with this reference and returns
+    /// whether there were any. The bytes stay in the store until
+    /// [`MindMap::prune_resources`], so undo can put the attachment back.
+    pub fn detach(&mut self, node_id: &str, data_ref: &str) -> Result<bool, BrainCoreError> {
+        let mut removed = false;
+        self.update_attachments(node_id, |attachments| {
+            let before = attachments.len();
+            attachments.retain(|a| a.data_ref != data_ref);
+            removed = attachments.len() != before;
+        })?;
+        Ok(removed)
+    }
+
+    /// The bytes of an attachment, if the store has them.
+    pub fn attachment_data(&self, attachment: &Attachment) -> Option<&[u8]> {
+        self.resources.get(&attachment.data_ref)
+    }
+
+    /// Drops stored files that no node refers to, counting trashed nodes,
+    /// and returns how many were dropped.
+    pub fn prune_resources(&mut self) -> usize {
+        let used: BTreeSet<&String> = self
+            .nodes
+            .values()
+            .chain(self.trash.iter().flat_map(|entry| &entry.nodes))
+            .flat_map(|n| &n.attachments)
+            .map(|a| &a.data_ref)
+            .collect();
+        let unused: Vec<String> = self
+            .resources
+            .data
+            .keys()
+            .filter(|r| !used.contains(r))
+            .cloned()
+            .collect();
+        for data_ref in &unused {
+            self.resources.data.remove(data_ref);
+        }
+        unused.len()
+    }
+
+    fn update_attachments(
+        &mut self,
+        node_id: &str,
+        update: impl FnOnce(&mut Vec<Attachment>),
+    ) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let mut attachments = node.attachments.clone();
+        update(&mut attachments);
+        if node.attachments != attachments {
+            node.attachments = attachments;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+/// The MIME type for a file name's extension, `application/octet-stream`
+/// when it isn't a common one.
+pub fn mime_type(name: &str) -> &'static str {
+    match extension(name).map(str::to_ascii_lowercase).as_deref() {
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("bmp") => "image/bmp",
+        Some("pdf") => "application/pdf",
+        Some("txt") => "text/plain",
+        Some("md") => "text/markdown",
+        Some("html" | "htm") => "text/html",
+        Some("zip") => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+fn extension(name: &str) -> Option<&str> {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    file.rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .filter(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Path inside a package of the file an attachment refers to.
+pub(crate) fn package_path(attachment: &Attachment) -> String {
+    format!("{PACKAGE_FOLDER}/{}", attachment.data_ref)
+}
+
+/// The files of the exported nodes' attachments, by their path in a
+/// package.
+pub(crate) fn package_files<'a>(
+    map: &'a MindMap,
+    options: &ExportOptions,
+) -> BTreeMap<String, &'a [u8]> {
+    map.nodes
+        .values()
+        .filter(|n| options.includes(n))
+        .flat_map(|n| &n.attachments)
+        .filter_map(|a| Some((package_path(a), map.attachment_data(a)?)))
+        .collect()
+}
+
+pub(crate) fn write_package_files<W: Write + Seek>(
+    files: &BTreeMap<String, &[u8]>,
+    zip: &mut ZipWriter<W>,
+    zip_options: SimpleFileOptions,
+) -> Result<(), BrainCoreError> {
+    for (path, data) in files {
+        zip.start_file(path.as_str(), zip_options)?;
+        zip.write_all(data)?;
+    }
+    Ok(())
+}
+
+/// Loads the files imported attachments point to. Importers fill in
+/// `data_ref` with the file's path in the package; here the file is moved
+/// into the map's store and the reference replaced. Attachments whose file
+/// is missing are dropped.
+pub(crate) fn read_package_files<R: Read + Seek>(
+    map: &mut MindMap,
+    archive: &mut ZipArchive<R>,
+) -> Result<(), BrainCoreError> {
+    let mut refs: HashMap<String, Option<String>> = HashMap::new();
+    for node in map.nodes.values_mut() {
+        for attachment in &mut node.attachments {
+            if !refs.contains_key(&attachment.data_ref) {
+                let data_ref = match archive.by_name(&attachment.data_ref) {
+                    Ok(mut file) => {
+                        let mut data = Vec::new();
+                        file.read_to_end(&mut data)?;
+                        Some(map.resources.insert(&attachment.data_ref, data))
+                    }
+                    Err(_) => None,
+                };
+                refs.insert(attachment.data_ref.clone(), data_ref);
+            }
+        }
+        node.attachments.retain_mut(|a| match &refs[&a.data_ref] {
+            Some(data_ref) => {
+                a.data_ref = data_ref.clone();
+                true
+            }
+            None => false,
+        });
+    }
+    Ok(())
+}
+
+/// An imported attachment for the file at `path` in a package, to be
+/// loaded by [`read_package_files`].
+pub(crate) fn package_attachment(path: &str) -> Attachment {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    Attachment {
+        name: name.to_string(),
+        mime: mime_type(name).to_string(),
+        data_ref: path.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mindnode, xmind};
+
+    #[test]
+    fn test_attachments() {
+        let png = b"\x89PNG\r\n\x1a\nnot really".to_vec();
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "Photo").unwrap();
+        let b = map.add_child(&root_id, "Same photo").unwrap();
+        let image = map.attach(&a, "shot.PNG", png.clone()).unwrap();
+        assert_eq!(map.attach(&b, "copy.png", png.clone()).unwrap(), image);
+        map.attach(&a, "spec.pdf", b"%PDF-1.4".to_vec()).unwrap();
+        assert_eq!(map.resources.len(), 2);
+        assert_eq!(map.nodes[&a].attachments[0].mime, "image/png");
+
+        let loaded: MindMap = serde_json::from_str(&serde_json::to_string(&map).unwrap()).unwrap();
+        assert_eq!(loaded.resources, map.resources);
+
+        let loaded = [
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+            mindnode::from_mindnode(&mindnode::to_mindnode(&map).unwrap()).unwrap(),
+        ];
+        for loaded in loaded {
+            let root = &loaded.nodes[&loaded.root_id];
+            let photo = &loaded.nodes[&root.children[0]];
+            assert_eq!(photo.attachments, map.nodes[&a].attachments);
+            assert_eq!(
+                loaded.attachment_data(&photo.attachments[0]),
+                Some(&png[..])
+            );
+        }
+
+        assert!(map.detach(&b, &image).unwrap());
+        assert_eq!(map.prune_resources(), 0);
+        assert!(map.detach(&a, &image).unwrap());
+        assert_eq!(map.prune_resources(), 1);
+        assert_eq!(map.resources.len(), 1);
+
+        // A different file whose hash collides keeps its own bytes.
+        let mut store = ResourceStore::default();
+        let taken = format!("{:016x}.png", fnv64(&png));
+        store.restore(taken.clone(), b"other".to_vec());
+        let data_ref = store.insert("shot.png", png.clone());
+        assert_ne!(data_ref, taken);
+        assert_eq!(store.get(&data_ref), Some(&png[..]));
+        assert_eq!(store.get(&taken), Some(&b"other"[..]));
+        assert_eq!(store.insert("again.png", png.clone()), data_ref);
+        let mut other = ResourceStore::default();
+        other.insert("shot.png", png.clone());
+        let renamed = map.resources.extend_from(&other);
+        assert!(renamed.is_empty());
+        let renamed = store.extend_from(&other);
+        assert_eq!(renamed[&taken], data_ref);
+        assert_eq!(store.len(), 2);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// The value of a node's attribute, such as "owner" or "estimate". When
+    /// an imported node repeats a name, the first value counts.
+    pub fn attribute(&self, node_id: &str, name: &str) -> Result<Option<&str>, BrainCoreError> {
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        Ok(node
+            .attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str()))
+    }
+
+    /// Sets an attribute, keeping its place if the node already has it and
+    /// dropping any repeats of the name. New attributes go last.
+    pub fn set_attribute(
+        &mut self,
+        node_id: &str,
+        name: &str,
+        value: &str,
+    ) -> Result<(), BrainCoreError> {
+        if name.trim().is_empty() {
+            return Err(BrainCoreError::InvalidOperation(
+                "Attribute name cannot be empty".to_string(),
+            ));
+        }
+        self.update_attributes(node_id, |attributes| {
+            let mut found = false;
+            attributes.retain_mut(|(n, v)| {
+                if n != name {
+                    return true;
+                }
+                if found {
+                    return false;
+                }
+                found = true;
+                *v = value.to_string();
+                true
+            });
+            if !found {
+                attributes.push((name.to_string(), value.to_string()));
+            }
+        })
+    }
+
+    /// Removes every value of an attribute and returns whether there was
+    /// one.
+    pub fn remove_attribute(&mut self, node_id: &str, name: &str) -> Result<bool, BrainCoreError> {
+        let mut removed = false;
+        self.update_attributes(node_id, |attributes| {
+            let before = attributes.len();
+            attributes.retain(|(n, _)| n != name);
+            removed = attributes.len() != before;
+        })?;
+        Ok(removed)
+    }
+
+    fn update_attributes(
+        &mut self,
+        node_id: &str,
+        update: impl FnOnce(&mut Vec<(String, String)>),
+    ) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let mut attributes = node.attributes.clone();
+        update(&mut attributes);
+        if node.attributes != attributes {
+            node.attributes = attributes;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage, xmind};
+
+    #[test]
+    fn test_attributes() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let task = map.add_child(&root_id, "Task").unwrap();
+        map.set_attribute(&task, "owner", "ann").unwrap();
+        map.set_attribute(&task, "estimate", "3d").unwrap();
+        map.set_attribute(&task, "owner", "bob").unwrap();
+        assert_eq!(map.attribute(&task, "owner").unwrap(), Some("bob"));
+        assert_eq!(
+            map.nodes[&task].attributes,
+            vec![
+                ("owner".to_string(), "bob".to_string()),
+                ("estimate".to_string(), "3d".to_string()),
+            ]
+        );
+        assert!(map.set_attribute(&task, " ", "x").is_err());
+
+        let loaded = [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+        ];
+        for loaded in loaded {
+            let root = &loaded.nodes[&loaded.root_id];
+            let node = &loaded.nodes[&root.children[0]];
+            assert_eq!(node.attributes, map.nodes[&task].attributes);
+        }
+
+        assert!(map.remove_attribute(&task, "owner").unwrap());
+        assert!(!map.remove_attribute(&task, "owner").unwrap());
+        assert_eq!(map.attribute(&task, "owner").unwrap(), None);
+    }
+}
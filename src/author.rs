@@ -0,0 +1,45 @@
+use crate::MindMap;
+
+impl MindMap {
+    /// The author recorded as `created_by`/`modified_by` on nodes changed
+    /// through this map. It belongs to the editing session and is not saved
+    /// with the map.
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_deref()
+    }
+
+    pub fn set_author(&mut self, author: Option<String>) {
+        self.author = author;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mmap, xmind};
+
+    #[test]
+    fn test_author_attribution_round_trips() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.set_author(Some("alice".to_string()));
+        let child = map.add_child(&root_id, "Draft").unwrap();
+        map.set_author(Some("bob".to_string()));
+        map.change_node(&child, "Reviewed").unwrap();
+
+        let node = &map.nodes[&child];
+        assert_eq!(node.created_by.as_deref(), Some("alice"));
+        assert_eq!(node.modified_by.as_deref(), Some("bob"));
+        assert_eq!(map.nodes[&root_id].modified_by, None);
+
+        let loaded = mmap::from_mmap(&mmap::to_mmap(&map).unwrap()).unwrap();
+        let node = &loaded.nodes[&loaded.nodes[&loaded.root_id].children[0]];
+        assert_eq!(node.created_by.as_deref(), Some("alice"));
+        assert_eq!(node.modified_by.as_deref(), Some("bob"));
+
+        let loaded = xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap();
+        assert_eq!(loaded.nodes[&child].created_by, None);
+        assert_eq!(loaded.nodes[&child].modified_by.as_deref(), Some("bob"));
+        assert_eq!(loaded.author(), None);
+    }
+}
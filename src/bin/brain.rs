@@ -0,0 +1,89 @@
+//! `brain`, a command-line front end to the library: converts maps between
+//! formats, prints statistics, searches and renders them.
+
+use brain_core::persistence::{load_from_path, save_to_path};
+use brain_core::{BrainCoreError, stats, svg};
+use std::fs;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage:
+  brain convert <input> <output>     Convert between formats, picked by extension
+  brain stats <input>                Print node counts and balance figures
+  brain search <input> <query>       Print the path of every node matching query
+  brain render --svg <input> [<output>]
+                                     Lay the map out and write it as SVG
+";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["convert", input, output] => convert(input, output),
+        ["stats", input] => print_stats(input),
+        ["search", input, query] => search(input, query),
+        ["render", "--svg", input] => render(input, None),
+        ["render", "--svg", input, output] => render(input, Some(output)),
+        ["help" | "--help" | "-h"] => {
+            print!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        _ => {
+            eprint!("{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("brain: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn convert(input: &str, output: &str) -> Result<(), BrainCoreError> {
+    let map = load_from_path(input)?;
+    save_to_path(&map, output)
+}
+
+fn print_stats(input: &str) -> Result<(), BrainCoreError> {
+    let map = load_from_path(input)?;
+    let nodes: Vec<_> = map.iter_dfs().collect();
+    let depth = nodes
+        .iter()
+        .map(|n| map.ancestors(&n.id).count())
+        .max()
+        .unwrap_or_default();
+    let leaves = nodes.iter().filter(|n| n.children.is_empty()).count();
+    let notes = nodes.iter().filter(|n| n.note.is_some()).count();
+    let report = stats::balance(&map);
+    println!("Nodes:          {}", nodes.len());
+    println!("Leaves:         {leaves}");
+    println!("Depth:          {depth}");
+    println!("Notes:          {notes}");
+    println!("Floating nodes: {}", map.floating_roots.len());
+    println!("Relationships:  {}", map.relationships.len());
+    println!("Trash entries:  {}", map.trash.len());
+    println!("Imbalance:      {:.2}", report.size_imbalance);
+    println!("Complexity:     {:.2}", report.complexity_score);
+    Ok(())
+}
+
+fn search(input: &str, query: &str) -> Result<(), BrainCoreError> {
+    let map = load_from_path(input)?;
+    for hit in map.search(query) {
+        println!("{}", map.breadcrumb_string(&hit.node_id, " > "));
+    }
+    Ok(())
+}
+
+fn render(input: &str, output: Option<&str>) -> Result<(), BrainCoreError> {
+    let mut map = load_from_path(input)?;
+    map.compute_layout();
+    let svg = svg::to_svg(&map);
+    match output {
+        Some(output) => fs::write(output, svg)?,
+        None => print!("{svg}"),
+    }
+    Ok(())
+}
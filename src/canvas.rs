@@ -0,0 +1,339 @@
+use crate::export::ExportOptions;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::layout::{HeuristicMeasurer, LayoutKind, TextMeasurer};
+use crate::progress::{Monitor, Stage};
+use crate::style::NodeStyle;
+use crate::svg::connector_sides;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Obsidian's JSON Canvas: positioned cards plus edges between them. A mind
+// map becomes text cards joined by parent-to-child edges.
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Canvas {
+    #[serde(default)]
+    pub nodes: Vec<CanvasNode>,
+    #[serde(default)]
+    pub edges: Vec<CanvasEdge>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct CanvasNode {
+    pub id: String,
+    /// `text`, `file`, `link` or `group`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub x: f32,
+    #[serde(default)]
+    pub y: f32,
+    #[serde(default)]
+    pub width: f32,
+    #[serde(default)]
+    pub height: f32,
+    /// A `#rrggbb` colour or one of Obsidian's presets `"1"` to `"6"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CanvasEdge {
+    pub id: String,
+    pub from_node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_side: Option<String>,
+    pub to_node: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to_side: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+pub fn to_canvas(map: &MindMap) -> Result<String, BrainCoreError> {
+    to_canvas_with(map, &ExportOptions::default())
+}
+
+/// Exports every included node as a text card placed by
+/// [`MindMap::compute_layout`], with folded branches laid out expanded.
+/// A node's note follows its content after a blank line; its background
+/// colour becomes the card colour.
+pub fn to_canvas_with(map: &MindMap, options: &ExportOptions) -> Result<String, BrainCoreError> {
+    to_canvas_measured(map, options, &HeuristicMeasurer)
+}
+
+/// Exports the map as [`to_canvas_with`] does, laying it out and sizing
+/// the cards with `measurer`.
+pub fn to_canvas_measured(
+    map: &MindMap,
+    options: &ExportOptions,
+    measurer: &dyn TextMeasurer,
+) -> Result<String, BrainCoreError> {
+    write_canvas(map, options, measurer, &mut Monitor::default())
+}
+
+pub fn to_canvas_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    write_canvas(map, options, &HeuristicMeasurer, monitor)
+}
+
+fn write_canvas(
+    map: &MindMap,
+    options: &ExportOptions,
+    measurer: &dyn TextMeasurer,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut laid_out = map.clone();
+    for node in laid_out.nodes.values_mut() {
+        node.folded = false;
+        node.archived = node.archived && !options.include_archived;
+    }
+    laid_out.apply_layout(LayoutKind::default().engine(), measurer);
+
+    let mut canvas = Canvas {
+        nodes: Vec::new(),
+        edges: Vec::new(),
+    };
+    let mut stack: Vec<&Node> = laid_out.nodes.get(&map.root_id).into_iter().collect();
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
+    while let Some(node) = stack.pop() {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let text = match node.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            Some(note) => format!("{}\n\n{}", node.content, note.trim()),
+            None => node.content.clone(),
+        };
+        let (width, height) = laid_out.node_size(node, measurer);
+        canvas.nodes.push(CanvasNode {
+            id: node.id.clone(),
+            kind: "text".to_string(),
+            text: Some(text),
+            file: None,
+            url: None,
+            x: node.x,
+            y: node.y,
+            width,
+            height,
+            color: node.style.background_color.clone(),
+        });
+        let children = laid_out.laid_out_children(node);
+        let size = |node: &Node| laid_out.node_size(node, measurer);
+        let sides = connector_sides(node, &children, size);
+        for (child, (from, to)) in children.iter().zip(sides) {
+            canvas.edges.push(CanvasEdge {
+                id: format!("edge-{}", child.id),
+                from_node: node.id.clone(),
+                from_side: Some(from.name().to_string()),
+                to_node: child.id.clone(),
+                to_side: Some(to.name().to_string()),
+                label: None,
+            });
+        }
+        stack.extend(children.into_iter().rev());
+    }
+    monitor.finish()?;
+
+    let mut buf = Vec::new();
+    options.write_json(&canvas, &mut buf)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+pub fn from_canvas(text: &str) -> Result<MindMap, BrainCoreError> {
+    from_canvas_with(text, &ImportOptions::default())
+}
+
+/// Imports a canvas whose edges form a tree or a forest: every card may
+/// have at most one incoming edge, and edges may not loop. Text cards split
+/// into content and note at the first blank line; file and link cards use
+/// their path or URL. Groups are ignored. Siblings are ordered top to
+/// bottom, and several top-level cards are gathered under a "Canvas" root.
+pub fn from_canvas_with(text: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_canvas_monitored(text, options, &mut Monitor::default())
+}
+
+pub fn from_canvas_monitored(
+    text: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let canvas: Canvas = serde_json::from_str(text)
+        .map_err(|e| BrainCoreError::json(ImportFormat::Canvas, text, e))?;
+    let invalid = |message: String| BrainCoreError::parse(ImportFormat::Canvas, message);
+    let now = now_millis();
+
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    monitor.begin(Stage::BuildingNodes, Some(canvas.nodes.len()))?;
+    for card in canvas.nodes.iter().filter(|c| c.kind != "group") {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let (content, note) = match (&card.text, &card.file, &card.url) {
+            (Some(text), _, _) => match text.split_once("\n\n") {
+                Some((content, note)) => (content, Some(note.trim().to_string())),
+                None => (text.as_str(), None),
+            },
+            (None, Some(file), _) => (file.as_str(), None),
+            (None, None, Some(url)) => (url.as_str(), None),
+            (None, None, None) => ("", None),
+        };
+        let node = Node {
+            id: card.id.clone(),
+            content: content.trim().to_string(),
+            note: note.filter(|n| !n.is_empty()),
+            link: card.url.clone(),
+            x: card.x,
+            y: card.y,
+            style: NodeStyle {
+                background_color: card.color.clone(),
+                ..Default::default()
+            },
+            created: now,
+            modified: now,
+            ..Default::default()
+        };
+        if nodes.insert(card.id.clone(), node).is_some() {
+            return Err(invalid(format!("Duplicate card id: {}", card.id)));
+        }
+    }
+    monitor.finish()?;
+
+    for edge in &canvas.edges {
+        if !nodes.contains_key(&edge.from_node) || !nodes.contains_key(&edge.to_node) {
+            continue;
+        }
+        let child = nodes.get_mut(&edge.to_node).unwrap();
+        if child.parent.replace(edge.from_node.clone()).is_some() {
+            return Err(invalid(format!(
+                "Card {} has more than one incoming edge",
+                edge.to_node
+            )));
+        }
+        nodes
+            .get_mut(&edge.from_node)
+            .unwrap()
+            .children
+            .push(edge.to_node.clone());
+    }
+    for node_id in nodes.keys().cloned().collect::<Vec<_>>() {
+        let mut children = std::mem::take(&mut nodes.get_mut(&node_id).unwrap().children);
+        children.sort_by(|a, b| {
+            let (a, b) = (&nodes[a], &nodes[b]);
+            a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+        });
+        nodes.get_mut(&node_id).unwrap().children = children;
+    }
+
+    let mut tops: Vec<String> = nodes
+        .values()
+        .filter(|n| n.parent.is_none())
+        .map(|n| n.id.clone())
+        .collect();
+    tops.sort_by(|a, b| {
+        let (a, b) = (&nodes[a], &nodes[b]);
+        a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+    });
+    let root_id = match tops.as_slice() {
+        [] if nodes.is_empty() => return Err(invalid("Canvas has no cards".to_string())),
+        [] => return Err(invalid("Canvas edges form a loop".to_string())),
+        [only] => only.clone(),
+        _ => {
+            let root_id = Uuid::new_v4().to_string();
+            for top in &tops {
+                nodes.get_mut(top).unwrap().parent = Some(root_id.clone());
+            }
+            nodes.insert(
+                root_id.clone(),
+                Node {
+                    id: root_id.clone(),
+                    content: "Canvas".to_string(),
+                    children: tops,
+                    created: now,
+                    modified: now,
+                    ..Default::default()
+                },
+            );
+            root_id
+        }
+    };
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    if !map.validate().is_empty() {
+        return Err(invalid("Canvas edges form a loop".to_string()));
+    }
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canvas_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Root").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        map.add_child(&a, "A1").unwrap();
+        map.set_note(&a, "About A").unwrap();
+        map.set_folded(&a, true).unwrap();
+        map.add_child(&root_id, "B").unwrap();
+
+        let json = to_canvas(&map).unwrap();
+        let canvas: Canvas = serde_json::from_str(&json).unwrap();
+        assert_eq!(canvas.nodes.len(), 4);
+        assert_eq!(canvas.edges.len(), 3);
+        assert_eq!(canvas.nodes[1].text.as_deref(), Some("A\n\nAbout A"));
+        assert_eq!(canvas.nodes[1].x, 92.0);
+
+        let loaded = from_canvas(&json).unwrap();
+        assert_eq!(loaded.root_id, root_id);
+        assert_eq!(
+            loaded.nodes[&root_id].children,
+            map.nodes[&root_id].children
+        );
+        assert_eq!(loaded.nodes[&a].note.as_deref(), Some("About A"));
+        assert!(loaded.validate().is_empty());
+    }
+
+    #[test]
+    fn test_canvas_import_rules() {
+        let forest = r##"{"nodes": [
+            {"id": "b", "type": "text", "text": "Below", "x": 0, "y": 200, "width": 100, "height": 50},
+            {"id": "a", "type": "text", "text": "Above", "x": 0, "y": 0, "width": 100, "height": 50},
+            {"id": "l", "type": "link", "url": "https://obsidian.md", "x": 200, "y": 0, "width": 100, "height": 50},
+            {"id": "g", "type": "group", "label": "Group", "x": 0, "y": 0, "width": 400, "height": 400}
+        ], "edges": [{"id": "e", "fromNode": "a", "toNode": "l"}]}"##;
+        let map = from_canvas(forest).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "Canvas");
+        assert_eq!(root.children, vec!["a", "b"]);
+        assert_eq!(map.nodes["l"].link.as_deref(), Some("https://obsidian.md"));
+
+        let two_parents = r#"{"nodes": [{"id": "a", "type": "text", "text": "A"},
+            {"id": "b", "type": "text", "text": "B"}, {"id": "c", "type": "text", "text": "C"}],
+            "edges": [{"id": "1", "fromNode": "a", "toNode": "c"},
+                      {"id": "2", "fromNode": "b", "toNode": "c"}]}"#;
+        assert!(from_canvas(two_parents).is_err());
+    }
+}
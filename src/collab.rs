@@ -0,0 +1,748 @@
+use crate::attachments::{Attachment, ResourceStore};
+use crate::groups::Group;
+use crate::relationship::Relationship;
+use crate::style::NodeStyle;
+use crate::summaries::Summary;
+use crate::task::TaskInfo;
+use crate::time_tracking::TimeEntry;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Lamport time of a change and the replica that made it. Later stamps
+/// win; the replica id orders concurrent changes made at the same time.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Stamp {
+    time: u64,
+    replica: String,
+}
+
+/// A last-writer-wins register.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Lww<T> {
+    value: T,
+    stamp: Stamp,
+}
+
+impl<T: Clone + PartialEq> Lww<T> {
+    fn new(value: T, stamp: &Stamp) -> Self {
+        Lww {
+            value,
+            stamp: stamp.clone(),
+        }
+    }
+
+    fn set(&mut self, value: &T, stamp: &Stamp) {
+        if self.value != *value {
+            self.value = value.clone();
+            self.stamp = stamp.clone();
+        }
+    }
+
+    fn merge(&mut self, other: &Self) {
+        if other.stamp > self.stamp {
+            *self = other.clone();
+        }
+    }
+}
+
+/// Where a node sits: its parent, or `None` for the root and floating
+/// nodes, and a key that orders it among its siblings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Placement {
+    parent: Option<String>,
+    key: String,
+}
+
+/// Where a node was drawn: `x`, `y` and whether it was placed by hand.
+type Position = (f32, f32, bool);
+
+/// The replicated state of one node. Each field is a register of its own,
+/// so concurrent edits to different fields of a node both survive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    created: u64,
+    /// Set by the replica that added the node.
+    created_by: Option<String>,
+    /// Merged by taking the later time.
+    modified: u64,
+    modified_by: Lww<Option<String>>,
+    placement: Lww<Placement>,
+    position: Lww<Position>,
+    deleted: Lww<bool>,
+    content: Lww<String>,
+    note: Lww<Option<String>>,
+    link: Lww<Option<String>>,
+    details: Lww<Option<String>>,
+    icons: Lww<Vec<String>>,
+    tags: Lww<Vec<String>>,
+    attributes: Lww<Vec<(String, String)>>,
+    folded: Lww<bool>,
+    archived: Lww<bool>,
+    style: Lww<NodeStyle>,
+    task: Lww<Option<TaskInfo>>,
+    attachments: Lww<Vec<Attachment>>,
+    time_entries: Lww<Vec<TimeEntry>>,
+}
+
+impl Entry {
+    fn new(node: &Node, placement: Placement, stamp: &Stamp) -> Self {
+        Entry {
+            created: node.created,
+            created_by: node.created_by.clone(),
+            modified: node.modified,
+            modified_by: Lww::new(node.modified_by.clone(), stamp),
+            placement: Lww::new(placement, stamp),
+            position: Lww::new((node.x, node.y, node.pinned), stamp),
+            deleted: Lww::new(false, stamp),
+            content: Lww::new(node.content.clone(), stamp),
+            note: Lww::new(node.note.clone(), stamp),
+            link: Lww::new(node.link.clone(), stamp),
+            details: Lww::new(node.details.clone(), stamp),
+            icons: Lww::new(node.icons.clone(), stamp),
+            tags: Lww::new(node.tags.clone(), stamp),
+            attributes: Lww::new(node.attributes.clone(), stamp),
+            folded: Lww::new(node.folded, stamp),
+            archived: Lww::new(node.archived, stamp),
+            style: Lww::new(node.style.clone(), stamp),
+            task: Lww::new(node.task.clone(), stamp),
+            attachments: Lww::new(node.attachments.clone(), stamp),
+            time_entries: Lww::new(node.time_entries.clone(), stamp),
+        }
+    }
+
+    fn record(&mut self, node: &Node, placement: &Placement, stamp: &Stamp) {
+        self.modified = self.modified.max(node.modified);
+        self.modified_by.set(&node.modified_by, stamp);
+        self.placement.set(placement, stamp);
+        self.position.set(&(node.x, node.y, node.pinned), stamp);
+        self.deleted.set(&false, stamp);
+        self.content.set(&node.content, stamp);
+        self.note.set(&node.note, stamp);
+        self.link.set(&node.link, stamp);
+        self.details.set(&node.details, stamp);
+        self.icons.set(&node.icons, stamp);
+        self.tags.set(&node.tags, stamp);
+        self.attributes.set(&node.attributes, stamp);
+        self.folded.set(&node.folded, stamp);
+        self.archived.set(&node.archived, stamp);
+        self.style.set(&node.style, stamp);
+        self.task.set(&node.task, stamp);
+        self.attachments.set(&node.attachments, stamp);
+        self.time_entries.set(&node.time_entries, stamp);
+    }
+
+    fn merge(&mut self, other: &Entry) {
+        if other.created < self.created {
+            self.created_by = other.created_by.clone();
+        }
+        self.created = self.created.min(other.created);
+        self.modified = self.modified.max(other.modified);
+        self.modified_by.merge(&other.modified_by);
+        self.placement.merge(&other.placement);
+        self.position.merge(&other.position);
+        self.deleted.merge(&other.deleted);
+        self.content.merge(&other.content);
+        self.note.merge(&other.note);
+        self.link.merge(&other.link);
+        self.details.merge(&other.details);
+        self.icons.merge(&other.icons);
+        self.tags.merge(&other.tags);
+        self.attributes.merge(&other.attributes);
+        self.folded.merge(&other.folded);
+        self.archived.merge(&other.archived);
+        self.style.merge(&other.style);
+        self.task.merge(&other.task);
+        self.attachments.merge(&other.attachments);
+        self.time_entries.merge(&other.time_entries);
+    }
+
+    fn latest(&self) -> u64 {
+        [
+            &self.modified_by.stamp,
+            &self.placement.stamp,
+            &self.position.stamp,
+            &self.deleted.stamp,
+            &self.content.stamp,
+            &self.note.stamp,
+            &self.link.stamp,
+            &self.details.stamp,
+            &self.icons.stamp,
+            &self.tags.stamp,
+            &self.attributes.stamp,
+            &self.folded.stamp,
+            &self.archived.stamp,
+            &self.style.stamp,
+            &self.task.stamp,
+            &self.attachments.stamp,
+            &self.time_entries.stamp,
+        ]
+        .into_iter()
+        .map(|s| s.time)
+        .max()
+        .unwrap_or(0)
+    }
+}
+
+/// Map-level items such as relationships, keyed by id. A removed item
+/// stays as `None`, so the removal wins over older edits.
+type Items<T> = BTreeMap<String, Lww<Option<T>>>;
+
+/// The part of a replica that is exchanged in updates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct State {
+    root: String,
+    entries: BTreeMap<String, Entry>,
+    relationships: Items<Relationship>,
+    groups: Items<Group>,
+    summaries: Items<Summary>,
+    /// Attached files only ever get added; their keys name their contents.
+    resources: ResourceStore,
+}
+
+impl State {
+    fn latest(&self) -> u64 {
+        let items = |stamps: Vec<&Stamp>| stamps.into_iter().map(|s| s.time).max();
+        [
+            self.entries.values().map(Entry::latest).max(),
+            items(self.relationships.values().map(|r| &r.stamp).collect()),
+            items(self.groups.values().map(|g| &g.stamp).collect()),
+            items(self.summaries.values().map(|s| &s.stamp).collect()),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or(0)
+    }
+}
+
+/// Records the items of the map under their ids and marks the ones it no
+/// longer has as removed.
+fn record_items<T: Clone + PartialEq>(
+    registers: &mut Items<T>,
+    items: &[T],
+    id: impl Fn(&T) -> &String,
+    stamp: &Stamp,
+) {
+    for item in items {
+        let value = Some(item.clone());
+        match registers.get_mut(id(item)) {
+            Some(register) => register.set(&value, stamp),
+            None => {
+                registers.insert(id(item).clone(), Lww::new(value, stamp));
+            }
+        }
+    }
+    for (key, register) in registers.iter_mut() {
+        if !items.iter().any(|item| id(item) == key) {
+            register.set(&None, stamp);
+        }
+    }
+}
+
+fn merge_items<T: Clone + PartialEq>(registers: &mut Items<T>, other: &Items<T>) {
+    for (id, register) in other {
+        match registers.get_mut(id) {
+            Some(existing) => existing.merge(register),
+            None => {
+                registers.insert(id.clone(), register.clone());
+            }
+        }
+    }
+}
+
+/// The items that weren't removed and that `keep` accepts, in id order.
+fn live_items<T: Clone>(registers: &Items<T>, keep: impl Fn(&T) -> bool) -> Vec<T> {
+    registers
+        .values()
+        .filter_map(|r| r.value.clone())
+        .filter(|item| keep(item))
+        .collect()
+}
+
+/// One replica of a map edited by several people at once.
+///
+/// The map is kept as a CRDT: every node is a set of last-writer-wins
+/// registers, one per field, plus one for its place in the tree, whose
+/// sibling order comes from fractional keys. Edit a [`MindMap`] as usual,
+/// call [`Collab::record`] to turn the differences into stamped changes,
+/// and exchange [`Collab::encode_update`] with the other replicas. Merging
+/// is commutative, associative and idempotent, so replicas that have seen
+/// the same updates produce the same map from [`Collab::to_map`], whatever
+/// the order the updates arrived in.
+///
+/// Content, notes, links, details, icons, tags, attributes, folding,
+/// archiving, styles, tasks, attachments, time entries, positions and
+/// authors are shared, as are relationships, groups, summaries and the
+/// attached files. The trash, the selection and the map's settings stay
+/// local; [`Collab::update_map`] brings in the rest while keeping them.
+#[derive(Debug, Clone)]
+pub struct Collab {
+    replica: String,
+    clock: u64,
+    state: State,
+}
+
+impl Collab {
+    /// Starts replicating `map`. Other replicas join with
+    /// [`Collab::join`] from this one's update rather than starting from
+    /// copies of the map of their own.
+    pub fn new(replica: impl Into<String>, map: &MindMap) -> Self {
+        let mut collab = Collab {
+            replica: replica.into(),
+            clock: 0,
+            state: State {
+                root: map.root_id.clone(),
+                entries: BTreeMap::new(),
+                relationships: BTreeMap::new(),
+                groups: BTreeMap::new(),
+                summaries: BTreeMap::new(),
+                resources: ResourceStore::default(),
+            },
+        };
+        collab.record(map);
+        collab
+    }
+
+    /// Starts a replica from another one's update.
+    pub fn join(replica: impl Into<String>, update: &[u8]) -> Result<Self, BrainCoreError> {
+        let state = decode(update)?;
+        let clock = state.latest();
+        Ok(Collab {
+            replica: replica.into(),
+            clock,
+            state,
+        })
+    }
+
+    /// Records how `map` differs from the replicated state as changes made
+    /// by this replica now. Nodes missing from `map` are deleted along with
+    /// their subtrees.
+    pub fn record(&mut self, map: &MindMap) {
+        self.clock += 1;
+        let stamp = Stamp {
+            time: self.clock,
+            replica: self.replica.clone(),
+        };
+
+        let mut placements = HashMap::new();
+        let tops: Vec<String> = map.tree_roots().cloned().collect();
+        for (parent, children) in std::iter::once((None, &tops)).chain(
+            map.nodes
+                .values()
+                .map(|n| (Some(n.id.as_str()), &n.children)),
+        ) {
+            let keys = self.order_keys(parent, children);
+            for (id, key) in children.iter().zip(keys) {
+                let placement = Placement {
+                    parent: parent.map(str::to_string),
+                    key,
+                };
+                placements.insert(id.as_str(), placement);
+            }
+        }
+
+        for node in map.nodes.values() {
+            let Some(placement) = placements.remove(node.id.as_str()) else {
+                continue;
+            };
+            match self.state.entries.get_mut(&node.id) {
+                Some(entry) => entry.record(node, &placement, &stamp),
+                None => {
+                    let entry = Entry::new(node, placement, &stamp);
+                    self.state.entries.insert(node.id.clone(), entry);
+                }
+            }
+        }
+        for (id, entry) in &mut self.state.entries {
+            if !map.nodes.contains_key(id) {
+                entry.deleted.set(&true, &stamp);
+            }
+        }
+        let state = &mut self.state;
+        record_items(
+            &mut state.relationships,
+            &map.relationships,
+            |r| &r.id,
+            &stamp,
+        );
+        record_items(&mut state.groups, &map.groups, |g| &g.id, &stamp);
+        record_items(&mut state.summaries, &map.summaries, |s| &s.id, &stamp);
+        state.resources.extend_from(&map.resources);
+    }
+
+    /// The replicated state, to be passed to [`Collab::apply_update`] on
+    /// the other replicas.
+    pub fn encode_update(&self) -> Vec<u8> {
+        serde_json::to_vec(&self.state).unwrap_or_default()
+    }
+
+    /// Merges another replica's update into this one. Fails if the update
+    /// can't be read or belongs to a different map.
+    pub fn apply_update(&mut self, update: &[u8]) -> Result<(), BrainCoreError> {
+        let state = decode(update)?;
+        if state.root != self.state.root {
+            return Err(BrainCoreError::InvalidOperation(
+                "Update belongs to another map".to_string(),
+            ));
+        }
+        self.clock = self.clock.max(state.latest());
+        for (id, entry) in state.entries {
+            match self.state.entries.get_mut(&id) {
+                Some(existing) => existing.merge(&entry),
+                None => {
+                    self.state.entries.insert(id, entry);
+                }
+            }
+        }
+        merge_items(&mut self.state.relationships, &state.relationships);
+        merge_items(&mut self.state.groups, &state.groups);
+        merge_items(&mut self.state.summaries, &state.summaries);
+        self.state.resources.extend_from(&state.resources);
+        Ok(())
+    }
+
+    /// Builds the map as it stands after every change this replica has
+    /// seen.
+    ///
+    /// Nodes under a deleted node are left out with it, as are the
+    /// relationships, groups and summaries that refer to them. Concurrent
+    /// moves can leave nodes parenting each other; the loop is broken by
+    /// putting the node moved last under the root.
+    pub fn to_map(&self) -> MindMap {
+        let root = self.state.root.as_str();
+        let alive: BTreeMap<&str, &Entry> = self
+            .state
+            .entries
+            .iter()
+            .filter(|(_, e)| !e.deleted.value)
+            .map(|(id, e)| (id.as_str(), e))
+            .collect();
+        let mut parents: HashMap<&str, Option<&str>> = alive
+            .iter()
+            .map(|(id, e)| (*id, e.placement.value.parent.as_deref()))
+            .collect();
+        parents.insert(root, None);
+
+        for &id in alive.keys() {
+            while let Some(cycle) = find_cycle(id, &parents) {
+                let last = cycle
+                    .into_iter()
+                    .max_by_key(|id| &alive[id].placement.stamp)
+                    .unwrap_or(id);
+                parents.insert(last, Some(root));
+            }
+        }
+
+        let mut children: HashMap<Option<&str>, Vec<&str>> = HashMap::new();
+        for (&id, &parent) in &parents {
+            if id == root || parent.is_some_and(|p| !alive.contains_key(p)) {
+                continue;
+            }
+            children.entry(parent).or_default().push(id);
+        }
+        for siblings in children.values_mut() {
+            siblings.sort_by_key(|id| (&alive[id].placement.value.key, *id));
+        }
+
+        let mut nodes = HashMap::new();
+        let tops = children.get(&None).cloned().unwrap_or_default();
+        let mut stack: Vec<&str> = tops.iter().copied().chain([root]).collect();
+        while let Some(id) = stack.pop() {
+            let Some(entry) = alive.get(id) else {
+                continue;
+            };
+            let child_ids = children.get(&Some(id)).cloned().unwrap_or_default();
+            stack.extend(&child_ids);
+            nodes.insert(
+                id.to_string(),
+                Node {
+                    id: id.to_string(),
+                    content: entry.content.value.clone(),
+                    children: child_ids.iter().map(|c| c.to_string()).collect(),
+                    parent: parents[id].map(str::to_string),
+                    x: entry.position.value.0,
+                    y: entry.position.value.1,
+                    pinned: entry.position.value.2,
+                    created: entry.created,
+                    created_by: entry.created_by.clone(),
+                    modified: entry.modified,
+                    modified_by: entry.modified_by.value.clone(),
+                    attachments: entry.attachments.value.clone(),
+                    time_entries: entry.time_entries.value.clone(),
+                    icons: entry.icons.value.clone(),
+                    archived: entry.archived.value,
+                    folded: entry.folded.value,
+                    note: entry.note.value.clone(),
+                    link: entry.link.value.clone(),
+                    details: entry.details.value.clone(),
+                    attributes: entry.attributes.value.clone(),
+                    style: entry.style.value.clone(),
+                    tags: entry.tags.value.clone(),
+                    task: entry.task.value.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+        let mut map = MindMap::from_nodes(nodes, root.to_string());
+        map.floating_roots = tops.iter().map(|id| id.to_string()).collect();
+        let has = |id: &String| map.nodes.contains_key(id);
+        map.relationships = live_items(&self.state.relationships, |r| has(&r.from) && has(&r.to));
+        map.groups = live_items(&self.state.groups, |g| has(&g.node_id));
+        map.summaries = live_items(&self.state.summaries, |s| {
+            [&s.parent_id, &s.first_child, &s.last_child]
+                .into_iter()
+                .all(has)
+        });
+        map.resources = self.state.resources.clone();
+        map
+    }
+
+    /// Replaces what `map` shares with the other replicas by the state from
+    /// [`Collab::to_map`], keeping its trash, selection and settings. The
+    /// selection falls back to the root if its node went away.
+    pub fn update_map(&self, map: &mut MindMap) {
+        let shared = self.to_map();
+        map.nodes = shared.nodes;
+        map.root_id = shared.root_id;
+        map.floating_roots = shared.floating_roots;
+        map.relationships = shared.relationships;
+        map.groups = shared.groups;
+        map.summaries = shared.summaries;
+        // Local files stay too, for the nodes in the trash.
+        let local = std::mem::replace(&mut map.resources, shared.resources);
+        map.resources.extend_from(&local);
+        if !map.nodes.contains_key(&map.selection.primary) {
+            map.selection.primary = map.root_id.clone();
+        }
+        map.prune_selection();
+        map.revision += 1;
+    }
+
+    /// Sibling keys for `children` in this order, keeping as many of the
+    /// current keys as possible so concurrent reorders rarely collide.
+    fn order_keys(&self, parent: Option<&str>, children: &[String]) -> Vec<String> {
+        let current: Vec<Option<&str>> = children
+            .iter()
+            .map(|id| {
+                let entry = self.state.entries.get(id)?;
+                let placement = &entry.placement.value;
+                (!entry.deleted.value && placement.parent.as_deref() == parent)
+                    .then_some(placement.key.as_str())
+            })
+            .collect();
+
+        // Keep the longest run of current keys that is already in order.
+        let mut length = vec![0usize; current.len()];
+        let mut previous = vec![None; current.len()];
+        for i in 0..current.len() {
+            let Some(key) = current[i] else {
+                continue;
+            };
+            length[i] = 1;
+            for j in 0..i {
+                if current[j].is_some_and(|k| k < key) && length[j] + 1 > length[i] {
+                    length[i] = length[j] + 1;
+                    previous[i] = Some(j);
+                }
+            }
+        }
+        let mut kept = vec![false; current.len()];
+        let mut next = (0..current.len()).max_by_key(|i| (length[*i], usize::MAX - i));
+        while let Some(i) = next.filter(|i| length[*i] > 0) {
+            kept[i] = true;
+            next = previous[i];
+        }
+
+        let mut keys: Vec<String> = Vec::with_capacity(children.len());
+        for i in 0..children.len() {
+            let key = match current[i] {
+                Some(key) if kept[i] => key.to_string(),
+                _ => {
+                    let high = (i + 1..children.len())
+                        .find(|j| kept[*j])
+                        .and_then(|j| current[j]);
+                    key_between(keys.last().map(String::as_str), high)
+                }
+            };
+            keys.push(key);
+        }
+        keys
+    }
+}
+
+fn decode(update: &[u8]) -> Result<State, BrainCoreError> {
+    serde_json::from_slice(update)
+        .map_err(|e| BrainCoreError::InvalidOperation(format!("Invalid update: {e}")))
+}
+
+/// The nodes of a parent loop that `id` leads into, if any.
+fn find_cycle<'a>(
+    id: &'a str,
+    parents: &HashMap<&'a str, Option<&'a str>>,
+) -> Option<Vec<&'a str>> {
+    let mut path = vec![id];
+    let mut current = id;
+    while let Some(Some(parent)) = parents.get(current) {
+        if let Some(start) = path.iter().position(|p| p == parent) {
+            return Some(path.split_off(start));
+        }
+        path.push(parent);
+        current = parent;
+    }
+    None
+}
+
+/// A key that sorts strictly between `low` and `high`, either of which may
+/// be open. Keys are strings of the letters `a` to `z`, read as base-26
+/// fractions, and never end in `a`, so there is always room below one.
+fn key_between(low: Option<&str>, high: Option<&str>) -> String {
+    let digit = |key: &[u8], i: usize| key.get(i).map(|d| d.saturating_sub(b'a').min(25));
+    let low = low.unwrap_or_default().as_bytes();
+    let mut high = high.map(str::as_bytes);
+    let mut key = String::new();
+    for i in 0.. {
+        let l = digit(low, i).unwrap_or(0);
+        let h = high.and_then(|h| digit(h, i)).unwrap_or(26);
+        if l + 1 < h {
+            key.push((b'a' + (l + h) / 2) as char);
+            break;
+        }
+        key.push((b'a' + l) as char);
+        if l < h {
+            // The key is now below `high` whatever follows.
+            high = None;
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn children(map: &MindMap, id: &str) -> Vec<String> {
+        map.nodes[id]
+            .children
+            .iter()
+            .map(|c| map.nodes[c].content.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_concurrent_edits_converge() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let mut alice = Collab::new("alice", &map);
+        let mut bob = Collab::join("bob", &alice.encode_update()).unwrap();
+
+        let mut alice_map = alice.to_map();
+        alice_map.change_node(&a, "Alpha").unwrap();
+        alice_map.add_child(&a, "A1").unwrap();
+        alice_map.add_floating_node("Loose", 0.0, 0.0).unwrap();
+        alice.record(&alice_map);
+
+        let mut bob_map = bob.to_map();
+        bob_map.set_note(&a, "Bob's note").unwrap();
+        bob_map.move_node(&b, &root_id, 0).unwrap();
+        bob_map.add_child(&b, "B1").unwrap();
+        bob.record(&bob_map);
+
+        alice.apply_update(&bob.encode_update()).unwrap();
+        bob.apply_update(&alice.encode_update()).unwrap();
+        bob.apply_update(&alice.encode_update()).unwrap();
+        let merged = alice.to_map();
+        assert_eq!(children(&merged, &root_id), ["B", "Alpha"]);
+        assert_eq!(children(&merged, &a), ["A1"]);
+        assert_eq!(children(&merged, &b), ["B1"]);
+        assert_eq!(merged.nodes[&a].note.as_deref(), Some("Bob's note"));
+        assert_eq!(merged.floating_roots.len(), 1);
+        assert!(merged.validate().is_empty());
+        assert_eq!(
+            serde_json::to_value(&merged.nodes).unwrap(),
+            serde_json::to_value(&bob.to_map().nodes).unwrap()
+        );
+
+        // Moving two nodes under each other at once must not lose either.
+        let mut alice_map = alice.to_map();
+        alice_map.move_node(&a, &b, 0).unwrap();
+        alice.record(&alice_map);
+        let mut bob_map = bob.to_map();
+        bob_map.move_node(&b, &a, 0).unwrap();
+        bob_map.trash_node(&b).unwrap();
+        bob_map.restore(&bob_map.trash[0].id.clone()).unwrap();
+        bob.record(&bob_map);
+        alice.apply_update(&bob.encode_update()).unwrap();
+        bob.apply_update(&alice.encode_update()).unwrap();
+        let merged = alice.to_map();
+        assert!(merged.validate().is_empty());
+        assert_eq!(merged.nodes.len(), 6);
+        assert_eq!(
+            serde_json::to_value(&merged.nodes).unwrap(),
+            serde_json::to_value(&bob.to_map().nodes).unwrap()
+        );
+
+        assert!(alice.apply_update(b"not an update").is_err());
+        let other = Collab::new("carol", &MindMap::new());
+        assert!(alice.apply_update(&other.encode_update()).is_err());
+    }
+
+    #[test]
+    fn test_shared_map_items() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let c = map.add_child(&root_id, "C").unwrap();
+        let mut alice = Collab::new("alice", &map);
+        let mut bob = Collab::join("bob", &alice.encode_update()).unwrap();
+
+        let mut alice_map = alice.to_map();
+        let link = alice_map.add_relationship(&a, &b, Some("see")).unwrap();
+        alice_map.add_group(&a, Some("First")).unwrap();
+        alice_map.attach(&a, "photo.png", vec![1, 2, 3]).unwrap();
+        alice_map.set_position(&b, 300.0, 40.0).unwrap();
+        alice.record(&alice_map);
+
+        let mut bob_map = bob.to_map();
+        bob_map.set_author(Some("bob".to_string()));
+        bob_map.trash_node(&c).unwrap();
+        bob_map.change_node(&b, "Bee").unwrap();
+        bob.record(&bob_map);
+        bob.apply_update(&alice.encode_update()).unwrap();
+        bob.update_map(&mut bob_map);
+
+        assert_eq!(bob_map.relationships, alice_map.relationships);
+        assert_eq!(bob_map.groups, alice_map.groups);
+        let photo = &bob_map.nodes[&a].attachments[0];
+        assert_eq!(bob_map.attachment_data(photo), Some(&[1, 2, 3][..]));
+        let node_b = &bob_map.nodes[&b];
+        assert_eq!((node_b.x, node_b.y, node_b.pinned), (300.0, 40.0, true));
+        assert_eq!(node_b.modified_by.as_deref(), Some("bob"));
+        assert_eq!(bob_map.trash.len(), 1);
+        assert!(!bob_map.nodes.contains_key(&c));
+
+        // Removals spread like any other change.
+        alice_map.remove_relationship(&link).unwrap();
+        alice.record(&alice_map);
+        bob.apply_update(&alice.encode_update()).unwrap();
+        assert!(bob.to_map().relationships.is_empty());
+        assert_eq!(bob.to_map().groups.len(), 1);
+    }
+
+    #[test]
+    fn test_key_between() {
+        assert_eq!(key_between(None, None), "n");
+        assert_eq!(key_between(Some("n"), None), "t");
+        assert_eq!(key_between(None, Some("b")), "an");
+        assert_eq!(key_between(Some("m"), Some("n")), "mn");
+        let mut keys = vec![key_between(None, None)];
+        for _ in 0..50 {
+            let key = key_between(None, Some(&keys[0]));
+            assert!(key < keys[0] && !key.ends_with('a'));
+            keys.insert(0, key);
+        }
+    }
+}
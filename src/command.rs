@@ -0,0 +1,325 @@
+use crate::trash::TrashEntry;
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+
+/// An edit described as data, so it can be recorded, replayed, sent to
+/// another instance or undone. [`MindMap::apply`] runs it through the same
+/// methods an editor would call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Command {
+    AddChild {
+        parent_id: String,
+        content: String,
+    },
+    /// Moves a node and its subtree into the trash.
+    Remove {
+        node_id: String,
+    },
+    /// Puts a trashed subtree back; the inverse of `Remove`.
+    Restore {
+        trash_id: String,
+    },
+    /// Deletes a node and its subtree without keeping them in the trash;
+    /// the inverse of `AddChild`, so undoing an addition leaves no trace.
+    Purge {
+        node_id: String,
+    },
+    /// Puts a purged subtree back under its old ids; the inverse of
+    /// `Purge`.
+    Reinsert {
+        entry: TrashEntry,
+    },
+    Rename {
+        node_id: String,
+        content: String,
+    },
+    Move {
+        node_id: String,
+        parent_id: String,
+        position: usize,
+    },
+    SetNote {
+        node_id: String,
+        note: Option<String>,
+    },
+    SetIcons {
+        node_id: String,
+        icons: Vec<String>,
+    },
+    SetFolded {
+        node_id: String,
+        folded: bool,
+    },
+    /// Commands run in order as one edit. If one fails, those before it are
+    /// undone. See [`MindMap::transaction`].
+    Batch {
+        commands: Vec<Command>,
+    },
+}
+
+/// What [`MindMap::apply`] did.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandResult {
+    /// The command that takes the map back to where it was. Applying it
+    /// yields the inverse of the inverse, so undo and redo can alternate
+    /// indefinitely: removing a node is undone by restoring it from the
+    /// trash and adding one by purging it, both of which keep its id.
+    pub inverse: Command,
+    /// The node an `AddChild` created, or the first one a `Batch` did.
+    pub node_id: Option<String>,
+}
+
+impl Command {
+    /// The node that must exist for the command to run.
+    fn target(&self) -> Option<&str> {
+        match self {
+            Command::AddChild { parent_id, .. } => Some(parent_id),
+            Command::Restore { .. } | Command::Reinsert { .. } | Command::Batch { .. } => None,
+            Command::Remove { node_id }
+            | Command::Purge { node_id }
+            | Command::Rename { node_id, .. }
+            | Command::Move { node_id, .. }
+            | Command::SetNote { node_id, .. }
+            | Command::SetIcons { node_id, .. }
+            | Command::SetFolded { node_id, .. } => Some(node_id),
+        }
+    }
+}
+
+impl MindMap {
+    /// Runs a command and returns its inverse. A command that fails leaves
+    /// the map unchanged.
+    pub fn apply(&mut self, command: Command) -> Result<CommandResult, BrainCoreError> {
+        let node = match command.target() {
+            Some(id) => Some(
+                self.nodes
+                    .get(id)
+                    .ok_or_else(|| BrainCoreError::NodeNotFound(id.to_string()))?,
+            ),
+            None => None,
+        };
+        let mut created = None;
+        let inverse = match command {
+            Command::AddChild { parent_id, content } => {
+                let node_id = self.add_child(&parent_id, &content)?;
+                created = Some(node_id.clone());
+                Command::Purge { node_id }
+            }
+            Command::Purge { node_id } => Command::Reinsert {
+                entry: self.purge_node(&node_id)?,
+            },
+            Command::Reinsert { entry } => {
+                let node_id = entry.node_id.clone();
+                self.reinsert(entry)?;
+                Command::Purge { node_id }
+            }
+            Command::Remove { node_id } => Command::Restore {
+                trash_id: self.trash_node(&node_id)?,
+            },
+            Command::Restore { trash_id } => {
+                let node_id = self
+                    .trash
+                    .iter()
+                    .find(|e| e.id == trash_id)
+                    .map(|e| e.node_id.clone())
+                    .ok_or_else(|| BrainCoreError::TrashEntryNotFound(trash_id.clone()))?;
+                self.restore(&trash_id)?;
+                Command::Remove { node_id }
+            }
+            Command::Rename { node_id, content } => {
+                let old = node.map(|n| n.content.clone()).unwrap_or_default();
+                self.change_node(&node_id, &content)?;
+                Command::Rename {
+                    node_id,
+                    content: old,
+                }
+            }
+            Command::Move {
+                node_id,
+                parent_id,
+                position,
+            } => {
+                let old_parent_id = node.and_then(|n| n.parent.clone()).ok_or_else(|| {
+                    if node_id == self.root_id {
+                        BrainCoreError::RootImmutable
+                    } else {
+                        BrainCoreError::InvalidOperation("Node has no parent".to_string())
+                    }
+                })?;
+                let old_position = self
+                    .nodes
+                    .get(&old_parent_id)
+                    .ok_or_else(|| BrainCoreError::NodeNotFound(old_parent_id.clone()))?
+                    .children
+                    .iter()
+                    .position(|c| *c == node_id)
+                    .ok_or_else(|| {
+                        BrainCoreError::InvalidOperation(
+                            "Node is not among its parent's children".to_string(),
+                        )
+                    })?;
+                self.move_node(&node_id, &parent_id, position)?;
+                Command::Move {
+                    node_id,
+                    parent_id: old_parent_id,
+                    position: old_position,
+                }
+            }
+            Command::SetNote { node_id, note } => {
+                let old = node.and_then(|n| n.note.clone());
+                match &note {
+                    Some(note) => self.set_note(&node_id, note)?,
+                    None => self.clear_note(&node_id)?,
+                }
+                Command::SetNote { node_id, note: old }
+            }
+            Command::SetIcons { node_id, icons } => {
+                let old = node.map(|n| n.icons.clone()).unwrap_or_default();
+                self.set_icons(&node_id, icons)?;
+                Command::SetIcons {
+                    node_id,
+                    icons: old,
+                }
+            }
+            Command::SetFolded { node_id, folded } => {
+                let old = node.is_some_and(|n| n.folded);
+                self.set_folded(&node_id, folded)?;
+                Command::SetFolded {
+                    node_id,
+                    folded: old,
+                }
+            }
+            Command::Batch { commands } => {
+                let mut inverses = Vec::with_capacity(commands.len());
+                for command in commands {
+                    match self.apply(command) {
+                        Ok(result) => {
+                            created = created.or(result.node_id);
+                            inverses.push(result.inverse);
+                        }
+                        Err(e) => {
+                            self.rollback(inverses);
+                            return Err(e);
+                        }
+                    }
+                }
+                inverses.reverse();
+                Command::Batch { commands: inverses }
+            }
+        };
+        Ok(CommandResult {
+            inverse,
+            node_id: created,
+        })
+    }
+
+    /// Applies the inverses of commands that ran, most recent first.
+    pub(crate) fn rollback(&mut self, inverses: Vec<Command>) {
+        for inverse in inverses.into_iter().rev() {
+            let _ = self.apply(inverse);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_and_inverse() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let before = serde_json::to_value(&map.nodes).unwrap();
+
+        let script = [
+            Command::AddChild {
+                parent_id: a.clone(),
+                content: "B".to_string(),
+            },
+            Command::Rename {
+                node_id: a.clone(),
+                content: "Renamed".to_string(),
+            },
+            Command::SetNote {
+                node_id: a.clone(),
+                note: Some("Note".to_string()),
+            },
+            Command::SetIcons {
+                node_id: a.clone(),
+                icons: vec!["idea".to_string()],
+            },
+            Command::SetFolded {
+                node_id: a.clone(),
+                folded: true,
+            },
+        ];
+        let mut undo = Vec::new();
+        let mut added = None;
+        for command in script {
+            let result = map.apply(command).unwrap();
+            added = added.or(result.node_id);
+            undo.push(result.inverse);
+        }
+        let b = added.unwrap();
+        let result = map
+            .apply(Command::Move {
+                node_id: b.clone(),
+                parent_id: root_id.clone(),
+                position: 0,
+            })
+            .unwrap();
+        undo.push(result.inverse);
+        assert_eq!(map.nodes[&root_id].children, vec![b.clone(), a.clone()]);
+        assert_eq!(map.nodes[&a].content, "Renamed");
+
+        let mut redo = Vec::new();
+        while let Some(command) = undo.pop() {
+            redo.push(map.apply(command).unwrap().inverse);
+        }
+        assert!(!map.nodes.contains_key(&b));
+        assert!(map.trash.is_empty());
+        let mut after = serde_json::to_value(&map.nodes).unwrap();
+        for node in after.as_object_mut().unwrap().values_mut() {
+            for field in ["modified", "revision"] {
+                node[field] = before[node["id"].as_str().unwrap()][field].clone();
+            }
+        }
+        assert_eq!(after, before);
+
+        // Redoing restores the removed node under its old id.
+        while let Some(command) = redo.pop() {
+            map.apply(command).unwrap();
+        }
+        assert_eq!(map.nodes[&root_id].children, vec![b.clone(), a.clone()]);
+        assert!(map.nodes[&a].folded);
+
+        let json = serde_json::to_string(&Command::Remove { node_id: a }).unwrap();
+        let command: Command = serde_json::from_str(&json).unwrap();
+        assert!(map.apply(command).is_ok());
+        assert!(matches!(
+            map.apply(Command::Remove {
+                node_id: root_id.clone()
+            }),
+            Err(BrainCoreError::RootImmutable)
+        ));
+
+        // Stale links are refused rather than panicking.
+        let c = map.add_child(&root_id, "C").unwrap();
+        let move_c = || Command::Move {
+            node_id: c.clone(),
+            parent_id: root_id.clone(),
+            position: 0,
+        };
+        map.nodes.get_mut(&c).unwrap().parent = Some("gone".to_string());
+        assert!(matches!(
+            map.apply(move_c()),
+            Err(BrainCoreError::NodeNotFound(id)) if id == "gone"
+        ));
+        map.nodes.get_mut(&c).unwrap().parent = Some(b.clone());
+        assert!(matches!(
+            map.apply(move_c()),
+            Err(BrainCoreError::InvalidOperation(_))
+        ));
+    }
+}
@@ -0,0 +1,385 @@
+use crate::export::ExportOptions;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use ::csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use std::collections::HashMap;
+use std::io::Read;
+use uuid::Uuid;
+
+/// How a spreadsheet lays out the hierarchy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvShape {
+    /// One column per level, `Level1,Level2,Level3...`: each row is the
+    /// path from a top-level node down to one node. Rows sharing a prefix
+    /// share those nodes, and leading blank cells repeat the row above.
+    #[default]
+    PathColumns,
+    /// `id,parent_id,content,note` rows, with a blank parent for top-level
+    /// nodes. The note column is optional.
+    EdgeList,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    pub shape: CsvShape,
+    /// Field separator; `b'\t'` for TSV.
+    pub delimiter: u8,
+    /// Whether the first row holds column names. Exports write one,
+    /// imports skip it.
+    pub headers: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            shape: CsvShape::PathColumns,
+            delimiter: b',',
+            headers: true,
+        }
+    }
+}
+
+pub fn to_csv(map: &MindMap, options: &CsvOptions) -> Result<String, BrainCoreError> {
+    to_csv_with(map, options, &ExportOptions::default())
+}
+
+/// Writes the map in the shape `options` asks for. Path columns get one
+/// row per leaf, padded to the depth of the deepest one; the edge list
+/// gets one row per node, parents first, using the map's node ids.
+pub fn to_csv_with(
+    map: &MindMap,
+    options: &CsvOptions,
+    export: &ExportOptions,
+) -> Result<String, BrainCoreError> {
+    to_csv_monitored(map, options, export, &mut Monitor::default())
+}
+
+pub fn to_csv_monitored(
+    map: &MindMap,
+    options: &CsvOptions,
+    export: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    monitor.begin(Stage::WritingNodes, Some(export.exported_count(map, false)))?;
+    let mut rows: Vec<Vec<&str>> = Vec::new();
+    let mut stack: Vec<(&Node, usize)> = map
+        .nodes
+        .get(&map.root_id)
+        .map(|root| (root, 0))
+        .into_iter()
+        .collect();
+    // The contents from the root down to the current node.
+    let mut path: Vec<&str> = Vec::new();
+    while let Some((node, depth)) = stack.pop() {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        path.truncate(depth);
+        path.push(&node.content);
+        let children: Vec<&Node> = node
+            .children
+            .iter()
+            .filter_map(|id| map.nodes.get(id))
+            .filter(|c| export.includes(c))
+            .collect();
+        match options.shape {
+            CsvShape::PathColumns if children.is_empty() => rows.push(path.clone()),
+            CsvShape::PathColumns => {}
+            CsvShape::EdgeList => rows.push(vec![
+                &node.id,
+                node.parent.as_deref().unwrap_or_default(),
+                &node.content,
+                node.note.as_deref().unwrap_or_default(),
+            ]),
+        }
+        stack.extend(children.into_iter().rev().map(|c| (c, depth + 1)));
+    }
+    monitor.finish()?;
+
+    let header: Vec<String> = match options.shape {
+        CsvShape::PathColumns => {
+            let levels = rows.iter().map(Vec::len).max().unwrap_or(0);
+            for row in &mut rows {
+                row.resize(levels, "");
+            }
+            (1..=levels).map(|level| format!("Level{level}")).collect()
+        }
+        CsvShape::EdgeList => ["id", "parent_id", "content", "note"]
+            .map(String::from)
+            .to_vec(),
+    };
+
+    let mut writer = WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(Vec::new());
+    if options.headers {
+        writer
+            .write_record(&header)
+            .map_err(BrainCoreError::export)?;
+    }
+    for row in rows {
+        writer.write_record(row).map_err(BrainCoreError::export)?;
+    }
+    let data = writer.into_inner().map_err(BrainCoreError::export)?;
+    String::from_utf8(data).map_err(BrainCoreError::export)
+}
+
+pub fn from_csv(reader: impl Read, options: &CsvOptions) -> Result<MindMap, BrainCoreError> {
+    from_csv_with(reader, options, &ImportOptions::default())
+}
+
+/// Reads a hierarchy laid out as `options.shape`. A single top-level node
+/// becomes the root; otherwise the top level is gathered under a "CSV"
+/// root. Blank rows are skipped.
+pub fn from_csv_with(
+    reader: impl Read,
+    options: &CsvOptions,
+    import: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    from_csv_monitored(reader, options, import, &mut Monitor::default())
+}
+
+pub fn from_csv_monitored(
+    reader: impl Read,
+    options: &CsvOptions,
+    import: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(import.limits.max_nodes);
+    let mut reader = ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .has_headers(options.headers)
+        .flexible(true)
+        .from_reader(reader);
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(root_id.clone(), new_node(root_id.clone(), "CSV", None, now));
+
+    monitor.begin(Stage::BuildingNodes, None)?;
+    let records = reader.records();
+    match options.shape {
+        CsvShape::PathColumns => read_paths(&mut nodes, &root_id, records, now, monitor)?,
+        CsvShape::EdgeList => read_edges(&mut nodes, &root_id, records, now, monitor)?,
+    }
+    monitor.finish()?;
+
+    let root_id = match nodes[&root_id].children.as_slice() {
+        [only] => {
+            let only = only.clone();
+            nodes.remove(&root_id);
+            nodes.get_mut(&only).unwrap().parent = None;
+            only
+        }
+        _ => root_id,
+    };
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    import.apply(&mut map)?;
+    Ok(map)
+}
+
+fn read_paths(
+    nodes: &mut HashMap<String, Node>,
+    root_id: &str,
+    records: impl Iterator<Item = Result<StringRecord, ::csv::Error>>,
+    now: u64,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    // Ids along the previous row's path.
+    let mut path: Vec<String> = Vec::new();
+    for record in records {
+        if monitor.should_stop() {
+            break;
+        }
+        let record = record.map_err(parse_error)?;
+        let cells: Vec<&str> = record.iter().map(str::trim).collect();
+        let Some(first) = cells.iter().position(|c| !c.is_empty()) else {
+            continue;
+        };
+        path.truncate(first);
+        for cell in cells[first..].iter().take_while(|c| !c.is_empty()) {
+            let parent = path.last().map_or(root_id, String::as_str).to_string();
+            let existing = nodes[&parent]
+                .children
+                .iter()
+                .find(|id| nodes[*id].content == *cell)
+                .cloned();
+            let id = match existing {
+                Some(id) => id,
+                None => {
+                    let id = Uuid::new_v4().to_string();
+                    nodes.get_mut(&parent).unwrap().children.push(id.clone());
+                    nodes.insert(id.clone(), new_node(id.clone(), cell, Some(parent), now));
+                    monitor.tick();
+                    id
+                }
+            };
+            path.push(id);
+        }
+    }
+    Ok(())
+}
+
+fn read_edges(
+    nodes: &mut HashMap<String, Node>,
+    root_id: &str,
+    records: impl Iterator<Item = Result<StringRecord, ::csv::Error>>,
+    now: u64,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    // Node ids by the ids used in the file, and each node's parent there.
+    let mut ids: HashMap<String, String> = HashMap::new();
+    let mut edges: Vec<(String, String, Option<u64>)> = Vec::new();
+    for record in records {
+        if monitor.should_stop() {
+            // Parents further down were never read; the caller reports the
+            // cancellation.
+            return Ok(());
+        }
+        let record = record.map_err(parse_error)?;
+        let offset = record.position().map(|p| p.byte());
+        let cell = |i: usize| record.get(i).map_or("", str::trim);
+        if record.iter().all(|c| c.trim().is_empty()) {
+            continue;
+        }
+        let key = cell(0);
+        let error = |message: String| BrainCoreError::Parse {
+            format: ImportFormat::Csv,
+            offset: offset.map(|o| o as usize),
+            message,
+        };
+        if key.is_empty() {
+            return Err(error("Row has no id".to_string()));
+        }
+        let id = Uuid::new_v4().to_string();
+        if ids.insert(key.to_string(), id.clone()).is_some() {
+            return Err(error(format!("Duplicate id: {key}")));
+        }
+        let mut node = new_node(id.clone(), cell(2), None, now);
+        node.note = Some(cell(3).to_string()).filter(|n| !n.is_empty());
+        nodes.insert(id.clone(), node);
+        edges.push((id, cell(1).to_string(), offset));
+        monitor.tick();
+    }
+
+    for (id, parent_key, offset) in edges {
+        let parent = if parent_key.is_empty() {
+            root_id.to_string()
+        } else {
+            ids.get(&parent_key)
+                .cloned()
+                .ok_or_else(|| BrainCoreError::Parse {
+                    format: ImportFormat::Csv,
+                    offset: offset.map(|o| o as usize),
+                    message: format!("Unknown parent id: {parent_key}"),
+                })?
+        };
+        nodes.get_mut(&parent).unwrap().children.push(id.clone());
+        nodes.get_mut(&id).unwrap().parent = Some(parent);
+    }
+
+    let map = MindMap::from_nodes(std::mem::take(nodes), root_id.to_string());
+    let issues = map.validate();
+    *nodes = map.nodes;
+    if !issues.is_empty() {
+        return Err(BrainCoreError::parse(
+            ImportFormat::Csv,
+            "Parent ids form a loop",
+        ));
+    }
+    Ok(())
+}
+
+fn new_node(id: String, content: &str, parent: Option<String>, now: u64) -> Node {
+    Node {
+        id,
+        content: content.to_string(),
+        parent,
+        created: now,
+        modified: now,
+        ..Default::default()
+    }
+}
+
+fn parse_error(error: ::csv::Error) -> BrainCoreError {
+    BrainCoreError::Parse {
+        format: ImportFormat::Csv,
+        offset: error.position().map(|p| p.byte() as usize),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents<'a>(map: &'a MindMap, id: &str) -> Vec<&'a str> {
+        map.nodes[id]
+            .children
+            .iter()
+            .map(|c| map.nodes[c].content.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_path_columns() {
+        let text = "Level1,Level2,Level3\nPlan,Research,Papers\nPlan,Research,Talks\n,Build,\n\nPlan,\"Ship, then rest\"\n";
+        let map = from_csv(text.as_bytes(), &CsvOptions::default()).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "Plan");
+        assert_eq!(
+            contents(&map, &map.root_id),
+            ["Research", "Build", "Ship, then rest"]
+        );
+        assert_eq!(map.nodes.len(), 6);
+
+        let written = to_csv(&map, &CsvOptions::default()).unwrap();
+        assert_eq!(
+            written,
+            "Level1,Level2,Level3\nPlan,Research,Papers\nPlan,Research,Talks\nPlan,Build,\nPlan,\"Ship, then rest\",\n"
+        );
+        let options = CsvOptions {
+            delimiter: b'\t',
+            headers: false,
+            ..Default::default()
+        };
+        let tsv = to_csv(&map, &options).unwrap();
+        assert!(tsv.starts_with("Plan\tResearch\tPapers\n"));
+        assert_eq!(from_csv(tsv.as_bytes(), &options).unwrap().nodes.len(), 6);
+    }
+
+    #[test]
+    fn test_edge_list() {
+        let options = CsvOptions {
+            shape: CsvShape::EdgeList,
+            ..Default::default()
+        };
+        let text = "id,parent_id,content,note\n2,1,Child,Some note\n1,,Top\n3,1,Other\n4,,Second\n";
+        let map = from_csv(text.as_bytes(), &options).unwrap();
+        assert_eq!(map.nodes[&map.root_id].content, "CSV");
+        assert_eq!(contents(&map, &map.root_id), ["Top", "Second"]);
+        let top = map.nodes[&map.root_id].children[0].clone();
+        assert_eq!(contents(&map, &top), ["Child", "Other"]);
+        assert!(map.validate().is_empty());
+
+        let written = to_csv(&map, &options).unwrap();
+        let again = from_csv(written.as_bytes(), &options).unwrap();
+        assert_eq!(again.nodes.len(), 5);
+        assert_eq!(again.nodes[&again.root_id].content, "CSV");
+
+        let unknown = "id,parent_id,content\n1,9,Lost\n";
+        assert!(matches!(
+            from_csv(unknown.as_bytes(), &options),
+            Err(BrainCoreError::Parse {
+                format: ImportFormat::Csv,
+                offset: Some(_),
+                ..
+            })
+        ));
+        let looped = "id,parent_id,content\n1,2,A\n2,1,B\n";
+        assert!(from_csv(looped.as_bytes(), &options).is_err());
+    }
+}
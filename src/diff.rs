@@ -0,0 +1,139 @@
+use crate::{MindMap, Node};
+
+/// One difference between two versions of a map, as listed by
+/// [`MindMap::diff`]. Nodes are matched by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapChange {
+    /// `parent_id` is `None` for floating nodes.
+    Added {
+        node_id: String,
+        parent_id: Option<String>,
+    },
+    Removed {
+        node_id: String,
+    },
+    Renamed {
+        node_id: String,
+        old_content: String,
+        new_content: String,
+    },
+    /// The node is under another parent. Reordering siblings is not
+    /// reported.
+    Moved {
+        node_id: String,
+        old_parent_id: Option<String>,
+        new_parent_id: Option<String>,
+    },
+    IconsChanged {
+        node_id: String,
+        old_icons: Vec<String>,
+        new_icons: Vec<String>,
+    },
+}
+
+impl MindMap {
+    /// What changed from this map to `other`, say from the last saved
+    /// version to the current one. Every node of an added or removed
+    /// subtree is listed. Removals come first, in this map's tree order,
+    /// followed by the other changes in `other`'s tree order, so the list
+    /// reads like an outline.
+    pub fn diff(&self, other: &MindMap) -> Vec<MapChange> {
+        let mut changes: Vec<MapChange> = self
+            .tree_nodes()
+            .into_iter()
+            .filter(|n| !other.nodes.contains_key(&n.id))
+            .map(|n| MapChange::Removed {
+                node_id: n.id.clone(),
+            })
+            .collect();
+
+        for new in other.tree_nodes() {
+            let node_id = new.id.clone();
+            let Some(old) = self.nodes.get(&new.id) else {
+                changes.push(MapChange::Added {
+                    node_id,
+                    parent_id: new.parent.clone(),
+                });
+                continue;
+            };
+            if old.content != new.content {
+                changes.push(MapChange::Renamed {
+                    node_id: node_id.clone(),
+                    old_content: old.content.clone(),
+                    new_content: new.content.clone(),
+                });
+            }
+            if old.parent != new.parent {
+                changes.push(MapChange::Moved {
+                    node_id: node_id.clone(),
+                    old_parent_id: old.parent.clone(),
+                    new_parent_id: new.parent.clone(),
+                });
+            }
+            if old.icons != new.icons {
+                changes.push(MapChange::IconsChanged {
+                    node_id,
+                    old_icons: old.icons.clone(),
+                    new_icons: new.icons.clone(),
+                });
+            }
+        }
+        changes
+    }
+
+    /// Nodes of the main tree and then of the floating trees, depth-first.
+    pub(crate) fn tree_nodes(&self) -> Vec<&Node> {
+        self.tree_roots()
+            .filter_map(|id| self.nodes.get(id))
+            .flat_map(|root| std::iter::once(root).chain(self.descendants(&root.id)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut old = MindMap::new();
+        let root_id = old.root_id.clone();
+        let a = old.add_child(&root_id, "A").unwrap();
+        let b = old.add_child(&root_id, "B").unwrap();
+        let c = old.add_child(&b, "C").unwrap();
+        assert!(old.diff(&old).is_empty());
+
+        let mut new = old.clone();
+        new.change_node(&a, "Renamed").unwrap();
+        new.set_icons(&a, vec!["idea".to_string()]).unwrap();
+        new.move_node(&c, &a, 0).unwrap();
+        new.trash_node(&b).unwrap();
+        let d = new.add_child(&c, "D").unwrap();
+
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                MapChange::Removed { node_id: b.clone() },
+                MapChange::Renamed {
+                    node_id: a.clone(),
+                    old_content: "A".to_string(),
+                    new_content: "Renamed".to_string(),
+                },
+                MapChange::IconsChanged {
+                    node_id: a.clone(),
+                    old_icons: Vec::new(),
+                    new_icons: vec!["idea".to_string()],
+                },
+                MapChange::Moved {
+                    node_id: c.clone(),
+                    old_parent_id: Some(b),
+                    new_parent_id: Some(a),
+                },
+                MapChange::Added {
+                    node_id: d,
+                    parent_id: Some(c),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,164 @@
+use crate::export::ExportOptions;
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::escape::escape;
+use std::io::{Cursor, Write};
+use zip::ZipWriter;
+
+// Word documents as a report skeleton: the root is the title, each level
+// below it a heading level and notes the body text. The package is the
+// minimal WordprocessingML set Word, LibreOffice and Pages open: content
+// types, relationships, the document and the heading styles it uses.
+
+/// Word has nine heading styles; deeper nodes become indented paragraphs.
+const HEADING_LEVELS: usize = 9;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/></Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+const NAMESPACE: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+pub fn to_docx(map: &MindMap) -> Result<Vec<u8>, BrainCoreError> {
+    to_docx_with(map, &ExportOptions::default())
+}
+
+/// Writes the root as the document title and nodes at depth 1 to 9 as
+/// `Heading1` to `Heading9`, so Word's navigation pane shows the map's
+/// outline. Deeper nodes are indented paragraphs. A note follows its node
+/// as body paragraphs, one per blank-line-separated block.
+pub fn to_docx_with(map: &MindMap, options: &ExportOptions) -> Result<Vec<u8>, BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+
+    let mut body = String::new();
+    let mut stack: Vec<(&Node, usize)> = vec![(root, 0)];
+    while let Some((node, depth)) = stack.pop() {
+        let style = match depth {
+            0 => "Title".to_string(),
+            1..=HEADING_LEVELS => format!("Heading{depth}"),
+            _ => "Deep".to_string(),
+        };
+        body.push_str(&paragraph(Some(&style), &node.content));
+        for block in node.note.iter().flat_map(|n| n.split("\n\n")) {
+            if !block.trim().is_empty() {
+                body.push_str(&paragraph(None, block.trim()));
+            }
+        }
+        for child_id in node.children.iter().rev() {
+            if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:document xmlns:w=\"{NAMESPACE}\"><w:body>{body}<w:sectPr/></w:body></w:document>"
+    );
+
+    let mut buf = Vec::new();
+    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+    let zip_options = options.zip_options(zip::CompressionMethod::Deflated);
+    for (name, contents) in [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", PACKAGE_RELS.to_string()),
+        ("word/_rels/document.xml.rels", DOCUMENT_RELS.to_string()),
+        ("word/document.xml", document),
+        ("word/styles.xml", styles()),
+    ] {
+        zip.start_file(name, zip_options)?;
+        zip.write_all(contents.as_bytes())?;
+    }
+    zip.finish()?;
+    Ok(buf)
+}
+
+/// A paragraph in `style`, or the default body style. Line breaks within
+/// the text become `<w:br/>`.
+fn paragraph(style: Option<&str>, text: &str) -> String {
+    let mut xml = String::from("<w:p>");
+    if let Some(style) = style {
+        xml.push_str(&format!("<w:pPr><w:pStyle w:val=\"{style}\"/></w:pPr>"));
+    }
+    xml.push_str("<w:r>");
+    for (i, line) in text.split('\n').enumerate() {
+        if i > 0 {
+            xml.push_str("<w:br/>");
+        }
+        xml.push_str(&format!(
+            "<w:t xml:space=\"preserve\">{}</w:t>",
+            escape(line)
+        ));
+    }
+    xml.push_str("</w:r></w:p>");
+    xml
+}
+
+/// The title and heading styles, with outline levels so they show in the
+/// navigation pane, and the indented style for deeper nodes.
+fn styles() -> String {
+    let mut styles = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<w:styles xmlns:w=\"{NAMESPACE}\"><w:style w:type=\"paragraph\" w:default=\"1\" w:styleId=\"Normal\"><w:name w:val=\"Normal\"/><w:pPr><w:spacing w:after=\"120\"/></w:pPr></w:style><w:style w:type=\"paragraph\" w:styleId=\"Title\"><w:name w:val=\"Title\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:rPr><w:b/><w:sz w:val=\"56\"/></w:rPr></w:style>"
+    );
+    for level in 1..=HEADING_LEVELS {
+        // 16pt for the first level, down to 11pt, in half-points.
+        let size = 32usize.saturating_sub(2 * (level - 1)).max(22);
+        styles.push_str(&format!(
+            "<w:style w:type=\"paragraph\" w:styleId=\"Heading{level}\"><w:name w:val=\"heading {level}\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:pPr><w:keepNext/><w:spacing w:before=\"240\"/><w:outlineLvl w:val=\"{}\"/></w:pPr><w:rPr><w:b/><w:sz w:val=\"{size}\"/></w:rPr></w:style>",
+            level - 1
+        ));
+    }
+    styles.push_str("<w:style w:type=\"paragraph\" w:styleId=\"Deep\"><w:name w:val=\"Deep Topic\"/><w:basedOn w:val=\"Normal\"/><w:pPr><w:ind w:left=\"720\"/></w:pPr></w:style></w:styles>");
+    styles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_docx_outline() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.nodes.get_mut(&root_id).unwrap().content = "Report".to_string();
+        let intro = map.add_child(&root_id, "Intro & scope").unwrap();
+        map.set_note(&intro, "Why we care.\n\nWhat we cover.")
+            .unwrap();
+        let mut parent = intro;
+        for level in 2..=10 {
+            parent = map.add_child(&parent, &format!("Level {level}")).unwrap();
+        }
+
+        let data = to_docx(&map).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(data)).unwrap();
+        let mut document = String::new();
+        archive
+            .by_name("word/document.xml")
+            .unwrap()
+            .read_to_string(&mut document)
+            .unwrap();
+        assert!(
+            document.contains(
+                r#"<w:pStyle w:val="Title"/></w:pPr><w:r><w:t xml:space="preserve">Report"#
+            )
+        );
+        assert!(document.contains(r#"<w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t xml:space="preserve">Intro &amp; scope"#));
+        assert!(document.contains("<w:p><w:r><w:t xml:space=\"preserve\">What we cover."));
+        assert!(document.contains(
+            r#"<w:pStyle w:val="Heading9"/></w:pPr><w:r><w:t xml:space="preserve">Level 9"#
+        ));
+        assert!(document.contains(
+            r#"<w:pStyle w:val="Deep"/></w:pPr><w:r><w:t xml:space="preserve">Level 10"#
+        ));
+        for part in ["[Content_Types].xml", "_rels/.rels", "word/styles.xml"] {
+            assert!(archive.by_name(part).is_ok(), "{part} missing");
+        }
+    }
+}
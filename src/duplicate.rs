@@ -0,0 +1,149 @@
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+impl MindMap {
+    /// Copies the map with a fresh id for every node, trashed ones included,
+    /// and returns the copy with the old→new id mapping. Unlike `clone`, the
+    /// copy can live next to the original without its ids colliding.
+    pub fn duplicate(&self) -> (MindMap, HashMap<String, String>) {
+        let mut ids = HashMap::new();
+        let trashed = self.trash.iter().flat_map(|e| &e.nodes);
+        for id in self.nodes.keys().chain(trashed.map(|n| &n.id)) {
+            ids.insert(id.clone(), Uuid::new_v4().to_string());
+        }
+        let remap = |id: &String| ids.get(id).cloned().unwrap_or_else(|| id.clone());
+        let remap_node = |node: &Node| Node {
+            id: remap(&node.id),
+            children: node.children.iter().map(remap).collect(),
+            parent: node.parent.as_ref().map(remap),
+            ..node.clone()
+        };
+
+        let mut copy = self.clone();
+        copy.nodes = self
+            .nodes
+            .values()
+            .map(|node| (remap(&node.id), remap_node(node)))
+            .collect();
+        copy.root_id = remap(&self.root_id);
+        copy.selection.primary = remap(&self.selection.primary);
+        copy.selection.additional = self.selection.additional.iter().map(remap).collect();
+        for entry in &mut copy.trash {
+            entry.id = Uuid::new_v4().to_string();
+            entry.node_id = remap(&entry.node_id);
+            entry.parent_id = remap(&entry.parent_id);
+            entry.nodes = entry.nodes.iter().map(remap_node).collect();
+        }
+        for relationship in &mut copy.relationships {
+            relationship.id = Uuid::new_v4().to_string();
+            relationship.from = remap(&relationship.from);
+            relationship.to = remap(&relationship.to);
+        }
+        copy.floating_roots = self.floating_roots.iter().map(remap).collect();
+        for group in &mut copy.groups {
+            group.id = Uuid::new_v4().to_string();
+            group.node_id = remap(&group.node_id);
+        }
+        for summary in &mut copy.summaries {
+            summary.id = Uuid::new_v4().to_string();
+            summary.parent_id = remap(&summary.parent_id);
+            summary.first_child = remap(&summary.first_child);
+            summary.last_child = remap(&summary.last_child);
+        }
+        (copy, ids)
+    }
+
+    /// Copies a node and its descendants under `target_parent_id`, after
+    /// its existing children, and returns the id of the copy. The copies get
+    /// fresh ids; everything else is kept. The target may lie inside the
+    /// copied subtree.
+    pub fn clone_subtree(
+        &mut self,
+        node_id: &str,
+        target_parent_id: &str,
+    ) -> Result<String, BrainCoreError> {
+        let subtree = self.copy_subtree(node_id)?;
+        let roots = self.paste_subtree(target_parent_id, subtree)?;
+        Ok(roots.into_iter().next().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_remaps_ids() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = map.add_child(&root_id, "Child").unwrap();
+        let grandchild = map.add_child(&child, "Grandchild").unwrap();
+        let gone = map.add_child(&root_id, "Gone").unwrap();
+        map.trash_node(&gone).unwrap();
+        let floating = map.add_floating_node("Floating", 0.0, 0.0).unwrap();
+        map.add_group(&child, None).unwrap();
+        map.add_summary(&child, 0, 0, "Sum").unwrap();
+        map.tag(&grandchild, "urgent").unwrap();
+        map.archive(&grandchild).unwrap();
+        map.selection.primary = grandchild.clone();
+
+        let (mut copy, ids) = map.duplicate();
+        assert_eq!(ids.len(), 5);
+        assert_eq!(copy.floating_roots, vec![ids[&floating].clone()]);
+        assert_eq!(copy.groups[0].node_id, ids[&child]);
+        assert_ne!(copy.groups[0].id, map.groups[0].id);
+        let summary = &copy.summaries[0];
+        assert_eq!(summary.parent_id, ids[&child]);
+        assert_eq!(summary.first_child, ids[&grandchild]);
+        assert_eq!(summary.last_child, ids[&grandchild]);
+        assert!(copy.nodes.keys().all(|id| !map.nodes.contains_key(id)));
+        assert_eq!(copy.root_id, ids[&root_id]);
+        assert_eq!(copy.selection.primary, ids[&grandchild]);
+        assert_eq!(
+            copy.nodes[&copy.root_id].children,
+            vec![ids[&child].clone()]
+        );
+        let new_grandchild = &copy.nodes[&ids[&grandchild]];
+        assert_eq!(new_grandchild.content, "Grandchild");
+        assert_eq!(new_grandchild.parent.as_ref(), Some(&ids[&child]));
+        assert_eq!(new_grandchild.tags, vec!["urgent".to_string()]);
+        assert!(new_grandchild.archived);
+
+        let entry = copy.trash[0].id.clone();
+        assert_ne!(entry, map.trash[0].id);
+        copy.restore(&entry).unwrap();
+        assert_eq!(copy.nodes[&ids[&gone]].parent.as_ref(), Some(&copy.root_id));
+    }
+
+    #[test]
+    fn test_clone_subtree() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let a2 = map.add_child(&a1, "A2").unwrap();
+        map.tag(&a1, "draft").unwrap();
+        map.archive(&a2).unwrap();
+
+        let copy = map.clone_subtree(&a, &a1).unwrap();
+        assert_eq!(map.nodes.len(), 7);
+        assert_eq!(map.nodes[&a1].children[1], copy);
+        let node = &map.nodes[&copy];
+        assert_eq!(node.content, "A");
+        assert_eq!(node.parent.as_ref(), Some(&a1));
+        let child = &map.nodes[&node.children[0]];
+        assert_eq!(child.content, "A1");
+        assert_ne!(child.id, a1);
+        assert_eq!(child.parent.as_ref(), Some(&copy));
+        assert_eq!(child.tags, vec!["draft".to_string()]);
+        assert_eq!(map.nodes[&child.children[0]].content, "A2");
+        assert!(map.nodes[&child.children[0]].archived);
+        assert!(map.validate().is_empty());
+
+        assert!(matches!(
+            map.clone_subtree("missing", &root_id),
+            Err(BrainCoreError::NodeNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::events::MapEvent;
+use crate::timestamp::now_millis;
+use crate::validation::Change;
+use crate::{BrainCoreError, MindMap, Node};
+use uuid::Uuid;
+
+impl MindMap {
+    /// Appends a new node under `parent_id` and returns its id. The content
+    /// passes through the map's validators first.
+    pub fn add_child(&mut self, parent_id: &str, content: &str) -> Result<String, BrainCoreError> {
+        if !self.nodes.contains_key(parent_id) {
+            return Err(BrainCoreError::NodeNotFound(parent_id.to_string()));
+        }
+        let content = self.validate_content(
+            &Change {
+                map: self,
+                node_id: None,
+                parent_id: Some(parent_id),
+            },
+            content,
+        )?;
+        self.limits
+            .check_node_count(self.nodes.len() + 1)
+            .and_then(|_| self.limits.check_depth(self.depth(parent_id) + 1))
+            .and_then(|_| self.limits.check_content(&content))?;
+
+        let id = Uuid::new_v4().to_string();
+        let now = now_millis();
+        self.nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content,
+                parent: Some(parent_id.to_string()),
+                created: now,
+                modified: now,
+                created_by: self.author.clone(),
+                modified_by: self.author.clone(),
+                ..Default::default()
+            },
+        );
+        if let Some(parent) = self.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        self.bump_revision(parent_id);
+        self.emit(MapEvent::NodeAdded {
+            node_id: id.clone(),
+            parent_id: Some(parent_id.to_string()),
+        });
+        Ok(id)
+    }
+
+    /// Replaces a node's content after running it through the validators.
+    pub fn change_node(&mut self, node_id: &str, content: &str) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let content = self.validate_content(
+            &Change {
+                map: self,
+                node_id: Some(node_id),
+                parent_id: node.parent.as_deref(),
+            },
+            content,
+        )?;
+        self.limits.check_content(&content)?;
+
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.content != content {
+            node.content = content;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+
+    /// Replaces a node's icons, named as in FreeMind's builtin set.
+    pub fn set_icons(&mut self, node_id: &str, icons: Vec<String>) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.icons != icons {
+            node.icons = icons;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+use crate::html;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::timestamp::now_millis;
+use crate::timestamp::parse_iso8601;
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::Reader;
+use quick_xml::de::from_str;
+use quick_xml::events::{BytesStart, Event};
+use serde::Deserialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Evernote export structure. Only the parts that map onto nodes are read;
+// attachments and note attributes are skipped.
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename = "en-export")]
+pub struct EnexExport {
+    #[serde(rename = "note", default)]
+    pub notes: Vec<EnexNote>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct EnexNote {
+    #[serde(default)]
+    pub title: String,
+    /// ENML document, usually wrapped in CDATA.
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub created: Option<String>,
+    #[serde(default)]
+    pub updated: Option<String>,
+    #[serde(rename = "tag", default)]
+    pub tags: Vec<String>,
+}
+
+pub fn from_enex(xml: &str) -> Result<MindMap, BrainCoreError> {
+    from_enex_with(xml, &ImportOptions::default())
+}
+
+/// Each note becomes a branch under an "Evernote" root: the title is the
+/// node, list items in the body become (nested) children, the remaining
+/// text becomes the node's note and Evernote tags become node tags.
+pub fn from_enex_with(xml: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_enex_monitored(xml, options, &mut Monitor::default())
+}
+
+pub fn from_enex_monitored(
+    xml: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let export: EnexExport =
+        from_str(xml).map_err(|e| BrainCoreError::parse(ImportFormat::Evernote, e))?;
+
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: "Evernote".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    monitor.begin(Stage::BuildingNodes, Some(export.notes.len()))?;
+    for note in &export.notes {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let created = note
+            .created
+            .as_deref()
+            .and_then(parse_iso8601)
+            .unwrap_or(now);
+        let modified = note
+            .updated
+            .as_deref()
+            .and_then(parse_iso8601)
+            .unwrap_or(created);
+        let id = add_node(&mut nodes, &root_id, note.title.trim(), created, modified);
+        let text = read_enml(&note.content, &id, &mut nodes, created, modified)?;
+
+        let node = nodes
+            .get_mut(&id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(id.clone()))?;
+        node.note = Some(text).filter(|t| !t.is_empty());
+        node.tags = note.tags.iter().map(|t| t.trim().to_string()).collect();
+    }
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+fn add_node(
+    nodes: &mut HashMap<String, Node>,
+    parent_id: &str,
+    content: &str,
+    created: u64,
+    modified: u64,
+) -> String {
+    let id = Uuid::new_v4().to_string();
+    nodes.insert(
+        id.clone(),
+        Node {
+            id: id.clone(),
+            content: content.to_string(),
+            parent: Some(parent_id.to_string()),
+            created,
+            modified,
+            ..Default::default()
+        },
+    );
+    if let Some(parent) = nodes.get_mut(parent_id) {
+        parent.children.push(id.clone());
+    }
+    id
+}
+
+fn enml_error(error: impl std::fmt::Display) -> BrainCoreError {
+    BrainCoreError::parse(ImportFormat::Evernote, error)
+}
+
+/// Walks an ENML body, adding a child node for every list item under
+/// `note_id` and returning the text found outside lists.
+fn read_enml(
+    enml: &str,
+    note_id: &str,
+    nodes: &mut HashMap<String, Node>,
+    created: u64,
+    modified: u64,
+) -> Result<String, BrainCoreError> {
+    let mut reader = Reader::from_str(enml);
+    reader.config_mut().check_end_names = false;
+
+    let mut lines = vec![String::new()];
+    // Nodes owning the currently open lists, and the open list items.
+    let mut list_owners: Vec<String> = Vec::new();
+    let mut items: Vec<String> = Vec::new();
+
+    loop {
+        let mut text = String::new();
+        match reader.read_event().map_err(enml_error)? {
+            Event::Start(e) => match element_name(&e).as_str() {
+                "ul" | "ol" => {
+                    list_owners.push(items.last().map_or(note_id, |i| i.as_str()).to_string())
+                }
+                "li" => {
+                    let owner = list_owners.last().map_or(note_id, |o| o.as_str());
+                    items.push(add_node(nodes, owner, "", created, modified));
+                }
+                name if items.is_empty() && html::is_block(name) => break_line(&mut lines),
+                _ => {}
+            },
+            Event::End(e) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase();
+                match name.as_str() {
+                    "ul" | "ol" => {
+                        list_owners.pop();
+                    }
+                    "li" => {
+                        if let Some(node) = items.pop().and_then(|id| nodes.get_mut(&id)) {
+                            node.content = node.content.trim().to_string();
+                        }
+                    }
+                    name if items.is_empty() && html::is_block(name) => break_line(&mut lines),
+                    _ => {}
+                }
+            }
+            Event::Empty(e) => match element_name(&e).as_str() {
+                "br" if items.is_empty() => lines.push(String::new()),
+                "en-todo" => {
+                    let checked = e
+                        .try_get_attribute("checked")
+                        .ok()
+                        .flatten()
+                        .is_some_and(|a| a.value.as_ref() == b"true");
+                    if let Some(node) = items.last().and_then(|id| nodes.get_mut(id))
+                        && checked
+                    {
+                        node.icons.push("button_ok".to_string());
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(e) => text = e.decode().map_err(enml_error)?.into_owned(),
+            Event::CData(e) => text = e.decode().map_err(enml_error)?.into_owned(),
+            Event::GeneralRef(e) => {
+                let name = e.decode().map_err(enml_error)?;
+                match html::entity(&name) {
+                    Some(c) => text.push(c),
+                    None => text = format!("&{name};"),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+        match items.last().and_then(|id| nodes.get_mut(id)) {
+            Some(node) => html::push_collapsed(&mut node.content, &text),
+            None => html::push_collapsed(lines.last_mut().unwrap(), &text),
+        }
+    }
+
+    let lines: Vec<&str> = lines.iter().map(|l| l.trim_matches(' ')).collect();
+    Ok(lines.join("\n").trim_matches('\n').to_string())
+}
+
+fn element_name(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase()
+}
+
+fn break_line(lines: &mut Vec<String>) {
+    if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+        lines.push(String::new());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE en-export SYSTEM "http://xml.evernote.com/pub/evernote-export4.dtd">
+<en-export export-date="20240205T101500Z" application="Evernote" version="10.72.4">
+  <note>
+    <title>Trip planning</title>
+    <created>20240131T120000Z</created>
+    <updated>20240201T083000Z</updated>
+    <tag>travel</tag>
+    <tag>2024</tag>
+    <note-attributes><author>me</author></note-attributes>
+    <content><![CDATA[<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<!DOCTYPE en-note SYSTEM "http://xml.evernote.com/pub/enml2.dtd">
+<en-note><div>Leave on&nbsp;Friday.</div><div><br/></div><div>Budget &amp; bookings:</div>
+<ul><li><div>Flights</div><ul><li><en-todo checked="true"/>Outbound</li><li><en-todo checked="false"/>Return</li></ul></li><li>Hotel</li></ul></en-note>]]></content>
+  </note>
+  <note>
+    <title>Empty</title>
+    <content><![CDATA[<en-note/>]]></content>
+  </note>
+</en-export>"#;
+
+    #[test]
+    fn test_enex_import() {
+        let map = from_enex_with(SAMPLE, &ImportOptions::raw()).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.children.len(), 2);
+
+        let trip = &map.nodes[&root.children[0]];
+        assert_eq!(trip.content, "Trip planning");
+        assert_eq!(trip.tags, vec!["travel".to_string(), "2024".to_string()]);
+        assert_eq!(trip.created, 1_706_702_400_000);
+        assert_eq!(
+            trip.note.as_deref(),
+            Some("Leave on\u{a0}Friday.\n\nBudget & bookings:")
+        );
+
+        let items: Vec<&Node> = trip.children.iter().map(|id| &map.nodes[id]).collect();
+        assert_eq!(items[0].content, "Flights");
+        assert_eq!(items[1].content, "Hotel");
+        let subitems: Vec<&Node> = items[0].children.iter().map(|id| &map.nodes[id]).collect();
+        assert_eq!(subitems[0].content, "Outbound");
+        assert_eq!(subitems[0].icons, vec!["button_ok".to_string()]);
+        assert!(subitems[1].icons.is_empty());
+
+        let empty = &map.nodes[&root.children[1]];
+        assert_eq!(empty.note, None);
+        assert!(empty.children.is_empty());
+    }
+}
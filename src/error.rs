@@ -0,0 +1,197 @@
+use crate::import::ImportFormat;
+use crate::limits::LimitError;
+use std::fmt;
+use std::io;
+use std::str::Utf8Error;
+use zip::result::ZipError;
+
+/// Error type of every fallible operation in the crate.
+#[derive(Debug)]
+pub enum BrainCoreError {
+    /// No node with this id exists in the map.
+    NodeNotFound(String),
+    /// The operation cannot be applied to the root node.
+    RootImmutable,
+    TrashEntryNotFound(String),
+    RelationshipNotFound(String),
+    GroupNotFound(String),
+    SummaryNotFound(String),
+    VersionNotFound(String),
+    /// The map is not in a state that allows the operation, e.g. stopping a
+    /// timer that is not running.
+    InvalidOperation(String),
+    /// A validator rejected the content.
+    Validation(String),
+    Limit(LimitError),
+    /// The input could not be read as `format`. `offset` is the byte offset
+    /// of the problem, when known, in the document that was being parsed;
+    /// for zip packages that is the entry inside the archive.
+    Parse {
+        format: ImportFormat,
+        offset: Option<usize>,
+        message: String,
+    },
+    Zip(ZipError),
+    Io(io::Error),
+    #[cfg(feature = "sqlite-store")]
+    Sqlite(rusqlite::Error),
+    /// An export could not be serialized.
+    Export(String),
+    /// The operation was cancelled through its monitor.
+    Cancelled,
+}
+
+impl BrainCoreError {
+    pub(crate) fn parse(format: ImportFormat, error: impl fmt::Display) -> Self {
+        BrainCoreError::Parse {
+            format,
+            offset: None,
+            message: error.to_string(),
+        }
+    }
+
+    /// A JSON error, with serde_json's line and column turned into a byte
+    /// offset into `input`.
+    pub(crate) fn json(format: ImportFormat, input: &str, error: serde_json::Error) -> Self {
+        let offset = (error.line() > 0).then(|| {
+            let line_start: usize = input
+                .split_inclusive('\n')
+                .take(error.line() - 1)
+                .map(str::len)
+                .sum();
+            (line_start + error.column().saturating_sub(1)).min(input.len())
+        });
+        BrainCoreError::Parse {
+            format,
+            offset,
+            message: error.to_string(),
+        }
+    }
+
+    pub(crate) fn utf8(format: ImportFormat, error: Utf8Error) -> Self {
+        BrainCoreError::Parse {
+            format,
+            offset: Some(error.valid_up_to()),
+            message: error.to_string(),
+        }
+    }
+
+    pub(crate) fn export(error: impl fmt::Display) -> Self {
+        BrainCoreError::Export(error.to_string())
+    }
+}
+
+impl fmt::Display for BrainCoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BrainCoreError::NodeNotFound(id) => write!(f, "Node not found: {id}"),
+            BrainCoreError::RootImmutable => write!(f, "Operation not allowed on the root node"),
+            BrainCoreError::TrashEntryNotFound(id) => write!(f, "Trash entry not found: {id}"),
+            BrainCoreError::RelationshipNotFound(id) => write!(f, "Relationship not found: {id}"),
+            BrainCoreError::GroupNotFound(id) => write!(f, "Group not found: {id}"),
+            BrainCoreError::SummaryNotFound(id) => write!(f, "Summary not found: {id}"),
+            BrainCoreError::VersionNotFound(id) => write!(f, "Version not found: {id}"),
+            BrainCoreError::InvalidOperation(message) | BrainCoreError::Validation(message) => {
+                write!(f, "{message}")
+            }
+            BrainCoreError::Limit(error) => write!(f, "{error}"),
+            BrainCoreError::Parse {
+                format,
+                offset,
+                message,
+            } => {
+                write!(f, "Invalid {} input", format.name())?;
+                if let Some(offset) = offset {
+                    write!(f, " at byte {offset}")?;
+                }
+                write!(f, ": {message}")
+            }
+            BrainCoreError::Zip(error) => write!(f, "Zip error: {error}"),
+            BrainCoreError::Io(error) => write!(f, "I/O error: {error}"),
+            #[cfg(feature = "sqlite-store")]
+            BrainCoreError::Sqlite(error) => write!(f, "SQLite error: {error}"),
+            BrainCoreError::Export(message) => write!(f, "Export failed: {message}"),
+            BrainCoreError::Cancelled => write!(f, "Operation cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for BrainCoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BrainCoreError::Limit(error) => Some(error),
+            BrainCoreError::Zip(error) => Some(error),
+            BrainCoreError::Io(error) => Some(error),
+            #[cfg(feature = "sqlite-store")]
+            BrainCoreError::Sqlite(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<LimitError> for BrainCoreError {
+    fn from(error: LimitError) -> Self {
+        BrainCoreError::Limit(error)
+    }
+}
+
+impl From<ZipError> for BrainCoreError {
+    fn from(error: ZipError) -> Self {
+        BrainCoreError::Zip(error)
+    }
+}
+
+impl From<io::Error> for BrainCoreError {
+    fn from(error: io::Error) -> Self {
+        BrainCoreError::Io(error)
+    }
+}
+
+#[cfg(feature = "sqlite-store")]
+impl From<rusqlite::Error> for BrainCoreError {
+    fn from(error: rusqlite::Error) -> Self {
+        BrainCoreError::Sqlite(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MindMap;
+    use crate::{trello, xmind};
+
+    #[test]
+    fn test_errors_are_typed() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        assert!(matches!(
+            map.change_node("missing", "x"),
+            Err(BrainCoreError::NodeNotFound(id)) if id == "missing"
+        ));
+        assert!(matches!(
+            map.trash_node(&root_id),
+            Err(BrainCoreError::RootImmutable)
+        ));
+        assert!(matches!(
+            xmind::from_xmind(b"not a zip"),
+            Err(BrainCoreError::Zip(_))
+        ));
+
+        let json = "{\n  \"name\": \"Board\",\n  \"lists\": [,]\n}";
+        let error = trello::from_trello(json).unwrap_err();
+        assert!(matches!(
+            error,
+            BrainCoreError::Parse {
+                format: ImportFormat::Trello,
+                offset: Some(33),
+                ..
+            }
+        ));
+        assert_eq!(&json[33..34], ",");
+        assert!(
+            error
+                .to_string()
+                .starts_with("Invalid Trello input at byte 33: ")
+        );
+    }
+}
@@ -0,0 +1,183 @@
+use crate::MindMap;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
+
+/// A change made to a map, as sent to its [`Observer`]s.
+///
+/// Every node whose revision is bumped gets a `NodeChanged`, so adding a
+/// child also reports its parent as changed. Structural events follow the
+/// `NodeChanged` events of the same operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapEvent {
+    /// A node, or the top of a subtree that was pasted or restored, was
+    /// inserted. `parent_id` is `None` for floating nodes.
+    NodeAdded {
+        node_id: String,
+        parent_id: Option<String>,
+    },
+    /// A node left the map together with its subtree, either for the
+    /// trash or for good.
+    NodeRemoved {
+        node_id: String,
+    },
+    NodeChanged {
+        node_id: String,
+    },
+    /// A node was attached to another parent, or moved to another position
+    /// under the same one.
+    NodeMoved {
+        node_id: String,
+        old_parent_id: String,
+        new_parent_id: String,
+    },
+    SelectionChanged {
+        from: String,
+        to: String,
+    },
+    /// The events of a [`MindMap::transaction`], in the order they
+    /// happened, sent together once it has finished.
+    Batch {
+        events: Vec<MapEvent>,
+    },
+}
+
+impl MapEvent {
+    /// The events of a `Batch`, or this event on its own.
+    pub fn events(&self) -> &[MapEvent] {
+        match self {
+            MapEvent::Batch { events } => events,
+            event => std::slice::from_ref(event),
+        }
+    }
+}
+
+/// Receives the [`MapEvent`]s of a map. Closures taking `&MapEvent` and the
+/// sending half of a channel implement it; a channel whose receiver is gone
+/// drops events silently.
+pub trait Observer: Send + Sync {
+    fn notify(&self, event: &MapEvent);
+}
+
+impl<F> Observer for F
+where
+    F: Fn(&MapEvent) + Send + Sync,
+{
+    fn notify(&self, event: &MapEvent) {
+        self(event)
+    }
+}
+
+impl Observer for Sender<MapEvent> {
+    fn notify(&self, event: &MapEvent) {
+        let _ = self.send(event.clone());
+    }
+}
+
+/// The observers registered on a map. Like validators they are
+/// configuration: skipped by serde and shared by clones of the map.
+#[derive(Clone, Default)]
+pub struct Observers(Vec<Arc<dyn Observer>>);
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Observers({})", self.0.len())
+    }
+}
+
+impl MindMap {
+    /// Registers an observer. Observers are called synchronously, in
+    /// registration order, once the change has been made.
+    pub fn add_observer(&mut self, observer: impl Observer + 'static) {
+        self.observers.0.push(Arc::new(observer));
+    }
+
+    pub fn clear_observers(&mut self) {
+        self.observers.0.clear();
+    }
+
+    pub(crate) fn emit(&self, event: MapEvent) {
+        for observer in &self.observers.0 {
+            observer.notify(&event);
+        }
+    }
+
+    /// Moves the selection to a single node, reporting it if the primary
+    /// node changed. Only records history when called through
+    /// [`MindMap::select`] and its relatives.
+    pub(crate) fn set_selected(&mut self, node_id: String) {
+        if node_id != self.selection.primary {
+            self.selection.additional.clear();
+            let from = std::mem::replace(&mut self.selection.primary, node_id);
+            let to = self.selection.primary.clone();
+            self.emit(MapEvent::SelectionChanged { from, to });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_observers() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        map.add_observer(move |event: &MapEvent| log.lock().unwrap().push(event.clone()));
+        let (sender, receiver) = channel();
+        map.add_observer(sender);
+
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.move_node(&b, &a, 0).unwrap();
+        map.select(&b).unwrap();
+        map.trash_node(&a).unwrap();
+
+        let changed = |id: &String| MapEvent::NodeChanged {
+            node_id: id.clone(),
+        };
+        let events = seen.lock().unwrap().clone();
+        assert_eq!(
+            events,
+            vec![
+                changed(&root_id),
+                MapEvent::NodeAdded {
+                    node_id: a.clone(),
+                    parent_id: Some(root_id.clone()),
+                },
+                changed(&root_id),
+                MapEvent::NodeAdded {
+                    node_id: b.clone(),
+                    parent_id: Some(root_id.clone()),
+                },
+                changed(&b),
+                changed(&root_id),
+                changed(&a),
+                MapEvent::NodeMoved {
+                    node_id: b.clone(),
+                    old_parent_id: root_id.clone(),
+                    new_parent_id: a.clone(),
+                },
+                MapEvent::SelectionChanged {
+                    from: root_id.clone(),
+                    to: b.clone(),
+                },
+                MapEvent::SelectionChanged {
+                    from: b,
+                    to: root_id.clone(),
+                },
+                changed(&root_id),
+                MapEvent::NodeRemoved { node_id: a },
+            ]
+        );
+        assert_eq!(receiver.try_iter().collect::<Vec<_>>(), events);
+
+        map.clear_observers();
+        map.add_child(&root_id, "C").unwrap();
+        assert_eq!(seen.lock().unwrap().len(), events.len());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::se::Serializer;
+use serde::Serialize;
+use std::{fmt, io};
+use zip::CompressionMethod;
+use zip::write::SimpleFileOptions;
+
+/// Options shared by all exporters. `Default` reproduces the historical
+/// output of the plain `to_*` functions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExportOptions {
+    /// Guarantee byte-identical output for identical maps: identifiers the
+    /// exporter would otherwise generate are derived from the map, and zip
+    /// entries get a fixed timestamp.
+    pub canonical: bool,
+    /// Omit per-node created/modified timestamps where the format allows it.
+    pub suppress_timestamps: bool,
+    /// Export archived nodes and their subtrees, which are left out by
+    /// default.
+    pub include_archived: bool,
+    /// Indent nested elements by this many spaces, one per line, so that
+    /// exported files diff well. `None` writes the document on one line.
+    /// XMind's JSON documents are pretty-printed the same way.
+    pub indent: Option<usize>,
+}
+
+impl ExportOptions {
+    /// Canonical output with timestamps suppressed: suitable for committing
+    /// exported files to version control.
+    pub fn canonical() -> Self {
+        Self {
+            canonical: true,
+            suppress_timestamps: true,
+            include_archived: false,
+            indent: None,
+        }
+    }
+
+    /// Whether `node` (and with it, its subtree) belongs in the output.
+    pub(crate) fn includes(&self, node: &Node) -> bool {
+        self.include_archived || !node.archived
+    }
+
+    /// The floating nodes whose trees belong in the output, for formats
+    /// that can hold detached topics.
+    pub(crate) fn exported_floating<'a>(&self, map: &'a MindMap) -> Vec<&'a Node> {
+        map.floating_roots
+            .iter()
+            .filter_map(|id| map.nodes.get(id))
+            .filter(|n| self.includes(n))
+            .collect()
+    }
+
+    /// Number of nodes an exporter will write, for progress totals. Trees
+    /// under floating nodes count only when `floating` is set.
+    pub(crate) fn exported_count(&self, map: &MindMap, floating: bool) -> usize {
+        let mut count = 0;
+        let mut stack = vec![&map.root_id];
+        if floating {
+            stack.extend(self.exported_floating(map).into_iter().map(|n| &n.id));
+        }
+        while let Some(id) = stack.pop() {
+            if let Some(node) = map.nodes.get(id) {
+                count += 1;
+                stack.extend(
+                    node.children
+                        .iter()
+                        .filter(|c| map.nodes.get(*c).is_some_and(|c| self.includes(c))),
+                );
+            }
+        }
+        count
+    }
+
+    pub(crate) fn write_xml<T: Serialize, W: fmt::Write>(
+        &self,
+        value: &T,
+        writer: &mut W,
+    ) -> Result<(), BrainCoreError> {
+        self.write_xml_as(value, None, writer)
+    }
+
+    /// Like [`write_xml`](Self::write_xml), naming the root element; needed
+    /// when the root has a flattened field and so serializes as a map.
+    pub(crate) fn write_xml_as<T: Serialize, W: fmt::Write>(
+        &self,
+        value: &T,
+        root: Option<&str>,
+        writer: &mut W,
+    ) -> Result<(), BrainCoreError> {
+        let mut serializer = Serializer::with_root(writer, root).map_err(BrainCoreError::export)?;
+        if let Some(width) = self.indent {
+            serializer.indent(' ', width);
+        }
+        value
+            .serialize(serializer)
+            .map_err(BrainCoreError::export)?;
+        Ok(())
+    }
+
+    pub(crate) fn write_json<T: Serialize, W: io::Write>(
+        &self,
+        value: &T,
+        writer: W,
+    ) -> Result<(), BrainCoreError> {
+        let Some(width) = self.indent else {
+            return serde_json::to_writer(writer, value).map_err(BrainCoreError::export);
+        };
+        let indent = " ".repeat(width);
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(writer, formatter);
+        value
+            .serialize(&mut serializer)
+            .map_err(BrainCoreError::export)
+    }
+
+    pub(crate) fn zip_options(&self, method: CompressionMethod) -> SimpleFileOptions {
+        let options = SimpleFileOptions::default().compression_method(method);
+        if self.canonical {
+            options.last_modified_time(zip::DateTime::default())
+        } else {
+            options
+        }
+    }
+}
+
+/// Lets the XML serializer, which writes to `fmt::Write`, stream into an
+/// `io::Write` without losing the underlying I/O error.
+pub(crate) struct TextWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> TextWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self { inner, error: None }
+    }
+
+    /// Replaces the serializer's opaque formatting error with the I/O error
+    /// that caused it, if any.
+    pub(crate) fn check(
+        &mut self,
+        result: Result<(), BrainCoreError>,
+    ) -> Result<(), BrainCoreError> {
+        match self.error.take() {
+            Some(error) => Err(error.into()),
+            None => result,
+        }
+    }
+}
+
+impl<W: io::Write> fmt::Write for TextWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{GenerateSpec, generate};
+    use crate::{mindnode, mmap, opml, smmx, storage, xmind};
+
+    #[test]
+    fn test_canonical_zip_exports_are_byte_identical() {
+        let map = generate(3, &GenerateSpec::default());
+        let options = ExportOptions::canonical();
+
+        // The same nodes inserted in another order, into a map with
+        // another capacity, and thus another iteration order.
+        let mut ids: Vec<&String> = map.nodes.keys().collect();
+        ids.sort();
+        ids.reverse();
+        let mut nodes = std::collections::HashMap::with_capacity(1000);
+        for id in ids {
+            nodes.insert(id.clone(), map.nodes[id].clone());
+        }
+        let mut reordered = map.clone();
+        reordered.nodes = nodes;
+        assert!(map.nodes.keys().ne(reordered.nodes.keys()));
+
+        assert_eq!(
+            xmind::to_xmind_with(&map, &options).unwrap(),
+            xmind::to_xmind_with(&reordered, &options).unwrap()
+        );
+        assert_eq!(
+            mmap::to_mmap_with(&map, &options).unwrap(),
+            mmap::to_mmap_with(&reordered, &options).unwrap()
+        );
+        assert_eq!(
+            mindnode::to_mindnode_with(&map, &options).unwrap(),
+            mindnode::to_mindnode_with(&reordered, &options).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_indented_exports_round_trip() {
+        let mut map = generate(5, &GenerateSpec::default());
+        let root_id = map.root_id.clone();
+        map.nodes.get_mut(&root_id).unwrap().content = "Two\nlines".to_string();
+        let options = ExportOptions {
+            indent: Some(2),
+            ..Default::default()
+        };
+
+        let xml = storage::to_xml_with(&map, &options).unwrap();
+        assert!(xml.lines().count() > map.nodes.len());
+        assert!(xml.contains("\n  <node "));
+        let loaded = storage::from_xml(&xml).unwrap();
+        assert_eq!(loaded.nodes[&root_id].content, "Two\nlines");
+        assert_eq!(
+            loaded.fingerprint(),
+            storage::from_xml(&storage::to_xml(&map).unwrap())
+                .unwrap()
+                .fingerprint()
+        );
+
+        let opml = opml::to_opml_with(&map, &options).unwrap();
+        assert_eq!(opml::from_opml(&opml).unwrap().nodes.len(), map.nodes.len());
+        let smmx = smmx::to_smmx_with(&map, &options).unwrap();
+        assert_eq!(smmx::from_smmx(&smmx).unwrap().nodes.len(), map.nodes.len());
+        for data in [
+            mmap::from_mmap(&mmap::to_mmap_with(&map, &options).unwrap()),
+            mindnode::from_mindnode(&mindnode::to_mindnode_with(&map, &options).unwrap()),
+            xmind::from_xmind(&xmind::to_xmind_with(&map, &options).unwrap()),
+        ] {
+            assert_eq!(data.unwrap().nodes.len(), map.nodes.len());
+        }
+    }
+
+    #[test]
+    fn test_streaming_writers_match_buffered_exports() {
+        let map = generate(8, &GenerateSpec::default());
+        let options = ExportOptions::canonical();
+
+        let mut xml = Vec::new();
+        storage::write_xml_with(&map, &mut xml, &options).unwrap();
+        assert_eq!(
+            xml,
+            storage::to_xml_with(&map, &options).unwrap().into_bytes()
+        );
+        let mut opml = Vec::new();
+        opml::write_opml(&map, &mut opml).unwrap();
+        assert_eq!(opml, opml::to_opml(&map).unwrap().into_bytes());
+        let mut smmx = Vec::new();
+        smmx::write_smmx(&map, &mut smmx).unwrap();
+        assert_eq!(smmx, smmx::to_smmx(&map).unwrap().into_bytes());
+
+        // Streamed archives use data descriptors, so compare what reads back.
+        let expected = map.fingerprint();
+        let mut data = Vec::new();
+        xmind::write_xmind(&map, &mut data).unwrap();
+        assert_eq!(xmind::from_xmind(&data).unwrap().fingerprint(), expected);
+        data.clear();
+        mmap::write_mmap(&map, &mut data).unwrap();
+        let loaded = mmap::from_mmap(&data).unwrap();
+        assert_eq!(loaded.nodes.len(), map.nodes.len());
+        data.clear();
+        mindnode::write_mindnode(&map, &mut data).unwrap();
+        let loaded = mindnode::from_mindnode(&data).unwrap();
+        assert_eq!(loaded.nodes.len(), map.nodes.len());
+    }
+}
@@ -0,0 +1,277 @@
+use crate::{MindMap, Node};
+use std::collections::HashMap;
+
+// 64-bit FNV-1a. Unlike std's DefaultHasher its output is fixed forever,
+// so fingerprints can be persisted and compared across builds.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+struct Fnv64(u64);
+
+impl Fnv64 {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    // Length-prefixed so that ("ab", "c") and ("a", "bc") hash differently.
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+
+    fn opt_str(&mut self, value: Option<&str>) {
+        match value {
+            Some(value) => {
+                self.u64(1);
+                self.str(value);
+            }
+            None => self.u64(0),
+        }
+    }
+
+    fn opt_u64(&mut self, value: Option<u64>) {
+        self.u64(value.map_or(0, |v| v.saturating_add(1)));
+    }
+}
+
+/// FNV-1a of `bytes`, stable across builds like the fingerprint.
+pub(crate) fn fnv64(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv64(FNV_OFFSET);
+    hasher.bytes(bytes);
+    hasher.0
+}
+
+impl MindMap {
+    /// Stable hash of the map's tree shape and everything saved with its
+    /// nodes: content, notes, links, styles, attributes, tasks, attached
+    /// files and the like, along with the floating trees, relationships,
+    /// groups, summaries and the elements kept from an imported FreeMind
+    /// or Freeplane map.
+    ///
+    /// Node IDs, positions, timestamps, authors, the selection and the
+    /// trash are ignored, so two imports of the same file fingerprint
+    /// identically even though importers may assign fresh IDs. Child order
+    /// is significant; the order relationships, groups and summaries are
+    /// listed in is not.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv64(FNV_OFFSET);
+        // Nodes are numbered in pre-order, so links between them can be
+        // hashed without their ids.
+        let mut index: HashMap<&str, u64> = HashMap::new();
+        hasher.u64(self.floating_roots.len() as u64);
+        for top in std::iter::once(&self.root_id).chain(&self.floating_roots) {
+            let mut stack: Vec<&Node> = self.nodes.get(top).into_iter().collect();
+            while let Some(node) = stack.pop() {
+                let children: Vec<&Node> = node
+                    .children
+                    .iter()
+                    .filter_map(|id| self.nodes.get(id))
+                    .collect();
+                index.insert(&node.id, index.len() as u64);
+                hash_node(&mut hasher, node);
+                // Pre-order plus child counts pins down the tree shape.
+                hasher.u64(children.len() as u64);
+                stack.extend(children.into_iter().rev());
+            }
+        }
+
+        let at = |id: &str| index.get(id).copied().unwrap_or(u64::MAX);
+        let mut relationships: Vec<_> = self
+            .relationships
+            .iter()
+            .map(|r| (at(&r.from), at(&r.to), r.label.as_deref()))
+            .collect();
+        relationships.sort();
+        hasher.u64(relationships.len() as u64);
+        for (from, to, label) in relationships {
+            hasher.u64(from);
+            hasher.u64(to);
+            hasher.opt_str(label);
+        }
+        let mut groups: Vec<_> = self
+            .groups
+            .iter()
+            .map(|g| {
+                (
+                    at(&g.node_id),
+                    g.title.as_deref(),
+                    g.style.fill_color.as_deref(),
+                    g.style.border_color.as_deref(),
+                )
+            })
+            .collect();
+        groups.sort();
+        hasher.u64(groups.len() as u64);
+        for (node, title, fill, border) in groups {
+            hasher.u64(node);
+            hasher.opt_str(title);
+            hasher.opt_str(fill);
+            hasher.opt_str(border);
+        }
+        let mut summaries: Vec<_> = self
+            .summaries
+            .iter()
+            .map(|s| {
+                (
+                    at(&s.parent_id),
+                    at(&s.first_child),
+                    at(&s.last_child),
+                    s.content.as_str(),
+                )
+            })
+            .collect();
+        summaries.sort();
+        hasher.u64(summaries.len() as u64);
+        for (parent, first, last, content) in summaries {
+            hasher.u64(parent);
+            hasher.u64(first);
+            hasher.u64(last);
+            hasher.str(content);
+        }
+        hasher.opt_str(self.format_version.as_deref());
+        hasher.opt_str(self.passthrough.as_deref());
+        hasher.0
+    }
+}
+
+fn hash_node(hasher: &mut Fnv64, node: &Node) {
+    hasher.str(&node.content);
+    hasher.u64(node.icons.len() as u64);
+    for icon in &node.icons {
+        hasher.str(icon);
+    }
+    hasher.u64(node.archived as u64);
+    hasher.u64(node.folded as u64);
+    hasher.opt_str(node.note.as_deref());
+    hasher.opt_str(node.link.as_deref());
+    hasher.opt_str(node.details.as_deref());
+    hasher.u64(node.attributes.len() as u64);
+    for (name, value) in &node.attributes {
+        hasher.str(name);
+        hasher.str(value);
+    }
+    hasher.u64(node.tags.len() as u64);
+    for tag in &node.tags {
+        hasher.str(tag);
+    }
+    let style = &node.style;
+    hasher.opt_str(style.background_color.as_deref());
+    hasher.opt_str(style.text_color.as_deref());
+    hasher.opt_u64(style.font_size.map(u64::from));
+    hasher.u64(style.bold as u64);
+    hasher.u64(style.italic as u64);
+    hasher.opt_str(style.edge_color.as_deref());
+    hasher.opt_u64(style.edge_width.map(u64::from));
+    match &node.task {
+        Some(task) => {
+            hasher.u64(1);
+            hasher.u64(task.done as u64);
+            hasher.u64(task.progress_pct as u64);
+            hasher.opt_u64(task.due);
+            hasher.opt_u64(task.priority.map(u64::from));
+        }
+        None => hasher.u64(0),
+    }
+    hasher.u64(node.time_entries.len() as u64);
+    for entry in &node.time_entries {
+        hasher.u64(entry.start);
+        hasher.opt_u64(entry.end);
+    }
+    // File references name their contents.
+    hasher.u64(node.attachments.len() as u64);
+    for attachment in &node.attachments {
+        hasher.str(&attachment.name);
+        hasher.str(&attachment.mime);
+        hasher.str(&attachment.data_ref);
+    }
+    hasher.opt_str(node.passthrough.as_deref());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::{GenerateSpec, generate};
+    use crate::{opml, storage};
+
+    #[test]
+    fn test_fingerprint_ignores_volatile_fields() {
+        let map = generate(11, &GenerateSpec::default());
+        let fingerprint = map.fingerprint();
+
+        // OPML import assigns fresh IDs and timestamps.
+        let reimported = opml::from_opml(&opml::to_opml(&map).unwrap()).unwrap();
+        let mut stripped = map.clone();
+        for node in stripped.nodes.values_mut() {
+            node.icons.clear();
+        }
+        assert_eq!(stripped.fingerprint(), reimported.fingerprint());
+
+        let mut moved = map.clone();
+        for node in moved.nodes.values_mut() {
+            node.x += 10.0;
+            node.modified += 1;
+        }
+        assert_eq!(moved.fingerprint(), fingerprint);
+
+        let mut renamed = storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap();
+        assert_eq!(renamed.fingerprint(), fingerprint);
+        let root_id = renamed.root_id.clone();
+        renamed.nodes.get_mut(&root_id).unwrap().content.push('!');
+        assert_ne!(renamed.fingerprint(), fingerprint);
+
+        let mut reordered = map.clone();
+        let root = reordered.nodes.get_mut(&reordered.root_id).unwrap();
+        if root.children.len() > 1 {
+            root.children.swap(0, 1);
+            assert_ne!(reordered.fingerprint(), fingerprint);
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_covers_saved_fields() {
+        let map = generate(5, &GenerateSpec::default());
+        let fingerprint = map.fingerprint();
+        let root_id = map.root_id.clone();
+        let child = map.nodes[&root_id].children[0].clone();
+        let edits: [fn(&mut crate::MindMap, &str, &str); 8] = [
+            |map, _, child| map.set_link(child, "https://example.com").unwrap(),
+            |map, _, child| map.update_style(child, |s| s.bold = !s.bold).unwrap(),
+            |map, _, child| map.nodes.get_mut(child).unwrap().folded ^= true,
+            |map, _, child| {
+                map.attach(child, "a.txt", b"a".to_vec()).unwrap();
+            },
+            |map, root, child| {
+                map.add_relationship(root, child, None).unwrap();
+            },
+            |map, _, child| {
+                map.add_group(child, None).unwrap();
+            },
+            |map, _, _| {
+                map.add_floating_node("Loose", 0.0, 0.0).unwrap();
+            },
+            |map, _, child| {
+                let node = map.nodes.get_mut(child).unwrap();
+                node.attributes.push(("k".to_string(), "v".to_string()));
+            },
+        ];
+        for edit in edits {
+            let mut edited = map.clone();
+            edit(&mut edited, &root_id, &child);
+            assert_ne!(edited.fingerprint(), fingerprint);
+        }
+
+        // Relationships count whatever order they are listed in.
+        let mut linked = map.clone();
+        linked.add_relationship(&root_id, &child, None).unwrap();
+        linked.add_relationship(&child, &root_id, None).unwrap();
+        let before = linked.fingerprint();
+        linked.relationships.reverse();
+        assert_eq!(linked.fingerprint(), before);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::events::MapEvent;
+use crate::timestamp::now_millis;
+use crate::validation::Change;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+impl MindMap {
+    /// Adds a node that isn't attached to the root, placed with its top-left
+    /// corner at `x`, `y`, and returns its id. It starts a tree of its own:
+    /// [`MindMap::add_child`] works under it as anywhere else, and layout
+    /// arranges its subtree around it.
+    pub fn add_floating_node(
+        &mut self,
+        content: &str,
+        x: f32,
+        y: f32,
+    ) -> Result<String, BrainCoreError> {
+        let content = self.validate_content(
+            &Change {
+                map: self,
+                node_id: None,
+                parent_id: None,
+            },
+            content,
+        )?;
+        self.limits
+            .check_node_count(self.nodes.len() + 1)
+            .and_then(|_| self.limits.check_content(&content))?;
+
+        let id = Uuid::new_v4().to_string();
+        let now = now_millis();
+        self.nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content,
+                x,
+                y,
+                created: now,
+                modified: now,
+                created_by: self.author.clone(),
+                modified_by: self.author.clone(),
+                ..Default::default()
+            },
+        );
+        self.floating_roots.push(id.clone());
+        self.bump_revision(&id);
+        self.emit(MapEvent::NodeAdded {
+            node_id: id.clone(),
+            parent_id: None,
+        });
+        Ok(id)
+    }
+
+    pub fn is_floating(&self, node_id: &str) -> bool {
+        self.floating_roots.iter().any(|id| id == node_id)
+    }
+
+    /// Deletes a floating node and its subtree for good; use
+    /// [`MindMap::trash_node`] for nodes below it. Relationships, groups and
+    /// summaries touching the removed nodes are dropped, and the selection
+    /// moves to the root if it was inside the removed tree.
+    pub fn remove_floating_node(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        if !self.is_floating(node_id) {
+            return Err(BrainCoreError::InvalidOperation(
+                "Node is not floating".to_string(),
+            ));
+        }
+        self.floating_roots.retain(|id| id != node_id);
+        let mut removed = HashSet::new();
+        let mut stack = vec![node_id.to_string()];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes.remove(&id) {
+                stack.extend(node.children);
+                removed.insert(id);
+            }
+        }
+        self.drop_references(&removed);
+        if !self.nodes.contains_key(&self.selection.primary) {
+            self.set_selected(self.root_id.clone());
+        }
+        self.prune_selection();
+        self.revision += 1;
+        self.emit(MapEvent::NodeRemoved {
+            node_id: node_id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// The root followed by the floating nodes: the tops of every tree in
+    /// the map.
+    pub fn tree_roots(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.root_id).chain(&self.floating_roots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_floating_nodes() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.add_child(&root_id, "Main").unwrap();
+        let idea = map.add_floating_node("Loose idea", 400.0, 300.0).unwrap();
+        let detail = map.add_child(&idea, "Detail").unwrap();
+        assert!(map.is_floating(&idea));
+        assert!(map.nodes[&idea].parent.is_none());
+        assert!(map.validate().is_empty());
+
+        map.compute_layout();
+        assert_eq!((map.nodes[&idea].x, map.nodes[&idea].y), (400.0, 300.0));
+        assert_eq!(map.nodes[&detail].x, 400.0 + 100.0 + 40.0);
+
+        let json = serde_json::to_string(&map).unwrap();
+        let loaded: MindMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.floating_roots, vec![idea.clone()]);
+
+        map.add_relationship(&idea, &root_id, None).unwrap();
+        map.add_relationship(&root_id, &detail, None).unwrap();
+        map.add_group(&detail, None).unwrap();
+        map.selection.primary = detail;
+        map.remove_floating_node(&idea).unwrap();
+        assert_eq!(map.nodes.len(), 2);
+        assert!(map.relationships.is_empty());
+        assert!(map.groups.is_empty());
+        assert_eq!(map.selection.primary, root_id);
+        assert!(map.remove_floating_node(&root_id).is_err());
+    }
+}
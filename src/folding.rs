@@ -0,0 +1,83 @@
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// Collapses an expanded node or expands a collapsed one, returning the
+    /// new state. Collapsing moves the selection up to the node if it was
+    /// inside the hidden subtree.
+    pub fn toggle_fold(&mut self, node_id: &str) -> Result<bool, BrainCoreError> {
+        let folded = !self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?
+            .folded;
+        self.set_folded(node_id, folded)?;
+        Ok(folded)
+    }
+
+    /// Folding is view state: it bumps the revision so it gets saved, but
+    /// leaves the modification time and author alone.
+    pub fn set_folded(&mut self, node_id: &str, folded: bool) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.folded != folded {
+            node.folded = folded;
+            self.bump_revision(node_id);
+        }
+        if self.is_hidden(&self.selection.primary) {
+            self.set_selected(node_id.to_string());
+        }
+        self.prune_selection();
+        Ok(())
+    }
+
+    /// Whether the node sits below a collapsed ancestor. The collapsed node
+    /// itself is still visible.
+    pub fn is_hidden(&self, node_id: &str) -> bool {
+        let mut current = self
+            .nodes
+            .get(node_id)
+            .and_then(|n| n.parent.as_ref())
+            .and_then(|p| self.nodes.get(p));
+        while let Some(node) = current {
+            if node.folded {
+                return true;
+            }
+            current = node.parent.as_ref().and_then(|p| self.nodes.get(p));
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage, xmind};
+
+    #[test]
+    fn test_folding() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let branch = map.add_child(&root_id, "Branch").unwrap();
+        let leaf = map.add_child(&branch, "Leaf").unwrap();
+        map.selection.primary = leaf.clone();
+
+        assert!(map.toggle_fold(&branch).unwrap());
+        assert!(map.is_hidden(&leaf));
+        assert!(!map.is_hidden(&branch));
+        assert_eq!(map.selection.primary, branch);
+
+        for loaded in [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+        ] {
+            let root = &loaded.nodes[&loaded.root_id];
+            assert!(!root.folded);
+            assert!(loaded.nodes[&root.children[0]].folded);
+        }
+
+        assert!(!map.toggle_fold(&branch).unwrap());
+        assert!(!map.is_hidden(&leaf));
+    }
+}
@@ -0,0 +1,388 @@
+use crate::csv::{self, CsvOptions};
+use crate::export::ExportOptions;
+use crate::import::ImportOptions;
+use crate::progress::Monitor;
+use crate::{
+    BrainCoreError, MindMap, canvas, itmz, json, markdown, mindnode, mmap, opml, smmx, storage,
+    taskpaper, xmind,
+};
+use serde_json::Value;
+use std::io::Cursor;
+use std::path::Path;
+use zip::ZipArchive;
+
+/// Every format the crate reads; the ones it also writes are those for
+/// which [`Format::can_export`] holds.
+pub use crate::import::ImportFormat as Format;
+
+impl Format {
+    /// The usual file extension, for formats that have their own.
+    pub fn extension(self) -> Option<&'static str> {
+        Some(match self {
+            Format::FreeMind => "mm",
+            Format::Opml => "opml",
+            Format::Xmind => "xmind",
+            Format::MindManager => "mmap",
+            Format::MindNode => "mindnode",
+            Format::SimpleMind => "smmx",
+            Format::Evernote => "enex",
+            Format::Markdown => "md",
+            Format::Csv => "csv",
+            Format::Itmz => "itmz",
+            Format::Canvas => "canvas",
+            Format::TaskPaper => "taskpaper",
+            Format::Notion
+            | Format::Trello
+            | Format::GithubIssues
+            | Format::Jira
+            | Format::Json => return None,
+        })
+    }
+
+    /// Picks the format from the extension of `path`, ignoring case.
+    /// Generic extensions such as `.json` or `.zip` give `None`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        Self::from_extension(path.extension()?.to_str()?)
+    }
+
+    /// Picks the format for a file extension without the dot, ignoring
+    /// case.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "markdown" => Some(Format::Markdown),
+            extension => ALL.into_iter().find(|f| f.extension() == Some(extension)),
+        }
+    }
+
+    pub fn can_export(self) -> bool {
+        matches!(
+            self,
+            Format::FreeMind
+                | Format::Opml
+                | Format::Xmind
+                | Format::MindManager
+                | Format::MindNode
+                | Format::SimpleMind
+                | Format::Markdown
+                | Format::Csv
+                | Format::Json
+                | Format::Itmz
+                | Format::Canvas
+                | Format::TaskPaper
+        )
+    }
+}
+
+const ALL: [Format; 17] = [
+    Format::FreeMind,
+    Format::Opml,
+    Format::Xmind,
+    Format::MindManager,
+    Format::MindNode,
+    Format::SimpleMind,
+    Format::Evernote,
+    Format::Notion,
+    Format::Trello,
+    Format::GithubIssues,
+    Format::Jira,
+    Format::Markdown,
+    Format::Csv,
+    Format::Json,
+    Format::Itmz,
+    Format::Canvas,
+    Format::TaskPaper,
+];
+
+/// Guesses the format of a document from its contents: the entries of a
+/// zip package, the root element of an XML document or the shape of a JSON
+/// one. Text that starts like a Markdown heading or list is Markdown, text
+/// that starts with a `Level1,Level2...` header is CSV and text that starts
+/// with a `Project:` line is TaskPaper.
+pub fn detect_format(data: &[u8]) -> Option<Format> {
+    if data.starts_with(b"PK\x03\x04") {
+        return detect_package(data);
+    }
+    let text = std::str::from_utf8(data).ok()?;
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('<') {
+        return match root_element(text)? {
+            "map" => Some(Format::FreeMind),
+            "opml" => Some(Format::Opml),
+            "simplemind-mindmaps" => Some(Format::SimpleMind),
+            "en-export" => Some(Format::Evernote),
+            "rss" => Some(Format::Jira),
+            _ => None,
+        };
+    }
+    if text.starts_with(['{', '[']) {
+        return detect_json(&serde_json::from_str(text).ok()?);
+    }
+    let first_line = text.lines().next()?;
+    if first_line.starts_with('#') || crate::paste::list_item(first_line).is_some() {
+        Some(Format::Markdown)
+    } else if is_csv_header(first_line) {
+        Some(Format::Csv)
+    } else {
+        taskpaper::is_project_line(first_line).then_some(Format::TaskPaper)
+    }
+}
+
+/// Whether the line is the `Level1,Level2...` header the CSV exporter
+/// writes.
+fn is_csv_header(line: &str) -> bool {
+    line.trim_end().split(',').enumerate().all(|(i, cell)| {
+        cell.strip_prefix("Level")
+            .is_some_and(|level| level == (i + 1).to_string())
+    })
+}
+
+fn detect_package(data: &[u8]) -> Option<Format> {
+    let archive = ZipArchive::new(Cursor::new(data)).ok()?;
+    let names: Vec<&str> = archive.file_names().collect();
+    let has = |name: &str| names.iter().any(|n| n.eq_ignore_ascii_case(name));
+    if has("content.json") {
+        Some(Format::Xmind)
+    } else if has("Document.xml") {
+        Some(Format::MindManager)
+    } else if has("contents.xml") || has("contents.json") {
+        Some(Format::MindNode)
+    } else if has("mapdata.xml") {
+        Some(Format::Itmz)
+    } else if names
+        .iter()
+        .any(|n| n.ends_with(".md") || n.ends_with(".html"))
+    {
+        Some(Format::Notion)
+    } else {
+        None
+    }
+}
+
+/// Name of the first element, skipping the declaration, comments and
+/// doctype.
+fn root_element(text: &str) -> Option<&str> {
+    let mut rest = text;
+    loop {
+        rest = rest.trim_start();
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = &comment[comment.find("-->")? + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[rest.find('>')? + 1..];
+        } else {
+            let name = rest.strip_prefix('<')?;
+            let end = name
+                .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+                .unwrap_or(name.len());
+            return Some(&name[..end]);
+        }
+    }
+}
+
+fn detect_json(value: &Value) -> Option<Format> {
+    let item = match value {
+        Value::Object(board) if board.contains_key("lists") && board.contains_key("cards") => {
+            return Some(Format::Trello);
+        }
+        Value::Object(result) if result.contains_key("issues") => return Some(Format::Jira),
+        Value::Object(canvas) if canvas.contains_key("nodes") && canvas.contains_key("edges") => {
+            return Some(Format::Canvas);
+        }
+        Value::Object(document)
+            if document.contains_key("version") && document.contains_key("nodes") =>
+        {
+            return Some(Format::Json);
+        }
+        Value::Array(items) => items.first()?,
+        _ => return None,
+    };
+    if item.get("fields").is_some() && item.get("key").is_some() {
+        Some(Format::Jira)
+    } else if item.get("number").is_some() {
+        Some(Format::GithubIssues)
+    } else {
+        None
+    }
+}
+
+pub fn import(data: &[u8]) -> Result<MindMap, BrainCoreError> {
+    import_with(data, &ImportOptions::default())
+}
+
+/// Detects the format with [`detect_format`] and runs its importer.
+pub fn import_with(data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    import_monitored(data, options, &mut Monitor::default())
+}
+
+/// Detects the format and runs its importer under `monitor`.
+pub fn import_monitored(
+    data: &[u8],
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    let format = detect_format(data).ok_or_else(|| {
+        BrainCoreError::InvalidOperation("Unrecognized document format".to_string())
+    })?;
+    format.import_monitored(data, options, monitor)
+}
+
+pub fn export(map: &MindMap, format: Format) -> Result<Vec<u8>, BrainCoreError> {
+    export_with(map, format, &ExportOptions::default())
+}
+
+/// Runs the exporter for `format`. Fails for formats that are import-only.
+pub fn export_with(
+    map: &MindMap,
+    format: Format,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, BrainCoreError> {
+    export_monitored(map, format, options, &mut Monitor::default())
+}
+
+/// Runs the exporter for `format` under `monitor`, stopping with
+/// [`BrainCoreError::Cancelled`] when it is cancelled.
+pub fn export_monitored(
+    map: &MindMap,
+    format: Format,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<Vec<u8>, BrainCoreError> {
+    let text = |result: Result<String, BrainCoreError>| result.map(String::into_bytes);
+    match format {
+        Format::FreeMind => text(storage::to_xml_monitored(map, options, monitor)),
+        Format::Opml => text(opml::to_opml_monitored(map, options, monitor)),
+        Format::Xmind => xmind::to_xmind_monitored(map, options, monitor),
+        Format::MindManager => mmap::to_mmap_monitored(map, options, monitor),
+        Format::MindNode => mindnode::to_mindnode_monitored(map, options, monitor),
+        Format::SimpleMind => text(smmx::to_smmx_monitored(map, options, monitor)),
+        Format::Markdown => text(markdown::to_markdown_monitored(map, options, monitor)),
+        Format::Csv => text(csv::to_csv_monitored(
+            map,
+            &CsvOptions::default(),
+            options,
+            monitor,
+        )),
+        Format::Json => text(json::to_json_monitored(map, options, monitor)),
+        Format::Itmz => itmz::to_itmz_monitored(map, options, monitor),
+        Format::Canvas => text(canvas::to_canvas_monitored(map, options, monitor)),
+        Format::TaskPaper => text(taskpaper::to_taskpaper_monitored(map, options, monitor)),
+        Format::Evernote
+        | Format::Notion
+        | Format::Trello
+        | Format::GithubIssues
+        | Format::Jira => Err(BrainCoreError::export(format!(
+            "{} documents can't be written",
+            format.name()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::progress::{CancellationToken, Stage};
+    use crate::testing::{GenerateSpec, generate};
+
+    #[test]
+    fn test_detect_and_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.add_child(&root_id, "Child").unwrap();
+
+        for format in ALL.into_iter().filter(|f| f.can_export()) {
+            let data = export(&map, format).unwrap();
+            assert_eq!(detect_format(&data), Some(format));
+            assert_eq!(import(&data).unwrap().nodes.len(), 2, "{format:?}");
+        }
+
+        let samples: [(&str, Format); 5] = [
+            (
+                "<?xml version=\"1.0\"?>\n<!-- x --><en-export>",
+                Format::Evernote,
+            ),
+            ("<rss version=\"0.92\"><channel/></rss>", Format::Jira),
+            (
+                "{\"name\": \"Board\", \"lists\": [], \"cards\": []}",
+                Format::Trello,
+            ),
+            (
+                "[{\"number\": 1, \"title\": \"Bug\"}]",
+                Format::GithubIssues,
+            ),
+            ("{\"issues\": []}", Format::Jira),
+        ];
+        for (text, format) in samples {
+            assert_eq!(detect_format(text.as_bytes()), Some(format));
+        }
+        assert_eq!(detect_format(b"just some text"), None);
+
+        // Recent MindNode packages may hold only a JSON document.
+        let mut package = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut package));
+        zip.start_file("contents.json", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(
+            &mut zip,
+            br#"{"mindMap": {"mainNodes": [{"nodeID": "r", "title": {"text": "Root"}}]}}"#,
+        )
+        .unwrap();
+        zip.finish().unwrap();
+        assert_eq!(detect_format(&package), Some(Format::MindNode));
+        assert_eq!(import(&package).unwrap().nodes["r"].content, "Root");
+        assert!(export(&map, Format::Trello).is_err());
+        assert_eq!(
+            Format::from_path(Path::new("a/b.MM")),
+            Some(Format::FreeMind)
+        );
+        assert_eq!(
+            Format::from_path(Path::new("notes.markdown")),
+            Some(Format::Markdown)
+        );
+        assert_eq!(Format::from_path(Path::new("board.json")), None);
+    }
+
+    #[test]
+    fn test_cancellation_stops_every_format() {
+        let spec = GenerateSpec {
+            node_count: 1_000,
+            ..Default::default()
+        };
+        let map = generate(3, &spec);
+
+        // Cancels once `stage` has seen 128 nodes; returns the furthest
+        // count reported for it.
+        fn run<T>(
+            stage: Stage,
+            convert: impl FnOnce(&mut Monitor) -> Result<T, BrainCoreError>,
+        ) -> (bool, usize) {
+            let token = CancellationToken::new();
+            let trigger = token.clone();
+            let mut reached = 0;
+            let mut monitor = Monitor::new()
+                .on_progress(|e| {
+                    if e.stage == stage {
+                        reached = reached.max(e.done);
+                        if e.done >= 128 {
+                            trigger.cancel();
+                        }
+                    }
+                })
+                .cancel_on(token);
+            let cancelled = matches!(convert(&mut monitor), Err(BrainCoreError::Cancelled));
+            drop(monitor);
+            (cancelled, reached)
+        }
+
+        for format in ALL.into_iter().filter(|f| f.can_export()) {
+            let (cancelled, reached) = run(Stage::WritingNodes, |monitor| {
+                export_monitored(&map, format, &ExportOptions::default(), monitor)
+            });
+            assert!(cancelled && reached < 1_000, "{format:?} export");
+
+            let data = export(&map, format).unwrap();
+            let (cancelled, reached) = run(Stage::BuildingNodes, |monitor| {
+                import_monitored(&data, &ImportOptions::default(), monitor)
+            });
+            assert!(cancelled && reached < 1_000, "{format:?} import");
+        }
+    }
+}
@@ -0,0 +1,274 @@
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::timestamp::parse_iso8601;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+// Issues as printed by `gh issue list --json ...`. The REST API's
+// snake_case field names are accepted too.
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubIssue {
+    pub number: u64,
+    pub title: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<GithubLabel>,
+    #[serde(default)]
+    pub milestone: Option<GithubMilestone>,
+    #[serde(default, alias = "created_at")]
+    pub created_at: Option<String>,
+    #[serde(default, alias = "updated_at")]
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GithubLabel {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubMilestone {
+    pub title: String,
+    #[serde(default, alias = "due_on")]
+    pub due_on: Option<String>,
+}
+
+/// One level of branches between the root and the issues.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueGroup {
+    Milestone,
+    /// Issues go under their first label; all labels are kept as tags.
+    Label,
+}
+
+pub fn from_github_issues(json: &str) -> Result<MindMap, BrainCoreError> {
+    from_github_issues_with(json, &[IssueGroup::Milestone], &ImportOptions::default())
+}
+
+/// Builds an "Issues" root with one branch level per entry of `group_by`
+/// (none puts issues straight under the root). Issues become `#N title`
+/// nodes with the body as note, labels as tags and their state as task
+/// completion. Group nodes carry the share of closed issues as progress,
+/// and milestone groups the milestone's due date.
+pub fn from_github_issues_with(
+    json: &str,
+    group_by: &[IssueGroup],
+    options: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    from_github_issues_monitored(json, group_by, options, &mut Monitor::default())
+}
+
+pub fn from_github_issues_monitored(
+    json: &str,
+    group_by: &[IssueGroup],
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let mut issues: Vec<GithubIssue> = serde_json::from_str(json)
+        .map_err(|e| BrainCoreError::json(ImportFormat::GithubIssues, json, e))?;
+    issues.sort_by_key(|issue| issue.number);
+
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: "Issues".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    let issues: Vec<&GithubIssue> = issues.iter().collect();
+    monitor.begin(Stage::BuildingNodes, Some(issues.len()))?;
+    add_groups(&mut nodes, &root_id, &issues, group_by, now, monitor);
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+fn add_groups(
+    nodes: &mut HashMap<String, Node>,
+    parent_id: &str,
+    issues: &[&GithubIssue],
+    group_by: &[IssueGroup],
+    now: u64,
+    monitor: &mut Monitor,
+) {
+    let Some((group, rest)) = group_by.split_first() else {
+        for issue in issues {
+            if monitor.should_stop() {
+                break;
+            }
+            monitor.tick();
+            add_issue(nodes, parent_id, issue, now);
+        }
+        return;
+    };
+
+    // Named groups sort by title; the catch-all group goes last.
+    let mut groups: BTreeMap<(bool, String), Vec<&GithubIssue>> = BTreeMap::new();
+    for issue in issues {
+        let key = match group {
+            IssueGroup::Milestone => issue.milestone.as_ref().map(|m| m.title.clone()),
+            IssueGroup::Label => issue.labels.first().map(|l| l.name.clone()),
+        };
+        let key = match key {
+            Some(title) => (false, title),
+            None => (true, String::new()),
+        };
+        groups.entry(key).or_default().push(issue);
+    }
+
+    for ((ungrouped, title), members) in groups {
+        if monitor.should_stop() {
+            break;
+        }
+        let content = match (ungrouped, group) {
+            (false, _) => title,
+            (true, IssueGroup::Milestone) => "No milestone".to_string(),
+            (true, IssueGroup::Label) => "No label".to_string(),
+        };
+        let closed = members.iter().filter(|i| is_closed(i)).count();
+        let due = match group {
+            IssueGroup::Milestone => members[0]
+                .milestone
+                .as_ref()
+                .and_then(|m| m.due_on.as_deref())
+                .and_then(parse_iso8601),
+            IssueGroup::Label => None,
+        };
+        let id = Uuid::new_v4().to_string();
+        insert(
+            nodes,
+            Node {
+                id: id.clone(),
+                content,
+                parent: Some(parent_id.to_string()),
+                created: now,
+                modified: now,
+                task: Some(TaskInfo {
+                    done: closed == members.len(),
+                    progress_pct: (closed * 100 / members.len()) as u8,
+                    due,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        add_groups(nodes, &id, &members, rest, now, monitor);
+    }
+}
+
+fn add_issue(nodes: &mut HashMap<String, Node>, parent_id: &str, issue: &GithubIssue, now: u64) {
+    let closed = is_closed(issue);
+    let created = issue
+        .created_at
+        .as_deref()
+        .and_then(parse_iso8601)
+        .unwrap_or(now);
+    insert(
+        nodes,
+        Node {
+            id: format!("issue-{}", issue.number),
+            content: format!("#{} {}", issue.number, issue.title),
+            parent: Some(parent_id.to_string()),
+            created,
+            modified: issue
+                .updated_at
+                .as_deref()
+                .and_then(parse_iso8601)
+                .unwrap_or(created),
+            note: issue
+                .body
+                .as_deref()
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(str::to_string),
+            tags: issue.labels.iter().map(|l| l.name.clone()).collect(),
+            task: Some(TaskInfo {
+                done: closed,
+                progress_pct: if closed { 100 } else { 0 },
+                ..Default::default()
+            }),
+            ..Default::default()
+        },
+    );
+}
+
+fn is_closed(issue: &GithubIssue) -> bool {
+    issue.state.eq_ignore_ascii_case("closed")
+}
+
+fn insert(nodes: &mut HashMap<String, Node>, node: Node) {
+    if let Some(parent) = node.parent.as_ref().and_then(|p| nodes.get_mut(p)) {
+        parent.children.push(node.id.clone());
+    }
+    nodes.insert(node.id.clone(), node);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISSUES: &str = r#"[
+      {"number": 12, "title": "Crash on empty map", "state": "OPEN", "body": "Steps:\n1. open",
+       "labels": [{"id": "LA_1", "name": "bug", "description": "", "color": "d73a4a"}],
+       "milestone": {"number": 3, "title": "v1.2", "description": "", "dueOn": "2024-06-30T00:00:00Z"},
+       "createdAt": "2024-05-02T08:00:00Z", "updatedAt": "2024-05-03T08:00:00Z"},
+      {"number": 9, "title": "Export to DOT", "state": "CLOSED", "body": "",
+       "labels": [{"name": "feature"}, {"name": "export"}],
+       "milestone": {"number": 3, "title": "v1.2", "dueOn": "2024-06-30T00:00:00Z"}},
+      {"number": 15, "title": "Docs typo", "state": "OPEN", "labels": [], "milestone": null}
+    ]"#;
+
+    #[test]
+    fn test_github_issues_import() {
+        let map = from_github_issues(ISSUES).unwrap();
+        let root = &map.nodes[&map.root_id];
+        let groups: Vec<&Node> = root.children.iter().map(|id| &map.nodes[id]).collect();
+        assert_eq!(groups[0].content, "v1.2");
+        assert_eq!(groups[1].content, "No milestone");
+        let milestone = groups[0].task.as_ref().unwrap();
+        assert_eq!(milestone.progress_pct, 50);
+        assert_eq!(milestone.due, Some(1_719_705_600_000));
+
+        assert_eq!(groups[0].children, ["issue-9", "issue-12"]);
+        let crash = &map.nodes["issue-12"];
+        assert_eq!(crash.content, "#12 Crash on empty map");
+        assert_eq!(crash.tags, ["bug"]);
+        assert_eq!(crash.note.as_deref(), Some("Steps:\n1. open"));
+        assert!(!crash.task.as_ref().unwrap().done);
+        assert!(map.nodes["issue-9"].task.as_ref().unwrap().done);
+
+        let map = from_github_issues_with(
+            ISSUES,
+            &[IssueGroup::Milestone, IssueGroup::Label],
+            &ImportOptions::default(),
+        )
+        .unwrap();
+        let v12 = &map.nodes[&map.nodes[&map.root_id].children[0]];
+        let labels: Vec<&str> = v12
+            .children
+            .iter()
+            .map(|id| map.nodes[id].content.as_str())
+            .collect();
+        assert_eq!(labels, ["bug", "feature"]);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::layout::{HeuristicMeasurer, TextMeasurer};
+use crate::{MindMap, Node};
+use quick_xml::escape::escape;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphmlOptions {
+    /// Write each node's position and size, taken from the map's current
+    /// layout (see [`MindMap::compute_layout`]), both as plain `x`, `y`,
+    /// `width` and `height` data for Gephi and as yEd shape graphics.
+    pub geometry: bool,
+    pub include_archived: bool,
+}
+
+pub fn to_graphml(map: &MindMap) -> String {
+    to_graphml_with(map, &GraphmlOptions::default())
+}
+
+/// Exports the map as a directed GraphML graph with an edge from every node
+/// to each of its children. As in [`crate::graphviz::to_dot`], nodes are
+/// numbered in depth-first order so the same map always gives the same
+/// file. Labels, notes and links are node data.
+pub fn to_graphml_with(map: &MindMap, options: &GraphmlOptions) -> String {
+    to_graphml_measured(map, options, &HeuristicMeasurer)
+}
+
+/// Exports the map as [`to_graphml_with`] does, sizing the boxes of the
+/// geometry with `measurer`. Pass the one the map was laid out with.
+pub fn to_graphml_measured(
+    map: &MindMap,
+    options: &GraphmlOptions,
+    measurer: &dyn TextMeasurer,
+) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\"",
+    );
+    if options.geometry {
+        out.push_str(" xmlns:y=\"http://www.yworks.com/xml/graphml\"");
+    }
+    out.push_str(">\n");
+    out.push_str("  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"note\" for=\"node\" attr.name=\"note\" attr.type=\"string\"/>\n");
+    out.push_str("  <key id=\"link\" for=\"node\" attr.name=\"link\" attr.type=\"string\"/>\n");
+    if options.geometry {
+        for name in ["x", "y", "width", "height"] {
+            let _ = writeln!(
+                out,
+                "  <key id=\"{name}\" for=\"node\" attr.name=\"{name}\" attr.type=\"double\"/>"
+            );
+        }
+        out.push_str("  <key id=\"graphics\" for=\"node\" yfiles.type=\"nodegraphics\"/>\n");
+    }
+    out.push_str("  <graph id=\"mindmap\" edgedefault=\"directed\">\n");
+
+    let mut next = 0;
+    let mut edges = Vec::new();
+    let mut stack: Vec<(&String, Option<usize>)> = vec![(&map.root_id, None)];
+    while let Some((id, parent)) = stack.pop() {
+        let Some(node) = map.nodes.get(id) else {
+            continue;
+        };
+        if node.archived && !options.include_archived {
+            continue;
+        }
+        let number = next;
+        next += 1;
+        write_node(&mut out, number, node, map, options, measurer);
+        if let Some(parent) = parent {
+            edges.push((parent, number));
+        }
+        stack.extend(node.children.iter().rev().map(|c| (c, Some(number))));
+    }
+    for (index, (source, target)) in edges.into_iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{index}\" source=\"n{source}\" target=\"n{target}\"/>"
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn write_node(
+    out: &mut String,
+    number: usize,
+    node: &Node,
+    map: &MindMap,
+    options: &GraphmlOptions,
+    measurer: &dyn TextMeasurer,
+) {
+    let label = escape(&node.content);
+    let _ = writeln!(out, "    <node id=\"n{number}\">");
+    let _ = writeln!(out, "      <data key=\"label\">{label}</data>");
+    if let Some(note) = node.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        let _ = writeln!(out, "      <data key=\"note\">{}</data>", escape(note));
+    }
+    if let Some(link) = &node.link {
+        let _ = writeln!(out, "      <data key=\"link\">{}</data>", escape(link));
+    }
+    if options.geometry {
+        let (width, height) = map.node_size(node, measurer);
+        for (key, value) in [
+            ("x", node.x),
+            ("y", node.y),
+            ("width", width),
+            ("height", height),
+        ] {
+            let _ = writeln!(out, "      <data key=\"{key}\">{value}</data>");
+        }
+        let _ = writeln!(
+            out,
+            "      <data key=\"graphics\"><y:ShapeNode>\
+             <y:Geometry x=\"{}\" y=\"{}\" width=\"{width}\" height=\"{height}\"/>\
+             <y:NodeLabel>{label}</y:NodeLabel></y:ShapeNode></data>",
+            node.x, node.y
+        );
+    }
+    out.push_str("    </node>\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_graphml() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Root").unwrap();
+        let a = map.add_child(&root_id, "R&D").unwrap();
+        map.set_note(&a, "Budget <tbd>").unwrap();
+        map.add_child(&a, "Leaf").unwrap();
+        let old = map.add_child(&root_id, "Old").unwrap();
+        map.archive(&old).unwrap();
+
+        let graphml = to_graphml(&map);
+        assert!(graphml.contains("<graph id=\"mindmap\" edgedefault=\"directed\">\n"));
+        assert!(graphml.contains(
+            "    <node id=\"n1\">\n      <data key=\"label\">R&amp;D</data>\n      \
+             <data key=\"note\">Budget &lt;tbd&gt;</data>\n    </node>\n"
+        ));
+        assert!(graphml.contains(
+            "    <edge id=\"e0\" source=\"n0\" target=\"n1\"/>\n    \
+             <edge id=\"e1\" source=\"n1\" target=\"n2\"/>\n  </graph>"
+        ));
+        assert!(!graphml.contains("Old"));
+        assert!(!graphml.contains("y:"));
+
+        map.compute_layout();
+        let options = GraphmlOptions {
+            geometry: true,
+            ..Default::default()
+        };
+        let graphml = to_graphml_with(&map, &options);
+        assert!(graphml.contains("<data key=\"x\">92</data>"));
+        assert!(graphml.contains(
+            "<y:Geometry x=\"92\" y=\"0\" width=\"44\" height=\"50\"/><y:NodeLabel>R&amp;D</y:NodeLabel>"
+        ));
+    }
+}
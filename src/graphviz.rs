@@ -0,0 +1,123 @@
+use crate::MindMap;
+use std::fmt::Write;
+
+/// Direction in which GraphViz lays out the ranks of the graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankDir {
+    TopToBottom,
+    LeftToRight,
+    BottomToTop,
+    RightToLeft,
+}
+
+impl RankDir {
+    fn as_str(self) -> &'static str {
+        match self {
+            RankDir::TopToBottom => "TB",
+            RankDir::LeftToRight => "LR",
+            RankDir::BottomToTop => "BT",
+            RankDir::RightToLeft => "RL",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DotOptions {
+    /// `None` leaves GraphViz's default, top to bottom.
+    pub rankdir: Option<RankDir>,
+    /// Layout engine, such as `dot`, `neato` or `twopi`. `None` leaves the
+    /// choice to the command that renders the file.
+    pub layout: Option<String>,
+    pub include_archived: bool,
+}
+
+pub fn to_dot(map: &MindMap) -> String {
+    to_dot_with(map, &DotOptions::default())
+}
+
+/// Exports the map as a directed graph with an edge from every node to each
+/// of its children. Nodes are numbered in depth-first order rather than by
+/// id, so the same map always gives the same file. Linked nodes carry their
+/// link as the GraphViz `URL` attribute.
+pub fn to_dot_with(map: &MindMap, options: &DotOptions) -> String {
+    let mut out = String::from("digraph mindmap {\n");
+    if let Some(rankdir) = options.rankdir {
+        let _ = writeln!(out, "  rankdir={};", rankdir.as_str());
+    }
+    if let Some(layout) = &options.layout {
+        let _ = writeln!(out, "  layout=\"{}\";", escape(layout));
+    }
+    out.push_str("  node [shape=box];\n");
+
+    let mut next = 0;
+    let mut stack: Vec<(&String, Option<usize>)> = vec![(&map.root_id, None)];
+    while let Some((id, parent)) = stack.pop() {
+        let Some(node) = map.nodes.get(id) else {
+            continue;
+        };
+        if node.archived && !options.include_archived {
+            continue;
+        }
+        let number = next;
+        next += 1;
+        let _ = write!(out, "  n{number} [label=\"{}\"", escape(&node.content));
+        if let Some(link) = &node.link {
+            let _ = write!(out, ", URL=\"{}\"", escape(link));
+        }
+        out.push_str("];\n");
+        if let Some(parent) = parent {
+            let _ = writeln!(out, "  n{parent} -> n{number};");
+        }
+        stack.extend(node.children.iter().rev().map(|c| (c, Some(number))));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Escapes text for a double-quoted DOT string. Line breaks become `\n`,
+/// which GraphViz renders as centered lines.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "Say \"hi\"\nC:\\temp").unwrap();
+        map.add_child(&a, "Leaf").unwrap();
+        let b = map.add_child(&root_id, "Docs").unwrap();
+        map.set_link(&b, "https://example.com").unwrap();
+        let hidden = map.add_child(&root_id, "Old").unwrap();
+        map.archive(&hidden).unwrap();
+
+        let options = DotOptions {
+            rankdir: Some(RankDir::LeftToRight),
+            layout: Some("dot".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            to_dot_with(&map, &options),
+            "digraph mindmap {\n  rankdir=LR;\n  layout=\"dot\";\n  node [shape=box];\n  \
+             n0 [label=\"Central Node\"];\n  \
+             n1 [label=\"Say \\\"hi\\\"\\nC:\\\\temp\"];\n  n0 -> n1;\n  \
+             n2 [label=\"Leaf\"];\n  n1 -> n2;\n  \
+             n3 [label=\"Docs\", URL=\"https://example.com\"];\n  n0 -> n3;\n}\n"
+        );
+        assert!(to_dot(&map).starts_with("digraph mindmap {\n  node [shape=box];\n"));
+    }
+}
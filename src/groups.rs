@@ -0,0 +1,121 @@
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A titled region drawn around a node and its descendants, such as an
+/// XMind boundary or a FreeMind cloud.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Group {
+    pub id: String,
+    /// Top node of the grouped subtree.
+    pub node_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub style: GroupStyle,
+}
+
+/// Colors as `#rrggbb`; `None` leaves the viewer's default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupStyle {
+    #[serde(default)]
+    pub fill_color: Option<String>,
+    #[serde(default)]
+    pub border_color: Option<String>,
+}
+
+impl MindMap {
+    /// Groups a node with its descendants and returns the group id.
+    pub fn add_group(
+        &mut self,
+        node_id: &str,
+        title: Option<&str>,
+    ) -> Result<String, BrainCoreError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(BrainCoreError::NodeNotFound(node_id.to_string()));
+        }
+        let id = Uuid::new_v4().to_string();
+        self.groups.push(Group {
+            id: id.clone(),
+            node_id: node_id.to_string(),
+            title: title.map(str::to_string).filter(|t| !t.is_empty()),
+            style: GroupStyle::default(),
+        });
+        self.bump_revision(node_id);
+        Ok(id)
+    }
+
+    pub fn set_group_style(&mut self, id: &str, style: GroupStyle) -> Result<(), BrainCoreError> {
+        let group = self
+            .groups
+            .iter_mut()
+            .find(|g| g.id == id)
+            .ok_or_else(|| BrainCoreError::GroupNotFound(id.to_string()))?;
+        if group.style != style {
+            group.style = style;
+            let node_id = group.node_id.clone();
+            self.bump_revision(&node_id);
+        }
+        Ok(())
+    }
+
+    pub fn remove_group(&mut self, id: &str) -> Result<Group, BrainCoreError> {
+        let pos = self
+            .groups
+            .iter()
+            .position(|g| g.id == id)
+            .ok_or_else(|| BrainCoreError::GroupNotFound(id.to_string()))?;
+        let removed = self.groups.remove(pos);
+        self.bump_revision(&removed.node_id);
+        Ok(removed)
+    }
+
+    /// Groups whose top node is `node_id`. Groups of trashed nodes stay in
+    /// the map, as relationships do, so a restore brings them back.
+    pub fn groups_of<'a>(&'a self, node_id: &'a str) -> impl Iterator<Item = &'a Group> + 'a {
+        self.groups.iter().filter(move |g| g.node_id == node_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage, xmind};
+
+    #[test]
+    fn test_groups() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "Phase 1").unwrap();
+        map.add_child(&a, "Design").unwrap();
+        map.add_child(&root_id, "Phase 2").unwrap();
+        let group = map.add_group(&a, Some("Q1")).unwrap();
+        let style = GroupStyle {
+            fill_color: Some("#ffeecc".to_string()),
+            border_color: None,
+        };
+        map.set_group_style(&group, style.clone()).unwrap();
+        assert!(map.add_group("missing", None).is_err());
+
+        let loaded = xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap();
+        let a_loaded = &loaded.nodes[&loaded.nodes[&loaded.root_id].children[0]];
+        let groups: Vec<&Group> = loaded.groups_of(&a_loaded.id).collect();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].title.as_deref(), Some("Q1"));
+        assert_eq!(groups[0].style, style);
+
+        // FreeMind clouds have a color but no title.
+        let loaded = storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap();
+        let a_loaded = &loaded.nodes[&loaded.nodes[&loaded.root_id].children[0]];
+        let groups: Vec<&Group> = loaded.groups_of(&a_loaded.id).collect();
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].style.fill_color.as_deref(), Some("#ffeecc"));
+
+        map.remove_group(&group).unwrap();
+        assert!(map.groups.is_empty());
+        assert!(matches!(
+            map.remove_group(&group),
+            Err(BrainCoreError::GroupNotFound(_))
+        ));
+    }
+}
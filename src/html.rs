@@ -0,0 +1,204 @@
+// Helpers for the HTML fragments some formats embed in node text.
+
+use std::borrow::Cow;
+use std::fmt;
+
+const BLOCK_TAGS: &[&str] = &[
+    "p",
+    "div",
+    "br",
+    "li",
+    "tr",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ul",
+    "ol",
+    "table",
+    "blockquote",
+    "pre",
+];
+
+/// Elements that start a new line when flattened to text.
+pub(crate) fn is_block(name: &str) -> bool {
+    BLOCK_TAGS.contains(&name)
+}
+
+pub(crate) fn is_html(text: &str) -> bool {
+    text.trim_start()
+        .get(..5)
+        .is_some_and(|start| start.eq_ignore_ascii_case("<html"))
+}
+
+/// Flattens an HTML fragment to the text a reader would see: tags are
+/// dropped, block elements and `<br>` become line breaks, whitespace
+/// collapses as in a browser and entities are decoded.
+pub(crate) fn to_plain_text(html: &str) -> String {
+    let mut lines: Vec<String> = vec![String::new()];
+    let mut skip_until: Option<String> = None;
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        if let Some(tag_start) = rest.strip_prefix('<') {
+            let end = tag_start.find('>').unwrap_or(tag_start.len());
+            let tag = &tag_start[..end];
+            rest = tag_start.get(end + 1..).unwrap_or("");
+
+            let closing = tag.starts_with('/');
+            let name: String = tag
+                .trim_start_matches('/')
+                .chars()
+                .take_while(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_ascii_lowercase();
+            if let Some(skipped) = &skip_until {
+                if closing && *skipped == name {
+                    skip_until = None;
+                }
+                continue;
+            }
+            let self_closing = tag.ends_with('/');
+            if !closing && !self_closing && matches!(name.as_str(), "head" | "style" | "script") {
+                skip_until = Some(name);
+            } else if is_block(&name)
+                && (name == "br" || !lines.last().is_some_and(|l| l.trim().is_empty()))
+            {
+                lines.push(String::new());
+            }
+            continue;
+        }
+
+        let end = rest.find('<').unwrap_or(rest.len());
+        if skip_until.is_none() {
+            push_collapsed(lines.last_mut().unwrap(), &decode_entities(&rest[..end]));
+        }
+        rest = &rest[end..];
+    }
+
+    let lines: Vec<&str> = lines.iter().map(|l| l.trim_matches(' ')).collect();
+    lines.join("\n").trim_matches('\n').to_string()
+}
+
+/// Appends `text` with whitespace runs collapsed to one space, as a browser
+/// renders it. Non-breaking spaces are kept.
+pub(crate) fn push_collapsed(line: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !line.is_empty() && !line.ends_with(' ') {
+                line.push(' ');
+            }
+        } else {
+            line.push(c);
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((entity(&rest[1..end + 1])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Resolves a named or numeric character reference, without the `&` and `;`.
+pub(crate) fn entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "ndash" => '\u{2013}',
+        "mdash" => '\u{2014}',
+        "hellip" => '\u{2026}',
+        "lsquo" => '\u{2018}',
+        "rsquo" => '\u{2019}',
+        "ldquo" => '\u{201c}',
+        "rdquo" => '\u{201d}',
+        "laquo" => '\u{ab}',
+        "raquo" => '\u{bb}',
+        "bull" => '\u{2022}',
+        "middot" => '\u{b7}',
+        "copy" => '\u{a9}',
+        "reg" => '\u{ae}',
+        "trade" => '\u{2122}',
+        "euro" => '\u{20ac}',
+        _ => return None,
+    })
+}
+
+/// Writes line breaks and non-breaking spaces inside attribute values as
+/// character references, the way FreeMind does. XML parsers turn a literal
+/// newline in an attribute into a space, and a raw NBSP is easily lost.
+pub(crate) struct AttributeEncoder<W> {
+    inner: W,
+    in_tag: bool,
+    in_value: bool,
+}
+
+impl<W: fmt::Write> AttributeEncoder<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        Self {
+            inner,
+            in_tag: false,
+            in_value: false,
+        }
+    }
+}
+
+impl<W: fmt::Write> fmt::Write for AttributeEncoder<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '<' if !self.in_value => self.in_tag = true,
+                '>' if !self.in_value => self.in_tag = false,
+                '"' if self.in_tag => self.in_value = !self.in_value,
+                _ => {}
+            }
+            let reference = match c {
+                '\n' if self.in_value => "&#xa;",
+                '\r' if self.in_value => "&#xd;",
+                '\t' if self.in_value => "&#x9;",
+                '\u{a0}' if self.in_value => "&#xa0;",
+                _ => continue,
+            };
+            self.inner.write_str(&s[start..i])?;
+            self.inner.write_str(reference)?;
+            start = i + c.len_utf8();
+        }
+        self.inner.write_str(&s[start..])
+    }
+}
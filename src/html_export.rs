@@ -0,0 +1,277 @@
+use crate::{MindMap, Node};
+use quick_xml::escape::escape;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HtmlOptions {
+    /// Document title; `None` uses the root's content.
+    pub title: Option<String>,
+    /// Wrap nodes that have children in `<details>` so readers can fold
+    /// them. Folded nodes start closed.
+    pub collapsible: bool,
+    /// Add "Expand all" and "Collapse all" buttons backed by a short inline
+    /// script. Only has an effect together with `collapsible`.
+    pub script: bool,
+    pub include_notes: bool,
+    pub include_archived: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            title: None,
+            collapsible: true,
+            script: false,
+            include_notes: true,
+            include_archived: false,
+        }
+    }
+}
+
+const STYLE: &str = "body { font-family: sans-serif; line-height: 1.4; }
+ul { list-style: none; padding-left: 1.5em; }
+summary { cursor: pointer; }
+.icon { margin-right: 0.25em; }
+.note { color: #555; font-size: 0.9em; margin: 0.2em 0 0.4em; }";
+
+const SCRIPT: &str = "document.querySelectorAll('[data-toggle]').forEach(function (button) {
+  button.addEventListener('click', function () {
+    var open = button.dataset.toggle === 'expand';
+    document.querySelectorAll('details').forEach(function (d) { d.open = open; });
+  });
+});";
+
+enum Step<'a> {
+    Open(&'a Node),
+    Close { details: bool },
+}
+
+pub fn to_html(map: &MindMap) -> String {
+    to_html_with(map, &HtmlOptions::default())
+}
+
+/// Exports the map as a standalone, read-only HTML page: the tree is a
+/// nested `<ul>`, with `<details>` around branches when collapsible.
+/// Known FreeMind icons are shown as emoji, others by name.
+pub fn to_html_with(map: &MindMap, options: &HtmlOptions) -> String {
+    let root = map.nodes.get(&map.root_id);
+    let title = options
+        .title
+        .clone()
+        .or_else(|| root.map(|r| r.content.replace('\n', " ")))
+        .unwrap_or_default();
+
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", escape(&title)));
+    out.push_str(&format!("<style>\n{STYLE}\n</style>\n</head>\n<body>\n"));
+    let script = options.script && options.collapsible;
+    if script {
+        out.push_str(
+            "<p><button type=\"button\" data-toggle=\"expand\">Expand all</button> \
+             <button type=\"button\" data-toggle=\"collapse\">Collapse all</button></p>\n",
+        );
+    }
+
+    out.push_str("<ul class=\"mindmap\">\n");
+    let mut stack: Vec<Step> = root.map(Step::Open).into_iter().collect();
+    while let Some(step) = stack.pop() {
+        let node = match step {
+            Step::Open(node) => node,
+            Step::Close { details } => {
+                out.push_str(if details {
+                    "</ul>\n</details>\n</li>\n"
+                } else {
+                    "</ul>\n</li>\n"
+                });
+                continue;
+            }
+        };
+        let children: Vec<&Node> = node
+            .children
+            .iter()
+            .filter_map(|id| map.nodes.get(id))
+            .filter(|c| options.include_archived || !c.archived)
+            .collect();
+        let details = options.collapsible && !children.is_empty();
+
+        out.push_str("<li>");
+        if details {
+            out.push_str(if node.folded {
+                "<details>\n<summary>"
+            } else {
+                "<details open>\n<summary>"
+            });
+        }
+        write_label(&mut out, node);
+        if details {
+            out.push_str("</summary>");
+        }
+        out.push('\n');
+        if options.include_notes {
+            write_note(&mut out, node);
+        }
+        if children.is_empty() {
+            out.push_str("</li>\n");
+            continue;
+        }
+        out.push_str("<ul>\n");
+        stack.push(Step::Close { details });
+        stack.extend(children.into_iter().rev().map(Step::Open));
+    }
+    out.push_str("</ul>\n");
+
+    if script {
+        out.push_str(&format!("<script>\n{SCRIPT}\n</script>\n"));
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn write_label(out: &mut String, node: &Node) {
+    for icon in &node.icons {
+        let name = escape(icon);
+        match emoji(icon) {
+            Some(emoji) => out.push_str(&format!(
+                "<span class=\"icon\" title=\"{name}\">{emoji}</span>"
+            )),
+            None => out.push_str(&format!("<span class=\"icon\">[{name}]</span>")),
+        }
+    }
+    let content = escape(&node.content).replace('\n', "<br>");
+    match node.link.as_deref().filter(|link| is_safe_link(link)) {
+        Some(link) => out.push_str(&format!("<a href=\"{}\">{content}</a>", escape(link))),
+        None => out.push_str(&content),
+    }
+}
+
+/// Whether a link may go into an `href`: http, https and mailto URLs and
+/// relative ones. Links come from imported files, so `javascript:` and the
+/// like are written as plain text. Browsers ignore whitespace and control
+/// characters in the scheme, so those are dropped before looking at it.
+fn is_safe_link(link: &str) -> bool {
+    let link: String = link
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    match link.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => ["http", "https", "mailto"]
+            .iter()
+            .any(|safe| scheme.eq_ignore_ascii_case(safe)),
+        _ => true,
+    }
+}
+
+fn write_note(out: &mut String, node: &Node) {
+    if let Some(note) = node.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        let note = escape(note.trim()).replace('\n', "<br>");
+        out.push_str(&format!("<div class=\"note\">{note}</div>\n"));
+    }
+}
+
+/// Emoji for FreeMind's built-in icons.
+fn emoji(icon: &str) -> Option<&'static str> {
+    Some(match icon {
+        "idea" => "💡",
+        "help" => "❓",
+        "yes" => "❗",
+        "messagebox_warning" => "⚠️",
+        "stop-sign" => "⛔",
+        "button_ok" => "✅",
+        "button_cancel" => "❌",
+        "go" => "🟢",
+        "prepare" => "🟡",
+        "stop" => "🔴",
+        "full-1" => "1️⃣",
+        "full-2" => "2️⃣",
+        "full-3" => "3️⃣",
+        "full-4" => "4️⃣",
+        "full-5" => "5️⃣",
+        "full-6" => "6️⃣",
+        "full-7" => "7️⃣",
+        "full-8" => "8️⃣",
+        "full-9" => "9️⃣",
+        "flag" => "🚩",
+        "bookmark" => "🔖",
+        "attach" => "📎",
+        "clock" => "⏰",
+        "calendar" => "📅",
+        "password" => "🔑",
+        "list" => "📋",
+        "edit" | "pencil" => "✏️",
+        "info" => "ℹ️",
+        "ksmiletris" => "🙂",
+        "smily_bad" => "🙁",
+        "family" => "👪",
+        "gohome" => "🏠",
+        "Mail" => "✉️",
+        "bell" => "🔔",
+        "launch" => "🚀",
+        "forward" => "➡️",
+        "back" => "⬅️",
+        "up" => "⬆️",
+        "down" => "⬇️",
+        "xmag" => "🔍",
+        "clanbomber" => "💣",
+        "wizard" => "🪄",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_html() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Plan <v2>").unwrap();
+        let a = map.add_child(&root_id, "Research & design").unwrap();
+        map.nodes.get_mut(&a).unwrap().icons = vec!["idea".to_string(), "custom".to_string()];
+        map.set_note(&a, "First\nsecond").unwrap();
+        let a1 = map.add_child(&a, "Papers").unwrap();
+        map.set_link(&a1, "https://example.com/?a=1&b=2").unwrap();
+        map.set_folded(&a, true).unwrap();
+        let old = map.add_child(&root_id, "Old").unwrap();
+        map.archive(&old).unwrap();
+
+        let html = to_html(&map);
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<title>Plan &lt;v2&gt;</title>"));
+        assert!(html.contains(
+            "<ul class=\"mindmap\">\n<li><details open>\n<summary>Plan &lt;v2&gt;</summary>\n<ul>\n\
+             <li><details>\n<summary><span class=\"icon\" title=\"idea\">💡</span>\
+             <span class=\"icon\">[custom]</span>Research &amp; design</summary>\n\
+             <div class=\"note\">First<br>second</div>\n<ul>\n\
+             <li><a href=\"https://example.com/?a=1&amp;b=2\">Papers</a>\n</li>\n\
+             </ul>\n</details>\n</li>\n</ul>\n</details>\n</li>\n</ul>\n"
+        ));
+        assert!(!html.contains("Old"));
+        assert!(!html.contains("<script>"));
+
+        let options = HtmlOptions {
+            collapsible: false,
+            script: true,
+            ..Default::default()
+        };
+        let html = to_html_with(&map, &options);
+        assert!(!html.contains("<details"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("<li>Plan &lt;v2&gt;\n<ul>\n"));
+
+        let options = HtmlOptions {
+            script: true,
+            ..Default::default()
+        };
+        assert!(to_html_with(&map, &options).contains("<script>\n"));
+
+        for (link, linked) in [
+            (" Java\tScript:alert(1)", false),
+            ("data:text/html,hi", false),
+            ("MAILTO:me@example.com", true),
+            ("notes/a.html#b:c", true),
+        ] {
+            map.set_link(&a1, link).unwrap();
+            assert_eq!(to_html(&map).contains("<a href="), linked, "{link}");
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use crate::csv::{self, CsvOptions};
+use crate::github::{self, IssueGroup};
+use crate::groups::Group;
+use crate::limits::MapLimits;
+use crate::paste::Subtree;
+use crate::progress::Monitor;
+use crate::relationship::Relationship;
+use crate::summaries::Summary;
+use crate::{BrainCoreError, MindMap};
+use crate::{
+    canvas, enex, itmz, jira, json, markdown, mindnode, mmap, notion, opml, smmx, storage,
+    taskpaper, trello, xmind,
+};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use uuid::Uuid;
+
+/// Options shared by all importers. `Default` cleans up node text without
+/// changing its layout; use [`ImportOptions::raw`] to keep it exactly as
+/// stored in the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportOptions {
+    /// Drop control characters and byte order marks. Line breaks are kept,
+    /// with `\r\n` and lone `\r` turned into `\n`.
+    pub strip_control: bool,
+    /// Normalize text to Unicode NFC so visually identical strings compare,
+    /// search and export the same.
+    pub normalize_nfc: bool,
+    /// Trim lines, collapse runs of spaces and tabs to a single space and
+    /// squeeze consecutive blank lines into one. Non-breaking spaces are
+    /// left alone since they are usually intentional. Off by default, as
+    /// it would reflow notes and multi-line text on every load.
+    pub collapse_whitespace: bool,
+    /// Limits the imported map must satisfy. They stay set on the map so
+    /// later edits are held to them too.
+    pub limits: MapLimits,
+    /// Keep the attributes of FreeMind nodes and OPML outlines that the
+    /// crate doesn't understand and write them back when the map is saved
+    /// in the same format, so tools' own settings survive an edit. Unknown
+    /// FreeMind elements are kept either way.
+    pub preserve_unknown: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            strip_control: true,
+            normalize_nfc: true,
+            collapse_whitespace: false,
+            limits: MapLimits::default(),
+            preserve_unknown: false,
+        }
+    }
+}
+
+impl ImportOptions {
+    /// Leave imported text untouched.
+    pub fn raw() -> Self {
+        Self {
+            strip_control: false,
+            normalize_nfc: false,
+            collapse_whitespace: false,
+            limits: MapLimits::default(),
+            preserve_unknown: false,
+        }
+    }
+
+    /// Applies the enabled passes to a single string.
+    pub fn sanitize(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        if self.strip_control {
+            text = text
+                .replace("\r\n", "\n")
+                .replace('\r', "\n")
+                .chars()
+                .filter(|c| *c == '\n' || *c == '\t' || !(c.is_control() || *c == '\u{feff}'))
+                .collect();
+        }
+        if self.normalize_nfc {
+            text = text.nfc().collect();
+        }
+        if self.collapse_whitespace {
+            text = collapse_whitespace(&text);
+        }
+        text
+    }
+
+    /// Sanitizes the content and note of every node in an imported map,
+    /// then checks the result against the limits.
+    pub(crate) fn apply(&self, map: &mut MindMap) -> Result<(), BrainCoreError> {
+        if self.strip_control || self.normalize_nfc || self.collapse_whitespace {
+            for node in map.nodes.values_mut() {
+                node.content = self.sanitize(&node.content);
+                if let Some(note) = &node.note {
+                    node.note = Some(self.sanitize(note)).filter(|n| !n.is_empty());
+                }
+            }
+        }
+        self.limits.check(map)?;
+        map.set_limits(self.limits);
+        Ok(())
+    }
+}
+
+/// File formats that can be imported into an existing map with
+/// [`MindMap::import_subtree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImportFormat {
+    FreeMind,
+    Opml,
+    Xmind,
+    MindManager,
+    MindNode,
+    SimpleMind,
+    Evernote,
+    Notion,
+    Trello,
+    GithubIssues,
+    Jira,
+    Markdown,
+    Csv,
+    Json,
+    Itmz,
+    Canvas,
+    TaskPaper,
+}
+
+impl ImportFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportFormat::FreeMind => "FreeMind",
+            ImportFormat::Opml => "OPML",
+            ImportFormat::Xmind => "XMind",
+            ImportFormat::MindManager => "MindManager",
+            ImportFormat::MindNode => "MindNode",
+            ImportFormat::SimpleMind => "SimpleMind",
+            ImportFormat::Evernote => "Evernote",
+            ImportFormat::Notion => "Notion",
+            ImportFormat::Trello => "Trello",
+            ImportFormat::GithubIssues => "GitHub issues",
+            ImportFormat::Jira => "Jira",
+            ImportFormat::Markdown => "Markdown",
+            ImportFormat::Csv => "CSV",
+            ImportFormat::Json => "JSON",
+            ImportFormat::Itmz => "iThoughts",
+            ImportFormat::Canvas => "Obsidian Canvas",
+            ImportFormat::TaskPaper => "TaskPaper",
+        }
+    }
+
+    /// Runs the format's importer. Text formats must be UTF-8.
+    pub fn import(self, data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+        self.import_monitored(data, options, &mut Monitor::default())
+    }
+
+    /// Runs the format's importer, reporting progress to `monitor` and
+    /// stopping with [`BrainCoreError::Cancelled`] when it is cancelled.
+    pub fn import_monitored(
+        self,
+        data: &[u8],
+        options: &ImportOptions,
+        monitor: &mut Monitor,
+    ) -> Result<MindMap, BrainCoreError> {
+        let text = || std::str::from_utf8(data).map_err(|e| BrainCoreError::utf8(self, e));
+        match self {
+            ImportFormat::FreeMind => storage::from_xml_monitored(text()?, options, monitor),
+            ImportFormat::Opml => opml::from_opml_monitored(text()?, options, monitor),
+            ImportFormat::Xmind => xmind::from_xmind_monitored(data, options, monitor),
+            ImportFormat::MindManager => mmap::from_mmap_monitored(data, options, monitor),
+            ImportFormat::MindNode => mindnode::from_mindnode_monitored(data, options, monitor),
+            ImportFormat::SimpleMind => smmx::from_smmx_monitored(text()?, options, monitor),
+            ImportFormat::Evernote => enex::from_enex_monitored(text()?, options, monitor),
+            ImportFormat::Notion => notion::from_notion_monitored(data, options, monitor),
+            ImportFormat::Trello => trello::from_trello_monitored(text()?, options, monitor),
+            ImportFormat::GithubIssues => github::from_github_issues_monitored(
+                text()?,
+                &[IssueGroup::Milestone],
+                options,
+                monitor,
+            ),
+            ImportFormat::Jira => jira::from_jira_monitored(text()?, options, monitor),
+            ImportFormat::Markdown => markdown::from_markdown_monitored(text()?, options, monitor),
+            ImportFormat::Csv => {
+                csv::from_csv_monitored(data, &CsvOptions::default(), options, monitor)
+            }
+            ImportFormat::Json => json::from_json_monitored(text()?, options, monitor),
+            ImportFormat::Itmz => itmz::from_itmz_monitored(data, options, monitor),
+            ImportFormat::Canvas => canvas::from_canvas_monitored(text()?, options, monitor),
+            ImportFormat::TaskPaper => {
+                taskpaper::from_taskpaper_monitored(text()?, options, monitor)
+            }
+        }
+    }
+}
+
+impl MindMap {
+    /// Imports a document and attaches its root, with fresh ids, as the last
+    /// child of `parent_id`. Returns the new id of the imported root. The
+    /// map's validators and limits apply as for [`MindMap::paste_subtree`].
+    ///
+    /// Attached files come along, and so do the document's relationships,
+    /// groups and summaries, with fresh ids. Those touching a floating node
+    /// of the document are left out with the floating trees.
+    pub fn import_subtree(
+        &mut self,
+        parent_id: &str,
+        data: &[u8],
+        format: ImportFormat,
+    ) -> Result<String, BrainCoreError> {
+        let mut imported = format.import(data, &ImportOptions::default())?;
+        let root = imported.root_id.clone();
+        let relationships = std::mem::take(&mut imported.relationships);
+        let groups = std::mem::take(&mut imported.groups);
+        let summaries = std::mem::take(&mut imported.summaries);
+        let (roots, ids) = self.graft(parent_id, Subtree::from_map(imported, vec![root]), false)?;
+
+        let new_id = |id: &String| ids.get(id).cloned();
+        for r in relationships {
+            if let (Some(from), Some(to)) = (new_id(&r.from), new_id(&r.to)) {
+                self.relationships.push(Relationship {
+                    id: Uuid::new_v4().to_string(),
+                    from,
+                    to,
+                    ..r
+                });
+            }
+        }
+        for g in groups {
+            if let Some(node_id) = new_id(&g.node_id) {
+                self.groups.push(Group {
+                    id: Uuid::new_v4().to_string(),
+                    node_id,
+                    ..g
+                });
+            }
+        }
+        for s in summaries {
+            if let (Some(parent_id), Some(first_child), Some(last_child)) = (
+                new_id(&s.parent_id),
+                new_id(&s.first_child),
+                new_id(&s.last_child),
+            ) {
+                self.summaries.push(Summary {
+                    id: Uuid::new_v4().to_string(),
+                    parent_id,
+                    first_child,
+                    last_child,
+                    ..s
+                });
+            }
+        }
+        roots
+            .into_iter()
+            .next()
+            .ok_or_else(|| BrainCoreError::InvalidOperation("Nothing was imported".to_string()))
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let words: Vec<&str> = line
+            .split(|c: char| c.is_whitespace() && c != '\u{a0}')
+            .filter(|w| !w.is_empty())
+            .collect();
+        let line = words.join(" ");
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    if lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::opml;
+
+    #[test]
+    fn test_import_subtree() {
+        let mut source = MindMap::new();
+        let source_root = source.root_id.clone();
+        source.nodes.get_mut(&source_root).unwrap().content = "Branch".to_string();
+        source.add_child(&source_root, "Leaf").unwrap();
+        let xml = storage::to_xml(&source).unwrap();
+
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let first = map
+            .import_subtree(&root_id, xml.as_bytes(), ImportFormat::FreeMind)
+            .unwrap();
+        let second = map
+            .import_subtree(&root_id, xml.as_bytes(), ImportFormat::FreeMind)
+            .unwrap();
+
+        assert_eq!(map.nodes.len(), 5);
+        assert_eq!(map.nodes[&root_id].children, vec![first.clone(), second]);
+        let branch = &map.nodes[&first];
+        assert_eq!(branch.content, "Branch");
+        assert_eq!(branch.parent.as_deref(), Some(root_id.as_str()));
+        assert_eq!(map.nodes[&branch.children[0]].content, "Leaf");
+        assert!(!map.nodes.contains_key(&source_root));
+        assert!(
+            map.import_subtree(&root_id, b"not a zip", ImportFormat::Xmind)
+                .is_err()
+        );
+
+        // Attached files, relationships, groups and summaries come along.
+        let mut source = MindMap::new();
+        let source_root = source.root_id.clone();
+        let a = source.add_child(&source_root, "A").unwrap();
+        let b = source.add_child(&source_root, "B").unwrap();
+        source.attach(&a, "photo.png", b"png".to_vec()).unwrap();
+        source.add_relationship(&a, &b, None).unwrap();
+        source.add_group(&b, None).unwrap();
+        source.add_summary(&source_root, 0, 1, "Both").unwrap();
+        let xmind = crate::xmind::to_xmind(&source).unwrap();
+
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let imported = map
+            .import_subtree(&root_id, &xmind, ImportFormat::Xmind)
+            .unwrap();
+        let children = map.nodes[&imported].children.clone();
+        let attachment = &map.nodes[&children[0]].attachments[0];
+        assert_eq!(map.attachment_data(attachment), Some(&b"png"[..]));
+        assert_eq!(map.relationships.len(), 1);
+        assert_eq!(map.relationships[0].from, children[0]);
+        assert_eq!(map.relationships[0].to, children[1]);
+        assert_eq!(map.groups.len(), 1);
+        assert_eq!(map.groups[0].node_id, children[1]);
+        assert_eq!(map.summaries.len(), 1);
+        assert_eq!(map.summaries[0].parent_id, imported);
+        assert_eq!(map.summaries[0].first_child, children[0]);
+        assert_eq!(map.summaries[0].last_child, children[1]);
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let text = "\u{feff}  Cafe\u{301}\u{7}\tau\u{a0}lait  \r\n\r\n\r\nnext\n\n";
+        assert_eq!(
+            ImportOptions::default().sanitize(text),
+            "  Caf\u{e9}\tau\u{a0}lait  \n\n\nnext\n\n"
+        );
+        let options = ImportOptions {
+            collapse_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(options.sanitize(text), "Caf\u{e9} au\u{a0}lait\n\nnext");
+        assert_eq!(ImportOptions::raw().sanitize("a\u{7}  b"), "a\u{7}  b");
+
+        let xml = "<opml version=\"2.0\"><head><title>T</title></head><body>\
+                   <outline text=\"  Re\u{301}sume\u{301}   draft \"/></body></opml>";
+        let map = opml::from_opml_with(xml, &options).unwrap();
+        assert_eq!(map.nodes[&map.root_id].content, "R\u{e9}sum\u{e9} draft");
+        let map = opml::from_opml(xml).unwrap();
+        assert_eq!(
+            map.nodes[&map.root_id].content,
+            "  R\u{e9}sum\u{e9}   draft "
+        );
+        let raw = opml::from_opml_with(xml, &ImportOptions::raw()).unwrap();
+        assert_eq!(
+            raw.nodes[&raw.root_id].content,
+            "  Re\u{301}sume\u{301}   draft "
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use crate::timestamp::now_millis;
+use crate::{MindMap, Node};
+use std::collections::HashSet;
+
+/// A broken invariant of the node tree, as found by [`MindMap::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Issue {
+    /// `root_id` names no node.
+    MissingRoot,
+    /// The root has a parent.
+    RootHasParent,
+    /// A parent lists a child id that names no node.
+    DanglingChild { parent_id: String, child_id: String },
+    /// A parent lists a child whose `parent` points elsewhere.
+    WrongParent { parent_id: String, child_id: String },
+    /// A node's parent exists but doesn't list it as a child.
+    UnlistedChild { node_id: String, parent_id: String },
+    /// A subtree that can't be reached from the root because its top node
+    /// has no parent, or a parent that doesn't exist.
+    Orphan(String),
+    /// Nodes whose parent links go round in a loop. Identified by the
+    /// smallest id in the loop.
+    Cycle(String),
+    /// An entry of `floating_roots` that names no node, a node with a
+    /// parent, the root, or a node listed earlier.
+    InvalidFloatingRoot(String),
+}
+
+/// Why a node can't be reached from the root.
+enum Detached {
+    Orphan(String),
+    Cycle(String),
+    /// Its parent chain reaches the tree, but some parent doesn't list it.
+    Unlisted,
+}
+
+impl MindMap {
+    /// Checks the parent and child links of every node and lists what is
+    /// wrong with them, ordered by node id. Maps built through the editing
+    /// API stay valid; imports from other tools often don't.
+    pub fn validate(&self) -> Vec<Issue> {
+        let mut issues = Vec::new();
+        match self.nodes.get(&self.root_id) {
+            None => issues.push(Issue::MissingRoot),
+            Some(root) if root.parent.is_some() => issues.push(Issue::RootHasParent),
+            Some(_) => {}
+        }
+        for (index, id) in self.floating_roots.iter().enumerate() {
+            if !self.is_valid_floating_root(index) {
+                issues.push(Issue::InvalidFloatingRoot(id.clone()));
+            }
+        }
+
+        for id in self.sorted_ids() {
+            let node = &self.nodes[id];
+            for child_id in &node.children {
+                let issue = match self.nodes.get(child_id) {
+                    None => Issue::DanglingChild {
+                        parent_id: id.clone(),
+                        child_id: child_id.clone(),
+                    },
+                    Some(child) if child.parent.as_ref() != Some(id) => Issue::WrongParent {
+                        parent_id: id.clone(),
+                        child_id: child_id.clone(),
+                    },
+                    Some(_) => continue,
+                };
+                issues.push(issue);
+            }
+            if let Some(parent_id) = &node.parent
+                && self
+                    .nodes
+                    .get(parent_id)
+                    .is_some_and(|p| !p.children.contains(id))
+            {
+                issues.push(Issue::UnlistedChild {
+                    node_id: id.clone(),
+                    parent_id: parent_id.clone(),
+                });
+            }
+        }
+
+        let reachable = self.reachable();
+        let mut seen = HashSet::new();
+        for id in self.sorted_ids() {
+            if reachable.contains(id.as_str()) {
+                continue;
+            }
+            let issue = match self.detached(id, &reachable) {
+                Detached::Orphan(top) => Issue::Orphan(top),
+                Detached::Cycle(min) => Issue::Cycle(min),
+                Detached::Unlisted => continue,
+            };
+            if seen.insert(issue.clone()) {
+                issues.push(issue);
+            }
+        }
+        issues
+    }
+
+    /// Fixes what [`MindMap::validate`] reports and returns the issues that
+    /// were found. Dangling child ids are dropped, a child listed by a
+    /// parent it doesn't point to is unlisted there, and a node missing
+    /// from its parent's children is appended to them. Invalid floating
+    /// roots are dropped from the list. Orphaned subtrees and loops are
+    /// reattached under the root, which is recreated if it is missing.
+    pub fn repair(&mut self) -> Vec<Issue> {
+        let issues = self.validate();
+        if issues.is_empty() {
+            return issues;
+        }
+        let now = now_millis();
+        let root_id = self.root_id.clone();
+        let root = self.nodes.entry(root_id.clone()).or_insert_with(|| Node {
+            id: root_id.clone(),
+            content: "Central Node".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        });
+        root.parent = None;
+
+        let ids: Vec<String> = self.sorted_ids().into_iter().cloned().collect();
+        // A listed child without a valid parent of its own belongs to the
+        // first parent that lists it.
+        for id in &ids {
+            for child_id in self.nodes[id].children.clone() {
+                let needs_parent = self.nodes.get(&child_id).is_some_and(|c| {
+                    c.id != root_id
+                        && c.parent
+                            .as_ref()
+                            .is_none_or(|p| !self.nodes.contains_key(p))
+                });
+                if needs_parent && let Some(child) = self.nodes.get_mut(&child_id) {
+                    child.parent = Some(id.clone());
+                }
+            }
+        }
+        // From here on `parent` is authoritative.
+        for id in &ids {
+            let mut children = std::mem::take(&mut self.nodes.get_mut(id).unwrap().children);
+            let mut listed = HashSet::new();
+            children.retain(|c| {
+                self.nodes
+                    .get(c)
+                    .is_some_and(|n| n.parent.as_ref() == Some(id))
+                    && listed.insert(c.clone())
+            });
+            self.nodes.get_mut(id).unwrap().children = children;
+        }
+        for id in &ids {
+            let Some(parent_id) = self.nodes[id].parent.clone() else {
+                continue;
+            };
+            if let Some(parent) = self.nodes.get_mut(&parent_id)
+                && !parent.children.contains(id)
+            {
+                parent.children.push(id.clone());
+            }
+        }
+        let mut index = 0;
+        while index < self.floating_roots.len() {
+            if self.is_valid_floating_root(index) {
+                index += 1;
+            } else {
+                self.floating_roots.remove(index);
+            }
+        }
+
+        loop {
+            let reachable = self.reachable();
+            let Some(top) = ids
+                .iter()
+                .filter(|id| !reachable.contains(id.as_str()))
+                .find_map(|id| match self.detached(id, &reachable) {
+                    Detached::Orphan(top) | Detached::Cycle(top) => Some(top),
+                    Detached::Unlisted => None,
+                })
+            else {
+                break;
+            };
+            if let Some(old_parent) = self.nodes[&top].parent.clone()
+                && let Some(parent) = self.nodes.get_mut(&old_parent)
+            {
+                parent.children.retain(|c| *c != top);
+            }
+            if let Some(node) = self.nodes.get_mut(&top) {
+                node.parent = Some(root_id.clone());
+            }
+            if let Some(root) = self.nodes.get_mut(&root_id) {
+                root.children.push(top);
+            }
+        }
+
+        if !self.nodes.contains_key(&self.selection.primary) {
+            self.set_selected(root_id.clone());
+        }
+        self.prune_selection();
+        if let Some(root) = self.nodes.get_mut(&root_id) {
+            root.modified = now;
+            root.modified_by = self.author.clone();
+        }
+        self.bump_revision(&root_id);
+        issues
+    }
+
+    fn sorted_ids(&self) -> Vec<&String> {
+        let mut ids: Vec<&String> = self.nodes.keys().collect();
+        ids.sort();
+        ids
+    }
+
+    fn is_valid_floating_root(&self, index: usize) -> bool {
+        let id = &self.floating_roots[index];
+        *id != self.root_id
+            && self.nodes.get(id).is_some_and(|n| n.parent.is_none())
+            && !self.floating_roots[..index].contains(id)
+    }
+
+    /// Ids reachable from the root and the floating roots through child
+    /// lists.
+    fn reachable(&self) -> HashSet<&str> {
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<&str> = self.tree_roots().map(String::as_str).collect();
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes.get(id)
+                && reachable.insert(id)
+            {
+                stack.extend(node.children.iter().map(String::as_str));
+            }
+        }
+        reachable
+    }
+
+    /// Follows parent links up from an unreachable node to find out why it
+    /// is cut off from the tree.
+    fn detached(&self, node_id: &str, reachable: &HashSet<&str>) -> Detached {
+        let mut path: Vec<&str> = vec![node_id];
+        loop {
+            let current = path[path.len() - 1];
+            let parent = self.nodes[current].parent.as_deref();
+            match parent.filter(|p| self.nodes.contains_key(*p)) {
+                None => return Detached::Orphan(current.to_string()),
+                Some(p) if reachable.contains(p) => return Detached::Unlisted,
+                Some(p) => match path.iter().position(|id| *id == p) {
+                    Some(start) => {
+                        let min = path[start..].iter().min().unwrap_or(&p);
+                        return Detached::Cycle(min.to_string());
+                    }
+                    None => path.push(p),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: &str, parent: Option<&str>, children: &[&str]) -> Node {
+        Node {
+            id: id.to_string(),
+            content: id.to_string(),
+            parent: parent.map(str::to_string),
+            children: children.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_validate_and_repair() {
+        let nodes = [
+            node("root", None, &["a", "ghost", "c"]),
+            node("a", Some("root"), &[]),
+            node("b", Some("root"), &[]),
+            node("c", Some("a"), &[]),
+            node("d", Some("missing"), &["e"]),
+            node("e", Some("d"), &[]),
+            node("x", Some("y"), &["y"]),
+            node("y", Some("x"), &["x"]),
+        ];
+        let mut map = MindMap::from_nodes(
+            nodes.into_iter().map(|n| (n.id.clone(), n)).collect(),
+            "root".to_string(),
+        );
+        assert!(MindMap::new().validate().is_empty());
+
+        let issues = map.validate();
+        let wrong = |parent: &str, child: &str| Issue::WrongParent {
+            parent_id: parent.to_string(),
+            child_id: child.to_string(),
+        };
+        assert_eq!(
+            issues,
+            vec![
+                Issue::UnlistedChild {
+                    node_id: "b".to_string(),
+                    parent_id: "root".to_string(),
+                },
+                Issue::UnlistedChild {
+                    node_id: "c".to_string(),
+                    parent_id: "a".to_string(),
+                },
+                Issue::DanglingChild {
+                    parent_id: "root".to_string(),
+                    child_id: "ghost".to_string(),
+                },
+                wrong("root", "c"),
+                Issue::Orphan("d".to_string()),
+                Issue::Cycle("x".to_string()),
+            ]
+        );
+
+        assert_eq!(map.repair(), issues);
+        assert!(map.validate().is_empty());
+        assert_eq!(map.nodes["root"].children, vec!["a", "b", "d", "x"]);
+        assert_eq!(map.nodes["a"].children, vec!["c"]);
+        assert_eq!(map.nodes["x"].children, vec!["y"]);
+        assert!(map.repair().is_empty());
+
+        map.nodes.remove("root");
+        assert_eq!(map.validate()[0], Issue::MissingRoot);
+        map.repair();
+        assert!(map.validate().is_empty());
+        assert_eq!(map.nodes.len(), 8);
+
+        map.nodes.insert("f".to_string(), node("f", None, &[]));
+        map.floating_roots = vec!["f".to_string(), "a".to_string(), "f".to_string()];
+        assert_eq!(
+            map.validate(),
+            vec![
+                Issue::InvalidFloatingRoot("a".to_string()),
+                Issue::InvalidFloatingRoot("f".to_string()),
+            ]
+        );
+        map.repair();
+        assert_eq!(map.floating_roots, vec!["f"]);
+        assert!(map.validate().is_empty());
+    }
+}
@@ -0,0 +1,343 @@
+use crate::export::{ExportOptions, TextWriter};
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::style::NodeStyle;
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node, html};
+use quick_xml::de::from_str;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Write};
+use uuid::Uuid;
+use zip::{ZipArchive, ZipWriter};
+
+// iThoughts documents are zip archives with the map in mapdata.xml. Topics
+// nest directly inside each other; the first top-level topic is the
+// central one and any others are floating topics.
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "iThoughts")]
+pub struct ItmzDocument {
+    #[serde(rename = "@version", default)]
+    pub version: String,
+    #[serde(rename = "topics")]
+    pub topics: ItmzTopics,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ItmzTopics {
+    #[serde(rename = "topic", default)]
+    pub topic: Vec<ItmzTopic>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct ItmzTopic {
+    #[serde(rename = "@uuid", default)]
+    pub uuid: String,
+    #[serde(rename = "@text", default)]
+    pub text: String,
+    #[serde(rename = "@note", default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(rename = "@link", default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
+    /// Fill colour as six hex digits, without a `#`.
+    #[serde(rename = "@color", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// Percent complete, 0 to 100.
+    #[serde(
+        rename = "@task-progress",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub task_progress: Option<String>,
+    #[serde(rename = "@folded", default, skip_serializing_if = "Option::is_none")]
+    pub folded: Option<String>,
+    /// `{x, y}`; kept for floating topics, which sit where they were put.
+    #[serde(rename = "@position", default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,
+    #[serde(rename = "topic", default)]
+    pub children: Vec<ItmzTopic>,
+}
+
+pub fn to_itmz(map: &MindMap) -> Result<Vec<u8>, BrainCoreError> {
+    to_itmz_with(map, &ExportOptions::default())
+}
+
+/// Writes an iThoughts archive. Topic ids are the node ids; the background
+/// colour becomes the topic colour and task completion its progress.
+/// Floating nodes follow the central topic as top-level topics with their
+/// positions.
+pub fn to_itmz_with(map: &MindMap, options: &ExportOptions) -> Result<Vec<u8>, BrainCoreError> {
+    to_itmz_monitored(map, options, &mut Monitor::default())
+}
+
+pub fn to_itmz_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<Vec<u8>, BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+    monitor.begin(Stage::WritingNodes, Some(options.exported_count(map, true)))?;
+    let mut topics = vec![node_to_topic(root, map, options, monitor)];
+    for node in options.exported_floating(map) {
+        if monitor.should_stop() {
+            break;
+        }
+        topics.push(ItmzTopic {
+            position: Some(format!("{{{}, {}}}", node.x, node.y)),
+            ..node_to_topic(node, map, options, monitor)
+        });
+    }
+    monitor.finish()?;
+    let document = ItmzDocument {
+        version: "5.0".to_string(),
+        topics: ItmzTopics { topic: topics },
+    };
+
+    let mut buf = Vec::new();
+    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+    zip.start_file(
+        "mapdata.xml",
+        options.zip_options(zip::CompressionMethod::Deflated),
+    )?;
+    zip.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    let mut text = TextWriter::new(&mut zip);
+    let result = options.write_xml(&document, &mut html::AttributeEncoder::new(&mut text));
+    text.check(result)?;
+    zip.finish()?;
+    Ok(buf)
+}
+
+fn node_to_topic(
+    node: &Node,
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> ItmzTopic {
+    monitor.tick();
+    let mut children = Vec::new();
+    for child in node
+        .children
+        .iter()
+        .filter_map(|id| map.nodes.get(id))
+        .filter(|c| options.includes(c))
+    {
+        if monitor.should_stop() {
+            break;
+        }
+        children.push(node_to_topic(child, map, options, monitor));
+    }
+    ItmzTopic {
+        uuid: node.id.clone(),
+        text: node.content.clone(),
+        note: node.note.clone(),
+        link: node.link.clone(),
+        color: node
+            .style
+            .background_color
+            .as_deref()
+            .map(|c| c.trim_start_matches('#').to_string()),
+        task_progress: node
+            .task
+            .as_ref()
+            .map(|t| if t.done { 100 } else { t.progress_pct.min(100) }.to_string()),
+        folded: node.folded.then(|| "1".to_string()),
+        position: None,
+        children,
+    }
+}
+
+pub fn from_itmz(data: &[u8]) -> Result<MindMap, BrainCoreError> {
+    from_itmz_with(data, &ImportOptions::default())
+}
+
+/// Reads an iThoughts archive. Top-level topics after the central one
+/// become floating nodes at their positions. Topics get fresh ids.
+pub fn from_itmz_with(data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_itmz_monitored(data, options, &mut Monitor::default())
+}
+
+pub fn from_itmz_monitored(
+    data: &[u8],
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    let mut xml = String::new();
+    archive
+        .by_name("mapdata.xml")
+        .map_err(|_| BrainCoreError::parse(ImportFormat::Itmz, "mapdata.xml not found in archive"))?
+        .read_to_string(&mut xml)?;
+    let document: ItmzDocument =
+        from_str(&xml).map_err(|e| BrainCoreError::parse(ImportFormat::Itmz, e))?;
+
+    let mut topics = document.topics.topic.iter();
+    let Some(central) = topics.next() else {
+        return Err(BrainCoreError::parse(
+            ImportFormat::Itmz,
+            "Document has no topics",
+        ));
+    };
+    let mut nodes = HashMap::new();
+    let now = now_millis();
+    let total = document.topics.topic.iter().map(count_topics).sum();
+    monitor.begin(Stage::BuildingNodes, Some(total))?;
+    let root_id = topic_to_node(central, None, &mut nodes, now, monitor);
+    let mut floating_roots = Vec::new();
+    for floating in topics {
+        if monitor.should_stop() {
+            break;
+        }
+        let id = topic_to_node(floating, None, &mut nodes, now, monitor);
+        if let Some((x, y)) = floating.position.as_deref().and_then(parse_position) {
+            let node = nodes.get_mut(&id).unwrap();
+            (node.x, node.y) = (x, y);
+        }
+        floating_roots.push(id);
+    }
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    map.floating_roots = floating_roots;
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+/// Reads `{x, y}`.
+fn parse_position(position: &str) -> Option<(f32, f32)> {
+    let inner = position.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let (x, y) = inner.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
+
+fn count_topics(topic: &ItmzTopic) -> usize {
+    1 + topic.children.iter().map(count_topics).sum::<usize>()
+}
+
+fn topic_to_node(
+    topic: &ItmzTopic,
+    parent_id: Option<&str>,
+    nodes: &mut HashMap<String, Node>,
+    now: u64,
+    monitor: &mut Monitor,
+) -> String {
+    monitor.tick();
+    let id = Uuid::new_v4().to_string();
+    let mut children = Vec::new();
+    for child in &topic.children {
+        if monitor.should_stop() {
+            break;
+        }
+        children.push(topic_to_node(child, Some(&id), nodes, now, monitor));
+    }
+    let progress: Option<u8> = topic
+        .task_progress
+        .as_deref()
+        .and_then(|p| p.trim().parse::<f32>().ok())
+        .filter(|p| *p >= 0.0)
+        .map(|p| p.min(100.0) as u8);
+    let node = Node {
+        id: id.clone(),
+        content: topic.text.clone(),
+        children,
+        parent: parent_id.map(str::to_string),
+        created: now,
+        modified: now,
+        note: topic.note.clone().filter(|n| !n.is_empty()),
+        link: topic.link.clone().filter(|l| !l.is_empty()),
+        folded: matches!(topic.folded.as_deref(), Some("1" | "true")),
+        style: NodeStyle {
+            background_color: topic
+                .color
+                .as_deref()
+                .filter(|c| !c.is_empty())
+                .map(|c| format!("#{}", c.trim_start_matches('#'))),
+            ..Default::default()
+        },
+        task: progress.map(|progress| TaskInfo {
+            done: progress == 100,
+            progress_pct: progress,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    nodes.insert(id.clone(), node);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_itmz_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Launch").unwrap();
+        let a = map.add_child(&root_id, "Website").unwrap();
+        map.set_note(&a, "Landing page").unwrap();
+        map.nodes.get_mut(&a).unwrap().style.background_color = Some("#3b84c6".to_string());
+        map.nodes.get_mut(&a).unwrap().task = Some(TaskInfo {
+            progress_pct: 40,
+            ..Default::default()
+        });
+        let b = map.add_child(&root_id, "Press").unwrap();
+        map.nodes.get_mut(&b).unwrap().task = Some(TaskInfo {
+            done: true,
+            ..Default::default()
+        });
+
+        let loaded = from_itmz(&to_itmz(&map).unwrap()).unwrap();
+        let root = &loaded.nodes[&loaded.root_id];
+        assert_eq!(root.content, "Launch");
+        let website = &loaded.nodes[&root.children[0]];
+        assert_eq!(website.note.as_deref(), Some("Landing page"));
+        assert_eq!(website.style.background_color.as_deref(), Some("#3b84c6"));
+        assert_eq!(website.task.as_ref().unwrap().progress_pct, 40);
+        assert!(loaded.nodes[&root.children[1]].task.as_ref().unwrap().done);
+    }
+
+    #[test]
+    fn test_floating_topics() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<iThoughts version="5.0" app="iThoughts">
+<topics>
+<topic uuid="A" text="Central" position="{0, 0}"><topic uuid="B" text="Child" color="ff0000"/></topic>
+<topic uuid="C" text="Floating" position="{300, 0}" task-progress="-1"/>
+</topics>
+<relationships/>
+</iThoughts>"#;
+        let mut buf = Vec::new();
+        let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+        zip.start_file("mapdata.xml", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(xml.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let map = from_itmz(&buf).unwrap();
+        let root = &map.nodes[&map.root_id];
+        let children: Vec<&Node> = root.children.iter().map(|id| &map.nodes[id]).collect();
+        assert_eq!(children[0].content, "Child");
+        assert_eq!(
+            children[0].style.background_color.as_deref(),
+            Some("#ff0000")
+        );
+        assert_eq!(children.len(), 1);
+        let floating = &map.nodes[&map.floating_roots[0]];
+        assert_eq!(floating.content, "Floating");
+        assert_eq!((floating.x, floating.y), (300.0, 0.0));
+        assert_eq!(floating.parent, None);
+        assert!(floating.task.is_none());
+
+        let loaded = from_itmz(&to_itmz(&map).unwrap()).unwrap();
+        assert_eq!(loaded.floating_roots.len(), 1);
+        let floating = &loaded.nodes[&loaded.floating_roots[0]];
+        assert_eq!((floating.content.as_str(), floating.x), ("Floating", 300.0));
+        assert_eq!(loaded.nodes[&loaded.root_id].children.len(), 1);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::html;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::timestamp::{parse_iso8601, parse_rfc2822};
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::de::from_str;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+// Jira's "Export XML" RSS feed. Only the fields that map onto nodes are read.
+
+#[derive(Debug, Deserialize)]
+#[serde(rename = "rss")]
+pub struct JiraRss {
+    pub channel: JiraChannel,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraChannel {
+    #[serde(rename = "item", default)]
+    pub items: Vec<JiraItem>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraItem {
+    pub key: JiraText,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(rename = "type", default)]
+    pub issue_type: Option<JiraText>,
+    #[serde(default)]
+    pub parent: Option<JiraText>,
+    #[serde(default)]
+    pub status: Option<JiraText>,
+    #[serde(default)]
+    pub status_category: Option<JiraStatusCategory>,
+    #[serde(default)]
+    pub priority: Option<JiraText>,
+    #[serde(default)]
+    pub created: Option<String>,
+    #[serde(default)]
+    pub updated: Option<String>,
+    #[serde(default)]
+    pub due: Option<String>,
+    #[serde(default)]
+    pub labels: Option<JiraLabels>,
+    #[serde(default)]
+    pub customfields: Option<JiraCustomFields>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct JiraText {
+    #[serde(rename = "$text", default)]
+    pub value: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraStatusCategory {
+    #[serde(rename = "@key")]
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraLabels {
+    #[serde(rename = "label", default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraCustomFields {
+    #[serde(rename = "customfield", default)]
+    pub fields: Vec<JiraCustomField>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraCustomField {
+    #[serde(default)]
+    pub customfieldname: String,
+    #[serde(default)]
+    pub customfieldvalues: Option<JiraCustomFieldValues>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraCustomFieldValues {
+    #[serde(rename = "customfieldvalue", default)]
+    pub values: Vec<JiraText>,
+}
+
+// Both export flavours are normalized to this before building the tree.
+struct Issue {
+    key: String,
+    summary: String,
+    description: Option<String>,
+    issue_type: String,
+    parent: Option<String>,
+    status: String,
+    status_category: Option<String>,
+    priority: Option<String>,
+    created: Option<u64>,
+    updated: Option<u64>,
+    due: Option<u64>,
+    labels: Vec<String>,
+    // Custom field values that may hold an epic key (JSON exports don't name
+    // their custom fields).
+    epic_candidates: Vec<String>,
+}
+
+/// Reads a Jira XML (RSS) export or a JSON search result (`{"issues": [...]}`
+/// or a bare array) and nests issues as Epic → Story → Sub-task under a
+/// "Jira" root. Status categories map to task icons and completion,
+/// priorities to `full-1`..`full-5` icons and task priority, labels to tags.
+pub fn from_jira(data: &str) -> Result<MindMap, BrainCoreError> {
+    from_jira_with(data, &ImportOptions::default())
+}
+
+pub fn from_jira_with(data: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_jira_monitored(data, options, &mut Monitor::default())
+}
+
+pub fn from_jira_monitored(
+    data: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let data = data.trim_start_matches('\u{feff}').trim_start();
+    let issues = if data.starts_with('<') {
+        read_xml(data)?
+    } else {
+        read_json(data)?
+    };
+
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: "Jira".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    let epics: HashSet<&str> = issues
+        .iter()
+        .filter(|i| i.issue_type.eq_ignore_ascii_case("epic"))
+        .map(|i| i.key.as_str())
+        .collect();
+    let keys: HashSet<&str> = issues.iter().map(|i| i.key.as_str()).collect();
+
+    monitor.begin(Stage::BuildingNodes, Some(issues.len()))?;
+    for issue in &issues {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        nodes.insert(issue.key.clone(), issue_node(issue, now));
+    }
+    monitor.finish()?;
+    for issue in &issues {
+        let parent = issue
+            .parent
+            .as_deref()
+            .or_else(|| {
+                issue
+                    .epic_candidates
+                    .iter()
+                    .map(String::as_str)
+                    .find(|key| epics.contains(key))
+            })
+            .filter(|key| keys.contains(key) && *key != issue.key)
+            .unwrap_or(&root_id)
+            .to_string();
+        if let Some(node) = nodes.get_mut(&issue.key) {
+            node.parent = Some(parent.clone());
+        }
+        if let Some(parent) = nodes.get_mut(&parent) {
+            parent.children.push(issue.key.clone());
+        }
+    }
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+fn issue_node(issue: &Issue, now: u64) -> Node {
+    let category = issue.status_category.as_deref().unwrap_or_else(|| {
+        match issue.status.to_ascii_lowercase().as_str() {
+            "done" | "closed" | "resolved" => "done",
+            "in progress" | "in review" => "indeterminate",
+            _ => "new",
+        }
+    });
+    let done = category == "done";
+    let priority = issue.priority.as_deref().and_then(priority_rank);
+
+    let mut icons = Vec::new();
+    match category {
+        "done" => icons.push("button_ok".to_string()),
+        "indeterminate" => icons.push("go".to_string()),
+        _ => {}
+    }
+    if let Some(rank) = priority {
+        icons.push(format!("full-{rank}"));
+    }
+
+    let created = issue.created.unwrap_or(now);
+    Node {
+        id: issue.key.clone(),
+        content: format!("{} {}", issue.key, issue.summary.trim()),
+        created,
+        modified: issue.updated.unwrap_or(created),
+        icons,
+        note: issue.description.clone(),
+        tags: issue.labels.clone(),
+        task: Some(TaskInfo {
+            done,
+            progress_pct: if done { 100 } else { 0 },
+            due: issue.due,
+            priority,
+        }),
+        ..Default::default()
+    }
+}
+
+// Jira's default scheme, Highest..Lowest, as 1..5.
+fn priority_rank(name: &str) -> Option<u8> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "highest" | "blocker" => Some(1),
+        "high" | "critical" => Some(2),
+        "medium" | "major" => Some(3),
+        "low" | "minor" => Some(4),
+        "lowest" | "trivial" => Some(5),
+        _ => None,
+    }
+}
+
+fn read_xml(xml: &str) -> Result<Vec<Issue>, BrainCoreError> {
+    let rss: JiraRss = from_str(xml).map_err(|e| BrainCoreError::parse(ImportFormat::Jira, e))?;
+    Ok(rss
+        .channel
+        .items
+        .into_iter()
+        .map(|item| {
+            let mut epic_candidates = Vec::new();
+            for field in item.customfields.into_iter().flat_map(|c| c.fields) {
+                if matches!(field.customfieldname.as_str(), "Epic Link" | "Parent Link") {
+                    epic_candidates.extend(
+                        field
+                            .customfieldvalues
+                            .into_iter()
+                            .flat_map(|v| v.values)
+                            .map(|v| v.value.trim().to_string()),
+                    );
+                }
+            }
+            let description = html::to_plain_text(&item.description);
+            Issue {
+                key: item.key.value.trim().to_string(),
+                summary: item.summary,
+                description: (!description.is_empty()).then_some(description),
+                issue_type: item.issue_type.unwrap_or_default().value,
+                parent: item.parent.map(|p| p.value.trim().to_string()),
+                status: item.status.unwrap_or_default().value,
+                status_category: item.status_category.map(|c| c.key),
+                priority: item.priority.map(|p| p.value),
+                created: item.created.as_deref().and_then(parse_rfc2822),
+                updated: item.updated.as_deref().and_then(parse_rfc2822),
+                due: item.due.as_deref().and_then(parse_rfc2822),
+                labels: item.labels.map(|l| l.labels).unwrap_or_default(),
+                epic_candidates,
+            }
+        })
+        .collect())
+}
+
+fn read_json(json: &str) -> Result<Vec<Issue>, BrainCoreError> {
+    let value: Value = serde_json::from_str(json)
+        .map_err(|e| BrainCoreError::json(ImportFormat::Jira, json, e))?;
+    let issues = match &value {
+        Value::Array(issues) => issues,
+        _ => value["issues"].as_array().ok_or_else(|| {
+            BrainCoreError::parse(
+                ImportFormat::Jira,
+                "Expected a Jira search result with an \"issues\" array",
+            )
+        })?,
+    };
+
+    issues
+        .iter()
+        .map(|issue| {
+            let key = issue["key"].as_str().ok_or_else(|| {
+                BrainCoreError::parse(ImportFormat::Jira, "Jira issue without a key")
+            })?;
+            let fields = &issue["fields"];
+            let text = |value: &Value| value.as_str().map(str::to_string);
+            let epic_candidates = fields
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter(|(name, _)| name.starts_with("customfield_"))
+                .filter_map(|(_, value)| text(value))
+                .collect();
+            let description = match &fields["description"] {
+                Value::String(text) => text.trim().to_string(),
+                // Cloud's v3 API returns Atlassian Document Format.
+                doc @ Value::Object(_) => adf_text(doc).trim().to_string(),
+                _ => String::new(),
+            };
+            Ok(Issue {
+                key: key.to_string(),
+                summary: text(&fields["summary"]).unwrap_or_default(),
+                description: (!description.is_empty()).then_some(description),
+                issue_type: text(&fields["issuetype"]["name"]).unwrap_or_default(),
+                parent: text(&fields["parent"]["key"]),
+                status: text(&fields["status"]["name"]).unwrap_or_default(),
+                status_category: text(&fields["status"]["statusCategory"]["key"]),
+                priority: text(&fields["priority"]["name"]),
+                created: fields["created"].as_str().and_then(parse_iso8601),
+                updated: fields["updated"].as_str().and_then(parse_iso8601),
+                due: fields["duedate"].as_str().and_then(parse_iso8601),
+                labels: fields["labels"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(text)
+                    .collect(),
+                epic_candidates,
+            })
+        })
+        .collect()
+}
+
+// Concatenates the text of an ADF document, one line per block.
+fn adf_text(node: &Value) -> String {
+    if let Some(text) = node["text"].as_str() {
+        return text.to_string();
+    }
+    let mut out = String::new();
+    for child in node["content"].as_array().into_iter().flatten() {
+        out.push_str(&adf_text(child));
+    }
+    if matches!(
+        node["type"].as_str(),
+        Some("paragraph" | "heading" | "listItem" | "codeBlock" | "hardBreak")
+    ) {
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="0.92">
+  <channel>
+    <title>Jira</title>
+    <issue start="0" end="3" total="3"/>
+    <item>
+      <title>[APP-3] Validate input</title>
+      <key id="10003">APP-3</key>
+      <summary>Validate input</summary>
+      <type id="10003">Sub-task</type>
+      <parent id="10002">APP-2</parent>
+      <priority id="2">High</priority>
+      <status id="3">In Progress</status>
+      <statusCategory id="4" key="indeterminate" colorName="yellow"/>
+      <created>Tue, 2 Jan 2024 09:00:00 +0000</created>
+      <due></due>
+    </item>
+    <item>
+      <title>[APP-2] Login form</title>
+      <key id="10002">APP-2</key>
+      <summary>Login form</summary>
+      <description>&lt;p&gt;Email &amp;amp; password&lt;/p&gt;</description>
+      <type id="10001">Story</type>
+      <priority id="3">Medium</priority>
+      <status id="10001">Done</status>
+      <statusCategory id="3" key="done" colorName="green"/>
+      <due>Fri, 1 Mar 2024 00:00:00 +0000</due>
+      <labels><label>frontend</label></labels>
+      <customfields>
+        <customfield id="customfield_10008" key="com.pyxis.greenhopper.jira:gh-epic-link">
+          <customfieldname>Epic Link</customfieldname>
+          <customfieldvalues><customfieldvalue>APP-1</customfieldvalue></customfieldvalues>
+        </customfield>
+      </customfields>
+    </item>
+    <item>
+      <title>[APP-1] Accounts</title>
+      <key id="10001">APP-1</key>
+      <summary>Accounts</summary>
+      <type id="10000">Epic</type>
+      <status id="1">To Do</status>
+    </item>
+  </channel>
+</rss>"#;
+
+    #[test]
+    fn test_jira_xml_and_json_import() {
+        let map = from_jira(XML).unwrap();
+        assert_eq!(map.nodes[&map.root_id].children, ["APP-1"]);
+        assert_eq!(map.nodes["APP-1"].children, ["APP-2"]);
+        assert_eq!(map.nodes["APP-2"].children, ["APP-3"]);
+
+        let story = &map.nodes["APP-2"];
+        assert_eq!(story.content, "APP-2 Login form");
+        assert_eq!(story.note.as_deref(), Some("Email & password"));
+        assert_eq!(story.icons, ["button_ok", "full-3"]);
+        assert_eq!(story.tags, ["frontend"]);
+        let task = story.task.as_ref().unwrap();
+        assert!(task.done);
+        assert_eq!(task.due, Some(1_709_251_200_000));
+        assert_eq!(task.priority, Some(3));
+        assert_eq!(map.nodes["APP-3"].icons, ["go", "full-2"]);
+
+        let json = r#"{"issues": [
+          {"key": "WEB-2", "fields": {"summary": "Header", "issuetype": {"name": "Story"},
+            "customfield_10014": "WEB-1", "status": {"name": "Open", "statusCategory": {"key": "new"}},
+            "description": {"type": "doc", "content": [{"type": "paragraph",
+              "content": [{"type": "text", "text": "Sticky on scroll"}]}]}}},
+          {"key": "WEB-1", "fields": {"summary": "Redesign", "issuetype": {"name": "Epic"},
+            "created": "2024-01-02T09:00:00.000+0000"}}
+        ]}"#;
+        let map = from_jira(json).unwrap();
+        assert_eq!(map.nodes["WEB-1"].children, ["WEB-2"]);
+        assert_eq!(map.nodes["WEB-1"].created, 1_704_186_000_000);
+        assert_eq!(map.nodes["WEB-2"].note.as_deref(), Some("Sticky on scroll"));
+    }
+}
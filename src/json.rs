@@ -0,0 +1,300 @@
+use crate::export::ExportOptions;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::relationship::Relationship;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// The documented interchange format for other tools, kept apart from the
+// serde derives on `Node` and `MindMap` so those can change freely:
+//
+// {
+//   "version": 1,
+//   "root": "<id>",
+//   "floating": ["<id>", ...],
+//   "nodes": [{"id": "<id>", "content": "...", "children": ["<id>", ...],
+//              "note": "...", "link": "...", "icons": [...], "tags": [...],
+//              "folded": false, "archived": false,
+//              "created": 0, "modified": 0}, ...],
+//   "relationships": [{"id": "<id>", "from": "<id>", "to": "<id>",
+//                      "label": "..."}, ...]
+// }
+//
+// Only `version`, `root`, `nodes` and each node's `id` are required; every
+// other field has a default and is left out when it has that value. The
+// tree is given by the `children` lists, which also fix the order;
+// `floating` lists the tops of detached trees kept beside the root's.
+//
+// Compatibility rules: new optional fields may be added within a version,
+// so readers must ignore fields they don't know (this one does). Removing
+// or changing the meaning of a field bumps `version`, and readers reject
+// versions newer than the one they implement.
+
+/// Version of the JSON document format written by [`to_json`].
+pub const JSON_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Document {
+    version: u32,
+    root: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    floating: Vec<String>,
+    nodes: Vec<JsonNode>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<JsonRelationship>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonNode {
+    id: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    icons: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    folded: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    archived: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    created: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    modified: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonRelationship {
+    id: String,
+    from: String,
+    to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+pub fn to_json(map: &MindMap) -> Result<String, BrainCoreError> {
+    to_json_with(map, &ExportOptions::default())
+}
+
+/// Writes the map as a version [`JSON_VERSION`] document, nodes in
+/// depth-first order from the root and then from each floating node.
+/// Relationships are kept when both ends are exported.
+pub fn to_json_with(map: &MindMap, options: &ExportOptions) -> Result<String, BrainCoreError> {
+    to_json_monitored(map, options, &mut Monitor::default())
+}
+
+pub fn to_json_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut nodes = Vec::new();
+    let floating = options.exported_floating(map);
+    let mut stack: Vec<&Node> = floating.iter().rev().copied().collect();
+    stack.extend(map.nodes.get(&map.root_id));
+    monitor.begin(Stage::WritingNodes, Some(options.exported_count(map, true)))?;
+    while let Some(node) = stack.pop() {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let children: Vec<&Node> = node
+            .children
+            .iter()
+            .filter_map(|id| map.nodes.get(id))
+            .filter(|c| options.includes(c))
+            .collect();
+        nodes.push(JsonNode {
+            id: node.id.clone(),
+            content: node.content.clone(),
+            children: children.iter().map(|c| c.id.clone()).collect(),
+            note: node.note.clone(),
+            link: node.link.clone(),
+            icons: node.icons.clone(),
+            tags: node.tags.clone(),
+            folded: node.folded,
+            archived: node.archived,
+            created: node.created,
+            modified: node.modified,
+        });
+        stack.extend(children.into_iter().rev());
+    }
+    monitor.finish()?;
+
+    let exported = |id: &String| nodes.iter().any(|n| n.id == *id);
+    let relationships = map
+        .relationships
+        .iter()
+        .filter(|r| exported(&r.from) && exported(&r.to))
+        .map(|r| JsonRelationship {
+            id: r.id.clone(),
+            from: r.from.clone(),
+            to: r.to.clone(),
+            label: r.label.clone(),
+        })
+        .collect();
+    let document = Document {
+        version: JSON_VERSION,
+        root: map.root_id.clone(),
+        floating: floating.iter().map(|n| n.id.clone()).collect(),
+        nodes,
+        relationships,
+    };
+    serde_json::to_string_pretty(&document).map_err(BrainCoreError::export)
+}
+
+pub fn from_json(text: &str) -> Result<MindMap, BrainCoreError> {
+    from_json_with(text, &ImportOptions::default())
+}
+
+/// Reads a JSON document of version [`JSON_VERSION`] or older. Fails if
+/// the node ids aren't unique, a child or relationship end names no node,
+/// or the `children` lists don't form trees under `root` and the
+/// `floating` nodes.
+pub fn from_json_with(text: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_json_monitored(text, options, &mut Monitor::default())
+}
+
+pub fn from_json_monitored(
+    text: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let document: Document = serde_json::from_str(text)
+        .map_err(|e| BrainCoreError::json(ImportFormat::Json, text, e))?;
+    let invalid = |message: String| BrainCoreError::parse(ImportFormat::Json, message);
+    if document.version == 0 || document.version > JSON_VERSION {
+        return Err(invalid(format!(
+            "Unsupported document version {}",
+            document.version
+        )));
+    }
+
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    monitor.begin(Stage::BuildingNodes, Some(document.nodes.len()))?;
+    for node in &document.nodes {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let previous = nodes.insert(
+            node.id.clone(),
+            Node {
+                id: node.id.clone(),
+                content: node.content.clone(),
+                children: node.children.clone(),
+                note: node.note.clone(),
+                link: node.link.clone(),
+                icons: node.icons.clone(),
+                tags: node.tags.clone(),
+                folded: node.folded,
+                archived: node.archived,
+                created: node.created,
+                modified: node.modified,
+                ..Default::default()
+            },
+        );
+        if previous.is_some() {
+            return Err(invalid(format!("Duplicate node id: {}", node.id)));
+        }
+    }
+    monitor.finish()?;
+    for node in &document.nodes {
+        for child_id in &node.children {
+            let child = nodes
+                .get_mut(child_id)
+                .ok_or_else(|| invalid(format!("Unknown child id: {child_id}")))?;
+            if child.parent.replace(node.id.clone()).is_some() {
+                return Err(invalid(format!("Node {child_id} has several parents")));
+            }
+        }
+    }
+
+    let mut map = MindMap::from_nodes(nodes, document.root);
+    map.floating_roots = document.floating;
+    if !map.validate().is_empty() {
+        return Err(invalid(
+            "The nodes don't form trees under the root and floating nodes".to_string(),
+        ));
+    }
+    for relationship in document.relationships {
+        for end in [&relationship.from, &relationship.to] {
+            if !map.nodes.contains_key(end) {
+                return Err(invalid(format!("Unknown relationship end: {end}")));
+            }
+        }
+        map.relationships.push(Relationship {
+            id: relationship.id,
+            from: relationship.from,
+            to: relationship.to,
+            label: relationship.label,
+        });
+    }
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.set_note(&a, "Details").unwrap();
+        map.set_folded(&a, true).unwrap();
+        map.add_relationship(&a, &b, Some("depends")).unwrap();
+        let idea = map.add_floating_node("Idea", 300.0, 0.0).unwrap();
+        map.add_child(&idea, "Detail").unwrap();
+
+        let json = to_json(&map).unwrap();
+        assert!(json.starts_with("{\n  \"version\": 1,\n"));
+        let loaded = from_json(&json).unwrap();
+        assert_eq!(loaded.root_id, root_id);
+        assert_eq!(loaded.nodes[&root_id].children, vec![a.clone(), b.clone()]);
+        assert_eq!(loaded.nodes[&a].parent.as_ref(), Some(&root_id));
+        assert_eq!(loaded.nodes[&a].note.as_deref(), Some("Details"));
+        assert!(loaded.nodes[&a].folded);
+        assert_eq!(loaded.relationships.len(), 1);
+        assert_eq!(loaded.floating_roots, vec![idea]);
+        assert_eq!(loaded.nodes.len(), 5);
+
+        // Fields a later revision of the format might add are ignored.
+        let extended = r#"{"version": 1, "root": "r", "theme": "dark",
+            "nodes": [{"id": "r", "content": "Root", "children": ["c"], "color": "red"},
+                      {"id": "c", "content": "Child"}]}"#;
+        assert_eq!(from_json(extended).unwrap().nodes.len(), 2);
+
+        let newer = r#"{"version": 2, "root": "r", "nodes": [{"id": "r"}]}"#;
+        let looped = r#"{"version": 1, "root": "r",
+            "nodes": [{"id": "r"}, {"id": "a", "children": ["b"]}, {"id": "b", "children": ["a"]}]}"#;
+        for text in [newer, looped, "{\"version\": 1}"] {
+            assert!(matches!(
+                from_json(text),
+                Err(BrainCoreError::Parse {
+                    format: ImportFormat::Json,
+                    ..
+                })
+            ));
+        }
+    }
+}
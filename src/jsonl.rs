@@ -0,0 +1,100 @@
+use crate::export::ExportOptions;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::Serialize;
+use std::io::Write;
+
+// One line of the JSON Lines stream. Fields borrow from the map so records
+// are written without cloning node contents.
+#[derive(Debug, Serialize)]
+pub struct JsonlNode<'a> {
+    pub id: &'a str,
+    pub parent: Option<&'a str>,
+    pub depth: usize,
+    pub content: &'a str,
+    pub created: u64,
+    pub modified: u64,
+    pub icons: &'a [String],
+    pub archived: bool,
+}
+
+/// Writes one JSON object per node, depth-first from the root in child order.
+pub fn write_jsonl<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_jsonl_with(map, writer, &ExportOptions::default())
+}
+
+pub fn write_jsonl_with<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+
+    let mut stack: Vec<(&Node, usize)> = vec![(root, 0)];
+    while let Some((node, depth)) = stack.pop() {
+        let record = JsonlNode {
+            id: &node.id,
+            parent: node.parent.as_deref(),
+            depth,
+            content: &node.content,
+            created: node.created,
+            modified: node.modified,
+            icons: &node.icons,
+            archived: node.archived,
+        };
+        serde_json::to_writer(&mut *writer, &record).map_err(BrainCoreError::export)?;
+        writer.write_all(b"\n")?;
+
+        for child_id in node.children.iter().rev() {
+            if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+
+    Ok(writer.flush()?)
+}
+
+pub fn to_jsonl(map: &MindMap) -> Result<String, BrainCoreError> {
+    let mut buf = Vec::new();
+    write_jsonl(map, &mut buf)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl_export() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let mut child = map.nodes[&root_id].clone();
+        child.id = "child".to_string();
+        child.content = "Child \"quoted\"".to_string();
+        child.parent = Some(root_id.clone());
+        child.icons = vec!["idea".to_string()];
+        map.nodes.insert(child.id.clone(), child);
+        map.nodes
+            .get_mut(&root_id)
+            .unwrap()
+            .children
+            .push("child".to_string());
+
+        let output = to_jsonl(&map).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["id"], root_id.as_str());
+        assert_eq!(lines[0]["parent"], serde_json::Value::Null);
+        assert_eq!(lines[1]["parent"], root_id.as_str());
+        assert_eq!(lines[1]["depth"], 1);
+        assert_eq!(lines[1]["content"], "Child \"quoted\"");
+        assert_eq!(lines[1]["icons"][0], "idea");
+    }
+}
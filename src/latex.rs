@@ -0,0 +1,300 @@
+use crate::layout::HeuristicMeasurer;
+use crate::{MindMap, Node};
+use std::fmt::Write;
+
+/// The document class to outline the map in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LatexClass {
+    /// Sections, subsections and subsubsections, then nested lists.
+    #[default]
+    Article,
+    /// Sections, then one frame per second-level node with its subtree as
+    /// nested lists.
+    Beamer,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LatexOptions {
+    pub class: LatexClass,
+    /// Write a whole document with its preamble. Otherwise only the body is
+    /// written, to be `\input` into a document of the reader's own.
+    pub standalone: bool,
+    /// Notes become paragraphs, or `\note`s in Beamer.
+    pub include_notes: bool,
+    /// Add a TikZ `mindmap` picture of the map, placed as
+    /// [`MindMap::compute_layout`] lays it out and scaled to the text width.
+    /// Without `standalone`, the document needs `tikz` and its `mindmap`
+    /// library.
+    pub tikz_mindmap: bool,
+    pub include_archived: bool,
+}
+
+impl Default for LatexOptions {
+    fn default() -> Self {
+        Self {
+            class: LatexClass::Article,
+            standalone: true,
+            include_notes: true,
+            tikz_mindmap: false,
+            include_archived: false,
+        }
+    }
+}
+
+/// Article sectioning commands, by depth below the root.
+const SECTIONS: [&str; 3] = ["section", "subsection", "subsubsection"];
+
+/// Escapes LaTeX's special characters. Line breaks are kept, so blank lines
+/// in notes still separate paragraphs.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Content on one line, for titles and list items.
+fn line(node: &Node) -> String {
+    escape(&node.content.replace('\n', " "))
+}
+
+pub fn to_latex(map: &MindMap) -> String {
+    to_latex_with(map, &LatexOptions::default())
+}
+
+/// Outlines the map: the root is the document title and its descendants
+/// are sectioned as [`LatexClass`] describes, so a paper or a slide deck
+/// can be written into the skeleton.
+pub fn to_latex_with(map: &MindMap, options: &LatexOptions) -> String {
+    let mut out = String::new();
+    let Some(root) = map.nodes.get(&map.root_id) else {
+        return out;
+    };
+    if options.standalone {
+        let class = match options.class {
+            LatexClass::Article => "article",
+            LatexClass::Beamer => "beamer",
+        };
+        let _ = writeln!(out, "\\documentclass{{{class}}}");
+        if options.tikz_mindmap {
+            out.push_str("\\usepackage{tikz}\n\\usetikzlibrary{mindmap}\n");
+        }
+        let _ = writeln!(out, "\\title{{{}}}", line(root));
+        out.push_str("\\begin{document}\n");
+        match options.class {
+            LatexClass::Article => out.push_str("\\maketitle\n"),
+            LatexClass::Beamer => out.push_str("\\begin{frame}\n\\titlepage\n\\end{frame}\n"),
+        }
+    }
+    if options.tikz_mindmap {
+        let picture = tikz_mindmap(map, options);
+        match options.class {
+            LatexClass::Article => {
+                let _ = write!(out, "\\begin{{center}}\n{picture}\\end{{center}}\n");
+            }
+            LatexClass::Beamer => {
+                let _ = write!(
+                    out,
+                    "\\begin{{frame}}{{{}}}\n{picture}\\end{{frame}}\n",
+                    line(root)
+                );
+            }
+        }
+    }
+    if options.include_notes {
+        write_note(&mut out, root, options);
+    }
+    for child in children(map, root, options) {
+        match options.class {
+            LatexClass::Article => write_section(&mut out, map, child, 0, options),
+            LatexClass::Beamer => write_beamer_section(&mut out, map, child, options),
+        }
+    }
+    if options.standalone {
+        out.push_str("\\end{document}\n");
+    }
+    out
+}
+
+fn children<'a>(map: &'a MindMap, node: &Node, options: &LatexOptions) -> Vec<&'a Node> {
+    node.children
+        .iter()
+        .filter_map(|id| map.nodes.get(id))
+        .filter(|c| options.include_archived || !c.archived)
+        .collect()
+}
+
+fn write_note(out: &mut String, node: &Node, options: &LatexOptions) {
+    let Some(note) = node.note.as_deref().filter(|n| !n.trim().is_empty()) else {
+        return;
+    };
+    match options.class {
+        LatexClass::Article => {
+            let _ = write!(out, "{}\n\n", escape(note.trim()));
+        }
+        LatexClass::Beamer => {
+            let _ = writeln!(out, "\\note{{{}}}", escape(note.trim()));
+        }
+    }
+}
+
+fn write_section(
+    out: &mut String,
+    map: &MindMap,
+    node: &Node,
+    level: usize,
+    options: &LatexOptions,
+) {
+    let _ = writeln!(out, "\\{}{{{}}}", SECTIONS[level], line(node));
+    if options.include_notes {
+        write_note(out, node, options);
+    }
+    let children = children(map, node, options);
+    if level + 1 < SECTIONS.len() {
+        for child in children {
+            write_section(out, map, child, level + 1, options);
+        }
+    } else {
+        write_list(out, map, &children, options);
+    }
+}
+
+fn write_beamer_section(out: &mut String, map: &MindMap, node: &Node, options: &LatexOptions) {
+    let _ = writeln!(out, "\\section{{{}}}", line(node));
+    let children = children(map, node, options);
+    if children.is_empty() {
+        write_frame(out, map, node, options);
+    }
+    for child in children {
+        write_frame(out, map, child, options);
+    }
+}
+
+fn write_frame(out: &mut String, map: &MindMap, node: &Node, options: &LatexOptions) {
+    let _ = writeln!(out, "\\begin{{frame}}{{{}}}", line(node));
+    write_list(out, map, &children(map, node, options), options);
+    if options.include_notes {
+        write_note(out, node, options);
+    }
+    out.push_str("\\end{frame}\n");
+}
+
+fn write_list(out: &mut String, map: &MindMap, items: &[&Node], options: &LatexOptions) {
+    if items.is_empty() {
+        return;
+    }
+    out.push_str("\\begin{itemize}\n");
+    for item in items {
+        write_item(out, map, item, options);
+    }
+    out.push_str("\\end{itemize}\n");
+}
+
+fn write_item(out: &mut String, map: &MindMap, node: &Node, options: &LatexOptions) {
+    let _ = writeln!(out, "\\item {}", line(node));
+    if options.include_notes
+        && options.class == LatexClass::Article
+        && let Some(note) = node.note.as_deref().filter(|n| !n.trim().is_empty())
+    {
+        let _ = writeln!(out, "\n{}\n", escape(note.trim()));
+    }
+    write_list(out, map, &children(map, node, options), options);
+}
+
+/// The map as a `mindmap` picture with every node at its laid-out centre
+/// and a connection bar to each child. Positions are in points, one per
+/// layout pixel, with y pointing down as in the layout.
+fn tikz_mindmap(map: &MindMap, options: &LatexOptions) -> String {
+    let mut laid_out = map.clone();
+    laid_out.compute_layout();
+    let mut out = String::from(
+        "\\resizebox{\\linewidth}{!}{%\n\\begin{tikzpicture}[mindmap, concept color=blue!20, x=1pt, y=-1pt,\n  \
+         every node/.style={concept, rectangle, rounded corners, minimum size=0pt, inner sep=4pt, align=center}]\n",
+    );
+    let mut names = Vec::new();
+    let mut stack: Vec<&Node> = laid_out
+        .floating_roots
+        .iter()
+        .rev()
+        .filter_map(|id| laid_out.nodes.get(id))
+        .chain(laid_out.nodes.get(&laid_out.root_id))
+        .filter(|n| options.include_archived || !n.archived)
+        .collect();
+    while let Some(node) = stack.pop() {
+        let (width, height) = laid_out.node_size(node, &HeuristicMeasurer);
+        let _ = writeln!(
+            out,
+            "\\node (n{}) at ({}, {}) {{{}}};",
+            names.len(),
+            node.x + width / 2.0,
+            node.y + height / 2.0,
+            line(node)
+        );
+        names.push(node.id.as_str());
+        stack.extend(children(&laid_out, node, options).into_iter().rev());
+    }
+    for (index, id) in names.iter().enumerate() {
+        for child in children(&laid_out, &laid_out.nodes[*id], options) {
+            if let Some(child_index) = names.iter().position(|n| *n == child.id) {
+                let _ = writeln!(
+                    out,
+                    "\\path (n{index}) to[circle connection bar] (n{child_index});"
+                );
+            }
+        }
+    }
+    out.push_str("\\end{tikzpicture}}\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latex_outline() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.nodes.get_mut(&root_id).unwrap().content = "Paper".to_string();
+        let intro = map.add_child(&root_id, "Intro & 100% motivation").unwrap();
+        map.set_note(&intro, "Why it matters.").unwrap();
+        let related = map.add_child(&intro, "Related").unwrap();
+        let early = map.add_child(&related, "Early work").unwrap();
+        map.add_child(&early, "Point_a").unwrap();
+
+        let article = to_latex(&map);
+        assert!(article.starts_with("\\documentclass{article}\n\\title{Paper}"));
+        for expected in [
+            "\\section{Intro \\& 100\\% motivation}\nWhy it matters.\n\n",
+            "\\subsection{Related}\n\\subsubsection{Early work}\n\\begin{itemize}\n\\item Point\\_a\n\\end{itemize}\n",
+        ] {
+            assert!(
+                article.contains(expected),
+                "{expected} missing from {article}"
+            );
+        }
+        assert!(article.ends_with("\\end{document}\n"));
+
+        let options = LatexOptions {
+            class: LatexClass::Beamer,
+            standalone: false,
+            tikz_mindmap: true,
+            ..Default::default()
+        };
+        let beamer = to_latex_with(&map, &options);
+        assert!(!beamer.contains("\\documentclass"));
+        assert!(beamer.contains("\\section{Intro \\& 100\\% motivation}\n\\begin{frame}{Related}\n\\begin{itemize}\n\\item Early work\n\\begin{itemize}\n\\item Point\\_a\n"));
+        assert!(beamer.contains("\\node (n0) at ("));
+        assert_eq!(beamer.matches("circle connection bar").count(), 4);
+    }
+}
@@ -0,0 +1,664 @@
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashMap;
+use unicode_width::UnicodeWidthStr;
+
+/// Minimum height of a node box.
+pub const NODE_HEIGHT: f32 = 50.0;
+/// Horizontal space between a node and its children.
+pub const LEVEL_GAP: f32 = 40.0;
+/// Vertical space between neighbouring subtrees.
+pub const SIBLING_GAP: f32 = 10.0;
+/// Space between a node's text and the edge of its box.
+pub const NODE_PADDING: f32 = 10.0;
+/// Font size of nodes without one in their style.
+pub const DEFAULT_FONT_SIZE: f32 = 14.0;
+
+/// Settings shared by all layouts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LayoutSettings {
+    /// Widest a node's box may get. Longer lines wrap at spaces; a single
+    /// word wider than this keeps its own line.
+    pub max_width: Option<f32>,
+}
+
+/// Measures node text so layout can size the boxes. GUI frontends
+/// implement it with their real font metrics.
+pub trait TextMeasurer {
+    /// Width and height of a single line of `text` set at `font_size`.
+    fn measure(&self, text: &str, font_size: f32) -> (f32, f32);
+
+    /// Splits `text` into the lines it is drawn as: one per `\n`, with
+    /// lines wider than `max_width` broken between words.
+    fn wrap(&self, text: &str, font_size: f32, max_width: Option<f32>) -> Vec<String> {
+        let mut lines = Vec::new();
+        for line in text.split('\n') {
+            let Some(max_width) = max_width else {
+                lines.push(line.to_string());
+                continue;
+            };
+            let mut current = String::new();
+            for word in line.split_whitespace() {
+                if current.is_empty() {
+                    current.push_str(word);
+                    continue;
+                }
+                let candidate = format!("{current} {word}");
+                if self.measure(&candidate, font_size).0 > max_width {
+                    lines.push(std::mem::replace(&mut current, word.to_string()));
+                } else {
+                    current = candidate;
+                }
+            }
+            lines.push(current);
+        }
+        lines
+    }
+}
+
+/// Estimates text size without font data: eight pixels per terminal
+/// column at the default font size, so wide characters such as CJK and
+/// most emoji count double, and one line of 1.2 times the font size.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicMeasurer;
+
+impl TextMeasurer for HeuristicMeasurer {
+    fn measure(&self, text: &str, font_size: f32) -> (f32, f32) {
+        let scale = font_size / DEFAULT_FONT_SIZE;
+        (text.width() as f32 * 8.0 * scale, font_size * 1.2)
+    }
+}
+
+/// Font size a node is drawn at.
+pub fn font_size(node: &Node) -> f32 {
+    node.style.font_size.map_or(DEFAULT_FONT_SIZE, |s| s as f32)
+}
+
+/// Width of a node's box as estimated by [`HeuristicMeasurer`], ignoring
+/// line breaks and wrapping. See [`MindMap::node_size`] for the full size.
+pub fn node_width(node: &Node) -> f32 {
+    HeuristicMeasurer.measure(&node.content, font_size(node)).0 + 2.0 * NODE_PADDING
+}
+
+/// Places the nodes of a map. Engines compute positions only;
+/// [`MindMap::apply_layout`] writes them to the nodes.
+pub trait LayoutEngine {
+    /// Top-left corner of every node in the tree under `root` that takes
+    /// part in the layout (see [`MindMap::laid_out_children`]), keyed by
+    /// node id. `root` is the map's root or one of its floating nodes. Box
+    /// sizes come from [`MindMap::node_size`] with `measurer`.
+    fn positions(
+        &self,
+        map: &MindMap,
+        root: &Node,
+        measurer: &dyn TextMeasurer,
+    ) -> HashMap<String, (f32, f32)>;
+}
+
+/// The built-in layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutKind {
+    #[default]
+    RightTree,
+    Balanced,
+    OrgChart,
+}
+
+impl LayoutKind {
+    pub fn engine(self) -> &'static dyn LayoutEngine {
+        match self {
+            LayoutKind::RightTree => &RightTreeLayout,
+            LayoutKind::Balanced => &BalancedLayout,
+            LayoutKind::OrgChart => &OrgChartLayout,
+        }
+    }
+}
+
+/// A tree growing to the right from `x = 0`, with the topmost subtree
+/// starting at `y = 0`. Children sit one level further right and each node
+/// is centred vertically against its subtree.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RightTreeLayout;
+
+/// The classic mind map: the root at `x = 0` with its first children
+/// growing to the right and the rest to the left, split so both sides are
+/// about equally tall. Left-hand nodes are right-aligned against their
+/// parent, so they get negative `x`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BalancedLayout;
+
+/// A top-down organisation chart: the root at the top, each level one row
+/// further down, and each node centred horizontally over its subtree.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OrgChartLayout;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Side {
+    Right,
+    Left,
+}
+
+impl LayoutEngine for RightTreeLayout {
+    fn positions(
+        &self,
+        map: &MindMap,
+        root: &Node,
+        measurer: &dyn TextMeasurer,
+    ) -> HashMap<String, (f32, f32)> {
+        let mut positions = HashMap::new();
+        let bands = map.bands(root, |n| map.node_size(n, measurer).1);
+        let start = (0.0, 0.0);
+        map.place_subtree(root, &bands, start, Side::Right, measurer, &mut positions);
+        positions
+    }
+}
+
+impl LayoutEngine for BalancedLayout {
+    fn positions(
+        &self,
+        map: &MindMap,
+        root: &Node,
+        measurer: &dyn TextMeasurer,
+    ) -> HashMap<String, (f32, f32)> {
+        let mut positions = HashMap::new();
+        let bands = map.bands(root, |n| map.node_size(n, measurer).1);
+        let children = map.laid_out_children(root);
+
+        // The right side takes children in order until it holds at least
+        // half the total height.
+        let half = stacked(&children, &bands) / 2.0;
+        let mut split = 0;
+        let mut height = 0.0;
+        while split < children.len() && height < half {
+            height += bands[children[split].id.as_str()] + SIBLING_GAP;
+            split += 1;
+        }
+        let (right, left) = children.split_at(split);
+
+        let (width, height) = map.node_size(root, measurer);
+        let band = stacked(right, &bands)
+            .max(stacked(left, &bands))
+            .max(height);
+        positions.insert(root.id.clone(), (0.0, (band - height) / 2.0));
+        for (side, children, x) in [
+            (Side::Right, right, width + LEVEL_GAP),
+            (Side::Left, left, -LEVEL_GAP),
+        ] {
+            let mut top = (band - stacked(children, &bands)) / 2.0;
+            for child in children {
+                map.place_subtree(child, &bands, (x, top), side, measurer, &mut positions);
+                top += bands[child.id.as_str()] + SIBLING_GAP;
+            }
+        }
+        positions
+    }
+}
+
+impl LayoutEngine for OrgChartLayout {
+    fn positions(
+        &self,
+        map: &MindMap,
+        root: &Node,
+        measurer: &dyn TextMeasurer,
+    ) -> HashMap<String, (f32, f32)> {
+        let mut positions = HashMap::new();
+        // Bands here are widths.
+        let bands = map.bands(root, |n| map.node_size(n, measurer).0);
+
+        let mut stack = vec![(root, 0.0, 0.0)];
+        while let Some((node, left, y)) = stack.pop() {
+            let band = bands[node.id.as_str()];
+            let (width, height) = map.node_size(node, measurer);
+            positions.insert(node.id.clone(), (left + (band - width) / 2.0, y));
+
+            let children = map.laid_out_children(node);
+            let mut child_left = left + (band - stacked(&children, &bands)) / 2.0;
+            for child in children {
+                stack.push((child, child_left, y + height + LEVEL_GAP));
+                child_left += bands[child.id.as_str()] + SIBLING_GAP;
+            }
+        }
+        positions
+    }
+}
+
+/// How a file measures the offset of a node placed by hand.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Offsets {
+    /// From the parent's top-left corner, as XMind and MindNode do.
+    FromParent,
+    /// FreeMind's `HGAP` and `VSHIFT`: across from the parent's right edge,
+    /// and down from where layout would put the node.
+    FromSlot,
+}
+
+/// Total extent of `children` placed side by side with gaps.
+fn stacked(children: &[&Node], bands: &HashMap<&str, f32>) -> f32 {
+    children.iter().map(|c| bands[c.id.as_str()]).sum::<f32>()
+        + SIBLING_GAP * children.len().saturating_sub(1) as f32
+}
+
+impl MindMap {
+    /// Positions the visible nodes with [`RightTreeLayout`]. `x` and `y`
+    /// are the top-left corner of the node's box, which is sized by
+    /// [`MindMap::node_size`] so multi-line and wrapped content gets a
+    /// taller box.
+    ///
+    /// Children of folded nodes and archived subtrees are skipped and keep
+    /// their old coordinates, and pinned nodes stay put as described in
+    /// [`MindMap::apply_layout`]. Layout is view state and doesn't count as
+    /// an edit.
+    pub fn compute_layout(&mut self) {
+        self.compute_layout_with(LayoutKind::default());
+    }
+
+    /// Positions the visible nodes with one of the built-in layouts, as
+    /// [`MindMap::compute_layout`] does.
+    pub fn compute_layout_with(&mut self, kind: LayoutKind) {
+        self.apply_layout(kind.engine(), &HeuristicMeasurer);
+    }
+
+    /// Positions the visible nodes with any [`LayoutEngine`], sizing the
+    /// boxes with `measurer`.
+    ///
+    /// Pinned nodes stay where they are. Their unpinned descendants keep
+    /// the offset the engine gave them from the pinned node, so a dragged
+    /// branch moves as a whole. Each floating node is laid out as a tree
+    /// of its own and anchored the same way, pinned or not.
+    pub fn apply_layout(&mut self, engine: &dyn LayoutEngine, measurer: &dyn TextMeasurer) {
+        let mut moved = Vec::new();
+        for root_id in self.tree_roots() {
+            let Some(root) = self.nodes.get(root_id) else {
+                continue;
+            };
+            let positions = engine.positions(self, root, measurer);
+            let mut stack = vec![(root_id.as_str(), (0.0, 0.0))];
+            while let Some((id, (dx, dy))) = stack.pop() {
+                let (Some(node), Some(&(x, y))) = (self.nodes.get(id), positions.get(id)) else {
+                    continue;
+                };
+                let anchored = node.pinned || (id == root_id && *root_id != self.root_id);
+                let shift = if anchored {
+                    (node.x - x, node.y - y)
+                } else {
+                    moved.push((id.to_string(), (x + dx, y + dy)));
+                    (dx, dy)
+                };
+                stack.extend(node.children.iter().map(|c| (c.as_str(), shift)));
+            }
+        }
+        for (id, (x, y)) in moved {
+            if let Some(node) = self.nodes.get_mut(&id) {
+                node.x = x;
+                node.y = y;
+            }
+        }
+    }
+
+    /// Places a node by hand and pins it there; see [`Node::pinned`].
+    /// Like folding, this is view state: it bumps the revision but leaves
+    /// the modification time and author alone.
+    pub fn set_position(&mut self, node_id: &str, x: f32, y: f32) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        node.x = x;
+        node.y = y;
+        node.pinned = true;
+        self.bump_revision(node_id);
+        Ok(())
+    }
+
+    /// Pins or unpins a node at its current position. An unpinned node
+    /// goes back to its computed place on the next layout.
+    pub fn set_pinned(&mut self, node_id: &str, pinned: bool) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.pinned != pinned {
+            node.pinned = pinned;
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+
+    /// Places the pinned nodes of a freshly imported map, whose `x` and `y`
+    /// still hold their offset as the file keeps it; see [`Offsets`]. The
+    /// map is laid out first so each parent has a place to be offset from,
+    /// and a pinned node's subtree moves along with it.
+    pub(crate) fn place_pinned_offsets(&mut self, kind: Offsets) {
+        let offsets: HashMap<String, (f32, f32)> = self
+            .nodes
+            .values()
+            .filter(|n| n.pinned && n.parent.is_some())
+            .map(|n| (n.id.clone(), (n.x, n.y)))
+            .collect();
+        if offsets.is_empty() {
+            return;
+        }
+        for id in offsets.keys() {
+            if let Some(node) = self.nodes.get_mut(id) {
+                node.pinned = false;
+            }
+        }
+        self.compute_layout();
+        let mut stack: Vec<String> = self.tree_roots().cloned().collect();
+        while let Some(id) = stack.pop() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            stack.extend(node.children.iter().cloned());
+            let (Some(&(dx, dy)), Some(parent)) = (
+                offsets.get(&id),
+                node.parent.as_ref().and_then(|p| self.nodes.get(p)),
+            ) else {
+                continue;
+            };
+            let shift = match kind {
+                Offsets::FromParent => (parent.x + dx - node.x, parent.y + dy - node.y),
+                Offsets::FromSlot => {
+                    let width = self.node_size(parent, &HeuristicMeasurer).0;
+                    (parent.x + width + dx - node.x, dy)
+                }
+            };
+            let mut subtree = vec![id];
+            while let Some(id) = subtree.pop() {
+                if let Some(node) = self.nodes.get_mut(&id) {
+                    node.x += shift.0;
+                    node.y += shift.1;
+                    subtree.extend(node.children.iter().cloned());
+                }
+            }
+        }
+        for id in offsets.keys() {
+            if let Some(node) = self.nodes.get_mut(id) {
+                node.pinned = true;
+            }
+        }
+    }
+
+    /// The offsets of the pinned nodes as [`Offsets::FromSlot`] measures
+    /// them, undoing what [`MindMap::place_pinned_offsets`] does with it.
+    pub(crate) fn slot_offsets(&self) -> HashMap<String, (f32, f32)> {
+        let mut unpinned = self.clone();
+        for node in unpinned.nodes.values_mut() {
+            node.pinned = false;
+        }
+        unpinned.compute_layout();
+        let slot = |id: &str| unpinned.nodes.get(id).map_or(0.0, |n| n.y);
+        self.nodes
+            .values()
+            .filter(|n| n.pinned)
+            .filter_map(|node| {
+                let parent = self.nodes.get(node.parent.as_ref()?)?;
+                let width = self.node_size(parent, &HeuristicMeasurer).0;
+                let offset = (
+                    node.x - parent.x - width,
+                    node.y - parent.y - (slot(&node.id) - slot(&parent.id)),
+                );
+                Some((node.id.clone(), offset))
+            })
+            .collect()
+    }
+
+    pub fn layout_settings(&self) -> LayoutSettings {
+        self.layout
+    }
+
+    /// Sets the settings used by [`MindMap::compute_layout`] and the other
+    /// layout methods.
+    pub fn set_layout_settings(&mut self, settings: LayoutSettings) {
+        self.layout = settings;
+    }
+
+    /// The lines a node's content is drawn as, wrapped to the map's
+    /// [`LayoutSettings::max_width`].
+    pub fn node_lines(&self, node: &Node, measurer: &dyn TextMeasurer) -> Vec<String> {
+        let max_width = self.layout.max_width.map(|w| w - 2.0 * NODE_PADDING);
+        measurer.wrap(&node.content, font_size(node), max_width)
+    }
+
+    /// Size of a node's box: its widest line and the height of all its
+    /// lines, plus padding, at least [`NODE_HEIGHT`] tall.
+    pub fn node_size(&self, node: &Node, measurer: &dyn TextMeasurer) -> (f32, f32) {
+        let (mut width, mut height) = (0.0f32, 0.0);
+        for line in self.node_lines(node, measurer) {
+            let (line_width, line_height) = measurer.measure(&line, font_size(node));
+            width = width.max(line_width);
+            height += line_height;
+        }
+        (
+            width + 2.0 * NODE_PADDING,
+            (height + 2.0 * NODE_PADDING).max(NODE_HEIGHT),
+        )
+    }
+
+    /// Children taking part in the layout.
+    pub fn laid_out_children<'a>(&'a self, node: &'a Node) -> Vec<&'a Node> {
+        if node.folded {
+            return Vec::new();
+        }
+        node.children
+            .iter()
+            .filter_map(|id| self.nodes.get(id))
+            .filter(|c| !c.archived)
+            .collect()
+    }
+
+    /// Extents of the bands the subtrees under `root` occupy across the
+    /// direction of growth, computed bottom-up from each node's `size`.
+    fn bands<'a>(&'a self, root: &'a Node, size: impl Fn(&Node) -> f32) -> HashMap<&'a str, f32> {
+        let mut order = Vec::new();
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            order.push(node);
+            stack.extend(self.laid_out_children(node));
+        }
+        let mut bands: HashMap<&str, f32> = HashMap::new();
+        for node in order.iter().rev() {
+            let children = self.laid_out_children(node);
+            bands.insert(&node.id, stacked(&children, &bands).max(size(node)));
+        }
+        bands
+    }
+
+    /// Places `node` and its subtree in the band starting at `top`. On the
+    /// right `x` is the left edge of `node`'s box, on the left its right
+    /// edge.
+    fn place_subtree(
+        &self,
+        node: &Node,
+        bands: &HashMap<&str, f32>,
+        (x, top): (f32, f32),
+        side: Side,
+        measurer: &dyn TextMeasurer,
+        positions: &mut HashMap<String, (f32, f32)>,
+    ) {
+        let mut stack = vec![(node, x, top)];
+        while let Some((node, x, top)) = stack.pop() {
+            let band = bands[node.id.as_str()];
+            let (width, height) = self.node_size(node, measurer);
+            let left = if side == Side::Right { x } else { x - width };
+            positions.insert(node.id.clone(), (left, top + (band - height) / 2.0));
+
+            let children = self.laid_out_children(node);
+            let child_x = match side {
+                Side::Right => x + width + LEVEL_GAP,
+                Side::Left => x - width - LEVEL_GAP,
+            };
+            let mut child_top = top + (band - stacked(&children, bands)) / 2.0;
+            for child in children {
+                stack.push((child, child_x, child_top));
+                child_top += bands[child.id.as_str()] + SIBLING_GAP;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mindnode, storage, xmind};
+
+    #[test]
+    fn test_compute_layout() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Root").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let a2 = map.add_child(&a, "A2").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let hidden = map.add_child(&b, "Hidden").unwrap();
+        map.set_folded(&b, true).unwrap();
+        let revision = map.revision;
+
+        map.compute_layout();
+        assert_eq!(map.revision, revision);
+        let at = |id: &String| (map.nodes[id].x, map.nodes[id].y);
+        // A's band holds two leaves (110px), B's one (50px); the root spans
+        // both plus a gap.
+        assert_eq!(at(&a), (52.0 + LEVEL_GAP, 30.0));
+        assert_eq!(at(&a1), (92.0 + 28.0 + LEVEL_GAP, 0.0));
+        assert_eq!(at(&a2), (160.0, 60.0));
+        assert_eq!(at(&b), (92.0, 120.0));
+        assert_eq!(at(&root_id), (0.0, 60.0));
+        assert_eq!(at(&hidden), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_layout_kinds() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Root").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let c = map.add_child(&root_id, "C").unwrap();
+        let at = |map: &MindMap, id: &String| (map.nodes[id].x, map.nodes[id].y);
+
+        // Two of the three equal branches fit in the right half.
+        map.compute_layout_with(LayoutKind::Balanced);
+        assert_eq!(at(&map, &root_id), (0.0, 30.0));
+        assert_eq!(at(&map, &a), (92.0, 0.0));
+        assert_eq!(at(&map, &b), (92.0, 60.0));
+        assert_eq!(at(&map, &c), (-LEVEL_GAP - 28.0, 30.0));
+
+        // The children's row (3 * 28px plus gaps) is wider than the root.
+        map.compute_layout_with(LayoutKind::OrgChart);
+        assert_eq!(at(&map, &root_id), (26.0, 0.0));
+        assert_eq!(at(&map, &a), (0.0, 90.0));
+        assert_eq!(at(&map, &b), (38.0, 90.0));
+        assert_eq!(at(&map, &c), (76.0, 90.0));
+    }
+
+    #[test]
+    fn test_text_measurer() {
+        struct Fixed;
+        impl TextMeasurer for Fixed {
+            fn measure(&self, _text: &str, font_size: f32) -> (f32, f32) {
+                (100.0, font_size * 3.0)
+            }
+        }
+
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "漢字").unwrap();
+        let a = map.add_child(&root_id, "😀 A").unwrap();
+        assert_eq!(node_width(&map.nodes[&root_id]), 52.0);
+        assert_eq!(node_width(&map.nodes[&a]), 52.0);
+
+        map.nodes.get_mut(&a).unwrap().style.font_size = Some(20);
+        map.apply_layout(LayoutKind::RightTree.engine(), &Fixed);
+        // The root's box is 62px tall and the larger child's 80px.
+        assert_eq!(map.nodes[&root_id].y, 9.0);
+        assert_eq!(map.nodes[&a].x, 120.0 + LEVEL_GAP);
+        assert_eq!(map.nodes[&a].y, 0.0);
+    }
+
+    #[test]
+    fn test_multi_line_layout() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Root").unwrap();
+        let a = map.add_child(&root_id, "Two\nlines").unwrap();
+        let b = map.add_child(&root_id, "alpha beta gamma").unwrap();
+
+        let (width, height) = map.node_size(&map.nodes[&a], &HeuristicMeasurer);
+        assert_eq!(width, 5.0 * 8.0 + 20.0);
+        assert!(height > NODE_HEIGHT);
+
+        // 100px boxes leave 80px, ten columns, for text.
+        map.set_layout_settings(LayoutSettings {
+            max_width: Some(100.0),
+        });
+        let b_node = &map.nodes[&b];
+        assert_eq!(
+            map.node_lines(b_node, &HeuristicMeasurer),
+            ["alpha beta", "gamma"]
+        );
+        assert_eq!(map.node_size(b_node, &HeuristicMeasurer).0, 100.0);
+
+        // The second node starts below the first's taller box.
+        map.compute_layout();
+        let (_, a_height) = map.node_size(&map.nodes[&a], &HeuristicMeasurer);
+        assert_eq!(map.nodes[&a].y, 0.0);
+        assert_eq!(map.nodes[&b].y, a_height + SIBLING_GAP);
+    }
+
+    #[test]
+    fn test_pinned_nodes() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.compute_layout();
+        let at = |map: &MindMap, id: &String| (map.nodes[id].x, map.nodes[id].y);
+        let (free_a, free_a1, free_b) = (at(&map, &a), at(&map, &a1), at(&map, &b));
+
+        let revision = map.revision;
+        map.set_position(&a, 300.0, 400.0).unwrap();
+        assert_eq!(map.revision, revision + 1);
+        map.compute_layout();
+        assert_eq!(at(&map, &a), (300.0, 400.0));
+        assert_eq!(
+            at(&map, &a1),
+            (free_a1.0 - free_a.0 + 300.0, free_a1.1 - free_a.1 + 400.0)
+        );
+        assert_eq!(at(&map, &b), free_b);
+
+        map.set_pinned(&a, false).unwrap();
+        map.compute_layout();
+        assert_eq!(at(&map, &a), free_a);
+        assert!(map.set_pinned("missing", true).is_err());
+    }
+
+    #[test]
+    fn test_imported_positions() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.compute_layout();
+        let root_at = (map.nodes[&root_id].x, map.nodes[&root_id].y);
+        map.set_position(&a, root_at.0 + 300.0, root_at.1 + 400.0)
+            .unwrap();
+        map.compute_layout();
+        let at = |map: &MindMap, id: &String| (map.nodes[id].x, map.nodes[id].y);
+
+        let loaded = [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+            mindnode::from_mindnode(&mindnode::to_mindnode(&map).unwrap()).unwrap(),
+        ];
+        for loaded in loaded {
+            assert!(loaded.nodes[&a].pinned);
+            assert!(!loaded.nodes[&b].pinned);
+            for id in [&a, &a1, &b] {
+                assert_eq!(at(&loaded, id), at(&map, id));
+            }
+        }
+    }
+}
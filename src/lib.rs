@@ -1,14 +1,111 @@
 use uuid::Uuid;
+#[cfg(feature = "analytics")]
+pub mod analytics;
+pub mod archive;
+pub mod arena;
+pub mod attachments;
+pub mod attributes;
+pub mod author;
+pub mod canvas;
+pub mod collab;
+pub mod command;
+pub mod csv;
+pub mod diff;
+#[cfg(feature = "docx")]
+pub mod docx;
+pub mod duplicate;
+pub mod editing;
+pub mod enex;
+pub mod error;
+pub mod events;
+pub mod export;
+pub mod fingerprint;
+pub mod floating;
+pub mod folding;
+pub mod formats;
+pub mod github;
+pub mod graphml;
+pub mod graphviz;
+pub mod groups;
+mod html;
+pub mod html_export;
+pub mod import;
+pub mod integrity;
+pub mod itmz;
+pub mod jira;
+pub mod json;
+pub mod jsonl;
+pub mod latex;
+pub mod layout;
+pub mod limits;
+pub mod link;
+pub mod markdown;
+pub mod merge;
 pub mod mindnode;
 pub mod mmap;
+pub mod moving;
+pub mod navigation;
+pub mod notes;
+pub mod notion;
+pub mod numbering;
 pub mod opml;
+pub mod outliner;
+pub mod paste;
+#[cfg(feature = "pdf")]
+pub mod pdf;
+pub mod persistence;
+mod plist;
+pub mod preserve;
+pub mod progress;
+pub mod relationship;
+pub mod revision;
+pub mod search;
+pub mod selection;
 pub mod smmx;
+#[cfg(feature = "sqlite-store")]
+pub mod sqlite_store;
+pub mod stats;
 pub mod storage;
+pub mod style;
+pub mod summaries;
+pub mod svg;
+pub mod tags;
+pub mod task;
+pub mod taskpaper;
+pub mod testing;
+pub mod text_outline;
+pub mod time_tracking;
+mod timestamp;
+pub mod transaction;
+pub mod trash;
+pub mod traversal;
+pub mod trello;
+pub mod validation;
+pub mod versions;
+pub mod views;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 pub mod xmind;
 
+use attachments::{Attachment, ResourceStore};
+pub use error::BrainCoreError;
+use events::Observers;
+use groups::Group;
+use layout::LayoutSettings;
+use limits::MapLimits;
+use navigation::SelectionHistory;
+use preserve::UnknownAttributes;
+use relationship::Relationship;
+use selection::Selection;
 use serde::{Deserialize, Serialize};
+use style::NodeStyle;
+use summaries::Summary;
+use task::TaskInfo;
+use time_tracking::TimeEntry;
+use trash::TrashEntry;
+use validation::Validators;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Node {
     pub id: String,
     pub content: String,
@@ -16,17 +113,105 @@ pub struct Node {
     pub parent: Option<String>,
     pub x: f32,
     pub y: f32,
+    /// Set when the node was placed by hand. Layout keeps its `x` and `y`
+    /// and moves its subtree along with it.
+    #[serde(default)]
+    pub pinned: bool,
     pub created: u64,
     pub modified: u64,
     #[serde(default)]
     pub icons: Vec<String>,
+    #[serde(default)]
+    pub time_entries: Vec<TimeEntry>,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub folded: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(default)]
+    pub link: Option<String>,
+    /// Freeplane's details text, shown under the node content.
+    #[serde(default)]
+    pub details: Option<String>,
+    /// Name and value pairs such as Freeplane's node attributes, in the
+    /// order they were added. Names may repeat.
+    #[serde(default)]
+    pub attributes: Vec<(String, String)>,
+    /// Elements of an imported FreeMind or Freeplane node that the crate
+    /// doesn't understand, as raw XML. They are written back unchanged on
+    /// FreeMind export so a round trip doesn't lose them.
+    #[serde(default)]
+    pub passthrough: Option<String>,
+    #[serde(default)]
+    pub unknown_attributes: Option<UnknownAttributes>,
+    #[serde(default)]
+    pub style: NodeStyle,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Files attached to the node; their bytes are in
+    /// [`MindMap::resources`].
+    #[serde(default)]
+    pub attachments: Vec<Attachment>,
+    #[serde(default)]
+    pub task: Option<TaskInfo>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub modified_by: Option<String>,
+    #[serde(default)]
+    pub revision: u64,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
+/// Serializing a map is deterministic: nodes are written in id order, so
+/// equal maps give identical bytes whatever order their nodes were
+/// inserted in. Sibling order lives in each node's `children`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MindMap {
+    #[serde(serialize_with = "serialize_nodes")]
     pub nodes: std::collections::HashMap<String, Node>,
     pub root_id: String,
-    pub selected_node_id: String,
+    #[serde(flatten)]
+    pub selection: Selection,
+    #[serde(default)]
+    pub trash: Vec<TrashEntry>,
+    #[serde(default)]
+    pub relationships: Vec<Relationship>,
+    #[serde(default)]
+    pub groups: Vec<Group>,
+    #[serde(default)]
+    pub summaries: Vec<Summary>,
+    /// Nodes without a parent that head trees of their own next to the
+    /// root's, in creation order. See [`MindMap::add_floating_node`].
+    #[serde(default)]
+    pub floating_roots: Vec<String>,
+    #[serde(default)]
+    pub revision: u64,
+    /// The bytes of the nodes' attachments.
+    #[serde(default, skip_serializing_if = "ResourceStore::is_empty")]
+    pub resources: ResourceStore,
+    /// The `<map version>` of an imported Freeplane file, written back on
+    /// FreeMind export so that Freeplane keeps treating it as its own.
+    #[serde(default)]
+    pub format_version: Option<String>,
+    /// Elements of an imported FreeMind or Freeplane map outside its nodes,
+    /// such as Freeplane's attribute registry, as raw XML. Like
+    /// [`Node::passthrough`], they are written back unchanged on FreeMind
+    /// export.
+    #[serde(default)]
+    pub passthrough: Option<String>,
+    #[serde(skip)]
+    pub(crate) validators: Validators,
+    #[serde(skip)]
+    pub(crate) observers: Observers,
+    #[serde(skip)]
+    pub(crate) limits: MapLimits,
+    #[serde(skip)]
+    pub(crate) layout: LayoutSettings,
+    #[serde(skip)]
+    pub(crate) history: SelectionHistory,
+    #[serde(skip)]
+    pub(crate) author: Option<String>,
 }
 
 impl MindMap {
@@ -39,22 +224,86 @@ impl MindMap {
             parent: None,
             x: 0.0,
             y: 0.0,
-            created: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as u64,
-            modified: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_millis() as u64,
+            created: timestamp::now_millis(),
+            modified: timestamp::now_millis(),
             icons: Vec::new(),
+            ..Default::default()
         };
         let mut nodes = std::collections::HashMap::new();
         nodes.insert(root_id.clone(), root);
+        Self::from_nodes(nodes, root_id)
+    }
+
+    /// Builds a map around already-linked nodes, selecting the root.
+    pub fn from_nodes(nodes: std::collections::HashMap<String, Node>, root_id: String) -> Self {
         Self {
             nodes,
             root_id: root_id.clone(),
-            selected_node_id: root_id,
+            selection: Selection::new(root_id),
+            trash: Vec::new(),
+            relationships: Vec::new(),
+            groups: Vec::new(),
+            summaries: Vec::new(),
+            floating_roots: Vec::new(),
+            revision: 0,
+            resources: ResourceStore::default(),
+            format_version: None,
+            passthrough: None,
+            validators: Validators::default(),
+            observers: Observers::default(),
+            limits: MapLimits::default(),
+            layout: LayoutSettings::default(),
+            history: SelectionHistory::default(),
+            author: None,
         }
     }
 }
+
+impl Default for MindMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn serialize_nodes<S: serde::Serializer>(
+    nodes: &std::collections::HashMap<String, Node>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(nodes.iter().collect::<std::collections::BTreeMap<_, _>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialization_is_byte_stable() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        for i in 0..50 {
+            map.add_child(&root_id, &format!("Node {i}")).unwrap();
+        }
+        let json = serde_json::to_string(&map).unwrap();
+
+        // The same nodes inserted in another order, into a map with
+        // another capacity, and thus another iteration order.
+        let mut ids: Vec<&String> = map.nodes.keys().collect();
+        ids.sort();
+        ids.reverse();
+        let mut nodes = std::collections::HashMap::with_capacity(1000);
+        for id in ids {
+            nodes.insert(id.clone(), map.nodes[id].clone());
+        }
+        let mut copy = MindMap::from_nodes(nodes, root_id);
+        copy.revision = map.revision;
+        assert_eq!(serde_json::to_string(&copy).unwrap(), json);
+
+        let loaded: MindMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(serde_json::to_string(&loaded).unwrap(), json);
+        let ids: Vec<&str> = json
+            .match_indices("\"id\":\"")
+            .map(|(at, _)| &json[at + 6..at + 42])
+            .collect();
+        assert!(ids.is_sorted());
+    }
+}
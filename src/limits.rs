@@ -0,0 +1,199 @@
+use crate::MindMap;
+use std::fmt;
+
+/// Upper bounds on map size, for hosts that must not let a map grow without
+/// bound. `Default` sets no limits. The root sits at depth 0.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MapLimits {
+    pub max_nodes: Option<usize>,
+    pub max_depth: Option<usize>,
+    /// Maximum node content length in characters.
+    pub max_content_len: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitError {
+    TooManyNodes { max: usize },
+    TooDeep { max: usize },
+    ContentTooLong { max: usize },
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitError::TooManyNodes { max } => write!(f, "Map is limited to {max} nodes"),
+            LimitError::TooDeep { max } => write!(f, "Map is limited to a depth of {max}"),
+            LimitError::ContentTooLong { max } => {
+                write!(f, "Node content is limited to {max} characters")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+impl MapLimits {
+    /// Checks a whole map, e.g. one that was just imported. Floating trees
+    /// count from their top node as the main one does from the root.
+    pub fn check(&self, map: &MindMap) -> Result<(), LimitError> {
+        self.check_node_count(map.nodes.len())?;
+        let mut stack: Vec<(&str, usize)> = map.tree_roots().map(|r| (r.as_str(), 0)).collect();
+        while let Some((id, depth)) = stack.pop() {
+            let Some(node) = map.nodes.get(id) else {
+                continue;
+            };
+            self.check_depth(depth)?;
+            self.check_content(&node.content)?;
+            stack.extend(node.children.iter().map(|c| (c.as_str(), depth + 1)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_node_count(&self, count: usize) -> Result<(), LimitError> {
+        match self.max_nodes {
+            Some(max) if count > max => Err(LimitError::TooManyNodes { max }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_depth(&self, depth: usize) -> Result<(), LimitError> {
+        match self.max_depth {
+            Some(max) if depth > max => Err(LimitError::TooDeep { max }),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn check_content(&self, content: &str) -> Result<(), LimitError> {
+        match self.max_content_len {
+            Some(max) if content.chars().count() > max => Err(LimitError::ContentTooLong { max }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MindMap {
+    pub fn limits(&self) -> MapLimits {
+        self.limits
+    }
+
+    /// Sets the limits enforced by [`MindMap::add_child`] and
+    /// [`MindMap::change_node`]. Nodes already in the map are not checked;
+    /// use [`MapLimits::check`] for that.
+    pub fn set_limits(&mut self, limits: MapLimits) {
+        self.limits = limits;
+    }
+
+    /// Number of ancestors between the node and the root.
+    pub(crate) fn depth(&self, node_id: &str) -> usize {
+        let mut depth = 0;
+        let mut current = self.nodes.get(node_id).and_then(|n| n.parent.as_ref());
+        while let Some(parent) = current {
+            depth += 1;
+            current = self.nodes.get(parent).and_then(|n| n.parent.as_ref());
+        }
+        depth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BrainCoreError;
+    use crate::import::ImportOptions;
+    use crate::progress::{Monitor, Stage};
+    use crate::testing::{GenerateSpec, generate};
+    use crate::{opml, storage};
+
+    #[test]
+    fn test_limits_guard_mutators_and_imports() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.set_limits(MapLimits {
+            max_nodes: Some(3),
+            max_depth: Some(1),
+            max_content_len: Some(12),
+        });
+
+        let child = map.add_child(&root_id, "Child").unwrap();
+        assert!(matches!(
+            map.add_child(&child, "Deeper"),
+            Err(BrainCoreError::Limit(LimitError::TooDeep { max: 1 }))
+        ));
+        assert!(map.change_node(&child, "Much too long here").is_err());
+        map.add_child(&root_id, "Second").unwrap();
+        assert!(map.add_child(&root_id, "Third").is_err());
+        assert_eq!(map.nodes.len(), 3);
+        assert_eq!(
+            map.limits().check(&map),
+            Ok(()),
+            "rejected changes must leave the map untouched"
+        );
+
+        // Floating trees are held to the same limits.
+        let mut floating = MindMap::new();
+        let idea = floating.add_floating_node("Idea", 0.0, 0.0).unwrap();
+        let detail = floating.add_child(&idea, "Detail").unwrap();
+        floating.add_child(&detail, "Deeper").unwrap();
+        let limits = MapLimits {
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(limits.check(&floating), Err(LimitError::TooDeep { max: 1 }));
+        floating.nodes.get_mut(&idea).unwrap().content = "Much too long here".to_string();
+        let limits = MapLimits {
+            max_content_len: Some(12),
+            ..Default::default()
+        };
+        assert_eq!(
+            limits.check(&floating),
+            Err(LimitError::ContentTooLong { max: 12 })
+        );
+
+        let xml = "<opml version=\"2.0\"><head><title>T</title></head><body>\
+                   <outline text=\"Root\"><outline text=\"A\"><outline text=\"B\"/>\
+                   </outline></outline></body></opml>";
+        let options = ImportOptions {
+            limits: MapLimits {
+                max_depth: Some(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(opml::from_opml_with(xml, &options).is_err());
+        let options = ImportOptions {
+            limits: MapLimits {
+                max_depth: Some(2),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let imported = opml::from_opml_with(xml, &options).unwrap();
+        assert_eq!(imported.limits().max_depth, Some(2));
+
+        // Oversized imports stop building nodes once they pass the limit.
+        let spec = GenerateSpec {
+            node_count: 1_000,
+            ..Default::default()
+        };
+        let xml = storage::to_xml(&generate(4, &spec)).unwrap();
+        let options = ImportOptions {
+            limits: MapLimits {
+                max_nodes: Some(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut built = 0;
+        let mut monitor = Monitor::new().on_progress(|e| {
+            if e.stage == Stage::BuildingNodes {
+                built = built.max(e.done);
+            }
+        });
+        assert!(matches!(
+            storage::from_xml_monitored(&xml, &options, &mut monitor),
+            Err(BrainCoreError::Limit(LimitError::TooManyNodes { max: 100 }))
+        ));
+        drop(monitor);
+        assert!(built < 200);
+    }
+}
@@ -0,0 +1,73 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// Points a node at a URL or file path, replacing any existing link. An
+    /// empty link clears it.
+    pub fn set_link(&mut self, node_id: &str, url: &str) -> Result<(), BrainCoreError> {
+        self.update_link(
+            node_id,
+            Some(url.trim().to_string()).filter(|u| !u.is_empty()),
+        )
+    }
+
+    pub fn clear_link(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        self.update_link(node_id, None)
+    }
+
+    fn update_link(&mut self, node_id: &str, link: Option<String>) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.link != link {
+            node.link = link;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mindnode, opml, storage, xmind};
+
+    #[test]
+    fn test_links_round_trip_through_formats() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = map.add_child(&root_id, "Docs").unwrap();
+        map.set_link(&child, " https://example.com/a?b=1&c=2 ")
+            .unwrap();
+        map.set_link(&root_id, "https://example.com").unwrap();
+        map.set_link(&root_id, "").unwrap();
+        assert_eq!(map.nodes[&root_id].link, None);
+
+        let loaded = [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            opml::from_opml(&opml::to_opml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+            mindnode::from_mindnode(&mindnode::to_mindnode(&map).unwrap()).unwrap(),
+        ];
+        for (format, loaded) in ["FreeMind", "OPML", "XMind", "MindNode"].iter().zip(loaded) {
+            let root = &loaded.nodes[&loaded.root_id];
+            assert_eq!(root.link, None, "{format}");
+            assert_eq!(
+                loaded.nodes[&root.children[0]].link.as_deref(),
+                Some("https://example.com/a?b=1&c=2"),
+                "{format}"
+            );
+        }
+
+        let feeds = "<opml version=\"2.0\"><head><title>Feeds</title></head><body>\
+                     <outline text=\"Blog\" htmlUrl=\"https://blog.example.com\"/></body></opml>";
+        let map = opml::from_opml(feeds).unwrap();
+        assert_eq!(
+            map.nodes[&map.root_id].link.as_deref(),
+            Some("https://blog.example.com")
+        );
+    }
+}
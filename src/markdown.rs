@@ -0,0 +1,362 @@
+use crate::export::ExportOptions;
+use crate::import::ImportOptions;
+use crate::paste::list_item;
+use crate::progress::{Monitor, Stage};
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Markdown outlines as used by Obsidian, Logseq and most note-taking tools:
+// the root is a `#` heading and its descendants are a nested bullet list
+// indented by two spaces per level. Notes are written as plain lines under
+// their node, backslash-escaped where they would read as a heading or list
+// item, and linked nodes as `[content](url)`. Tags follow the content as
+// `#tag`, the way Obsidian and Logseq write them. Line breaks inside node
+// content are written as `<br>`.
+
+pub fn to_markdown(map: &MindMap) -> String {
+    to_markdown_with(map, &ExportOptions::default())
+}
+
+pub fn to_markdown_with(map: &MindMap, options: &ExportOptions) -> String {
+    to_markdown_monitored(map, options, &mut Monitor::default()).unwrap_or_default()
+}
+
+pub fn to_markdown_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut out = String::new();
+    let Some(root) = map.nodes.get(&map.root_id) else {
+        return Ok(out);
+    };
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
+    monitor.tick();
+    out.push_str("# ");
+    out.push_str(&label(root));
+    out.push('\n');
+    write_note(&mut out, root, "");
+    if !root.children.is_empty() {
+        out.push('\n');
+    }
+
+    let mut stack: Vec<(&Node, usize)> = Vec::new();
+    push_children(&mut stack, root, 0, map, options);
+    while let Some((node, depth)) = stack.pop() {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let indent = "  ".repeat(depth);
+        out.push_str(&indent);
+        out.push_str("- ");
+        out.push_str(&label(node));
+        out.push('\n');
+        write_note(&mut out, node, &format!("{indent}  "));
+        push_children(&mut stack, node, depth + 1, map, options);
+    }
+    monitor.finish()?;
+    Ok(out)
+}
+
+fn push_children<'a>(
+    stack: &mut Vec<(&'a Node, usize)>,
+    node: &Node,
+    depth: usize,
+    map: &'a MindMap,
+    options: &ExportOptions,
+) {
+    for child_id in node.children.iter().rev() {
+        if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            stack.push((child, depth));
+        }
+    }
+}
+
+/// Node content on one line, wrapped in a Markdown link if the node has
+/// one, followed by its tags.
+fn label(node: &Node) -> String {
+    let content = node.content.replace('\n', "<br>");
+    let mut label = match &node.link {
+        Some(link) => format!("[{content}]({link})"),
+        None => content,
+    };
+    for tag in &node.tags {
+        label.push_str(" #");
+        label.push_str(tag);
+    }
+    label
+}
+
+fn write_note(out: &mut String, node: &Node, indent: &str) {
+    let Some(note) = &node.note else {
+        return;
+    };
+    for line in note.lines().filter(|l| !l.trim().is_empty()) {
+        out.push_str(indent);
+        out.push_str(&escape_note_line(line.trim()));
+        out.push('\n');
+    }
+}
+
+/// Backslash-escapes the marker of a note line that would otherwise read
+/// back as a heading or list item, and a leading backslash so that the
+/// escape itself survives.
+fn escape_note_line(line: &str) -> String {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if list_item(line).is_some() && digits > 0 {
+        format!("{}\\{}", &line[..digits], &line[digits..])
+    } else if list_item(line).is_some() || heading(line).is_some() || line.starts_with('\\') {
+        format!("\\{line}")
+    } else {
+        line.to_string()
+    }
+}
+
+/// Undoes [`escape_note_line`].
+fn unescape_note_line(line: &str) -> String {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if let Some(rest) = line[digits..].strip_prefix('\\') {
+        let unescaped = format!("{}{rest}", &line[..digits]);
+        let marker = list_item(&unescaped).is_some() || heading(&unescaped).is_some();
+        if marker || (digits == 0 && rest.starts_with('\\')) {
+            return unescaped;
+        }
+    }
+    line.to_string()
+}
+
+pub fn from_markdown(text: &str) -> Result<MindMap, BrainCoreError> {
+    from_markdown_with(text, &ImportOptions::default())
+}
+
+/// Reads `#` headings and `-`, `*`, `+` or numbered list items into nodes.
+/// Headings nest by level, list items by indentation and under the heading
+/// they follow. Any other text becomes the note of the node above it. A
+/// document with a single top-level heading or item uses it as the root;
+/// otherwise the top level is gathered under a "Markdown" root.
+pub fn from_markdown_with(text: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_markdown_monitored(text, options, &mut Monitor::default())
+}
+
+pub fn from_markdown_monitored(
+    text: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: "Markdown".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    // Open ancestors, ranked so that headings enclose lists and shallower
+    // headings or indents enclose deeper ones.
+    let mut stack: Vec<((u8, usize), String)> = Vec::new();
+    let mut last = root_id.clone();
+    monitor.begin(Stage::BuildingNodes, None)?;
+    for line in skip_front_matter(text).lines() {
+        if monitor.should_stop() {
+            break;
+        }
+        let body = line.trim_start();
+        if body.is_empty() {
+            continue;
+        }
+        let indent: usize = line[..line.len() - body.len()]
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        let (rank, content) = match heading(body).filter(|_| indent < 4) {
+            Some((level, content)) => ((0, level), content),
+            None => match list_item(body) {
+                Some(content) => ((1, indent), content),
+                None => {
+                    let node = nodes.get_mut(&last).unwrap();
+                    let note = node.note.get_or_insert_with(String::new);
+                    if !note.is_empty() {
+                        note.push('\n');
+                    }
+                    note.push_str(&unescape_note_line(body.trim_end()));
+                    continue;
+                }
+            },
+        };
+
+        while stack.last().is_some_and(|(open, _)| *open >= rank) {
+            stack.pop();
+        }
+        let parent = stack.last().map_or(&root_id, |(_, id)| id).clone();
+        let (content, tags) = split_tags(content.trim());
+        let (content, link) = split_link(content);
+        let id = Uuid::new_v4().to_string();
+        nodes.get_mut(&parent).unwrap().children.push(id.clone());
+        nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content: line_breaks(content),
+                parent: Some(parent),
+                link: link.map(str::to_string),
+                tags,
+                created: now,
+                modified: now,
+                ..Default::default()
+            },
+        );
+        stack.push((rank, id.clone()));
+        last = id;
+        monitor.tick();
+    }
+    monitor.finish()?;
+
+    let container = &nodes[&root_id];
+    let root_id = match container.children.as_slice() {
+        [only] if container.note.is_none() => {
+            let only = only.clone();
+            nodes.remove(&root_id);
+            nodes.get_mut(&only).unwrap().parent = None;
+            only
+        }
+        _ => root_id,
+    };
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+/// Level and text of an ATX heading such as `## Title ##`.
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.len() - line.trim_start_matches('#').len();
+    let rest = &line[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with([' ', '\t'])) {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#')))
+}
+
+/// Turns `<br>` tags back into line breaks.
+fn line_breaks(content: &str) -> String {
+    ["<br />", "<br/>"]
+        .iter()
+        .fold(content.to_string(), |text, tag| text.replace(tag, "<br>"))
+        .split("<br>")
+        .map(str::trim)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Splits `[text](url)` into its text and url.
+fn split_link(content: &str) -> (&str, Option<&str>) {
+    content
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .and_then(|rest| rest.rsplit_once("]("))
+        .map_or((content, None), |(text, url)| (text, Some(url)))
+}
+
+/// Splits trailing `#tag` words off the content. Words of digits only, as
+/// in `Fix #12`, are not tags.
+fn split_tags(content: &str) -> (&str, Vec<String>) {
+    let mut content = content;
+    let mut tags = Vec::new();
+    while let Some((rest, word)) = content.rsplit_once(' ') {
+        let Some(tag) = word.strip_prefix('#') else {
+            break;
+        };
+        if tag.is_empty() || tag.contains('#') || tag.bytes().all(|b| b.is_ascii_digit()) {
+            break;
+        }
+        tags.push(tag.to_string());
+        content = rest.trim_end();
+    }
+    tags.reverse();
+    (content, tags)
+}
+
+/// Drops a leading YAML block delimited by `---` lines.
+fn skip_front_matter(text: &str) -> &str {
+    let text = text.trim_start_matches('\u{feff}');
+    let Some(rest) = text.strip_prefix("---\n") else {
+        return text;
+    };
+    match rest.find("\n---") {
+        Some(end) => rest[end + 4..]
+            .split_once('\n')
+            .map_or("", |(_, body)| body),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Plan").unwrap();
+        let a = map.add_child(&root_id, "Research").unwrap();
+        map.set_note(&a, "Start with\nthe basics").unwrap();
+        let tricky = "- not a child\n# not a heading\n2. not a step\n\\- kept as is";
+        map.set_note(&root_id, tricky).unwrap();
+        let a1 = map.add_child(&a, "Papers").unwrap();
+        map.set_link(&a1, "https://example.com/papers").unwrap();
+        map.add_child(&root_id, "Write\nit up").unwrap();
+
+        let markdown = to_markdown(&map);
+        assert_eq!(
+            markdown,
+            "# Plan\n\\- not a child\n\\# not a heading\n2\\. not a step\n\\\\- kept as is\n\n\
+             - Research\n  Start with\n  the basics\n  \
+             - [Papers](https://example.com/papers)\n- Write<br>it up\n"
+        );
+
+        let loaded = from_markdown(&markdown).unwrap();
+        let root = &loaded.nodes[&loaded.root_id];
+        assert_eq!(root.content, "Plan");
+        assert_eq!(root.note.as_deref(), Some(tricky));
+        let research = &loaded.nodes[&root.children[0]];
+        assert_eq!(research.note.as_deref(), Some("Start with\nthe basics"));
+        let papers = &loaded.nodes[&research.children[0]];
+        assert_eq!(papers.content, "Papers");
+        assert_eq!(papers.link.as_deref(), Some("https://example.com/papers"));
+        assert_eq!(loaded.nodes[&root.children[1]].content, "Write\nit up");
+        assert_eq!(to_markdown(&loaded), markdown);
+    }
+
+    #[test]
+    fn test_markdown_headings_and_lists() {
+        let text = "---\ntags: [a]\n---\n## One\n- a\n    - b\n* c\n### Two\n1. d\n## Three ##\n";
+        let map = from_markdown(text).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "Markdown");
+        let content = |id: &String| map.nodes[id].content.as_str();
+        let top: Vec<&str> = root.children.iter().map(content).collect();
+        assert_eq!(top, vec!["One", "Three"]);
+
+        let one = &map.nodes[&root.children[0]];
+        let under_one: Vec<&str> = one.children.iter().map(content).collect();
+        assert_eq!(under_one, vec!["a", "c", "Two"]);
+        let a = &map.nodes[&one.children[0]];
+        assert_eq!(content(&a.children[0]), "b");
+        let two = &map.nodes[&one.children[2]];
+        assert_eq!(content(&two.children[0]), "d");
+    }
+}
@@ -0,0 +1,555 @@
+use crate::paste::Subtree;
+use crate::relationship::Relationship;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
+
+/// A change that both sides of a [`diff3`] made in incompatible ways, and
+/// how it was settled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Conflict {
+    /// Both sides renamed the node differently. Ours was kept.
+    Content {
+        node_id: String,
+        ours: String,
+        theirs: String,
+    },
+    /// Both sides changed another field of the node differently, such as
+    /// its note or icons. Ours was kept.
+    Field {
+        node_id: String,
+        field: &'static str,
+    },
+    /// Both sides moved the node to different parents, or their moves
+    /// together would have made a node its own ancestor. Ours was kept.
+    Moved { node_id: String },
+    /// One side deleted the node while the other edited it or something
+    /// below it. The node was kept.
+    DeleteEdit { node_id: String, deleted_by: Side },
+}
+
+/// Which of the two edited versions given to [`diff3`] did something.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// The outcome of [`diff3`]: the merged map, valid even when there were
+/// conflicts, and the conflicts in node id order.
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    pub map: MindMap,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MindMap {
+    /// Grafts a copy of `other`'s tree under `attach_under`, after its
+    /// existing children, and returns the mapping from `other`'s node ids to
+    /// the ids they got here. Nodes keep their ids unless they collide with
+    /// one already in this map; icons, notes, timestamps and authors carry
+    /// over. Relationships between the grafted nodes and attached files
+    /// come along too, while `other`'s trash does not.
+    ///
+    /// Content goes through this map's validators and the result must fit
+    /// its limits, as with [`MindMap::paste_subtree`].
+    pub fn merge(
+        &mut self,
+        other: &MindMap,
+        attach_under: &str,
+    ) -> Result<HashMap<String, String>, BrainCoreError> {
+        if !other.nodes.contains_key(&other.root_id) {
+            return Err(BrainCoreError::NodeNotFound(other.root_id.clone()));
+        }
+        let subtree = Subtree::copy_of(other, &other.root_id);
+        let (_, ids) = self.graft(attach_under, subtree, true)?;
+
+        for relationship in &other.relationships {
+            let (Some(from), Some(to)) = (ids.get(&relationship.from), ids.get(&relationship.to))
+            else {
+                continue;
+            };
+            let id = if self.relationships.iter().any(|r| r.id == relationship.id) {
+                Uuid::new_v4().to_string()
+            } else {
+                relationship.id.clone()
+            };
+            self.relationships.push(Relationship {
+                id,
+                from: from.clone(),
+                to: to.clone(),
+                label: relationship.label.clone(),
+            });
+        }
+        Ok(ids)
+    }
+}
+
+/// Merges two edited versions of `base`, matching nodes by id, as a
+/// version control system would merge text. Changes made on one side only
+/// are taken as they are: edits, additions, deletions, moves and reorders.
+/// Where both sides changed the same thing differently, ours wins and a
+/// [`Conflict`] says so, except that edits win over deletions.
+///
+/// The root, selection and trash come from `ours`. Relationships, groups
+/// and summaries are merged by id, and attached files are pooled from both
+/// sides. The merged map keeps ours' limits and fails if it doesn't fit
+/// them.
+pub fn diff3(
+    base: &MindMap,
+    ours: &MindMap,
+    theirs: &MindMap,
+) -> Result<MergeResult, BrainCoreError> {
+    let mut conflicts = Vec::new();
+    let ids: BTreeSet<&String> = ours.nodes.keys().chain(theirs.nodes.keys()).collect();
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    for id in &ids {
+        let base_node = base.nodes.get(*id);
+        let merged = match (ours.nodes.get(*id), theirs.nodes.get(*id)) {
+            (Some(o), Some(t)) => Some(merge_node(base_node, o, t, &mut conflicts)),
+            (Some(kept), None) | (None, Some(kept)) => match base_node {
+                Some(b) if same_node(b, kept) => None,
+                Some(_) => {
+                    conflicts.push(deleted(id, !ours.nodes.contains_key(*id)));
+                    Some(kept.clone())
+                }
+                None => Some(kept.clone()),
+            },
+            (None, None) => None,
+        };
+        if let Some(node) = merged {
+            nodes.insert(node.id.clone(), node);
+        }
+    }
+
+    // A node kept on one side brings back the ancestors the other side
+    // deleted.
+    let mut pending: Vec<String> = nodes.keys().cloned().collect();
+    while let Some(id) = pending.pop() {
+        let Some(parent_id) = nodes[&id].parent.clone() else {
+            continue;
+        };
+        if nodes.contains_key(&parent_id) {
+            continue;
+        }
+        let parent = ours.nodes.get(&parent_id).or(theirs.nodes.get(&parent_id));
+        match parent {
+            Some(parent) => {
+                conflicts.push(deleted(&parent_id, !ours.nodes.contains_key(&parent_id)));
+                nodes.insert(parent_id.clone(), parent.clone());
+                pending.push(parent_id);
+            }
+            None => nodes.get_mut(&id).unwrap().parent = Some(ours.root_id.clone()),
+        }
+    }
+
+    // Moves that are fine on their own can form a loop together; put the
+    // nodes involved back where ours has them until none is left.
+    let mut sorted: Vec<String> = nodes.keys().cloned().collect();
+    sorted.sort();
+    for id in &sorted {
+        while let Some((node_id, parent)) = loop_breaker(id, &nodes, ours) {
+            nodes.get_mut(&node_id).unwrap().parent = parent;
+            conflicts.push(Conflict::Moved { node_id });
+        }
+    }
+
+    let mut children: HashMap<String, Vec<String>> = HashMap::new();
+    for id in &sorted {
+        let empty = Vec::new();
+        let list = |map: &MindMap| map.nodes.get(id).map_or(&empty, |n| &n.children).clone();
+        let (base_list, ours_list, theirs_list) = (list(base), list(ours), list(theirs));
+        let mut order = merge_order(&base_list, &ours_list, &theirs_list);
+        order.retain(|c| nodes.get(c).is_some_and(|n| n.parent.as_ref() == Some(id)));
+        children.insert(id.clone(), order);
+    }
+    for id in &sorted {
+        if let Some(parent) = nodes[id].parent.clone() {
+            let siblings = children.get_mut(&parent).unwrap();
+            if !siblings.contains(id) {
+                siblings.push(id.clone());
+            }
+        }
+    }
+    for (id, list) in children {
+        nodes.get_mut(&id).unwrap().children = list;
+    }
+
+    let floating: Vec<String> = ours
+        .floating_roots
+        .iter()
+        .chain(&theirs.floating_roots)
+        .filter(|id| nodes.get(*id).is_some_and(|n| n.parent.is_none()))
+        .fold(Vec::new(), |mut list, id| {
+            if !list.contains(id) {
+                list.push(id.clone());
+            }
+            list
+        });
+    let relationships = merge_relationships(base, ours, theirs, &nodes);
+    let mut map = MindMap::from_nodes(nodes, ours.root_id.clone());
+    map.floating_roots = floating;
+    map.relationships = relationships;
+    map.groups = merge_by_id([&base.groups, &ours.groups, &theirs.groups], |g| &g.id);
+    map.groups.retain(|g| map.nodes.contains_key(&g.node_id));
+    map.summaries = merge_by_id([&base.summaries, &ours.summaries, &theirs.summaries], |s| {
+        &s.id
+    });
+    map.summaries
+        .retain(|s| map.nodes.contains_key(&s.parent_id));
+    map.trash = ours.trash.clone();
+    map.format_version = ours.format_version.clone();
+    map.passthrough = ours.passthrough.clone();
+    map.resources = ours.resources.clone();
+    let renamed = map.resources.extend_from(&theirs.resources);
+    for (id, node) in &mut map.nodes {
+        // Files ours has at the node keep their reference.
+        let ours_refs: Vec<&str> = ours.nodes.get(id).map_or(Vec::new(), |n| {
+            n.attachments.iter().map(|a| a.data_ref.as_str()).collect()
+        });
+        rename_refs(node, &renamed, |data_ref| !ours_refs.contains(&data_ref));
+    }
+    if map.nodes.contains_key(&ours.selection.primary) {
+        map.selection.primary = ours.selection.primary.clone();
+    }
+    map.revision = ours.revision.max(theirs.revision) + 1;
+    map.limits = ours.limits;
+    map.limits.check(&map)?;
+    conflicts.sort_by(|a, b| conflict_node(a).cmp(conflict_node(b)));
+    Ok(MergeResult { map, conflicts })
+}
+
+/// Points the node's attachments that `applies` accepts at the new
+/// references of files that had to be stored under another one.
+fn rename_refs(node: &mut Node, renamed: &HashMap<String, String>, applies: impl Fn(&str) -> bool) {
+    for attachment in &mut node.attachments {
+        if let Some(data_ref) = renamed.get(&attachment.data_ref)
+            && applies(&attachment.data_ref)
+        {
+            attachment.data_ref = data_ref.clone();
+        }
+    }
+}
+
+/// Merges two reorderings of a child list. The children both sides have
+/// keep the order of the side that changed it, ours if both did; the ones
+/// only theirs has follow the child they follow there.
+fn merge_order(base: &[String], ours: &[String], theirs: &[String]) -> Vec<String> {
+    let shared = |list: &[String]| -> Vec<String> {
+        list.iter()
+            .filter(|c| ours.contains(c) && theirs.contains(c))
+            .cloned()
+            .collect()
+    };
+    let shared_ours = shared(ours);
+    let mut shared_order = if shared(base) == shared_ours {
+        shared(theirs)
+    } else {
+        shared_ours
+    }
+    .into_iter();
+    let mut order: Vec<String> = ours
+        .iter()
+        .map(|c| {
+            if theirs.contains(c) {
+                shared_order.next().unwrap()
+            } else {
+                c.clone()
+            }
+        })
+        .collect();
+    for (i, child) in theirs.iter().enumerate() {
+        if order.contains(child) {
+            continue;
+        }
+        let at = match i {
+            0 => 0,
+            _ => order.iter().position(|c| *c == theirs[i - 1]).unwrap() + 1,
+        };
+        order.insert(at, child.clone());
+    }
+    order
+}
+
+fn deleted(node_id: &str, by_ours: bool) -> Conflict {
+    Conflict::DeleteEdit {
+        node_id: node_id.to_string(),
+        deleted_by: if by_ours { Side::Ours } else { Side::Theirs },
+    }
+}
+
+fn conflict_node(conflict: &Conflict) -> &str {
+    match conflict {
+        Conflict::Content { node_id, .. }
+        | Conflict::Field { node_id, .. }
+        | Conflict::Moved { node_id }
+        | Conflict::DeleteEdit { node_id, .. } => node_id,
+    }
+}
+
+/// Whether a node still has its base content and place, ignoring its
+/// children, which are judged on their own.
+fn same_node(base: &Node, node: &Node) -> bool {
+    base.parent == node.parent
+        && base.content == node.content
+        && base.note == node.note
+        && base.link == node.link
+        && base.details == node.details
+        && base.icons == node.icons
+        && base.tags == node.tags
+        && base.attachments == node.attachments
+        && base.attributes == node.attributes
+        && base.folded == node.folded
+        && base.archived == node.archived
+        && base.style == node.style
+        && base.task == node.task
+}
+
+/// The three-way choice for one value: the side that changed it, or ours
+/// with a conflict when both did.
+fn pick<T: PartialEq + Clone>(base: Option<&T>, ours: &T, theirs: &T) -> (T, bool) {
+    if ours == theirs || base == Some(theirs) {
+        (ours.clone(), false)
+    } else if base == Some(ours) {
+        (theirs.clone(), false)
+    } else {
+        (ours.clone(), true)
+    }
+}
+
+fn merge_node(
+    base: Option<&Node>,
+    ours: &Node,
+    theirs: &Node,
+    conflicts: &mut Vec<Conflict>,
+) -> Node {
+    let mut node = ours.clone();
+    let id = &ours.id;
+    let field = |name: &'static str| Conflict::Field {
+        node_id: id.clone(),
+        field: name,
+    };
+
+    let (content, clash) = pick(base.map(|b| &b.content), &ours.content, &theirs.content);
+    if clash {
+        conflicts.push(Conflict::Content {
+            node_id: id.clone(),
+            ours: ours.content.clone(),
+            theirs: theirs.content.clone(),
+        });
+    }
+    node.content = content;
+    let (parent, clash) = pick(base.map(|b| &b.parent), &ours.parent, &theirs.parent);
+    if clash {
+        conflicts.push(Conflict::Moved {
+            node_id: id.clone(),
+        });
+    }
+    node.parent = parent;
+
+    let mut clashes = Vec::new();
+    macro_rules! merge_fields {
+        ($($name:ident),*) => {$(
+            let (value, clash) = pick(base.map(|b| &b.$name), &ours.$name, &theirs.$name);
+            if clash {
+                clashes.push(stringify!($name));
+            }
+            node.$name = value;
+        )*};
+    }
+    merge_fields!(note, link, details, icons, tags, attributes, attachments);
+    merge_fields!(folded, archived, style, task);
+    conflicts.extend(clashes.into_iter().map(field));
+
+    node.modified = ours.modified.max(theirs.modified);
+    node.revision = ours.revision.max(theirs.revision);
+    node
+}
+
+/// If `id` leads into a parent loop, a node on it and the parent that
+/// breaks the loop: ours, or the root when ours no longer applies.
+fn loop_breaker(
+    id: &str,
+    nodes: &HashMap<String, Node>,
+    ours: &MindMap,
+) -> Option<(String, Option<String>)> {
+    let mut path: Vec<&str> = vec![id];
+    let mut current = id;
+    while let Some(parent) = nodes.get(current).and_then(|n| n.parent.as_deref()) {
+        if let Some(start) = path.iter().position(|p| *p == parent) {
+            let mut members = path.split_off(start);
+            members.sort();
+            let reverted = members.iter().find_map(|m| {
+                let parent = &ours.nodes.get(*m)?.parent;
+                let applies = parent.as_ref().is_none_or(|p| nodes.contains_key(p));
+                (applies && *parent != nodes[*m].parent).then(|| (m.to_string(), parent.clone()))
+            });
+            return reverted.or_else(|| Some((members[0].to_string(), Some(ours.root_id.clone()))));
+        }
+        path.push(parent);
+        current = parent;
+    }
+    None
+}
+
+fn merge_relationships(
+    base: &MindMap,
+    ours: &MindMap,
+    theirs: &MindMap,
+    nodes: &HashMap<String, Node>,
+) -> Vec<Relationship> {
+    merge_by_id(
+        [
+            &base.relationships,
+            &ours.relationships,
+            &theirs.relationships,
+        ],
+        |r| &r.id,
+    )
+    .into_iter()
+    .filter(|r| nodes.contains_key(&r.from) && nodes.contains_key(&r.to))
+    .collect()
+}
+
+/// Ours, without what theirs deleted, followed by what theirs added.
+fn merge_by_id<T: Clone>([base, ours, theirs]: [&[T]; 3], id: impl Fn(&T) -> &String) -> Vec<T> {
+    let ids = |list: &[T]| -> HashSet<String> { list.iter().map(|t| id(t).clone()).collect() };
+    let (base_ids, ours_ids, theirs_ids) = (ids(base), ids(ours), ids(theirs));
+    ours.iter()
+        .filter(|t| theirs_ids.contains(id(t)) || !base_ids.contains(id(t)))
+        .chain(
+            theirs
+                .iter()
+                .filter(|t| !ours_ids.contains(id(t)) && !base_ids.contains(id(t))),
+        )
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::{LimitError, MapLimits};
+
+    #[test]
+    fn test_merge_maps() {
+        let mut master = MindMap::new();
+        let master_root = master.root_id.clone();
+        let inbox = master.add_child(&master_root, "Inbox").unwrap();
+
+        let mut other = MindMap::new();
+        let other_root = other.root_id.clone();
+        other.change_node(&other_root, "Imported").unwrap();
+        let a = other.add_child(&other_root, "A").unwrap();
+        other
+            .nodes
+            .get_mut(&a)
+            .unwrap()
+            .icons
+            .push("idea".to_string());
+        let b = other.add_child(&other_root, "B").unwrap();
+        other.add_relationship(&a, &b, Some("see")).unwrap();
+        let created = other.nodes[&a].created;
+
+        let ids = master.merge(&other, &inbox).unwrap();
+        assert_eq!(ids[&a], a);
+        assert_eq!(master.nodes.len(), 5);
+        assert_eq!(master.nodes[&inbox].children, vec![other_root.clone()]);
+        assert_eq!(master.nodes[&a].icons, vec!["idea".to_string()]);
+        assert_eq!(master.nodes[&a].created, created);
+        assert_eq!(master.relationships.len(), 1);
+
+        // Merging the same map again collides on every id.
+        let ids = master.merge(&other, &master_root).unwrap();
+        assert_eq!(master.nodes.len(), 8);
+        assert!(ids.iter().all(|(old, new)| old != new));
+        let copy = &master.nodes[&ids[&other_root]];
+        assert_eq!(copy.parent.as_ref(), Some(&master_root));
+        assert_eq!(copy.children, vec![ids[&a].clone(), ids[&b].clone()]);
+        assert_eq!(master.relationships.len(), 2);
+        assert_eq!(master.relationships[1].from, ids[&a]);
+        assert_ne!(master.relationships[1].id, master.relationships[0].id);
+        assert!(master.validate().is_empty());
+
+        assert!(matches!(
+            master.merge(&other, "missing"),
+            Err(BrainCoreError::NodeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_diff3() {
+        let mut base = MindMap::new();
+        let root_id = base.root_id.clone();
+        let a = base.add_child(&root_id, "A").unwrap();
+        let b = base.add_child(&root_id, "B").unwrap();
+        let c = base.add_child(&root_id, "C").unwrap();
+        let d = base.add_child(&c, "D").unwrap();
+
+        let mut ours = base.clone();
+        ours.change_node(&a, "Ours").unwrap();
+        ours.set_note(&b, "Note").unwrap();
+        ours.trash_node(&c).unwrap();
+        let e = ours.add_child(&root_id, "E").unwrap();
+
+        let mut theirs = base.clone();
+        theirs.change_node(&a, "Theirs").unwrap();
+        theirs.move_node(&b, &root_id, 0).unwrap();
+        theirs.change_node(&d, "Edited").unwrap();
+        let f = theirs.add_child(&b, "F").unwrap();
+
+        let result = diff3(&base, &ours, &theirs).unwrap();
+        let map = &result.map;
+        assert!(map.validate().is_empty());
+        assert_eq!(map.nodes[&a].content, "Ours");
+        assert_eq!(map.nodes[&b].note.as_deref(), Some("Note"));
+        assert_eq!(map.nodes[&b].children, vec![f]);
+        assert_eq!(map.nodes[&c].children, vec![d.clone()]);
+        assert_eq!(map.nodes[&d].content, "Edited");
+        assert_eq!(
+            map.nodes[&root_id].children,
+            vec![b.clone(), a.clone(), c.clone(), e]
+        );
+
+        let mut expected = vec![
+            Conflict::Content {
+                node_id: a.clone(),
+                ours: "Ours".to_string(),
+                theirs: "Theirs".to_string(),
+            },
+            Conflict::DeleteEdit {
+                node_id: c,
+                deleted_by: Side::Ours,
+            },
+            Conflict::DeleteEdit {
+                node_id: d,
+                deleted_by: Side::Ours,
+            },
+        ];
+        expected.sort_by(|x, y| conflict_node(x).cmp(conflict_node(y)));
+        assert_eq!(result.conflicts, expected);
+
+        // Moves that only loop together are undone on their side.
+        let mut ours = base.clone();
+        ours.move_node(&a, &b, 0).unwrap();
+        let mut theirs = base.clone();
+        theirs.move_node(&b, &a, 0).unwrap();
+        let result = diff3(&base, &ours, &theirs).unwrap();
+        assert!(result.map.validate().is_empty());
+        assert_eq!(result.conflicts.len(), 1);
+
+        // Each side fits ours' limits on its own, but not both together.
+        ours = base.clone();
+        ours.set_limits(MapLimits {
+            max_nodes: Some(6),
+            ..Default::default()
+        });
+        ours.add_child(&root_id, "Ours").unwrap();
+        theirs = base.clone();
+        theirs.add_child(&root_id, "Theirs").unwrap();
+        assert!(matches!(
+            diff3(&base, &ours, &theirs),
+            Err(BrainCoreError::Limit(LimitError::TooManyNodes { max: 6 }))
+        ));
+    }
+}
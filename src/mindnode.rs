@@ -1,11 +1,19 @@
-use crate::{MindMap, Node};
+use crate::attachments::{self, Attachment};
+use crate::export::{ExportOptions, TextWriter};
+use crate::import::{ImportFormat, ImportOptions};
+use crate::layout::Offsets;
+use crate::progress::{Monitor, Stage};
+use crate::relationship::Relationship;
+use crate::style::NodeStyle;
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node, html, plist};
 use quick_xml::de::from_str;
-use quick_xml::se::to_string;
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use std::collections::HashMap;
-use std::io::{Cursor, Read, Write};
-use std::time::{SystemTime, UNIX_EPOCH};
-use zip::write::SimpleFileOptions;
+use std::io::{Cursor, Read, Seek, Write};
+use uuid::Uuid;
 use zip::{ZipArchive, ZipWriter};
 
 // MindNode XML Structure (Simplified)
@@ -34,22 +42,155 @@ pub struct MindNodeNodes {
 pub struct MindNodeNode {
     #[serde(rename = "@id")]
     pub id: String,
+    /// Offset from the parent of a node placed by hand, as `{x, y}`.
+    #[serde(rename = "@location", default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
     #[serde(rename = "title")]
     pub title: MindNodeTitle,
+    #[serde(rename = "note", default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<MindNodeNote>,
+    #[serde(rename = "url", default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(rename = "attachment", default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<MindNodeAttachment>,
     #[serde(rename = "nodes", default)]
     pub children: Option<MindNodeNodes>,
 }
 
+/// A file attached to a node; `src` is its path in the package.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MindNodeAttachment {
+    #[serde(rename = "@name")]
+    pub name: String,
+    #[serde(rename = "@mime")]
+    pub mime: String,
+    #[serde(rename = "@src")]
+    pub src: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct MindNodeTitle {
     #[serde(rename = "text")]
     pub text: String,
 }
 
-pub fn to_mindnode(map: &MindMap) -> Result<Vec<u8>, String> {
-    let root_node = map.nodes.get(&map.root_id).ok_or("Root node not found")?;
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MindNodeNote {
+    #[serde(rename = "text", default)]
+    pub text: String,
+}
+
+/// The two layouts of a `.mindnode` package. Older versions keep the
+/// `<mindMap>` XML above in `contents.xml`; recent ones keep a document of
+/// `mindMap.mainNodes` there as a property list, or as `contents.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MindNodeVariant {
+    #[default]
+    Legacy,
+    Document,
+}
+
+/// Tells which layout the package in `data` uses.
+pub fn detect_variant(data: &[u8]) -> Result<MindNodeVariant, BrainCoreError> {
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+    Ok(read_contents(&mut archive)?.0)
+}
+
+pub fn to_mindnode(map: &MindMap) -> Result<Vec<u8>, BrainCoreError> {
+    to_mindnode_with(map, &ExportOptions::default())
+}
+
+pub fn to_mindnode_with(map: &MindMap, options: &ExportOptions) -> Result<Vec<u8>, BrainCoreError> {
+    to_mindnode_monitored(map, options, &mut Monitor::default())
+}
+
+/// Writes the package in the given layout; the other entry points write
+/// [`MindNodeVariant::Legacy`], which every MindNode version opens.
+pub fn to_mindnode_as(
+    map: &MindMap,
+    variant: MindNodeVariant,
+    options: &ExportOptions,
+) -> Result<Vec<u8>, BrainCoreError> {
+    let mut buf = Vec::new();
+    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+    write_archive(map, &mut zip, variant, options, &mut Monitor::default())?;
+    zip.finish()?;
 
-    let mindnode_root = node_to_mindnode_node(root_node, map);
+    Ok(buf)
+}
+
+pub fn to_mindnode_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<Vec<u8>, BrainCoreError> {
+    let mut buf = Vec::new();
+    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+    write_archive(map, &mut zip, MindNodeVariant::Legacy, options, monitor)?;
+    zip.finish()?;
+
+    Ok(buf)
+}
+
+/// Streams the MindNode archive to `writer`. The document is serialized
+/// straight into the zip entry, so no copy of the file is held in memory.
+pub fn write_mindnode<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_mindnode_with(map, writer, &ExportOptions::default())
+}
+
+pub fn write_mindnode_with<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    write_mindnode_monitored(map, writer, options, &mut Monitor::default())
+}
+
+pub fn write_mindnode_as<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    variant: MindNodeVariant,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    let mut zip = ZipWriter::new_stream(&mut *writer);
+    write_archive(map, &mut zip, variant, options, &mut Monitor::default())?;
+    zip.finish()?;
+    Ok(writer.flush()?)
+}
+
+pub fn write_mindnode_monitored<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let mut zip = ZipWriter::new_stream(&mut *writer);
+    write_archive(map, &mut zip, MindNodeVariant::Legacy, options, monitor)?;
+    zip.finish()?;
+    Ok(writer.flush()?)
+}
+
+fn write_archive<W: Write + Seek>(
+    map: &MindMap,
+    zip: &mut ZipWriter<W>,
+    variant: MindNodeVariant,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let root_node = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+    if variant == MindNodeVariant::Document {
+        return write_document(map, root_node, zip, options, monitor);
+    }
+
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
+    let mindnode_root = node_to_mindnode_node(root_node, map, options, monitor);
+    monitor.finish()?;
 
     let mindnode_map = MindNodeMap {
         document: MindNodeDocument {
@@ -59,34 +200,37 @@ pub fn to_mindnode(map: &MindMap) -> Result<Vec<u8>, String> {
         },
     };
 
-    let xml_content = to_string(&mindnode_map).map_err(|e| e.to_string())?;
-    let xml_content = format!(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
-        xml_content
-    );
-
-    let mut buf = Vec::new();
-    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
-
-    let options = SimpleFileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
+    let zip_options = options
+        .zip_options(zip::CompressionMethod::Stored)
         .unix_permissions(0o755);
 
-    zip.start_file("contents.xml", options)
-        .map_err(|e| e.to_string())?;
-    zip.write_all(xml_content.as_bytes())
-        .map_err(|e| e.to_string())?;
-
-    zip.finish().map_err(|e| e.to_string())?;
-
-    Ok(buf)
+    monitor.begin(Stage::WritingArchive, Some(2))?;
+    zip.start_file("contents.xml", zip_options)?;
+    zip.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    let mut text = TextWriter::new(&mut *zip);
+    let result = options.write_xml(&mindnode_map, &mut text);
+    text.check(result)?;
+    monitor.tick();
+    let files = attachments::package_files(map, options);
+    attachments::write_package_files(&files, zip, zip_options)?;
+    monitor.tick();
+    monitor.finish()
 }
 
-fn node_to_mindnode_node(node: &Node, map: &MindMap) -> MindNodeNode {
+fn node_to_mindnode_node(
+    node: &Node,
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> MindNodeNode {
+    monitor.tick();
     let mut children_vec = Vec::new();
     for child_id in &node.children {
-        if let Some(child) = map.nodes.get(child_id) {
-            children_vec.push(node_to_mindnode_node(child, map));
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            children_vec.push(node_to_mindnode_node(child, map, options, monitor));
         }
     }
 
@@ -98,25 +242,165 @@ fn node_to_mindnode_node(node: &Node, map: &MindMap) -> MindNodeNode {
 
     MindNodeNode {
         id: node.id.clone(),
+        location: node
+            .parent
+            .as_ref()
+            .and_then(|p| map.nodes.get(p))
+            .filter(|_| node.pinned)
+            .map(|p| format!("{{{}, {}}}", node.x - p.x, node.y - p.y)),
         title: MindNodeTitle {
             text: node.content.clone(),
         },
+        note: node.note.clone().map(|text| MindNodeNote { text }),
+        url: node.link.clone(),
+        attachments: node
+            .attachments
+            .iter()
+            .map(|a| MindNodeAttachment {
+                name: a.name.clone(),
+                mime: a.mime.clone(),
+                src: attachments::package_path(a),
+            })
+            .collect(),
         children,
     }
 }
 
-pub fn from_mindnode(data: &[u8]) -> Result<MindMap, String> {
-    let reader = Cursor::new(data);
-    let mut archive = ZipArchive::new(reader).map_err(|e| e.to_string())?;
+fn write_document<W: Write + Seek>(
+    map: &MindMap,
+    root: &Node,
+    zip: &mut ZipWriter<W>,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    monitor.begin(Stage::WritingNodes, Some(options.exported_count(map, true)))?;
+    let mut main_nodes = vec![node_to_document(root, map, options, monitor)];
+    for node in options.exported_floating(map) {
+        let mut main_node = node_to_document(node, map, options, monitor);
+        main_node["location"] = json!(format!("{{{}, {}}}", node.x - root.x, node.y - root.y));
+        main_nodes.push(main_node);
+    }
+    monitor.finish()?;
+
+    let cross_connections: Vec<Value> = map
+        .exported_relationships(options)
+        .map(|r| {
+            json!({
+                "startNodeID": r.from,
+                "endNodeID": r.to,
+                "title": r.label.as_ref().map(|text| json!({ "text": text })),
+            })
+        })
+        .collect();
+    let document = json!({
+        "mindMap": {
+            "mainNodes": main_nodes,
+            "crossConnections": cross_connections,
+        }
+    });
 
-    let mut xml_content = String::new();
-    let mut file = archive
-        .by_name("contents.xml")
-        .map_err(|_| "contents.xml not found in archive")?;
-    file.read_to_string(&mut xml_content)
-        .map_err(|e| e.to_string())?;
+    let zip_options = options
+        .zip_options(zip::CompressionMethod::Stored)
+        .unix_permissions(0o755);
+
+    monitor.begin(Stage::WritingArchive, Some(2))?;
+    zip.start_file("contents.xml", zip_options)?;
+    zip.write_all(plist::to_xml(&document).as_bytes())?;
+    monitor.tick();
+    let files = attachments::package_files(map, options);
+    attachments::write_package_files(&files, zip, zip_options)?;
+    monitor.tick();
+    monitor.finish()
+}
+
+fn node_to_document(
+    node: &Node,
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Value {
+    monitor.tick();
+    let mut subnodes = Vec::new();
+    for child_id in &node.children {
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            let mut subnode = node_to_document(child, map, options, monitor);
+            if child.pinned {
+                subnode["location"] =
+                    json!(format!("{{{}, {}}}", child.x - node.x, child.y - node.y));
+            }
+            subnodes.push(subnode);
+        }
+    }
+
+    let style = &node.style;
+    json!({
+        "nodeID": node.id,
+        "title": { "text": node.content },
+        "note": node.note.as_ref().map(|text| json!({ "text": text })),
+        "link": node.link,
+        "task": node.task.as_ref().map(|t| json!({ "state": if t.done { 2 } else { 1 } })),
+        "nodeStyle": (!style.is_default()).then(|| json!({
+            "fillColor": style.background_color,
+            "strokeColor": style.edge_color,
+            "strokeWidth": style.edge_width,
+            "titleStyle": {
+                "color": style.text_color,
+                "fontSize": style.font_size,
+                "bold": style.bold.then_some(true),
+                "italic": style.italic.then_some(true),
+            },
+        })),
+        "attachments": node.attachments.iter().map(|a| json!({
+            "name": a.name,
+            "mime": a.mime,
+            "src": attachments::package_path(a),
+        })).collect::<Vec<_>>(),
+        "subnodes": subnodes,
+    })
+}
+
+pub fn from_mindnode(data: &[u8]) -> Result<MindMap, BrainCoreError> {
+    from_mindnode_with(data, &ImportOptions::default())
+}
+
+pub fn from_mindnode_with(data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_mindnode_monitored(data, options, &mut Monitor::default())
+}
+
+pub fn from_mindnode_monitored(
+    data: &[u8],
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let reader = Cursor::new(data);
+    let mut archive = ZipArchive::new(reader)?;
+
+    monitor.begin(Stage::ReadingArchive, Some(1))?;
+    let (variant, contents) = read_contents(&mut archive)?;
+    monitor.tick();
+
+    if variant == MindNodeVariant::Document {
+        let document = if contents.trim_start().starts_with('{') {
+            serde_json::from_str(&contents)
+                .map_err(|e| BrainCoreError::parse(ImportFormat::MindNode, e))?
+        } else {
+            plist::from_xml(&contents)
+                .map_err(|e| BrainCoreError::parse(ImportFormat::MindNode, e))?
+        };
+        let mut map = document_to_map(&document, monitor)?;
+        attachments::read_package_files(&mut map, &mut archive)?;
+        options.apply(&mut map)?;
+        map.place_pinned_offsets(Offsets::FromParent);
+        return Ok(map);
+    }
+    let xml_content = contents;
 
-    let mindnode_map: MindNodeMap = from_str(&xml_content).map_err(|e| e.to_string())?;
+    let mindnode_map: MindNodeMap =
+        from_str(&xml_content).map_err(|e| BrainCoreError::parse(ImportFormat::MindNode, e))?;
 
     let mut nodes = HashMap::new();
     // MindNode can have multiple top level nodes in the XML structure defined above,
@@ -126,20 +410,239 @@ pub fn from_mindnode(data: &[u8]) -> Result<MindMap, String> {
         return Ok(MindMap::new());
     }
 
-    let root_id = mindnode_node_to_node(&mindnode_map.document.nodes.node[0], None, &mut nodes);
+    let root = &mindnode_map.document.nodes.node[0];
+    monitor.begin(Stage::BuildingNodes, Some(count_nodes(root)))?;
+    let root_id = mindnode_node_to_node(root, None, &mut nodes, monitor);
+    monitor.finish()?;
 
-    Ok(MindMap {
-        nodes,
-        root_id: root_id.clone(),
-        selected_node_id: root_id,
-    })
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    attachments::read_package_files(&mut map, &mut archive)?;
+    options.apply(&mut map)?;
+    map.place_pinned_offsets(Offsets::FromParent);
+    Ok(map)
+}
+
+/// The package's document and the layout it is in. `contents.json` is
+/// the modern document as JSON; `contents.xml` is the modern one when it
+/// is a property list.
+fn read_contents<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+) -> Result<(MindNodeVariant, String), BrainCoreError> {
+    let mut contents = String::new();
+    if let Ok(mut file) = archive.by_name("contents.json") {
+        file.read_to_string(&mut contents)?;
+        return Ok((MindNodeVariant::Document, contents));
+    }
+    archive
+        .by_name("contents.xml")
+        .map_err(|_| {
+            BrainCoreError::parse(
+                ImportFormat::MindNode,
+                "Neither contents.xml nor contents.json found in archive",
+            )
+        })?
+        .read_to_string(&mut contents)?;
+    let variant = if is_plist(&contents) {
+        MindNodeVariant::Document
+    } else {
+        MindNodeVariant::Legacy
+    };
+    Ok((variant, contents))
+}
+
+/// Whether the XML's root element is `<plist>`, looking past the
+/// declaration, doctype and comments.
+fn is_plist(xml: &str) -> bool {
+    let mut rest = xml.trim_start();
+    while let Some(tag) = rest.strip_prefix('<') {
+        if !tag.starts_with(['?', '!']) {
+            return tag.starts_with("plist");
+        }
+        let end = if tag.starts_with("!--") { "-->" } else { ">" };
+        rest = match tag.find(end) {
+            Some(i) => tag[i + end.len()..].trim_start(),
+            None => return false,
+        };
+    }
+    false
+}
+
+fn document_to_map(document: &Value, monitor: &mut Monitor) -> Result<MindMap, BrainCoreError> {
+    let mind_map = &document["mindMap"];
+    let main_nodes = mind_map["mainNodes"].as_array().map_or(&[][..], |n| &n[..]);
+    let Some((root, floating)) = main_nodes.split_first() else {
+        return Ok(MindMap::new());
+    };
+
+    let mut nodes = HashMap::new();
+    monitor.begin(
+        Stage::BuildingNodes,
+        Some(main_nodes.iter().map(count_document_nodes).sum()),
+    )?;
+    let root_id = document_node(root, None, &mut nodes, monitor);
+    // Main nodes other than the first float beside it, placed relative to it.
+    let origin = location(root).unwrap_or_default();
+    let mut floating_roots = Vec::new();
+    for main_node in floating {
+        let id = document_node(main_node, None, &mut nodes, monitor);
+        if let Some(node) = nodes.get_mut(&id) {
+            let (x, y) = location(main_node).unwrap_or_default();
+            node.x = x - origin.0;
+            node.y = y - origin.1;
+        }
+        floating_roots.push(id);
+    }
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    map.floating_roots = floating_roots;
+    map.relationships = mind_map["crossConnections"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|c| {
+            let from = c["startNodeID"].as_str()?;
+            let to = c["endNodeID"].as_str()?;
+            (map.nodes.contains_key(from) && map.nodes.contains_key(to)).then(|| Relationship {
+                id: Uuid::new_v4().to_string(),
+                from: from.to_string(),
+                to: to.to_string(),
+                label: c["title"]["text"].as_str().map(document_text),
+            })
+        })
+        .collect();
+    Ok(map)
+}
+
+fn count_document_nodes(node: &Value) -> usize {
+    1 + node["subnodes"]
+        .as_array()
+        .map_or(0, |s| s.iter().map(count_document_nodes).sum())
+}
+
+fn document_node(
+    value: &Value,
+    parent_id: Option<&str>,
+    nodes: &mut HashMap<String, Node>,
+    monitor: &mut Monitor,
+) -> String {
+    monitor.tick();
+    let id = value["nodeID"]
+        .as_str()
+        .filter(|id| !id.is_empty() && !nodes.contains_key(*id))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+
+    let mut children_ids = Vec::new();
+    for subnode in value["subnodes"].as_array().into_iter().flatten() {
+        if monitor.should_stop() {
+            break;
+        }
+        children_ids.push(document_node(subnode, Some(&id), nodes, monitor));
+    }
+
+    let text = |key: &str| value[key]["text"].as_str().map(document_text);
+    let location = location(value).filter(|_| parent_id.is_some());
+    let node = Node {
+        id: id.clone(),
+        content: text("title").unwrap_or_default(),
+        children: children_ids,
+        parent: parent_id.map(|s| s.to_string()),
+        x: location.map_or(0.0, |(x, _)| x),
+        y: location.map_or(0.0, |(_, y)| y),
+        pinned: location.is_some(),
+        created: now_millis(),
+        modified: now_millis(),
+        note: text("note").filter(|n| !n.is_empty()),
+        link: value["link"]
+            .as_str()
+            .filter(|u| !u.is_empty())
+            .map(str::to_string),
+        // MindNode's task states are 1 for open and 2 for done.
+        task: value["task"]["state"].as_u64().map(|state| TaskInfo {
+            done: state == 2,
+            progress_pct: if state == 2 { 100 } else { 0 },
+            ..Default::default()
+        }),
+        style: document_style(&value["nodeStyle"]),
+        attachments: value["attachments"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|a| {
+                Some(Attachment {
+                    name: a["name"].as_str()?.to_string(),
+                    mime: a["mime"].as_str().unwrap_or_default().to_string(),
+                    data_ref: a["src"].as_str()?.to_string(),
+                })
+            })
+            .collect(),
+        ..Default::default()
+    };
+
+    nodes.insert(id.clone(), node);
+    id
+}
+
+fn location(value: &Value) -> Option<(f32, f32)> {
+    value["location"].as_str().and_then(parse_location)
+}
+
+/// Titles and notes may be stored as HTML fragments, such as `<p>` runs
+/// without an `<html>` wrapper.
+fn document_text(text: &str) -> String {
+    if text.trim_start().starts_with('<') {
+        html::to_plain_text(text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn document_style(style: &Value) -> NodeStyle {
+    let title = &style["titleStyle"];
+    let size = |value: &Value| value.as_f64().map(|v| v.round() as u32);
+    NodeStyle {
+        background_color: document_color(&style["fillColor"]),
+        text_color: document_color(&title["color"]),
+        font_size: size(&title["fontSize"]),
+        bold: title["bold"].as_bool().unwrap_or(false),
+        italic: title["italic"].as_bool().unwrap_or(false),
+        edge_color: document_color(&style["strokeColor"]),
+        edge_width: size(&style["strokeWidth"]),
+    }
+}
+
+/// Colors come as `#rrggbb` or as `{r, g, b, a}` with components from 0
+/// to 1; the alpha is dropped.
+fn document_color(value: &Value) -> Option<String> {
+    let color = value.as_str()?.trim();
+    if color.starts_with('#') {
+        return Some(color.to_lowercase());
+    }
+    let components: Vec<f32> = color
+        .strip_prefix('{')?
+        .strip_suffix('}')?
+        .split(',')
+        .map(|c| c.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    let channel = |i: usize| (components[i].clamp(0.0, 1.0) * 255.0).round() as u8;
+    (components.len() >= 3)
+        .then(|| format!("#{:02x}{:02x}{:02x}", channel(0), channel(1), channel(2)))
+}
+
+fn count_nodes(mn_node: &MindNodeNode) -> usize {
+    1 + mn_node
+        .children
+        .as_ref()
+        .map_or(0, |c| c.node.iter().map(count_nodes).sum())
 }
 
 fn mindnode_node_to_node(
     mn_node: &MindNodeNode,
     parent_id: Option<&str>,
     nodes: &mut HashMap<String, Node>,
+    monitor: &mut Monitor,
 ) -> String {
+    monitor.tick();
     let id = mn_node.id.clone(); // Use existing ID if possible, or generate new? MindNode IDs are UUIDs usually.
     // If ID is not a valid UUID or we want to ensure uniqueness, we might generate new one.
     // But let's try to use it.
@@ -147,31 +650,55 @@ fn mindnode_node_to_node(
     let mut children_ids = Vec::new();
     if let Some(children) = &mn_node.children {
         for child in &children.node {
-            children_ids.push(mindnode_node_to_node(child, Some(&id), nodes));
+            if monitor.should_stop() {
+                break;
+            }
+            children_ids.push(mindnode_node_to_node(child, Some(&id), nodes, monitor));
         }
     }
 
+    let location = mn_node
+        .location
+        .as_deref()
+        .filter(|_| parent_id.is_some())
+        .and_then(parse_location);
     let node = Node {
         id: id.clone(),
         content: mn_node.title.text.clone(),
         children: children_ids,
         parent: parent_id.map(|s| s.to_string()),
-        x: 0.0,
-        y: 0.0,
+        x: location.map_or(0.0, |(x, _)| x),
+        y: location.map_or(0.0, |(_, y)| y),
+        pinned: location.is_some(),
         created: now_millis(),
         modified: now_millis(),
         icons: Vec::new(),
+        note: mn_node.note.as_ref().map(|n| n.text.clone()),
+        link: mn_node.url.clone().filter(|u| !u.is_empty()),
+        // `data_ref` holds the package path until the files are read.
+        attachments: mn_node
+            .attachments
+            .iter()
+            .map(|a| Attachment {
+                name: a.name.clone(),
+                mime: a.mime.clone(),
+                data_ref: a.src.clone(),
+            })
+            .collect(),
+        ..Default::default()
     };
 
     nodes.insert(id.clone(), node);
     id
 }
 
-fn now_millis() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
+fn parse_location(location: &str) -> Option<(f32, f32)> {
+    let (x, y) = location
+        .trim()
+        .strip_prefix('{')?
+        .strip_suffix('}')?
+        .split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
 }
 
 #[cfg(test)]
@@ -191,6 +718,7 @@ mod tests {
             created: timestamp,
             modified: timestamp,
             icons: Vec::new(),
+            ..Default::default()
         };
         map.nodes.insert(id.clone(), node);
         if let Some(parent) = map.nodes.get_mut(parent_id) {
@@ -215,4 +743,68 @@ mod tests {
         assert_eq!(root.content, "Root MindNode");
         assert_eq!(root.children.len(), 1);
     }
+
+    #[test]
+    fn test_mindnode_document() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = add_child_for_test(&mut map, &root_id, "Child & more");
+        map.nodes.get_mut(&child).unwrap().note = Some("A note".to_string());
+        map.set_task(
+            &child,
+            Some(TaskInfo {
+                done: true,
+                progress_pct: 100,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        map.set_style(
+            &child,
+            NodeStyle {
+                background_color: Some("#ff8000".to_string()),
+                bold: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let data =
+            to_mindnode_as(&map, MindNodeVariant::Document, &ExportOptions::default()).unwrap();
+        assert_eq!(detect_variant(&data).unwrap(), MindNodeVariant::Document);
+        assert_eq!(
+            detect_variant(&to_mindnode(&map).unwrap()).unwrap(),
+            MindNodeVariant::Legacy
+        );
+        let loaded = from_mindnode(&data).unwrap();
+        let loaded_child = &loaded.nodes[&child];
+        assert_eq!(loaded_child.content, "Child & more");
+        assert_eq!(loaded_child.note.as_deref(), Some("A note"));
+        assert!(loaded_child.task.as_ref().unwrap().done);
+        assert_eq!(loaded_child.style, map.nodes[&child].style);
+
+        let json = r#"{"mindMap": {"mainNodes": [
+            {"nodeID": "r", "title": {"text": "<p>Root <b>map</b></p>"}, "location": "{100, 50}",
+             "subnodes": [{"nodeID": "a", "title": {"text": "Open task"}, "task": {"state": 1},
+                           "nodeStyle": {"fillColor": "{1, 0, 0.5, 1}", "titleStyle": {"italic": true}}}]},
+            {"nodeID": "f", "title": {"text": "Floating"}, "location": "{400, -50}"}],
+            "crossConnections": [{"startNodeID": "a", "endNodeID": "f", "title": {"text": "see"}}]}}"#;
+        let mut buf = Vec::new();
+        let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+        zip.start_file("contents.json", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(json.as_bytes()).unwrap();
+        zip.finish().unwrap();
+        let loaded = from_mindnode(&buf).unwrap();
+        assert_eq!(loaded.nodes["r"].content, "Root map");
+        assert!(!loaded.nodes["a"].task.as_ref().unwrap().done);
+        assert_eq!(
+            loaded.nodes["a"].style.background_color.as_deref(),
+            Some("#ff0080")
+        );
+        assert!(loaded.nodes["a"].style.italic);
+        assert_eq!(loaded.floating_roots, ["f"]);
+        assert_eq!((loaded.nodes["f"].x, loaded.nodes["f"].y), (300.0, -100.0));
+        assert_eq!(loaded.relationships[0].label.as_deref(), Some("see"));
+    }
 }
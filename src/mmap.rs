@@ -1,16 +1,21 @@
-use crate::{MindMap, Node};
-use quick_xml::de::from_str;
-use quick_xml::se::to_string;
+use crate::export::{ExportOptions, TextWriter};
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::relationship::Relationship;
+use crate::task::TaskInfo;
+use crate::timestamp::{now_millis, parse_iso8601};
+use crate::{BrainCoreError, MindMap, Node, html};
+use quick_xml::Reader;
+use quick_xml::events::Event;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::io::{Cursor, Read, Write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::{Cursor, Read, Seek, Write};
 use uuid::Uuid;
-use zip::write::SimpleFileOptions;
 use zip::{ZipArchive, ZipWriter};
 
 // MindManager XML Structure (Simplified)
-// Usually Document.xml
+// Usually Document.xml. These structures are what the writer emits; the
+// reader walks the document by local names instead, see `Element`.
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "ap:Map")]
@@ -19,12 +24,29 @@ pub struct MmapMap {
     pub xmlns_ap: String,
     #[serde(rename = "ap:OneTopic", alias = "OneTopic")]
     pub root_topic: MmapTopic,
+    #[serde(
+        rename = "ap:Relationships",
+        alias = "Relationships",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub relationships: Option<MmapRelationships>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct MmapTopic {
+    /// Object id, referenced by relationships.
+    #[serde(rename = "@OId", default, skip_serializing_if = "Option::is_none")]
+    pub oid: Option<String>,
     #[serde(rename = "ap:Text", alias = "Text")]
     pub text: MmapText,
+    #[serde(
+        rename = "ap:DateTimeStamps",
+        alias = "DateTimeStamps",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub stamps: Option<MmapDateTimeStamps>,
     #[serde(rename = "ap:SubTopics", alias = "SubTopics", default)]
     pub sub_topics: Option<MmapSubTopics>,
 }
@@ -35,50 +57,163 @@ pub struct MmapText {
     pub plain_text: String,
 }
 
+// Only the author attributes are written; the dates would make exports
+// differ on every save.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MmapDateTimeStamps {
+    #[serde(rename = "@CreatedBy", skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(rename = "@LastModifiedBy", skip_serializing_if = "Option::is_none")]
+    pub last_modified_by: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MmapRelationships {
+    #[serde(rename = "ap:Relationship", alias = "Relationship", default)]
+    pub relationships: Vec<MmapRelationship>,
+}
+
+/// Connection group 0 is the start of the arrow, group 1 its end.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MmapRelationship {
+    #[serde(rename = "@OId", default, skip_serializing_if = "Option::is_none")]
+    pub oid: Option<String>,
+    #[serde(rename = "ap:ConnectionGroup", alias = "ConnectionGroup", default)]
+    pub groups: Vec<MmapConnectionGroup>,
+    #[serde(
+        rename = "ap:Text",
+        alias = "Text",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub text: Option<MmapText>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MmapConnectionGroup {
+    #[serde(rename = "@Index")]
+    pub index: usize,
+    #[serde(rename = "ap:Connection", alias = "Connection")]
+    pub connection: MmapConnection,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MmapConnection {
+    #[serde(rename = "ap:ObjectReference", alias = "ObjectReference")]
+    pub reference: MmapObjectReference,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct MmapObjectReference {
+    #[serde(rename = "@OIdRef")]
+    pub oid_ref: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct MmapSubTopics {
     #[serde(rename = "ap:Topic", alias = "Topic", default)]
     pub topics: Vec<MmapTopic>,
 }
 
-pub fn to_mmap(map: &MindMap) -> Result<Vec<u8>, String> {
-    let root_node = map.nodes.get(&map.root_id).ok_or("Root node not found")?;
+pub fn to_mmap(map: &MindMap) -> Result<Vec<u8>, BrainCoreError> {
+    to_mmap_with(map, &ExportOptions::default())
+}
+
+pub fn to_mmap_with(map: &MindMap, options: &ExportOptions) -> Result<Vec<u8>, BrainCoreError> {
+    to_mmap_monitored(map, options, &mut Monitor::default())
+}
 
-    let mmap_root = node_to_mmap_topic(root_node, map);
+pub fn to_mmap_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<Vec<u8>, BrainCoreError> {
+    let mut buf = Vec::new();
+    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+    write_archive(map, &mut zip, options, monitor)?;
+    zip.finish()?;
+
+    Ok(buf)
+}
+
+/// Streams the MindManager archive to `writer`. The document is serialized
+/// straight into the zip entry, so no copy of the file is held in memory.
+pub fn write_mmap<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_mmap_with(map, writer, &ExportOptions::default())
+}
+
+pub fn write_mmap_with<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    write_mmap_monitored(map, writer, options, &mut Monitor::default())
+}
+
+pub fn write_mmap_monitored<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let mut zip = ZipWriter::new_stream(&mut *writer);
+    write_archive(map, &mut zip, options, monitor)?;
+    zip.finish()?;
+    Ok(writer.flush()?)
+}
+
+fn write_archive<W: Write + Seek>(
+    map: &MindMap,
+    zip: &mut ZipWriter<W>,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let root_node = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
+    let mmap_root = node_to_mmap_topic(root_node, map, options, monitor);
+    monitor.finish()?;
 
     let mmap_map = MmapMap {
         xmlns_ap: "http://schemas.mindjet.com/MindManager/Application/2003".to_string(),
         root_topic: mmap_root,
+        relationships: relationships_to_mmap(map, options),
     };
 
-    let xml_content = to_string(&mmap_map).map_err(|e| e.to_string())?;
-    let xml_content = format!(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n{}",
-        xml_content
-    );
-
-    let mut buf = Vec::new();
-    let mut zip = ZipWriter::new(Cursor::new(&mut buf));
-
-    let options = SimpleFileOptions::default()
-        .compression_method(zip::CompressionMethod::Stored)
+    let zip_options = options
+        .zip_options(zip::CompressionMethod::Stored)
         .unix_permissions(0o755);
 
-    zip.start_file("Document.xml", options)
-        .map_err(|e| e.to_string())?;
-    zip.write_all(xml_content.as_bytes())
-        .map_err(|e| e.to_string())?;
-
-    zip.finish().map_err(|e| e.to_string())?;
-
-    Ok(buf)
+    monitor.begin(Stage::WritingArchive, Some(1))?;
+    zip.start_file("Document.xml", zip_options)?;
+    zip.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n")?;
+    let mut text = TextWriter::new(&mut *zip);
+    let result = options.write_xml(&mmap_map, &mut html::AttributeEncoder::new(&mut text));
+    text.check(result)?;
+    monitor.tick();
+    monitor.finish()
 }
 
-fn node_to_mmap_topic(node: &Node, map: &MindMap) -> MmapTopic {
+fn node_to_mmap_topic(
+    node: &Node,
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> MmapTopic {
+    monitor.tick();
     let mut sub_topics_vec = Vec::new();
     for child_id in &node.children {
-        if let Some(child) = map.nodes.get(child_id) {
-            sub_topics_vec.push(node_to_mmap_topic(child, map));
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            sub_topics_vec.push(node_to_mmap_topic(child, map, options, monitor));
         }
     }
 
@@ -91,78 +226,380 @@ fn node_to_mmap_topic(node: &Node, map: &MindMap) -> MmapTopic {
     };
 
     MmapTopic {
+        oid: Some(node.id.clone()),
         text: MmapText {
             plain_text: node.content.clone(),
         },
+        stamps: (node.created_by.is_some() || node.modified_by.is_some()).then(|| {
+            MmapDateTimeStamps {
+                created_by: node.created_by.clone(),
+                last_modified_by: node.modified_by.clone(),
+            }
+        }),
         sub_topics,
     }
 }
 
-pub fn from_mmap(data: &[u8]) -> Result<MindMap, String> {
+fn relationships_to_mmap(map: &MindMap, options: &ExportOptions) -> Option<MmapRelationships> {
+    let connect = |index, id: &str| MmapConnectionGroup {
+        index,
+        connection: MmapConnection {
+            reference: MmapObjectReference {
+                oid_ref: id.to_string(),
+            },
+        },
+    };
+    let relationships: Vec<MmapRelationship> = map
+        .exported_relationships(options)
+        .map(|r| MmapRelationship {
+            oid: Some(r.id.clone()),
+            groups: vec![connect(0, &r.from), connect(1, &r.to)],
+            text: r.label.clone().map(|plain_text| MmapText { plain_text }),
+        })
+        .collect();
+    (!relationships.is_empty()).then_some(MmapRelationships { relationships })
+}
+
+pub fn from_mmap(data: &[u8]) -> Result<MindMap, BrainCoreError> {
+    from_mmap_with(data, &ImportOptions::default())
+}
+
+pub fn from_mmap_with(data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_mmap_monitored(data, options, &mut Monitor::default())
+}
+
+pub fn from_mmap_monitored(
+    data: &[u8],
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
     let reader = Cursor::new(data);
-    let mut archive = ZipArchive::new(reader).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(reader)?;
 
+    monitor.begin(Stage::ReadingArchive, Some(1))?;
     let mut xml_content = String::new();
     // Try Document.xml, case insensitive if possible, but zip crate is case sensitive usually.
     // MindManager usually uses "Document.xml".
 
     if let Ok(mut file) = archive.by_name("Document.xml") {
-        file.read_to_string(&mut xml_content)
-            .map_err(|e| e.to_string())?;
+        file.read_to_string(&mut xml_content)?;
     } else if let Ok(mut file) = archive.by_name("document.xml") {
-        file.read_to_string(&mut xml_content)
-            .map_err(|e| e.to_string())?;
+        file.read_to_string(&mut xml_content)?;
     } else {
-        return Err("Document.xml not found in archive".to_string());
+        return Err(BrainCoreError::parse(
+            ImportFormat::MindManager,
+            "Document.xml not found in archive",
+        ));
     }
-
-    let mmap_map: MmapMap = from_str(&xml_content).map_err(|e| e.to_string())?;
+    monitor.tick();
+
+    let document = Element::parse(&xml_content)?;
+    // MindManager wraps the central topic in `OneTopic`; older exports of
+    // this crate made `OneTopic` the topic itself.
+    let root_topic = document
+        .child("OneTopic")
+        .map(|one| one.child("Topic").unwrap_or(one));
+    let Some(root_topic) = root_topic else {
+        return Err(BrainCoreError::parse(
+            ImportFormat::MindManager,
+            "Document has no central topic",
+        ));
+    };
 
     let mut nodes = HashMap::new();
-    let root_id = mmap_topic_to_node(&mmap_map.root_topic, None, &mut nodes);
+    let mut floating = Vec::new();
+    monitor.begin(Stage::BuildingNodes, Some(count_topics(root_topic)))?;
+    let root_id = topic_to_node(root_topic, None, &mut nodes, &mut floating, monitor);
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    map.floating_roots = floating;
+    let relationships = document
+        .child("Relationships")
+        .into_iter()
+        .flat_map(|r| r.children("Relationship"));
+    for relationship in relationships {
+        let end = |index: &str| {
+            relationship
+                .children("ConnectionGroup")
+                .find(|g| g.attribute("Index") == Some(index))?
+                .child("Connection")?
+                .child("ObjectReference")?
+                .attribute("OIdRef")
+        };
+        let (Some(from), Some(to)) = (end("0"), end("1")) else {
+            continue;
+        };
+        if map.nodes.contains_key(from) && map.nodes.contains_key(to) {
+            map.relationships.push(Relationship {
+                id: relationship
+                    .attribute("OId")
+                    .map_or_else(|| Uuid::new_v4().to_string(), str::to_string),
+                from: from.to_string(),
+                to: to.to_string(),
+                label: relationship
+                    .child("Text")
+                    .map(topic_text)
+                    .filter(|t| !t.is_empty()),
+            });
+        }
+    }
+    options.apply(&mut map)?;
+    Ok(map)
+}
 
-    Ok(MindMap {
-        nodes,
-        root_id: root_id.clone(),
-        selected_node_id: root_id,
-    })
+/// An element of a MindManager document, read without its namespace
+/// prefix. Files from different versions bind the `ap` namespace under
+/// other prefixes, and parts the reader doesn't know are skipped.
+#[derive(Debug, Default)]
+struct Element {
+    name: String,
+    attributes: HashMap<String, String>,
+    children: Vec<Element>,
+    text: String,
+}
+
+impl Element {
+    /// The document element.
+    fn parse(xml: &str) -> Result<Element, BrainCoreError> {
+        let error = |e: &dyn std::fmt::Display| BrainCoreError::parse(ImportFormat::MindManager, e);
+        let mut reader = Reader::from_str(xml);
+        let mut open = vec![Element::default()];
+        loop {
+            let (e, empty) = match reader.read_event().map_err(|e| error(&e))? {
+                Event::Start(e) => (e, false),
+                Event::Empty(e) => (e, true),
+                Event::End(_) => {
+                    if open.len() > 1
+                        && let Some(element) = open.pop()
+                        && let Some(parent) = open.last_mut()
+                    {
+                        parent.children.push(element);
+                    }
+                    continue;
+                }
+                Event::Text(e) => {
+                    if let Some(element) = open.last_mut() {
+                        element.text.push_str(&e.decode().map_err(|e| error(&e))?);
+                    }
+                    continue;
+                }
+                Event::CData(e) => {
+                    if let Some(element) = open.last_mut() {
+                        element.text.push_str(&e.decode().map_err(|e| error(&e))?);
+                    }
+                    continue;
+                }
+                Event::GeneralRef(e) => {
+                    let name = e.decode().map_err(|e| error(&e))?;
+                    if let (Some(element), Some(c)) = (open.last_mut(), html::entity(&name)) {
+                        element.text.push(c);
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+            let mut element = Element {
+                name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+                ..Element::default()
+            };
+            for attribute in e.attributes() {
+                let attribute = attribute.map_err(|e| error(&e))?;
+                let value = attribute
+                    .decode_and_unescape_value(reader.decoder())
+                    .map_err(|e| error(&e))?;
+                let name = attribute.key.local_name();
+                let name = String::from_utf8_lossy(name.as_ref()).into_owned();
+                element.attributes.insert(name, value.into_owned());
+            }
+            match open.last_mut() {
+                Some(parent) if empty => parent.children.push(element),
+                _ => open.push(element),
+            }
+        }
+        open.into_iter()
+            .next()
+            .and_then(|document| document.children.into_iter().next())
+            .ok_or_else(|| error(&"Document is empty"))
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.get(name).map(String::as_str)
+    }
+
+    fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// The `Topic` elements inside the child `list`, such as `SubTopics`.
+    fn topics<'a>(&'a self, list: &'a str) -> impl Iterator<Item = &'a Element> + 'a {
+        self.child(list)
+            .into_iter()
+            .flat_map(|l| l.children("Topic"))
+    }
+
+    /// The text of the element and everything inside it.
+    fn inner_text(&self) -> String {
+        let mut text = self.text.clone();
+        for child in &self.children {
+            text.push_str(&child.inner_text());
+        }
+        text
+    }
+}
+
+// `PlainText` carries the title; rich titles without it keep their text in
+// formatted runs inside the element.
+fn topic_text(text: &Element) -> String {
+    match text.attribute("PlainText") {
+        Some(plain) if !plain.is_empty() => plain.to_string(),
+        _ => text.inner_text().trim().to_string(),
+    }
+}
+
+fn count_topics(topic: &Element) -> usize {
+    1 + topic
+        .topics("SubTopics")
+        .chain(topic.topics("FloatingTopics"))
+        .map(count_topics)
+        .sum::<usize>()
 }
 
-fn mmap_topic_to_node(
-    topic: &MmapTopic,
+/// MindManager's stock icons and the FreeMind icons they stand for. The
+/// priority icons become the task's priority instead.
+const STOCK_ICONS: [(&str, &str); 12] = [
+    ("SmileyHappy", "ksmiletris"),
+    ("SmileyNeutral", "smiley-neutral"),
+    ("SmileyAngry", "smiley-angry"),
+    ("SmileySad", "smily_bad"),
+    ("FlagRed", "flag"),
+    ("FlagGreen", "flag-green"),
+    ("FlagBlue", "flag-blue"),
+    ("FlagYellow", "flag-yellow"),
+    ("FlagOrange", "flag-orange"),
+    ("ArrowRight", "forward"),
+    ("ArrowLeft", "back"),
+    ("QuestionMark", "help"),
+];
+
+fn topic_to_node(
+    topic: &Element,
     parent_id: Option<&str>,
     nodes: &mut HashMap<String, Node>,
+    floating: &mut Vec<String>,
+    monitor: &mut Monitor,
 ) -> String {
-    let id = Uuid::new_v4().to_string();
+    let id = topic
+        .attribute("OId")
+        .filter(|oid| !oid.is_empty() && !nodes.contains_key(*oid))
+        .map_or_else(|| Uuid::new_v4().to_string(), str::to_string);
+    monitor.tick();
 
     let mut children_ids = Vec::new();
-    if let Some(sub) = &topic.sub_topics {
-        for child in &sub.topics {
-            children_ids.push(mmap_topic_to_node(child, Some(&id), nodes));
+    for child in topic.topics("SubTopics") {
+        if monitor.should_stop() {
+            break;
         }
+        children_ids.push(topic_to_node(child, Some(&id), nodes, floating, monitor));
     }
+    // Floating topics sit freely next to the map, at an offset from the
+    // central topic.
+    for child in topic.topics("FloatingTopics") {
+        if monitor.should_stop() {
+            break;
+        }
+        let floating_id = topic_to_node(child, None, nodes, floating, monitor);
+        floating.push(floating_id);
+    }
+
+    let icon_types: Vec<&str> = topic
+        .child("IconsGroup")
+        .and_then(|g| g.child("Icons"))
+        .into_iter()
+        .flat_map(|icons| icons.children("Icon"))
+        .filter_map(|icon| icon.attribute("IconType")?.strip_prefix("urn:mindjet:"))
+        .collect();
+    let icons = icon_types
+        .iter()
+        .filter_map(|t| STOCK_ICONS.iter().find(|(stock, _)| stock == t))
+        .map(|(_, icon)| icon.to_string())
+        .collect();
+    let stamps = topic.child("DateTimeStamps");
+    let stamp = |name: &str| stamps.and_then(|s| s.attribute(name));
+    let date = |name: &str| stamp(name).and_then(parse_iso8601);
+    let offset = topic.child("Offset");
+    let coordinate = |name: &str| {
+        offset
+            .and_then(|o| o.attribute(name)?.parse().ok())
+            .unwrap_or(0.0)
+    };
+    // Only the offsets of floating topics are kept; the layout places the
+    // rest.
+    let (x, y) = match parent_id {
+        None => (coordinate("CX"), coordinate("CY")),
+        Some(_) => (0.0, 0.0),
+    };
 
     let node = Node {
         id: id.clone(),
-        content: topic.text.plain_text.clone(),
+        content: topic.child("Text").map(topic_text).unwrap_or_default(),
         children: children_ids,
         parent: parent_id.map(|s| s.to_string()),
-        x: 0.0,
-        y: 0.0,
-        created: now_millis(),
-        modified: now_millis(),
-        icons: Vec::new(),
+        x,
+        y,
+        created: date("Created").unwrap_or_else(now_millis),
+        modified: date("LastModified").unwrap_or_else(now_millis),
+        icons,
+        note: topic
+            .child("NotesGroup")
+            .and_then(|g| g.child("NotesXhtmlData"))
+            .map(|notes| match notes.attribute("PreviewPlainText") {
+                Some(preview) => preview.to_string(),
+                None => notes.inner_text().trim().to_string(),
+            })
+            .filter(|n| !n.is_empty()),
+        link: topic
+            .child("Hyperlink")
+            .and_then(|h| h.attribute("Url").or(h.attribute("AbsolutePath")))
+            .filter(|l| !l.is_empty())
+            .map(str::to_string),
+        task: topic_task(topic, &icon_types),
+        created_by: stamp("CreatedBy").map(str::to_string),
+        modified_by: stamp("LastModifiedBy").map(str::to_string),
+        ..Default::default()
     };
 
     nodes.insert(id.clone(), node);
     id
 }
 
-fn now_millis() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
+// A `Task` element or a priority icon makes the topic a task.
+fn topic_task(topic: &Element, icon_types: &[&str]) -> Option<TaskInfo> {
+    let task = topic.child("Task");
+    let priority = task
+        .and_then(|t| t.attribute("TaskPriority")?.strip_prefix("urn:mindjet:"))
+        .into_iter()
+        .chain(icon_types.iter().copied())
+        .find_map(|t| t.strip_prefix("Prio")?.parse().ok());
+    if task.is_none() && priority.is_none() {
+        return None;
+    }
+    let progress: u8 = task
+        .and_then(|t| t.attribute("TaskPercentage")?.parse().ok())
+        .unwrap_or(0)
+        .min(100);
+    Some(TaskInfo {
+        done: progress == 100,
+        progress_pct: progress,
+        due: task.and_then(|t| parse_iso8601(t.attribute("DueDateTime")?)),
+        priority,
+    })
 }
 
 #[cfg(test)]
@@ -182,6 +619,7 @@ mod tests {
             created: timestamp,
             modified: timestamp,
             icons: Vec::new(),
+            ..Default::default()
         };
         map.nodes.insert(id.clone(), node);
         if let Some(parent) = map.nodes.get_mut(parent_id) {
@@ -206,4 +644,60 @@ mod tests {
         assert_eq!(root.content, "Root Mmap");
         assert_eq!(root.children.len(), 1);
     }
+
+    // Shaped like a MindManager 2020 Document.xml, with parts the reader
+    // doesn't know left in.
+    const DOCUMENT: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<ap:Map xmlns:ap="http://schemas.mindjet.com/MindManager/Application/2003" xmlns:cor="http://schemas.mindjet.com/MindManager/Core/2003" Gen="0000" Dirty="0000000000000000">
+<cor:Custom Uri="http://schemas.mindjet.com/MindManager/UpdateCompatibility/2004" Index="0"/>
+<ap:OneTopic><ap:Topic OId="root" Gen="0000">
+  <ap:SubTopics>
+    <ap:Topic OId="launch">
+      <ap:Text ReadOnly="false"><ap:Font Bold="true"/><ap:TextRun>Launch </ap:TextRun><ap:TextRun>plan</ap:TextRun></ap:Text>
+      <ap:IconsGroup><ap:Icons><ap:Icon xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:type="ap:StockIcon" IconType="urn:mindjet:Prio2"/><ap:Icon IconType="urn:mindjet:FlagRed"/><ap:Icon IconType="urn:mindjet:Unheard"/></ap:Icons></ap:IconsGroup>
+      <ap:Task TaskPercentage="50" DueDateTime="2024-05-01T00:00:00"/>
+      <ap:Hyperlink Url="https://example.com/launch"/>
+      <ap:NotesGroup><ap:NotesXhtmlData PreviewPlainText="Ship it &amp; tell"><xhtml:html xmlns:xhtml="http://www.w3.org/1999/xhtml"><xhtml:p>Ship it</xhtml:p></xhtml:html></ap:NotesXhtmlData></ap:NotesGroup>
+      <ap:DateTimeStamps Created="2024-01-31T12:00:00" CreatedBy="ann" LastModified="2024-02-01T12:00:00" LastModifiedBy="bob"/>
+    </ap:Topic>
+  </ap:SubTopics>
+  <ap:FloatingTopics>
+    <ap:Topic OId="idea"><ap:Text PlainText="Idea"/><ap:Offset CX="300" CY="-40"/></ap:Topic>
+  </ap:FloatingTopics>
+  <ap:Text PlainText="Central Topic" ReadOnly="false"/>
+  <ap:TopicViewGroup ViewIndex="0"><ap:Visibility Hidden="false"/></ap:TopicViewGroup>
+</ap:Topic></ap:OneTopic>
+<ap:Relationships><ap:Relationship OId="rel">
+  <ap:ConnectionGroup Index="0"><ap:Connection><ap:ObjectReference OIdRef="launch"/></ap:Connection></ap:ConnectionGroup>
+  <ap:ConnectionGroup Index="1"><ap:Connection><ap:ObjectReference OIdRef="idea"/></ap:Connection></ap:ConnectionGroup>
+</ap:Relationship></ap:Relationships>
+</ap:Map>"#;
+
+    #[test]
+    fn test_mindmanager_document() {
+        let mut data = Vec::new();
+        let mut zip = ZipWriter::new(Cursor::new(&mut data));
+        zip.start_file("Document.xml", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(DOCUMENT.as_bytes()).unwrap();
+        zip.finish().unwrap();
+
+        let map = from_mmap(&data).unwrap();
+        assert_eq!(map.nodes[&map.root_id].content, "Central Topic");
+        assert_eq!(map.nodes[&map.root_id].children, ["launch"]);
+        let launch = &map.nodes["launch"];
+        assert_eq!(launch.content, "Launch plan");
+        assert_eq!(launch.icons, ["flag"]);
+        let task = launch.task.as_ref().unwrap();
+        assert_eq!((task.progress_pct, task.priority), (50, Some(2)));
+        assert_eq!(task.due, Some(1_714_521_600_000));
+        assert_eq!(launch.link.as_deref(), Some("https://example.com/launch"));
+        assert_eq!(launch.note.as_deref(), Some("Ship it & tell"));
+        assert_eq!(launch.created, 1_706_702_400_000);
+        assert_eq!(launch.modified_by.as_deref(), Some("bob"));
+        assert_eq!(map.floating_roots, ["idea"]);
+        assert_eq!((map.nodes["idea"].x, map.nodes["idea"].y), (300.0, -40.0));
+        assert_eq!(map.relationships[0].to, "idea");
+        assert!(map.validate().is_empty());
+    }
 }
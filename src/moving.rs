@@ -0,0 +1,249 @@
+use crate::events::MapEvent;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// Detaches a node, together with its subtree, and inserts it among the
+    /// children of `new_parent_id` at `position`. Positions past the end
+    /// append. Ids, timestamps and all other node data are kept.
+    ///
+    /// Fails if the node is the root or `new_parent_id` is the node itself
+    /// or one of its descendants.
+    pub fn move_node(
+        &mut self,
+        node_id: &str,
+        new_parent_id: &str,
+        position: usize,
+    ) -> Result<(), BrainCoreError> {
+        if node_id == self.root_id {
+            return Err(BrainCoreError::RootImmutable);
+        }
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let old_parent_id = node
+            .parent
+            .clone()
+            .ok_or_else(|| BrainCoreError::InvalidOperation("Node has no parent".to_string()))?;
+        if !self.nodes.contains_key(new_parent_id) {
+            return Err(BrainCoreError::NodeNotFound(new_parent_id.to_string()));
+        }
+        if self.is_within(new_parent_id, node_id) {
+            return Err(BrainCoreError::InvalidOperation(
+                "Cannot move a node under itself or its descendants".to_string(),
+            ));
+        }
+        self.limits
+            .check_depth(self.depth(new_parent_id) + self.height(node_id))?;
+
+        if let Some(old_parent) = self.nodes.get_mut(&old_parent_id) {
+            old_parent.children.retain(|c| c != node_id);
+        }
+        if let Some(new_parent) = self.nodes.get_mut(new_parent_id) {
+            let position = position.min(new_parent.children.len());
+            new_parent.children.insert(position, node_id.to_string());
+        }
+        if let Some(node) = self.nodes.get_mut(node_id) {
+            node.parent = Some(new_parent_id.to_string());
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+        }
+
+        self.bump_revision(node_id);
+        self.bump_revision(&old_parent_id);
+        if old_parent_id != new_parent_id {
+            self.bump_revision(new_parent_id);
+        }
+        self.emit(MapEvent::NodeMoved {
+            node_id: node_id.to_string(),
+            old_parent_id,
+            new_parent_id: new_parent_id.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Swaps a node with its previous sibling. Does nothing if it is already
+    /// first.
+    pub fn move_up(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        let (parent_id, index) = self.sibling_index(node_id)?;
+        if index > 0 {
+            self.swap_children(&parent_id, index - 1, index);
+        }
+        Ok(())
+    }
+
+    /// Swaps a node with its next sibling. Does nothing if it is already
+    /// last.
+    pub fn move_down(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        let (parent_id, index) = self.sibling_index(node_id)?;
+        let count = self.nodes.get(&parent_id).map_or(0, |p| p.children.len());
+        if index + 1 < count {
+            self.swap_children(&parent_id, index, index + 1);
+        }
+        Ok(())
+    }
+
+    /// Replaces the order of a node's children. `order` must contain each
+    /// current child exactly once.
+    pub fn reorder_children(
+        &mut self,
+        parent_id: &str,
+        order: Vec<String>,
+    ) -> Result<(), BrainCoreError> {
+        let parent = self
+            .nodes
+            .get_mut(parent_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(parent_id.to_string()))?;
+        let mut current = parent.children.clone();
+        let mut proposed = order.clone();
+        current.sort();
+        proposed.sort();
+        if current != proposed {
+            return Err(BrainCoreError::InvalidOperation(
+                "New order must list each child exactly once".to_string(),
+            ));
+        }
+        if parent.children != order {
+            parent.children = order;
+            parent.modified = now_millis();
+            parent.modified_by = self.author.clone();
+            self.bump_revision(parent_id);
+        }
+        Ok(())
+    }
+
+    fn sibling_index(&self, node_id: &str) -> Result<(String, usize), BrainCoreError> {
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let parent_id = node.parent.clone().ok_or(BrainCoreError::RootImmutable)?;
+        let index = self
+            .nodes
+            .get(&parent_id)
+            .and_then(|p| p.children.iter().position(|c| c == node_id))
+            .ok_or_else(|| BrainCoreError::NodeNotFound(parent_id.clone()))?;
+        Ok((parent_id, index))
+    }
+
+    fn swap_children(&mut self, parent_id: &str, a: usize, b: usize) {
+        if let Some(parent) = self.nodes.get_mut(parent_id) {
+            parent.children.swap(a, b);
+            parent.modified = now_millis();
+            parent.modified_by = self.author.clone();
+        }
+        self.bump_revision(parent_id);
+    }
+
+    /// Whether `node_id` is `ancestor_id` or lies below it.
+    fn is_within(&self, node_id: &str, ancestor_id: &str) -> bool {
+        let mut current = Some(node_id);
+        while let Some(id) = current {
+            if id == ancestor_id {
+                return true;
+            }
+            current = self.nodes.get(id).and_then(|n| n.parent.as_deref());
+        }
+        false
+    }
+
+    /// Number of levels in the subtree rooted at `node_id`, counting the
+    /// node itself.
+    fn height(&self, node_id: &str) -> usize {
+        let mut tallest = 0;
+        let mut stack = vec![(node_id, 1)];
+        while let Some((id, height)) = stack.pop() {
+            tallest = tallest.max(height);
+            if let Some(node) = self.nodes.get(id) {
+                stack.extend(node.children.iter().map(|c| (c.as_str(), height + 1)));
+            }
+        }
+        tallest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_move_node() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let a2 = map.add_child(&a1, "A2").unwrap();
+        let created = map.nodes[&a1].created;
+
+        map.move_node(&a1, &b, 5).unwrap();
+        assert!(map.nodes[&a].children.is_empty());
+        assert_eq!(map.nodes[&b].children, vec![a1.clone()]);
+        assert_eq!(map.nodes[&a1].parent.as_deref(), Some(b.as_str()));
+        assert_eq!(map.nodes[&a1].created, created);
+        assert_eq!(map.nodes[&a2].parent.as_deref(), Some(a1.as_str()));
+
+        map.move_node(&b, &root_id, 0).unwrap();
+        assert_eq!(map.nodes[&root_id].children, vec![b.clone(), a.clone()]);
+
+        assert!(matches!(
+            map.move_node(&b, &a2, 0),
+            Err(BrainCoreError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            map.move_node(&b, &b, 0),
+            Err(BrainCoreError::InvalidOperation(_))
+        ));
+        assert!(matches!(
+            map.move_node(&root_id, &a, 0),
+            Err(BrainCoreError::RootImmutable)
+        ));
+        assert_eq!(map.nodes[&root_id].children, vec![b, a]);
+    }
+
+    #[test]
+    fn test_reorder_siblings() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let c = map.add_child(&root_id, "C").unwrap();
+
+        map.move_up(&c).unwrap();
+        assert_eq!(
+            map.nodes[&root_id].children,
+            vec![a.clone(), c.clone(), b.clone()]
+        );
+        map.move_up(&a).unwrap();
+        map.move_down(&b).unwrap();
+        map.move_down(&a).unwrap();
+        assert_eq!(
+            map.nodes[&root_id].children,
+            vec![c.clone(), a.clone(), b.clone()]
+        );
+
+        let revision = map.revision;
+        map.reorder_children(&root_id, vec![b.clone(), c.clone(), a.clone()])
+            .unwrap();
+        assert_eq!(
+            map.nodes[&root_id].children,
+            vec![b.clone(), c.clone(), a.clone()]
+        );
+        assert_eq!(map.revision, revision + 1);
+
+        assert!(
+            map.reorder_children(&root_id, vec![b.clone(), c.clone()])
+                .is_err()
+        );
+        assert!(
+            map.reorder_children(&root_id, vec![b.clone(), b.clone(), a.clone()])
+                .is_err()
+        );
+        assert!(matches!(
+            map.move_up(&root_id),
+            Err(BrainCoreError::RootImmutable)
+        ));
+        assert_eq!(map.nodes[&root_id].children, vec![b, c, a]);
+    }
+}
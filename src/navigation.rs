@@ -0,0 +1,240 @@
+use crate::layout::{HeuristicMeasurer, TextMeasurer};
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::VecDeque;
+
+/// Off-axis distance counts this many times as much as distance along the
+/// direction, so a node straight ahead beats a closer one off to the side.
+const SIDEWAYS_WEIGHT: f32 = 2.0;
+
+/// Number of earlier selections [`MindMap::go_back`] can return to.
+pub const HISTORY_LIMIT: usize = 100;
+
+/// A direction on screen, with `y` growing downwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Earlier and later selections for back and forward, like a browser's.
+/// Selecting a node through [`MindMap::select`] or moving with
+/// [`MindMap::navigate_spatial`] records the node it left; the selection
+/// changes edits make along the way, such as moving off a trashed node,
+/// are not recorded. Kept in memory only.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SelectionHistory {
+    back: VecDeque<String>,
+    forward: Vec<String>,
+}
+
+impl MindMap {
+    /// Moves the selection to the nearest visible node in `direction`, as
+    /// arrow keys do in visual mind map editors. Distances are measured
+    /// between box centres at the stored positions, so run
+    /// [`MindMap::compute_layout`] first; boxes are sized with
+    /// [`HeuristicMeasurer`]. Returns whether the selection moved.
+    pub fn navigate_spatial(&mut self, direction: Direction) -> bool {
+        self.navigate_spatial_with(direction, &HeuristicMeasurer)
+    }
+
+    /// [`MindMap::navigate_spatial`] with boxes sized by `measurer`, which
+    /// should be the one the layout used.
+    pub fn navigate_spatial_with(
+        &mut self,
+        direction: Direction,
+        measurer: &dyn TextMeasurer,
+    ) -> bool {
+        let Some(selected) = self.nodes.get(&self.selection.primary) else {
+            return false;
+        };
+        let (x, y) = self.centre(selected, measurer);
+        let mut best: Option<(f32, &Node)> = None;
+        for node in self.visible_nodes() {
+            let (cx, cy) = self.centre(node, measurer);
+            let (ahead, sideways) = match direction {
+                Direction::Up => (y - cy, cx - x),
+                Direction::Down => (cy - y, cx - x),
+                Direction::Left => (x - cx, cy - y),
+                Direction::Right => (cx - x, cy - y),
+            };
+            if ahead <= 0.0 || node.id == selected.id {
+                continue;
+            }
+            let score = ahead + SIDEWAYS_WEIGHT * sideways.abs();
+            if best.is_none_or(|(best, _)| score < best) {
+                best = Some((score, node));
+            }
+        }
+        let Some((_, node)) = best else {
+            return false;
+        };
+        self.move_selection(node.id.clone());
+        true
+    }
+
+    /// Selects only this node and records the one it replaces in the
+    /// selection history. Selecting the current node again just drops the
+    /// others selected with it.
+    pub fn select(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(BrainCoreError::NodeNotFound(node_id.to_string()));
+        }
+        self.clear_selection();
+        self.move_selection(node_id.to_string());
+        Ok(())
+    }
+
+    /// Returns to the previous selection, skipping nodes that have been
+    /// deleted since. Returns whether the selection moved.
+    pub fn go_back(&mut self) -> bool {
+        while let Some(id) = self.history.back.pop_back() {
+            if self.nodes.contains_key(&id) && id != self.selection.primary {
+                let left = self.selection.primary.clone();
+                self.set_selected(id);
+                self.history.forward.push(left);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Undoes a [`MindMap::go_back`]. The forward history is dropped as
+    /// soon as a new node is selected.
+    pub fn go_forward(&mut self) -> bool {
+        while let Some(id) = self.history.forward.pop() {
+            if self.nodes.contains_key(&id) && id != self.selection.primary {
+                let left = self.selection.primary.clone();
+                self.set_selected(id);
+                self.history.back.push_back(left);
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.history
+            .back
+            .iter()
+            .any(|id| self.nodes.contains_key(id) && *id != self.selection.primary)
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.history
+            .forward
+            .iter()
+            .any(|id| self.nodes.contains_key(id) && *id != self.selection.primary)
+    }
+
+    fn move_selection(&mut self, node_id: String) {
+        if node_id == self.selection.primary {
+            return;
+        }
+        let left = self.selection.primary.clone();
+        self.set_selected(node_id);
+        let history = &mut self.history;
+        history.back.push_back(left);
+        if history.back.len() > HISTORY_LIMIT {
+            history.back.pop_front();
+        }
+        history.forward.clear();
+    }
+
+    fn centre(&self, node: &Node, measurer: &dyn TextMeasurer) -> (f32, f32) {
+        let (width, height) = self.node_size(node, measurer);
+        (node.x + width / 2.0, node.y + height / 2.0)
+    }
+
+    /// Nodes shown by the layout, in depth-first order from the root and
+    /// then from each floating node.
+    fn visible_nodes(&self) -> Vec<&Node> {
+        let mut nodes = Vec::new();
+        let mut stack: Vec<&Node> = self
+            .floating_roots
+            .iter()
+            .rev()
+            .filter_map(|id| self.nodes.get(id))
+            .filter(|n| !n.archived)
+            .collect();
+        stack.extend(self.nodes.get(&self.root_id));
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+            stack.extend(self.laid_out_children(node).into_iter().rev());
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_navigate_spatial() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let b1 = map.add_child(&b, "B1").unwrap();
+        map.compute_layout();
+
+        assert!(!map.navigate_spatial(Direction::Left));
+        assert!(map.navigate_spatial(Direction::Right));
+        assert_eq!(map.selection.primary, a);
+        assert!(map.navigate_spatial(Direction::Down));
+        assert_eq!(map.selection.primary, b);
+        assert!(map.navigate_spatial(Direction::Up));
+        assert!(map.navigate_spatial(Direction::Right));
+        assert_eq!(map.selection.primary, a1);
+        assert!(map.navigate_spatial(Direction::Down));
+        assert_eq!(map.selection.primary, b1);
+        assert!(map.navigate_spatial(Direction::Left));
+        assert_eq!(map.selection.primary, b);
+
+        map.set_folded(&b, true).unwrap();
+        map.selection.primary = a1;
+        assert!(map.navigate_spatial(Direction::Down));
+        assert_eq!(map.selection.primary, b);
+    }
+
+    #[test]
+    fn test_selection_history() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        let c = map.add_child(&root_id, "C").unwrap();
+        assert!(!map.go_back());
+
+        map.select(&a).unwrap();
+        map.select(&b).unwrap();
+        map.select(&c).unwrap();
+        assert!(map.go_back());
+        assert_eq!(map.selection.primary, b);
+        assert!(map.go_back());
+        assert!(map.go_forward());
+        assert_eq!(map.selection.primary, b);
+
+        // Deleted nodes are skipped, and a new selection drops the
+        // forward history.
+        map.trash_node(&a).unwrap();
+        assert!(map.go_back());
+        assert_eq!(map.selection.primary, root_id);
+        map.select(&b).unwrap();
+        assert!(!map.can_go_forward());
+        assert!(map.select("missing").is_err());
+
+        for _ in 0..HISTORY_LIMIT {
+            map.select(&c).unwrap();
+            map.select(&b).unwrap();
+        }
+        let mut steps = 0;
+        while map.go_back() {
+            steps += 1;
+        }
+        assert_eq!(steps, HISTORY_LIMIT);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// Attaches a free-text note to a node, replacing any existing one. An
+    /// empty note clears it.
+    pub fn set_note(&mut self, node_id: &str, note: &str) -> Result<(), BrainCoreError> {
+        self.update_note(node_id, Some(note.to_string()).filter(|n| !n.is_empty()))
+    }
+
+    pub fn clear_note(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        self.update_note(node_id, None)
+    }
+
+    fn update_note(&mut self, node_id: &str, note: Option<String>) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.note != note {
+            node.note = note;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mindnode, opml, smmx, storage, xmind};
+
+    #[test]
+    fn test_notes_round_trip_through_formats() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = map.add_child(&root_id, "Child").unwrap();
+        map.set_note(&child, "First line\nSecond line").unwrap();
+        map.set_note(&root_id, "Dropped").unwrap();
+        map.clear_note(&root_id).unwrap();
+        assert_eq!(map.nodes[&root_id].note, None);
+
+        let loaded = [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            opml::from_opml(&opml::to_opml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+            mindnode::from_mindnode(&mindnode::to_mindnode(&map).unwrap()).unwrap(),
+            smmx::from_smmx(&smmx::to_smmx(&map).unwrap()).unwrap(),
+        ];
+        for (format, loaded) in ["FreeMind", "OPML", "XMind", "MindNode", "SMMX"]
+            .iter()
+            .zip(loaded)
+        {
+            let root = &loaded.nodes[&loaded.root_id];
+            assert_eq!(root.note, None, "{format}");
+            assert_eq!(
+                loaded.nodes[&root.children[0]].note.as_deref(),
+                Some("First line\nSecond line"),
+                "{format}"
+            );
+        }
+    }
+}
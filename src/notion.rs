@@ -0,0 +1,318 @@
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use uuid::Uuid;
+use zip::ZipArchive;
+
+pub fn from_notion(data: &[u8]) -> Result<MindMap, BrainCoreError> {
+    from_notion_with(data, &ImportOptions::default())
+}
+
+/// Reads Notion's "Markdown & CSV" export zip. Every page becomes a node
+/// whose children are the pages in its folder; page bodies become notes.
+/// Each database CSV becomes a node with one child per row, merged with the
+/// row's own page when the export includes it.
+pub fn from_notion_with(data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_notion_monitored(data, options, &mut Monitor::default())
+}
+
+pub fn from_notion_monitored(
+    data: &[u8],
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let mut archive = ZipArchive::new(Cursor::new(data))?;
+
+    monitor.begin(Stage::ReadingArchive, Some(archive.len()))?;
+    let mut files: Vec<(String, String)> = Vec::new();
+    for i in 0..archive.len() {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let mut file = archive.by_index(i)?;
+        let path = file.name().to_string();
+        if !file.is_file() || !(path.ends_with(".md") || path.ends_with(".csv")) {
+            continue;
+        }
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        files.push((path, contents));
+    }
+    monitor.finish()?;
+    if files.is_empty() {
+        return Err(BrainCoreError::parse(
+            ImportFormat::Notion,
+            "No Notion pages found in archive",
+        ));
+    }
+    // Sorting puts `Page <id>.md` ahead of the `Page <id>/` folder.
+    files.sort();
+
+    monitor.begin(Stage::BuildingNodes, Some(files.len()))?;
+    let mut tree = Tree::new();
+    for (path, contents) in &files {
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(key) = path.strip_suffix(".md") {
+            monitor.tick();
+            tree.add_page(key, contents);
+        }
+    }
+    let mut databases = HashSet::new();
+    for (path, contents) in &files {
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(key) = path.strip_suffix(".csv") {
+            monitor.tick();
+            // Newer exports ship both `Db <id>.csv` and `Db <id>_all.csv`.
+            let key = key.strip_suffix("_all").unwrap_or(key);
+            if databases.insert(key.to_string()) {
+                tree.add_database(key, contents);
+            }
+        }
+    }
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(tree.nodes, tree.root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+// Nodes keyed by their path inside the export, minus the extension, so a
+// page and the folder holding its sub-pages resolve to the same node.
+struct Tree {
+    nodes: HashMap<String, Node>,
+    by_path: HashMap<String, String>,
+    root_id: String,
+    now: u64,
+}
+
+impl Tree {
+    fn new() -> Self {
+        let now = now_millis();
+        let root_id = Uuid::new_v4().to_string();
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            root_id.clone(),
+            Node {
+                id: root_id.clone(),
+                content: "Notion".to_string(),
+                created: now,
+                modified: now,
+                ..Default::default()
+            },
+        );
+        Self {
+            nodes,
+            by_path: HashMap::new(),
+            root_id,
+            now,
+        }
+    }
+
+    fn node_for(&mut self, key: &str) -> String {
+        if key.is_empty() {
+            return self.root_id.clone();
+        }
+        if let Some(id) = self.by_path.get(key) {
+            return id.clone();
+        }
+        let (parent_key, name) = key.rsplit_once('/').unwrap_or(("", key));
+        let parent_id = self.node_for(parent_key);
+        let id = self.add_child(&parent_id, clean_title(name));
+        self.by_path.insert(key.to_string(), id.clone());
+        id
+    }
+
+    fn add_child(&mut self, parent_id: &str, content: &str) -> String {
+        let id = Uuid::new_v4().to_string();
+        self.nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content: content.to_string(),
+                parent: Some(parent_id.to_string()),
+                created: self.now,
+                modified: self.now,
+                ..Default::default()
+            },
+        );
+        if let Some(parent) = self.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        id
+    }
+
+    fn add_page(&mut self, key: &str, markdown: &str) {
+        let id = self.node_for(key);
+        let markdown = markdown.trim_start_matches('\u{feff}');
+        let (title, body) = match markdown.trim_start().strip_prefix("# ") {
+            Some(rest) => rest.split_once('\n').unwrap_or((rest, "")),
+            None => ("", markdown),
+        };
+        if let Some(node) = self.nodes.get_mut(&id) {
+            if !title.trim().is_empty() {
+                node.content = title.trim().to_string();
+            }
+            let body = body.trim();
+            node.note = (!body.is_empty()).then(|| body.to_string());
+        }
+    }
+
+    fn add_database(&mut self, key: &str, csv: &str) {
+        let id = self.node_for(key);
+        let mut rows = parse_csv(csv.trim_start_matches('\u{feff}')).into_iter();
+        let header = rows.next().unwrap_or_default();
+
+        // Rows exported as pages already sit under the database node; match
+        // them by title and keep the CSV's row order.
+        let mut unmatched: Vec<String> = self.nodes[&id].children.clone();
+        let mut ordered = Vec::new();
+        for row in rows {
+            let Some(title) = row.first().map(|t| t.trim()) else {
+                continue;
+            };
+            let existing = unmatched
+                .iter()
+                .position(|child| self.nodes[child].content == title);
+            let child_id = match existing {
+                Some(index) => unmatched.remove(index),
+                None => {
+                    let child_id = self.add_child(&id, title);
+                    let properties: Vec<String> = header
+                        .iter()
+                        .zip(&row)
+                        .skip(1)
+                        .filter(|(_, value)| !value.trim().is_empty())
+                        .map(|(name, value)| format!("{}: {}", name.trim(), value.trim()))
+                        .collect();
+                    if let Some(node) = self.nodes.get_mut(&child_id) {
+                        node.note = (!properties.is_empty()).then(|| properties.join("\n"));
+                    }
+                    child_id
+                }
+            };
+            ordered.push(child_id);
+        }
+        ordered.extend(unmatched);
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.children = ordered;
+        }
+    }
+}
+
+/// Drops the 32-digit hex id Notion appends to every file and folder name.
+fn clean_title(name: &str) -> &str {
+    match name.rsplit_once(' ') {
+        Some((title, id)) if id.len() == 32 && id.bytes().all(|b| b.is_ascii_hexdigit()) => title,
+        _ => name,
+    }
+}
+
+// RFC 4180: quoted fields may contain commas, newlines and doubled quotes.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    fn notion_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let mut zip = ZipWriter::new(Cursor::new(&mut buf));
+        for (name, contents) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_notion_import() {
+        let data = notion_zip(&[
+            (
+                "Projects 0123456789abcdef0123456789abcdef/Tasks 11111111111111111111111111111111/Write docs 22222222222222222222222222222222.md",
+                "# Write docs\n\nStatus: Doing\n\nOutline first.",
+            ),
+            (
+                "Projects 0123456789abcdef0123456789abcdef/Tasks 11111111111111111111111111111111.csv",
+                "\u{feff}Name,Status,Tags\r\nShip release,Done,\"launch, q1\"\r\nWrite docs,Doing,\r\n",
+            ),
+            (
+                "Projects 0123456789abcdef0123456789abcdef.md",
+                "# Projects\n\nEverything in flight.\n",
+            ),
+            (
+                "Projects 0123456789abcdef0123456789abcdef/Ideas 33333333333333333333333333333333.md",
+                "# Ideas\n",
+            ),
+            ("Projects 0123456789abcdef0123456789abcdef/cover.png", "png"),
+        ]);
+
+        let map = from_notion(&data).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "Notion");
+        let projects = &map.nodes[&root.children[0]];
+        assert_eq!(projects.content, "Projects");
+        assert_eq!(projects.note.as_deref(), Some("Everything in flight."));
+
+        let children: Vec<&str> = projects
+            .children
+            .iter()
+            .map(|id| map.nodes[id].content.as_str())
+            .collect();
+        assert_eq!(children, ["Ideas", "Tasks"]);
+
+        let tasks = &map.nodes[&projects.children[1]];
+        let rows: Vec<&Node> = tasks.children.iter().map(|id| &map.nodes[id]).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].content, "Ship release");
+        assert_eq!(
+            rows[0].note.as_deref(),
+            Some("Status: Done\nTags: launch, q1")
+        );
+        assert_eq!(rows[1].content, "Write docs");
+        assert_eq!(
+            rows[1].note.as_deref(),
+            Some("Status: Doing\n\nOutline first.")
+        );
+    }
+}
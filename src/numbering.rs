@@ -0,0 +1,262 @@
+use crate::{BrainCoreError, MindMap};
+
+/// How the counter of a single outline level is rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberStyle {
+    Decimal,
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl NumberStyle {
+    /// Renders a 1-based counter.
+    pub fn format(self, n: usize) -> String {
+        match self {
+            NumberStyle::Decimal => n.to_string(),
+            NumberStyle::LowerAlpha => alpha(n),
+            NumberStyle::UpperAlpha => alpha(n).to_uppercase(),
+            NumberStyle::LowerRoman => roman(n).to_lowercase(),
+            NumberStyle::UpperRoman => roman(n),
+        }
+    }
+}
+
+/// Numbering applied to the levels below the root. `levels[0]` styles the
+/// first level; the last style repeats for deeper levels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberingScheme {
+    pub levels: Vec<NumberStyle>,
+    /// Prefix each number with its ancestors' ("1.2.3.") instead of showing
+    /// only the level's own counter ("3.").
+    pub full_path: bool,
+}
+
+impl Default for NumberingScheme {
+    fn default() -> Self {
+        Self {
+            levels: vec![NumberStyle::Decimal],
+            full_path: true,
+        }
+    }
+}
+
+impl NumberingScheme {
+    /// Renders the label for a node given its 1-based position at each level,
+    /// e.g. `[1, 1, 2]` → `"1.1.2."` with the default scheme.
+    pub fn label(&self, path: &[usize]) -> String {
+        let style = |level: usize| {
+            self.levels
+                .get(level)
+                .or(self.levels.last())
+                .copied()
+                .unwrap_or(NumberStyle::Decimal)
+        };
+
+        let first = if self.full_path {
+            0
+        } else {
+            path.len().saturating_sub(1)
+        };
+        let mut label = String::new();
+        for (level, n) in path.iter().enumerate().skip(first) {
+            label.push_str(&style(level).format(*n));
+            label.push('.');
+        }
+        label
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineFormat {
+    PlainText,
+    Markdown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberedOutlineOptions {
+    pub scheme: NumberingScheme,
+    pub format: OutlineFormat,
+    /// Spaces per level in plain text output. Markdown output is never
+    /// indented, since indentation would turn entries into code blocks.
+    pub indent: usize,
+    pub include_archived: bool,
+}
+
+impl Default for NumberedOutlineOptions {
+    fn default() -> Self {
+        Self {
+            scheme: NumberingScheme::default(),
+            format: OutlineFormat::PlainText,
+            indent: 2,
+            include_archived: false,
+        }
+    }
+}
+
+/// Exports the map as a numbered outline with the root as its title.
+pub fn to_numbered_outline(
+    map: &MindMap,
+    options: &NumberedOutlineOptions,
+) -> Result<String, BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+
+    let mut out = String::new();
+    match options.format {
+        OutlineFormat::PlainText => out.push_str(&root.content.replace('\n', " ")),
+        OutlineFormat::Markdown => {
+            out.push_str("# ");
+            out.push_str(&root.content.replace('\n', "<br>"));
+        }
+    }
+    out.push('\n');
+
+    let mut path = Vec::new();
+    write_children(map, &root.children, &mut path, options, &mut out);
+    Ok(out)
+}
+
+fn write_children(
+    map: &MindMap,
+    children: &[String],
+    path: &mut Vec<usize>,
+    options: &NumberedOutlineOptions,
+    out: &mut String,
+) {
+    let mut counter = 0;
+    for child_id in children {
+        let Some(child) = map.nodes.get(child_id) else {
+            continue;
+        };
+        if child.archived && !options.include_archived {
+            continue;
+        }
+        counter += 1;
+        path.push(counter);
+
+        let label = options.scheme.label(path);
+        match options.format {
+            OutlineFormat::PlainText => {
+                out.push_str(&" ".repeat(options.indent * (path.len() - 1)));
+                out.push_str(&label);
+            }
+            OutlineFormat::Markdown => {
+                // A lone "1." at line start would open a Markdown ordered list.
+                out.push('\n');
+                out.push_str(&label.replace('.', "\\."));
+            }
+        }
+        out.push(' ');
+        match options.format {
+            OutlineFormat::PlainText => out.push_str(&child.content.replace('\n', " ")),
+            OutlineFormat::Markdown => out.push_str(&child.content.replace('\n', "<br>")),
+        }
+        out.push('\n');
+
+        write_children(map, &child.children, path, options, out);
+        path.pop();
+    }
+}
+
+fn alpha(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while n >= value {
+            out.push_str(numeral);
+            n -= value;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn add_child_for_test(map: &mut MindMap, parent_id: &str, content: &str) -> String {
+        let id = format!("node-{}", map.nodes.len());
+        let node = Node {
+            id: id.clone(),
+            content: content.to_string(),
+            parent: Some(parent_id.to_string()),
+            ..Default::default()
+        };
+        map.nodes.insert(id.clone(), node);
+        if let Some(parent) = map.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        id
+    }
+
+    #[test]
+    fn test_numbered_outline() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.nodes.get_mut(&root_id).unwrap().content = "Spec".to_string();
+        let a = add_child_for_test(&mut map, &root_id, "Goals");
+        add_child_for_test(&mut map, &a, "Speed");
+        let b = add_child_for_test(&mut map, &a, "Safety");
+        add_child_for_test(&mut map, &b, "Audits");
+        add_child_for_test(&mut map, &root_id, "Risks");
+
+        let text = to_numbered_outline(&map, &NumberedOutlineOptions::default()).unwrap();
+        assert_eq!(
+            text,
+            "Spec\n1. Goals\n  1.1. Speed\n  1.2. Safety\n    1.2.1. Audits\n2. Risks\n"
+        );
+
+        let options = NumberedOutlineOptions {
+            format: OutlineFormat::Markdown,
+            ..Default::default()
+        };
+        let md = to_numbered_outline(&map, &options).unwrap();
+        assert!(md.starts_with("# Spec\n\n1\\. Goals\n"));
+        assert!(md.contains("\n1\\.2\\.1\\. Audits\n"));
+    }
+
+    #[test]
+    fn test_numbering_scheme_styles() {
+        let scheme = NumberingScheme {
+            levels: vec![
+                NumberStyle::UpperRoman,
+                NumberStyle::UpperAlpha,
+                NumberStyle::Decimal,
+            ],
+            full_path: false,
+        };
+        assert_eq!(scheme.label(&[4]), "IV.");
+        assert_eq!(scheme.label(&[4, 28]), "AB.");
+        assert_eq!(scheme.label(&[4, 28, 3, 5]), "5.");
+        assert_eq!(NumberStyle::LowerAlpha.format(26), "z");
+    }
+}
@@ -1,9 +1,17 @@
-use crate::{MindMap, Node};
+use crate::export::{ExportOptions, TextWriter};
+use crate::html;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::preserve::{UnknownAttributes, XmlAttributes};
+use crate::progress::{Monitor, Stage};
+use crate::task::TaskInfo;
+use crate::timestamp::{format_iso8601, format_rfc2822, now_millis, parse_iso8601, parse_rfc2822};
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::Reader;
 use quick_xml::de::from_str;
-use quick_xml::se::to_string;
+use quick_xml::events::Event;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::Write;
 use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -17,6 +25,8 @@ pub struct Opml {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct OpmlHead {
+    /// Optional in OPML 2.0; Workflowy leaves it out.
+    #[serde(default)]
     pub title: String,
     #[serde(rename = "dateCreated", skip_serializing_if = "Option::is_none")]
     pub date_created: Option<String>,
@@ -30,18 +40,110 @@ pub struct OpmlBody {
     pub outlines: Vec<OpmlOutline>,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct OpmlOutline {
     #[serde(rename = "@text")]
     pub text: String,
     #[serde(rename = "@_note", skip_serializing_if = "Option::is_none")]
     pub note: Option<String>,
+    #[serde(rename = "@url", default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Feed lists put the site address here; read as the link when `url`
+    /// is missing.
+    #[serde(rename = "@htmlUrl", default, skip_serializing)]
+    pub html_url: Option<String>,
+    /// Task state, in the underscore-prefixed style of `_note`. `_complete`
+    /// follows OmniOutliner and Workflowy; the others are this crate's.
+    #[serde(
+        rename = "@_complete",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub complete: Option<bool>,
+    #[serde(
+        rename = "@_progress",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub progress: Option<u8>,
+    #[serde(
+        rename = "@_priority",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub priority: Option<u8>,
+    /// ISO 8601.
+    #[serde(rename = "@_due", default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    /// OmniOutliner's checkbox: `checked`, `unchecked` or `indeterminate`.
+    #[serde(rename = "@_status", default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// RFC 822, as OPML 2.0 has it; ISO 8601 is read too.
+    #[serde(rename = "@created", default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    /// What the outline stands for, such as `link` or `rss`. Kept as the
+    /// node's `type` attribute.
+    #[serde(rename = "@type", default, skip_serializing_if = "Option::is_none")]
+    pub outline_type: Option<String>,
+    /// Namespaced attributes such as `wf:color` and the `xmlns:`
+    /// declarations they rely on, which are kept as node attributes. Only
+    /// written, like `unknown`.
+    #[serde(flatten, skip_deserializing)]
+    pub extensions: XmlAttributes,
+    /// Attributes kept from an imported file. Only written: the importer
+    /// reads them in a pass of its own.
+    #[serde(flatten, skip_deserializing)]
+    pub unknown: XmlAttributes,
     #[serde(rename = "outline", default)]
     pub children: Vec<OpmlOutline>,
 }
 
-pub fn to_opml(map: &MindMap) -> Result<String, String> {
-    let root_node = map.nodes.get(&map.root_id).ok_or("Root node not found")?;
+pub fn to_opml(map: &MindMap) -> Result<String, BrainCoreError> {
+    to_opml_with(map, &ExportOptions::default())
+}
+
+/// OPML carries no generated identifiers, so the output is always
+/// canonical. Each outline's `created` date is written unless
+/// `suppress_timestamps` is set.
+pub fn to_opml_with(map: &MindMap, options: &ExportOptions) -> Result<String, BrainCoreError> {
+    let mut buf = Vec::new();
+    write_opml_with(map, &mut buf, options)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+/// Streams the OPML document to `writer` instead of building a string.
+pub fn write_opml<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_opml_with(map, writer, &ExportOptions::default())
+}
+
+pub fn write_opml_with<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    write_opml_monitored(map, writer, options, &mut Monitor::default())
+}
+
+pub fn to_opml_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut buf = Vec::new();
+    write_opml_monitored(map, &mut buf, options, monitor)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+pub fn write_opml_monitored<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let root_node = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
 
     let head = OpmlHead {
         title: root_node.content.clone(),
@@ -49,9 +151,14 @@ pub fn to_opml(map: &MindMap) -> Result<String, String> {
         date_modified: None,
     };
 
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
     let body = OpmlBody {
-        outlines: vec![node_to_outline(root_node, map)],
+        outlines: vec![node_to_outline(root_node, map, options, monitor)],
     };
+    monitor.finish()?;
 
     let opml = Opml {
         version: "2.0".to_string(),
@@ -59,28 +166,81 @@ pub fn to_opml(map: &MindMap) -> Result<String, String> {
         body,
     };
 
-    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
-    xml.push_str(&to_string(&opml).map_err(|e| e.to_string())?);
-    Ok(xml)
+    writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    let mut text = TextWriter::new(&mut *writer);
+    let result = options.write_xml(&opml, &mut html::AttributeEncoder::new(&mut text));
+    text.check(result)?;
+    Ok(writer.flush()?)
 }
 
-fn node_to_outline(node: &Node, map: &MindMap) -> OpmlOutline {
+fn node_to_outline(
+    node: &Node,
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> OpmlOutline {
+    monitor.tick();
     let mut children = Vec::new();
     for child_id in &node.children {
-        if let Some(child) = map.nodes.get(child_id) {
-            children.push(node_to_outline(child, map));
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            children.push(node_to_outline(child, map, options, monitor));
         }
     }
 
+    let task = node.task.as_ref();
     OpmlOutline {
         text: node.content.clone(),
-        note: None, // Could map to something if we had notes
+        note: node.note.clone(),
+        url: node.link.clone(),
+        html_url: None,
+        complete: task.map(|t| t.done),
+        progress: task.map(|t| t.progress_pct),
+        priority: task.and_then(|t| t.priority),
+        due: task.and_then(|t| t.due).map(format_iso8601),
+        status: task.map(|t| {
+            match (t.done, t.progress_pct) {
+                (true, _) => "checked",
+                (false, 0) => "unchecked",
+                (false, _) => "indeterminate",
+            }
+            .to_string()
+        }),
+        created: (!options.suppress_timestamps).then(|| format_rfc2822(node.created)),
+        outline_type: node
+            .attributes
+            .iter()
+            .find(|(name, _)| name == "type")
+            .map(|(_, value)| value.clone()),
+        extensions: XmlAttributes(
+            node.attributes
+                .iter()
+                .filter(|(name, _)| is_namespaced(name))
+                .cloned()
+                .collect(),
+        ),
+        unknown: XmlAttributes::of(node, ImportFormat::Opml),
         children,
     }
 }
 
-pub fn from_opml(xml: &str) -> Result<MindMap, String> {
-    let opml: Opml = from_str(xml).map_err(|e| e.to_string())?;
+pub fn from_opml(xml: &str) -> Result<MindMap, BrainCoreError> {
+    from_opml_with(xml, &ImportOptions::default())
+}
+
+pub fn from_opml_with(xml: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_opml_monitored(xml, options, &mut Monitor::default())
+}
+
+pub fn from_opml_monitored(
+    xml: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let opml: Opml = from_str(xml).map_err(|e| BrainCoreError::parse(ImportFormat::Opml, e))?;
 
     let mut nodes = HashMap::new();
     let root_id;
@@ -92,8 +252,12 @@ pub fn from_opml(xml: &str) -> Result<MindMap, String> {
         return Ok(MindMap::new());
     }
 
+    monitor.begin(
+        Stage::BuildingNodes,
+        Some(count_outlines(&opml.body.outlines)),
+    )?;
     if opml.body.outlines.len() == 1 {
-        root_id = outline_to_node(&opml.body.outlines[0], None, &mut nodes);
+        root_id = outline_to_node(&opml.body.outlines[0], None, &mut nodes, monitor);
     } else {
         // Create a virtual root using the title
         let root = Node {
@@ -106,35 +270,133 @@ pub fn from_opml(xml: &str) -> Result<MindMap, String> {
             created: now_millis(),
             modified: now_millis(),
             icons: Vec::new(),
+            ..Default::default()
         };
         root_id = root.id.clone();
         nodes.insert(root_id.clone(), root);
 
         for outline in &opml.body.outlines {
-            let child_id = outline_to_node(outline, Some(&root_id), &mut nodes);
+            let child_id = outline_to_node(outline, Some(&root_id), &mut nodes, monitor);
             if let Some(root_node) = nodes.get_mut(&root_id) {
                 root_node.children.push(child_id);
             }
         }
     }
 
-    Ok(MindMap {
-        nodes,
-        root_id: root_id.clone(),
-        selected_node_id: root_id,
-    })
+    monitor.finish()?;
+
+    // Outlines are read in document order, which is the order a
+    // depth-first walk of their nodes visits them in.
+    let (declarations, outlines) = other_attributes(xml);
+    let mut outlines = outlines.into_iter();
+    let mut stack: Vec<&String> = match opml.body.outlines.len() {
+        1 => vec![&root_id],
+        _ => nodes[&root_id].children.iter().rev().collect(),
+    };
+    let mut found = Vec::new();
+    while let (Some(id), Some(attributes)) = (stack.pop(), outlines.next()) {
+        stack.extend(nodes[id].children.iter().rev());
+        found.push((id.clone(), attributes));
+    }
+    for (id, attributes) in found {
+        if let Some(node) = nodes.get_mut(&id) {
+            let (namespaced, unknown) = attributes
+                .into_iter()
+                .partition(|(name, _): &(String, String)| is_namespaced(name));
+            node.attributes.extend(namespaced);
+            if options.preserve_unknown {
+                node.unknown_attributes = UnknownAttributes::new(ImportFormat::Opml, unknown);
+            }
+        }
+    }
+    // Declarations on `<opml>` move to the root outline, where the
+    // exporter writes them back.
+    if let Some(root) = nodes.get_mut(&root_id) {
+        root.attributes.splice(0..0, declarations);
+    }
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+/// Attributes with a prefix, such as `dynalist:color` or `xmlns:dynalist`.
+fn is_namespaced(name: &str) -> bool {
+    name.contains(':')
+}
+
+type Attributes = Vec<(String, String)>;
+
+/// The `xmlns:` declarations on `<opml>`, and the attributes of each
+/// outline that [`OpmlOutline`] doesn't read, in document order.
+fn other_attributes(xml: &str) -> (Attributes, Vec<Attributes>) {
+    const KNOWN: [&[u8]; 10] = [
+        b"text",
+        b"_note",
+        b"url",
+        b"_complete",
+        b"_progress",
+        b"_priority",
+        b"_due",
+        b"_status",
+        b"created",
+        b"type",
+    ];
+    let mut reader = Reader::from_str(xml);
+    let mut declarations = Vec::new();
+    let mut outlines = Vec::new();
+    loop {
+        let (e, is_outline) = match reader.read_event() {
+            Ok(Event::Start(e) | Event::Empty(e)) => match e.local_name().as_ref() {
+                b"outline" => (e, true),
+                b"opml" => (e, false),
+                _ => continue,
+            },
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => continue,
+        };
+        let attributes = e
+            .attributes()
+            .filter_map(Result::ok)
+            .filter(|a| is_outline || a.key.as_ref().starts_with(b"xmlns:"))
+            .filter(|a| !KNOWN.contains(&a.key.as_ref()))
+            .filter_map(|a| {
+                let value = a.decode_and_unescape_value(reader.decoder()).ok()?;
+                let name = String::from_utf8_lossy(a.key.as_ref()).into_owned();
+                Some((name, value.into_owned()))
+            })
+            .collect();
+        match is_outline {
+            true => outlines.push(attributes),
+            false => declarations = attributes,
+        }
+    }
+    (declarations, outlines)
+}
+
+/// Outlines in `outlines` and below them.
+fn count_outlines(outlines: &[OpmlOutline]) -> usize {
+    outlines
+        .iter()
+        .map(|o| 1 + count_outlines(&o.children))
+        .sum()
 }
 
-fn outline_to_node(
+pub(crate) fn outline_to_node(
     outline: &OpmlOutline,
     parent_id: Option<&str>,
     nodes: &mut HashMap<String, Node>,
+    monitor: &mut Monitor,
 ) -> String {
+    monitor.tick();
     let id = Uuid::new_v4().to_string();
 
     let mut children_ids = Vec::new();
     for child in &outline.children {
-        children_ids.push(outline_to_node(child, Some(&id), nodes));
+        if monitor.should_stop() {
+            break;
+        }
+        children_ids.push(outline_to_node(child, Some(&id), nodes, monitor));
     }
 
     let node = Node {
@@ -144,20 +406,53 @@ fn outline_to_node(
         parent: parent_id.map(|s| s.to_string()),
         x: 0.0,
         y: 0.0,
-        created: now_millis(),
+        created: outline
+            .created
+            .as_deref()
+            .and_then(|c| parse_rfc2822(c).or_else(|| parse_iso8601(c)))
+            .unwrap_or_else(now_millis),
         modified: now_millis(),
         icons: Vec::new(),
+        note: outline.note.clone(),
+        attributes: outline
+            .outline_type
+            .iter()
+            .map(|t| ("type".to_string(), t.clone()))
+            .collect(),
+        link: outline
+            .url
+            .clone()
+            .or_else(|| outline.html_url.clone())
+            .filter(|l| !l.is_empty()),
+        task: outline_task(outline),
+        ..Default::default()
     };
 
     nodes.insert(id.clone(), node);
     id
 }
 
-fn now_millis() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
+// Any of the task attributes makes the outline a task.
+fn outline_task(outline: &OpmlOutline) -> Option<TaskInfo> {
+    let due = outline.due.as_deref().and_then(parse_iso8601);
+    let status = outline
+        .status
+        .as_deref()
+        .filter(|s| ["checked", "unchecked", "indeterminate"].contains(s));
+    if outline.complete.is_none()
+        && status.is_none()
+        && outline.progress.is_none()
+        && outline.priority.is_none()
+        && due.is_none()
+    {
+        return None;
+    }
+    Some(TaskInfo {
+        done: outline.complete.unwrap_or(status == Some("checked")),
+        progress_pct: outline.progress.unwrap_or(0).min(100),
+        due,
+        priority: outline.priority,
+    })
 }
 
 #[cfg(test)]
@@ -177,6 +472,7 @@ mod tests {
             created: timestamp,
             modified: timestamp,
             icons: Vec::new(),
+            ..Default::default()
         };
         map.nodes.insert(id.clone(), node);
         if let Some(parent) = map.nodes.get_mut(parent_id) {
@@ -226,4 +522,59 @@ mod tests {
         assert_eq!(root.content, "Root");
         assert_eq!(root.children.len(), 2);
     }
+
+    #[test]
+    fn test_opml_extension_attributes() {
+        let xml = r#"<opml version="2.0" xmlns:wf="https://workflowy.com/opml">
+  <head><title>Outliner</title></head>
+  <body>
+    <outline text="Root" created="Wed, 31 Jan 2024 12:00:00 GMT">
+      <outline text="Done" _status="checked" wf:color="red"/>
+      <outline text="Half" _status="indeterminate" _progress="50"/>
+      <outline text="Feed" type="rss" created="2024-01-31T12:00:00Z"/>
+    </outline>
+  </body>
+</opml>"#;
+        let map = from_opml(xml).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.created, 1_706_702_400_000);
+        assert_eq!(
+            root.attributes,
+            [(
+                "xmlns:wf".to_string(),
+                "https://workflowy.com/opml".to_string()
+            )]
+        );
+        let child = |i: usize| &map.nodes[&root.children[i]];
+        assert!(child(0).task.as_ref().unwrap().done);
+        assert_eq!(
+            map.attribute(&child(0).id, "wf:color").unwrap(),
+            Some("red")
+        );
+        assert!(!child(1).task.as_ref().unwrap().done);
+        assert_eq!(map.attribute(&child(2).id, "type").unwrap(), Some("rss"));
+        assert_eq!(child(2).created, 1_706_702_400_000);
+
+        let saved = to_opml(&map).unwrap();
+        for attribute in [
+            r#"xmlns:wf="https://workflowy.com/opml""#,
+            r#"created="Wed, 31 Jan 2024 12:00:00 GMT""#,
+            r#"_status="checked""#,
+            r#"_status="indeterminate""#,
+            r#"wf:color="red""#,
+            r#"type="rss""#,
+        ] {
+            assert!(
+                saved.contains(attribute),
+                "{attribute} missing from {saved}"
+            );
+        }
+        let loaded = from_opml(&saved).unwrap();
+        assert_eq!(loaded.nodes[&loaded.root_id].attributes, root.attributes);
+        assert!(
+            !to_opml_with(&map, &ExportOptions::canonical())
+                .unwrap()
+                .contains("created=")
+        );
+    }
 }
@@ -0,0 +1,431 @@
+use crate::export::ExportOptions;
+use crate::html;
+use crate::import::ImportOptions;
+use crate::opml::{self, Opml, OpmlBody, OpmlHead, OpmlOutline};
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// The OPML and plain-text exports of the Workflowy and Dynalist outliners.
+// Both keep notes in `_note` and completion in `_complete`, but Workflowy
+// stores item text as HTML, so a literal `&` arrives as `&amp;amp;`, while
+// Dynalist stores Markdown and escapes its markers with backslashes. Their
+// plain-text exports are `- ` bullets, two spaces per level in Workflowy
+// and four in Dynalist.
+
+/// The outliner whose dialect to read or write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outliner {
+    Workflowy,
+    Dynalist,
+}
+
+/// Characters Dynalist's Markdown gives a meaning to.
+const MARKDOWN_MARKERS: [char; 5] = ['\\', '*', '_', '~', '`'];
+
+impl Outliner {
+    fn indent(self) -> &'static str {
+        match self {
+            Outliner::Workflowy => "  ",
+            Outliner::Dynalist => "    ",
+        }
+    }
+
+    /// How the plain-text export marks a task.
+    fn task_marker(self, task: &TaskInfo) -> &'static str {
+        match (self, task.done) {
+            (Outliner::Workflowy, true) => "[COMPLETE] ",
+            (Outliner::Workflowy, false) => "",
+            (Outliner::Dynalist, true) => "[x] ",
+            (Outliner::Dynalist, false) => "[ ] ",
+        }
+    }
+
+    /// Text as the outliner stores it. Workflowy's plain text is the only
+    /// place it isn't escaped.
+    fn escape(self, text: &str, in_opml: bool) -> String {
+        match self {
+            Outliner::Workflowy if in_opml => text
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;"),
+            Outliner::Workflowy => text.to_string(),
+            Outliner::Dynalist => {
+                let mut escaped = String::with_capacity(text.len());
+                for c in text.chars() {
+                    if MARKDOWN_MARKERS.contains(&c) {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+                escaped
+            }
+        }
+    }
+
+    /// The inverse of [`Outliner::escape`]. Workflowy's formatting tags,
+    /// such as `<b>`, are dropped.
+    fn unescape(self, text: &str, in_opml: bool) -> String {
+        match self {
+            Outliner::Workflowy if in_opml => strip_html(text),
+            Outliner::Workflowy => text.to_string(),
+            Outliner::Dynalist => {
+                let mut unescaped = String::with_capacity(text.len());
+                let mut chars = text.chars().peekable();
+                while let Some(c) = chars.next() {
+                    match chars.peek() {
+                        Some(next) if c == '\\' && MARKDOWN_MARKERS.contains(next) => {
+                            unescaped.push(*next);
+                            chars.next();
+                        }
+                        _ => unescaped.push(c),
+                    }
+                }
+                unescaped
+            }
+        }
+    }
+}
+
+/// Drops tags and decodes character references, keeping line breaks.
+fn strip_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find(['<', '&']) {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        if rest.starts_with('<') {
+            match rest.find('>') {
+                Some(end) => rest = &rest[end + 1..],
+                None => {
+                    out.push_str(rest);
+                    rest = "";
+                }
+            }
+            continue;
+        }
+        let reference = rest[1..]
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| Some((html::entity(&rest[1..1 + end])?, end)));
+        match reference {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+pub fn to_outliner_opml(map: &MindMap, outliner: Outliner) -> Result<String, BrainCoreError> {
+    to_outliner_opml_with(map, outliner, &ExportOptions::default())
+}
+
+/// Writes the root as the single top-level outline with only the
+/// attributes the outliner reads: `text`, `_note` and `_complete` on done
+/// tasks.
+pub fn to_outliner_opml_with(
+    map: &MindMap,
+    outliner: Outliner,
+    options: &ExportOptions,
+) -> Result<String, BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+    let document = Opml {
+        version: "2.0".to_string(),
+        head: OpmlHead {
+            title: root.content.clone(),
+            date_created: None,
+            date_modified: None,
+        },
+        body: OpmlBody {
+            outlines: vec![node_to_outline(root, map, outliner, options)],
+        },
+    };
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    options.write_xml(&document, &mut html::AttributeEncoder::new(&mut xml))?;
+    Ok(xml)
+}
+
+fn node_to_outline(
+    node: &Node,
+    map: &MindMap,
+    outliner: Outliner,
+    options: &ExportOptions,
+) -> OpmlOutline {
+    OpmlOutline {
+        text: outliner.escape(&node.content, true),
+        note: node.note.as_deref().map(|n| outliner.escape(n, true)),
+        complete: node.task.as_ref().filter(|t| t.done).map(|_| true),
+        children: node
+            .children
+            .iter()
+            .filter_map(|id| map.nodes.get(id))
+            .filter(|c| options.includes(c))
+            .map(|c| node_to_outline(c, map, outliner, options))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+pub fn from_outliner_opml(xml: &str, outliner: Outliner) -> Result<MindMap, BrainCoreError> {
+    from_outliner_opml_with(xml, outliner, &ImportOptions::default())
+}
+
+/// Reads the export as [`opml::from_opml_with`] does, several top-level
+/// outlines included, then undoes the outliner's escaping before `options`
+/// sanitize the text.
+pub fn from_outliner_opml_with(
+    xml: &str,
+    outliner: Outliner,
+    options: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    let mut map = opml::from_opml_with(
+        xml,
+        &ImportOptions {
+            preserve_unknown: options.preserve_unknown,
+            ..ImportOptions::raw()
+        },
+    )?;
+    for node in map.nodes.values_mut() {
+        node.content = outliner.unescape(&node.content, true);
+        node.note = node.note.as_deref().map(|n| outliner.unescape(n, true));
+    }
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+pub fn to_outliner_text(map: &MindMap, outliner: Outliner) -> String {
+    to_outliner_text_with(map, outliner, &ExportOptions::default())
+}
+
+/// Writes one `- ` bullet per node, tasks marked as the outliner does.
+/// Notes follow their item one level deeper; Workflowy wraps them in
+/// double quotes.
+pub fn to_outliner_text_with(map: &MindMap, outliner: Outliner, options: &ExportOptions) -> String {
+    let mut out = String::new();
+    let unit = outliner.indent();
+    let mut stack: Vec<(&Node, usize)> = map
+        .nodes
+        .get(&map.root_id)
+        .map(|root| (root, 0))
+        .into_iter()
+        .collect();
+    while let Some((node, depth)) = stack.pop() {
+        out.push_str(&unit.repeat(depth));
+        out.push_str("- ");
+        if let Some(task) = &node.task {
+            out.push_str(outliner.task_marker(task));
+        }
+        out.push_str(&outliner.escape(&node.content.replace('\n', " "), false));
+        out.push('\n');
+        if let Some(note) = node.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            let note = match outliner {
+                Outliner::Workflowy => format!("\"{note}\""),
+                Outliner::Dynalist => outliner.escape(note, false),
+            };
+            for line in note.lines() {
+                out.push_str(&unit.repeat(depth + 1));
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        for child_id in node.children.iter().rev() {
+            if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+    out
+}
+
+pub fn from_outliner_text(text: &str, outliner: Outliner) -> Result<MindMap, BrainCoreError> {
+    from_outliner_text_with(text, outliner, &ImportOptions::default())
+}
+
+/// Reads each `- ` line as a node, nested by indentation as in
+/// [`crate::text_outline`], and other lines as the note of the item above.
+/// A single top-level item becomes the root; otherwise the top level is
+/// gathered under an "Outline" root.
+pub fn from_outliner_text_with(
+    text: &str,
+    outliner: Outliner,
+    options: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: "Outline".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    // Open ancestors with their indentation width, and note lines by node.
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let mut notes: HashMap<String, Vec<&str>> = HashMap::new();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let body = line.trim_start();
+        if body.is_empty() {
+            continue;
+        }
+        let item = body.strip_prefix("- ").or((body == "-").then_some(""));
+        if let (None, Some((_, last))) = (item, stack.last()) {
+            notes.entry(last.clone()).or_default().push(body.trim_end());
+            continue;
+        }
+        let width: usize = line[..line.len() - body.len()]
+            .chars()
+            .map(|c| {
+                if c == '\t' {
+                    outliner.indent().len()
+                } else {
+                    1
+                }
+            })
+            .sum();
+        while stack.last().is_some_and(|(open, _)| *open >= width) {
+            stack.pop();
+        }
+        let parent = stack.last().map_or(&root_id, |(_, id)| id).clone();
+        let (content, task) = text_item(item.unwrap_or(body).trim_end(), outliner);
+        let id = Uuid::new_v4().to_string();
+        nodes.get_mut(&parent).unwrap().children.push(id.clone());
+        nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content,
+                parent: Some(parent),
+                task,
+                created: now,
+                modified: now,
+                ..Default::default()
+            },
+        );
+        stack.push((width, id));
+    }
+
+    for (id, lines) in notes {
+        let note = lines.join("\n");
+        let note = match outliner {
+            Outliner::Workflowy => note
+                .strip_prefix('"')
+                .and_then(|n| n.strip_suffix('"'))
+                .unwrap_or(&note)
+                .to_string(),
+            Outliner::Dynalist => outliner.unescape(&note, false),
+        };
+        if let Some(node) = nodes.get_mut(&id) {
+            node.note = Some(note);
+        }
+    }
+
+    let root_id = match nodes[&root_id].children.as_slice() {
+        [only] => {
+            let only = only.clone();
+            nodes.remove(&root_id);
+            nodes.get_mut(&only).unwrap().parent = None;
+            only
+        }
+        _ => root_id,
+    };
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+/// An item's text and task, from its task marker.
+fn text_item(item: &str, outliner: Outliner) -> (String, Option<TaskInfo>) {
+    let markers: &[(&str, bool)] = match outliner {
+        Outliner::Workflowy => &[("[COMPLETE] ", true)],
+        Outliner::Dynalist => &[("[x] ", true), ("[ ] ", false)],
+    };
+    for &(marker, done) in markers {
+        if let Some(rest) = item.strip_prefix(marker) {
+            let task = TaskInfo {
+                done,
+                progress_pct: if done { 100 } else { 0 },
+                ..Default::default()
+            };
+            return (outliner.unescape(rest, false), Some(task));
+        }
+    }
+    (outliner.unescape(item, false), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_outliner_round_trips() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.nodes.get_mut(&root_id).unwrap().content = "Plan & <ship>".to_string();
+        let done = map.add_child(&root_id, "Write *docs*").unwrap();
+        map.set_task(
+            &done,
+            Some(TaskInfo {
+                done: true,
+                progress_pct: 100,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+        map.set_note(&done, "First line\nSecond_line").unwrap();
+        map.add_child(&done, "Review").unwrap();
+
+        for outliner in [Outliner::Workflowy, Outliner::Dynalist] {
+            let xml = to_outliner_opml(&map, outliner).unwrap();
+            let text = to_outliner_text(&map, outliner);
+            for loaded in [
+                from_outliner_opml(&xml, outliner).unwrap(),
+                from_outliner_text(&text, outliner).unwrap(),
+            ] {
+                let root = &loaded.nodes[&loaded.root_id];
+                assert_eq!(root.content, "Plan & <ship>", "{outliner:?}");
+                let child = &loaded.nodes[&root.children[0]];
+                assert_eq!(child.content, "Write *docs*");
+                assert_eq!(child.note.as_deref(), Some("First line\nSecond_line"));
+                assert!(child.task.as_ref().unwrap().done);
+                assert_eq!(loaded.nodes[&child.children[0]].content, "Review");
+            }
+        }
+        assert!(
+            to_outliner_opml(&map, Outliner::Workflowy)
+                .unwrap()
+                .contains("&amp;amp;")
+        );
+        assert!(to_outliner_text(&map, Outliner::Dynalist).contains("- [x] Write \\*docs\\*"));
+
+        let xml = r#"<?xml version="1.0"?><opml version="2.0"><head><ownerEmail>a@b.c</ownerEmail></head><body>
+            <outline text="&lt;b&gt;Bold&lt;/b&gt; &amp;amp; more" _complete="true"/>
+            <outline text="Second" _note="Tom &amp;amp; Jerry"/></body></opml>"#;
+        let loaded = from_outliner_opml(xml, Outliner::Workflowy).unwrap();
+        let root = &loaded.nodes[&loaded.root_id];
+        assert_eq!(root.children.len(), 2);
+        let first = &loaded.nodes[&root.children[0]];
+        assert_eq!(first.content, "Bold & more");
+        assert!(first.task.as_ref().unwrap().done);
+        let second = &loaded.nodes[&root.children[1]];
+        assert_eq!(second.note.as_deref(), Some("Tom & Jerry"));
+    }
+}
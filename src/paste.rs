@@ -0,0 +1,537 @@
+use crate::attachments::ResourceStore;
+use crate::events::MapEvent;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::opml::{self, Opml};
+use crate::progress::Monitor;
+use crate::storage;
+use crate::timestamp::now_millis;
+use crate::validation::Change;
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::de::from_str;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Formats recognized by [`parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteFormat {
+    /// A FreeMind `<map>` document or one or more bare `<node>` elements.
+    FreeMind,
+    /// An OPML document or one or more bare `<outline>` elements.
+    Opml,
+    /// A Markdown list using `-`, `*`, `+` or `1.` markers.
+    Markdown,
+    /// Plain lines, nested by indentation.
+    Indented,
+}
+
+/// Detached nodes ready to be attached with [`MindMap::paste_subtree`].
+/// `roots` are the top-level nodes in order; they have no parent.
+///
+/// This is also the clipboard fragment produced by
+/// [`MindMap::copy_subtree`] and [`MindMap::cut_subtree`]. Pasting gives the
+/// nodes fresh ids, so one fragment can be pasted any number of times, into
+/// the map it came from or another one.
+#[derive(Debug, Clone, Default)]
+pub struct Subtree {
+    pub nodes: HashMap<String, Node>,
+    pub roots: Vec<String>,
+    /// The bytes of the nodes' attachments.
+    pub resources: ResourceStore,
+}
+
+impl Subtree {
+    /// Takes `roots` and their descendants out of `map`.
+    pub(crate) fn from_map(mut map: MindMap, roots: Vec<String>) -> Self {
+        let mut nodes = HashMap::new();
+        let mut stack = roots.clone();
+        while let Some(id) = stack.pop() {
+            if let Some(node) = map.nodes.remove(&id) {
+                stack.extend(node.children.iter().cloned());
+                nodes.insert(id, node);
+            }
+        }
+        for root in &roots {
+            if let Some(node) = nodes.get_mut(root) {
+                node.parent = None;
+            }
+        }
+        let resources = resources_of(&nodes, &map.resources);
+        Self {
+            nodes,
+            roots,
+            resources,
+        }
+    }
+
+    /// Copies `root` and its descendants out of `map`, leaving it untouched.
+    pub(crate) fn copy_of(map: &MindMap, root: &str) -> Self {
+        let mut nodes = HashMap::new();
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = map.nodes.get(id) {
+                stack.extend(node.children.iter().map(String::as_str));
+                nodes.insert(id.to_string(), node.clone());
+            }
+        }
+        if let Some(node) = nodes.get_mut(root) {
+            node.parent = None;
+        }
+        Self {
+            resources: resources_of(&nodes, &map.resources),
+            nodes,
+            roots: vec![root.to_string()],
+        }
+    }
+
+    /// Fails unless the nodes form trees under `roots`, keyed by their ids,
+    /// with parent and child links that agree. Fragments built by hand may
+    /// not.
+    fn check(&self) -> Result<(), BrainCoreError> {
+        let invalid = |message: String| Err(BrainCoreError::InvalidOperation(message));
+        let mut seen = HashSet::new();
+        let mut stack: Vec<(&String, Option<&String>)> =
+            self.roots.iter().map(|r| (r, None)).collect();
+        while let Some((id, parent)) = stack.pop() {
+            let Some(node) = self.nodes.get(id) else {
+                return invalid(format!("Subtree has no node {id}"));
+            };
+            if node.id != *id || node.parent.as_ref() != parent || !seen.insert(id) {
+                return invalid(format!("Subtree node {id} is linked inconsistently"));
+            }
+            stack.extend(node.children.iter().map(|c| (c, Some(id))));
+        }
+        if seen.len() != self.nodes.len() {
+            return invalid("Subtree has nodes outside its roots' trees".to_string());
+        }
+        Ok(())
+    }
+
+    fn depth(&self) -> usize {
+        let mut deepest = 0;
+        let mut stack: Vec<(&str, usize)> = self.roots.iter().map(|r| (r.as_str(), 1)).collect();
+        while let Some((id, depth)) = stack.pop() {
+            deepest = deepest.max(depth);
+            if let Some(node) = self.nodes.get(id) {
+                stack.extend(node.children.iter().map(|c| (c.as_str(), depth + 1)));
+            }
+        }
+        deepest
+    }
+}
+
+/// The entries of `store` that the attachments of `nodes` refer to.
+fn resources_of(nodes: &HashMap<String, Node>, store: &ResourceStore) -> ResourceStore {
+    let mut resources = ResourceStore::default();
+    for attachment in nodes.values().flat_map(|n| &n.attachments) {
+        if let Some(data) = store.get(&attachment.data_ref) {
+            resources.restore(attachment.data_ref.clone(), data.to_vec());
+        }
+    }
+    resources
+}
+
+/// Guesses the format of clipboard contents.
+pub fn detect(text: &str) -> PasteFormat {
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with('<') {
+        if text.contains("<opml") || text.contains("<outline") {
+            return PasteFormat::Opml;
+        }
+        return PasteFormat::FreeMind;
+    }
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty()).peekable();
+    if lines.peek().is_some() && lines.all(|l| list_item(l.trim_start()).is_some()) {
+        PasteFormat::Markdown
+    } else {
+        PasteFormat::Indented
+    }
+}
+
+/// Parses pasted text or bytes in whichever format [`detect`] recognizes.
+/// Text is sanitized as by the default [`ImportOptions`]; invalid UTF-8 is
+/// replaced rather than rejected.
+pub fn parse(data: impl AsRef<[u8]>) -> Result<Subtree, BrainCoreError> {
+    let text = String::from_utf8_lossy(data.as_ref());
+    let text = text.trim_start_matches('\u{feff}');
+    let options = ImportOptions::default();
+
+    let (mut map, roots) = match detect(text) {
+        PasteFormat::FreeMind if text.contains("<map") => {
+            let map = storage::from_xml_with(text, &ImportOptions::raw())?;
+            let root = map.root_id.clone();
+            (map, vec![root])
+        }
+        PasteFormat::FreeMind => {
+            let xml = format!("<map version=\"1.0.1\"><node>{text}</node></map>");
+            let map = storage::from_xml_with(&xml, &ImportOptions::raw())?;
+            let roots = map.nodes[&map.root_id].children.clone();
+            (map, roots)
+        }
+        PasteFormat::Opml => {
+            let opml: Opml = if text.contains("<opml") {
+                from_str(text)
+            } else {
+                from_str(&format!(
+                    "<opml version=\"2.0\"><head><title/></head><body>{text}</body></opml>"
+                ))
+            }
+            .map_err(|e| BrainCoreError::parse(ImportFormat::Opml, e))?;
+            let mut nodes = HashMap::new();
+            let roots = opml
+                .body
+                .outlines
+                .iter()
+                .map(|outline| {
+                    opml::outline_to_node(outline, None, &mut nodes, &mut Monitor::default())
+                })
+                .collect();
+            (container(nodes), roots)
+        }
+        PasteFormat::Markdown => outline_from_lines(text, true),
+        PasteFormat::Indented => outline_from_lines(text, false),
+    };
+
+    options.apply(&mut map)?;
+    Ok(Subtree::from_map(map, roots))
+}
+
+/// Wraps loose nodes in a map so they can go through [`ImportOptions`].
+fn container(mut nodes: HashMap<String, Node>) -> MindMap {
+    let id = Uuid::new_v4().to_string();
+    nodes.insert(
+        id.clone(),
+        Node {
+            id: id.clone(),
+            ..Default::default()
+        },
+    );
+    MindMap::from_nodes(nodes, id)
+}
+
+fn outline_from_lines(text: &str, markdown: bool) -> (MindMap, Vec<String>) {
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    let mut roots = Vec::new();
+    // Open ancestors with their indentation width.
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    let now = now_millis();
+
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let body = line.trim_start();
+        let indent: usize = line[..line.len() - body.len()]
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        let content = if markdown {
+            list_item(body).unwrap_or(body)
+        } else {
+            body
+        };
+
+        while stack.last().is_some_and(|(width, _)| *width >= indent) {
+            stack.pop();
+        }
+        let id = Uuid::new_v4().to_string();
+        let parent = stack.last().map(|(_, id)| id.clone());
+        match &parent {
+            Some(parent) => nodes.get_mut(parent).unwrap().children.push(id.clone()),
+            None => roots.push(id.clone()),
+        }
+        nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content: content.trim_end().to_string(),
+                parent,
+                created: now,
+                modified: now,
+                ..Default::default()
+            },
+        );
+        stack.push((indent, id));
+    }
+    (container(nodes), roots)
+}
+
+/// The text of a Markdown list item, or `None` if `line` is not one.
+pub(crate) fn list_item(line: &str) -> Option<&str> {
+    if let Some(rest) = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))
+    {
+        return Some(rest);
+    }
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    let rest = &line[digits..];
+    (digits > 0)
+        .then(|| rest.strip_prefix(". ").or_else(|| rest.strip_prefix(") ")))
+        .flatten()
+}
+
+impl MindMap {
+    /// Copies a node and its descendants into a fragment for
+    /// [`MindMap::paste_subtree`]. The map is left unchanged.
+    pub fn copy_subtree(&self, node_id: &str) -> Result<Subtree, BrainCoreError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(BrainCoreError::NodeNotFound(node_id.to_string()));
+        }
+        Ok(Subtree::copy_of(self, node_id))
+    }
+
+    /// Removes a node and its descendants from the map and returns them as a
+    /// fragment for [`MindMap::paste_subtree`]. Relationships, groups and
+    /// summaries touching the removed nodes are dropped. If the selection was
+    /// inside the subtree, the parent becomes selected.
+    pub fn cut_subtree(&mut self, node_id: &str) -> Result<Subtree, BrainCoreError> {
+        if node_id == self.root_id {
+            return Err(BrainCoreError::RootImmutable);
+        }
+        let parent_id = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?
+            .parent
+            .clone()
+            .ok_or_else(|| BrainCoreError::InvalidOperation("Node has no parent".to_string()))?;
+
+        let subtree = Subtree::copy_of(self, node_id);
+        for id in subtree.nodes.keys() {
+            self.nodes.remove(id);
+        }
+        self.drop_references(&subtree.nodes.keys().cloned().collect());
+        if let Some(parent) = self.nodes.get_mut(&parent_id) {
+            parent.children.retain(|c| c != node_id);
+            parent.modified = now_millis();
+            parent.modified_by = self.author.clone();
+        }
+        if !self.nodes.contains_key(&self.selection.primary) {
+            self.set_selected(parent_id.clone());
+        }
+        self.prune_selection();
+        self.bump_revision(&parent_id);
+        self.emit(MapEvent::NodeRemoved {
+            node_id: node_id.to_string(),
+        });
+        Ok(subtree)
+    }
+
+    /// Attaches a parsed subtree under `parent_id` with fresh ids, after the
+    /// parent's existing children, and returns the new ids of its roots.
+    /// Attached files come along. Content goes through the validators and
+    /// the whole subtree must fit the map's limits. A fragment whose links
+    /// don't form trees under its roots is refused with
+    /// [`BrainCoreError::InvalidOperation`].
+    pub fn paste_subtree(
+        &mut self,
+        parent_id: &str,
+        subtree: Subtree,
+    ) -> Result<Vec<String>, BrainCoreError> {
+        self.graft(parent_id, subtree, false)
+            .map(|(roots, _)| roots)
+    }
+
+    /// Does the work of [`MindMap::paste_subtree`] and also returns the
+    /// old→new id mapping. With `keep_ids`, nodes keep their ids unless they
+    /// collide with one already in the map.
+    pub(crate) fn graft(
+        &mut self,
+        parent_id: &str,
+        subtree: Subtree,
+        keep_ids: bool,
+    ) -> Result<(Vec<String>, HashMap<String, String>), BrainCoreError> {
+        if !self.nodes.contains_key(parent_id) {
+            return Err(BrainCoreError::NodeNotFound(parent_id.to_string()));
+        }
+        subtree.check()?;
+        self.limits
+            .check_node_count(self.nodes.len() + subtree.nodes.len())
+            .and_then(|_| {
+                self.limits
+                    .check_depth(self.depth(parent_id) + subtree.depth())
+            })?;
+
+        let ids: HashMap<&String, String> = subtree
+            .nodes
+            .keys()
+            .map(|id| {
+                let new_id = if keep_ids && !self.nodes.contains_key(id) {
+                    id.clone()
+                } else {
+                    Uuid::new_v4().to_string()
+                };
+                (id, new_id)
+            })
+            .collect();
+        let mut pasted = Vec::new();
+        for node in subtree.nodes.values() {
+            let parent = match &node.parent {
+                Some(parent) => ids[parent].clone(),
+                None => parent_id.to_string(),
+            };
+            let content = self.validate_content(
+                &Change {
+                    map: self,
+                    node_id: None,
+                    parent_id: Some(&parent),
+                },
+                &node.content,
+            )?;
+            self.limits.check_content(&content)?;
+            pasted.push(Node {
+                id: ids[&node.id].clone(),
+                content,
+                children: node.children.iter().map(|c| ids[c].clone()).collect(),
+                parent: Some(parent),
+                created_by: node.created_by.clone().or_else(|| self.author.clone()),
+                modified_by: node.modified_by.clone().or_else(|| self.author.clone()),
+                revision: 0,
+                ..node.clone()
+            });
+        }
+
+        let renamed = self.resources.extend_from(&subtree.resources);
+        for mut node in pasted {
+            for attachment in &mut node.attachments {
+                if let Some(data_ref) = renamed.get(&attachment.data_ref) {
+                    attachment.data_ref = data_ref.clone();
+                }
+            }
+            self.nodes.insert(node.id.clone(), node);
+        }
+        let roots: Vec<String> = subtree.roots.iter().map(|r| ids[r].clone()).collect();
+        if let Some(parent) = self.nodes.get_mut(parent_id) {
+            parent.children.extend(roots.iter().cloned());
+        }
+        self.bump_revision(parent_id);
+        for root in &roots {
+            self.emit(MapEvent::NodeAdded {
+                node_id: root.clone(),
+                parent_id: Some(parent_id.to_string()),
+            });
+        }
+        let ids = ids
+            .into_iter()
+            .map(|(old, new)| (old.clone(), new))
+            .collect();
+        Ok((roots, ids))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(map: &MindMap, ids: &[String]) -> Vec<String> {
+        ids.iter().map(|id| map.nodes[id].content.clone()).collect()
+    }
+
+    #[test]
+    fn test_parse_detects_formats_and_pastes() {
+        let samples = [
+            (
+                "<node TEXT=\"Plan\"><node TEXT=\"Design\"/></node><node TEXT=\"Ship\"/>",
+                PasteFormat::FreeMind,
+            ),
+            (
+                "<outline text=\"Plan\"><outline text=\"Design\"/></outline>\n\
+                 <outline text=\"Ship\"/>",
+                PasteFormat::Opml,
+            ),
+            ("- Plan\n  - Design\n\n1. Ship\n", PasteFormat::Markdown),
+            ("Plan\n\tDesign\nShip", PasteFormat::Indented),
+        ];
+
+        for (text, format) in samples {
+            assert_eq!(detect(text), format);
+            let mut map = MindMap::new();
+            let root_id = map.root_id.clone();
+            let roots = map.paste_subtree(&root_id, parse(text).unwrap()).unwrap();
+            assert_eq!(titles(&map, &roots), ["Plan", "Ship"], "{format:?}");
+            let plan = &map.nodes[&roots[0]];
+            assert_eq!(plan.parent.as_deref(), Some(root_id.as_str()));
+            assert_eq!(titles(&map, &plan.children), ["Design"], "{format:?}");
+            assert_eq!(map.nodes[&root_id].children, roots);
+        }
+
+        // Pasting the same FreeMind fragment twice must not collide on ids.
+        let full = "<map version=\"1.0.1\"><node ID=\"a\" TEXT=\"Root\">\
+                    <node ID=\"b\" TEXT=\"Leaf\"/></node></map>";
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.paste_subtree(&root_id, parse(full).unwrap()).unwrap();
+        map.paste_subtree(&root_id, parse(full.as_bytes()).unwrap())
+            .unwrap();
+        assert_eq!(map.nodes.len(), 5);
+    }
+
+    #[test]
+    fn test_cut_and_paste_between_maps() {
+        let mut source = MindMap::new();
+        let source_root = source.root_id.clone();
+        let a = source.add_child(&source_root, "A").unwrap();
+        let a1 = source.add_child(&a, "A1").unwrap();
+        let b = source.add_child(&source_root, "B").unwrap();
+        source.add_relationship(&a1, &b, None).unwrap();
+        source.add_group(&a, None).unwrap();
+        source.add_summary(&a, 0, 0, "Sum").unwrap();
+        let data_ref = source.attach(&a1, "photo.png", b"png".to_vec()).unwrap();
+        source.selection.primary = a1.clone();
+
+        let copied = source.copy_subtree(&a).unwrap();
+        assert_eq!(copied.nodes.len(), 2);
+        assert_eq!(source.nodes.len(), 4);
+
+        let fragment = source.cut_subtree(&a).unwrap();
+        assert_eq!(source.nodes.len(), 2);
+        assert_eq!(source.nodes[&source_root].children, vec![b.clone()]);
+        assert_eq!(source.selection.primary, source_root);
+        assert!(source.relationships.is_empty());
+        assert!(source.groups.is_empty());
+        assert!(source.summaries.is_empty());
+        assert!(matches!(
+            source.cut_subtree(&source_root),
+            Err(BrainCoreError::RootImmutable)
+        ));
+
+        let mut target = MindMap::new();
+        let target_root = target.root_id.clone();
+        // A different file already stored under the same reference.
+        target.resources.restore(data_ref.clone(), b"gif".to_vec());
+        let first = target
+            .paste_subtree(&target_root, fragment.clone())
+            .unwrap();
+        let second = target.paste_subtree(&target_root, fragment).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(target.nodes.len(), 5);
+        assert_eq!(titles(&target, &first), ["A"]);
+        assert_eq!(titles(&target, &target.nodes[&first[0]].children), ["A1"]);
+        assert!(!target.nodes.contains_key(&a));
+        for root in first.iter().chain(&second) {
+            let leaf = &target.nodes[&target.nodes[root].children[0]];
+            let attachment = &leaf.attachments[0];
+            assert_ne!(attachment.data_ref, data_ref);
+            assert_eq!(target.attachment_data(attachment), Some(&b"png"[..]));
+        }
+        assert_eq!(target.resources.get(&data_ref), Some(&b"gif"[..]));
+
+        // Hand-built fragments with dangling links are refused, not pasted.
+        let mut dangling = copied.clone();
+        dangling
+            .nodes
+            .get_mut(&a)
+            .unwrap()
+            .children
+            .push("gone".to_string());
+        let mut orphaned = copied.clone();
+        orphaned.nodes.get_mut(&a1).unwrap().parent = Some("gone".to_string());
+        let mut unrooted = copied;
+        unrooted.roots = vec!["gone".to_string()];
+        for fragment in [dangling, orphaned, unrooted] {
+            assert!(matches!(
+                target.paste_subtree(&target_root, fragment),
+                Err(BrainCoreError::InvalidOperation(_))
+            ));
+        }
+        assert_eq!(target.nodes.len(), 5);
+    }
+}
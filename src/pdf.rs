@@ -0,0 +1,351 @@
+use crate::layout::{HeuristicMeasurer, TextMeasurer, font_size};
+use crate::svg::{bounds, connector_curve, connector_sides, drawn_nodes};
+use crate::{MindMap, Node};
+use std::fmt::Write;
+
+// Single-page vector PDFs of the laid-out map, drawn like the SVG export:
+// rounded boxes, bezier connectors and centred text. Text is set in the
+// standard Helvetica faces, so no fonts are embedded; characters outside
+// their WinAnsi encoding print as `?`.
+
+/// Paper sizes in PostScript points, portrait.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A3,
+    Letter,
+    Custom {
+        width: f32,
+        height: f32,
+    },
+}
+
+impl PageSize {
+    fn points(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.0, 842.0),
+            PageSize::A3 => (842.0, 1191.0),
+            PageSize::Letter => (612.0, 792.0),
+            PageSize::Custom { width, height } => (width, height),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PdfScale {
+    /// As large as fits inside the margins, up or down.
+    Fit,
+    /// Points per layout pixel. The map is centred and may run off the page.
+    Factor(f32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    pub landscape: bool,
+    /// Space kept clear around the map, in points.
+    pub margin: f32,
+    pub scale: PdfScale,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            page_size: PageSize::A4,
+            landscape: true,
+            margin: 36.0,
+            scale: PdfScale::Fit,
+        }
+    }
+}
+
+/// Helvetica faces by bold and italic, as resource names and base fonts.
+const FONTS: [(&str, &str); 4] = [
+    ("F1", "Helvetica"),
+    ("F2", "Helvetica-Bold"),
+    ("F3", "Helvetica-Oblique"),
+    ("F4", "Helvetica-BoldOblique"),
+];
+
+/// How far the control points of a quarter-circle bezier sit along its
+/// tangents, relative to the radius.
+const KAPPA: f32 = 0.552_284_8;
+
+pub fn to_pdf(map: &MindMap) -> Vec<u8> {
+    to_pdf_with(map, &PdfOptions::default())
+}
+
+/// Draws the visible nodes at the positions stored on them, as
+/// [`crate::svg::to_svg`] does, onto one page. Run
+/// [`MindMap::compute_layout`] first.
+pub fn to_pdf_with(map: &MindMap, options: &PdfOptions) -> Vec<u8> {
+    to_pdf_measured(map, options, &HeuristicMeasurer)
+}
+
+/// Draws the map as [`to_pdf_with`] does, sizing boxes and placing text
+/// with `measurer`. Pass the one the map was laid out with.
+pub fn to_pdf_measured(
+    map: &MindMap,
+    options: &PdfOptions,
+    measurer: &dyn TextMeasurer,
+) -> Vec<u8> {
+    let (mut page_width, mut page_height) = options.page_size.points();
+    if options.landscape != (page_width > page_height) {
+        (page_width, page_height) = (page_height, page_width);
+    }
+    let nodes = drawn_nodes(map);
+    let (left, top, right, bottom) = bounds(map, &nodes, measurer);
+    let (width, height) = ((right - left).max(1.0), (bottom - top).max(1.0));
+    let scale = match options.scale {
+        PdfScale::Fit => ((page_width - 2.0 * options.margin) / width)
+            .min((page_height - 2.0 * options.margin) / height)
+            .max(f32::MIN_POSITIVE),
+        PdfScale::Factor(factor) => factor,
+    };
+
+    // Layout coordinates grow downwards; flip them and centre the map.
+    let mut content = format!(
+        "{} 0 0 {} {} {} cm\n",
+        number(scale),
+        number(-scale),
+        number((page_width - width * scale) / 2.0 - left * scale),
+        number((page_height + height * scale) / 2.0 + top * scale)
+    );
+    let size = |node: &Node| map.node_size(node, measurer);
+    for node in &nodes {
+        let children = map.laid_out_children(node);
+        let sides = connector_sides(node, &children, size);
+        for (child, sides) in children.into_iter().zip(sides) {
+            let [(x1, y1), (cx1, cy1), (cx2, cy2), (x2, y2)] =
+                connector_curve(node, child, sides, size);
+            let color = child.style.edge_color.as_deref().and_then(rgb);
+            let _ = writeln!(
+                content,
+                "{} RG {} w {} {} m {} {} {} {} {} {} c S",
+                color_operands(color.unwrap_or((0.533, 0.533, 0.533))),
+                child.style.edge_width.unwrap_or(1),
+                number(x1),
+                number(y1),
+                number(cx1),
+                number(cy1),
+                number(cx2),
+                number(cy2),
+                number(x2),
+                number(y2)
+            );
+        }
+    }
+
+    for node in &nodes {
+        let style = &node.style;
+        let (width, height) = size(node);
+        let fill = style.background_color.as_deref().and_then(rgb);
+        let _ = writeln!(
+            content,
+            "{} rg 0.333 0.333 0.333 RG 1 w",
+            color_operands(fill.unwrap_or((1.0, 1.0, 1.0)))
+        );
+        rounded_rect(&mut content, node.x, node.y, width, height, 8.0);
+        content.push_str("B\n");
+
+        let font_size = font_size(node);
+        let font = FONTS[usize::from(style.bold) + 2 * usize::from(style.italic)].0;
+        let text_color = style.text_color.as_deref().and_then(rgb);
+        let lines = map.node_lines(node, measurer);
+        // One line height apart, centred as a block on the box.
+        let line_height = measurer.measure("", font_size).1;
+        let first = node.y + height / 2.0 - line_height * (lines.len() as f32 - 1.0) / 2.0;
+        for (i, line) in lines.iter().enumerate() {
+            let line_width = measurer.measure(line, font_size).0;
+            // The baseline sits about a third of the font size below the
+            // line's centre.
+            let _ = writeln!(
+                content,
+                "BT /{font} {} Tf {} rg 1 0 0 -1 {} {} Tm ({}) Tj ET",
+                number(font_size),
+                color_operands(text_color.unwrap_or((0.0, 0.0, 0.0))),
+                number(node.x + (width - line_width) / 2.0),
+                number(first + line_height * i as f32 + font_size * 0.35),
+                pdf_string(line)
+            );
+        }
+    }
+
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_string(),
+    ];
+    let fonts: String = FONTS
+        .iter()
+        .enumerate()
+        .map(|(i, (name, _))| format!("/{name} {} 0 R ", i + 5))
+        .collect();
+    objects.push(format!(
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << {fonts}>> >> /Contents 4 0 R >>",
+        number(page_width),
+        number(page_height)
+    ));
+    objects.push(format!(
+        "<< /Length {} >>\nstream\n{content}endstream",
+        content.len()
+    ));
+    for (_, base) in FONTS {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{base} /Encoding /WinAnsiEncoding >>"
+        ));
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n{object}\nendobj\n", i + 1).as_bytes());
+    }
+    let xref = pdf.len();
+    let mut trailer = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(trailer, "{offset:010} 00000 n ");
+    }
+    let _ = write!(
+        trailer,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n",
+        objects.len() + 1
+    );
+    pdf.extend_from_slice(trailer.as_bytes());
+    pdf
+}
+
+/// Up to two decimals, without trailing zeros.
+fn number(value: f32) -> String {
+    let text = format!("{value:.2}");
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" | "" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+fn color_operands((r, g, b): (f32, f32, f32)) -> String {
+    format!("{} {} {}", number(r), number(g), number(b))
+}
+
+/// Components of a `#rrggbb` colour, from 0 to 1.
+fn rgb(color: &str) -> Option<(f32, f32, f32)> {
+    let hex = color.strip_prefix('#').filter(|h| h.len() == 6)?;
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|c| f32::from(c) / 255.0)
+    };
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Appends the path of a rectangle with corners rounded by `radius`.
+fn rounded_rect(out: &mut String, x: f32, y: f32, width: f32, height: f32, radius: f32) {
+    let r = radius.min(width / 2.0).min(height / 2.0);
+    let k = r * KAPPA;
+    let (right, bottom) = (x + width, y + height);
+    let _ = write!(
+        out,
+        "{} {} m {} {} l {} {} {} {} {} {} c {} {} l {} {} {} {} {} {} c {} {} l {} {} {} {} {} {} c {} {} l {} {} {} {} {} {} c h ",
+        number(x + r),
+        number(y),
+        number(right - r),
+        number(y),
+        number(right - r + k),
+        number(y),
+        number(right),
+        number(y + r - k),
+        number(right),
+        number(y + r),
+        number(right),
+        number(bottom - r),
+        number(right),
+        number(bottom - r + k),
+        number(right - r + k),
+        number(bottom),
+        number(right - r),
+        number(bottom),
+        number(x + r),
+        number(bottom),
+        number(x + r - k),
+        number(bottom),
+        number(x),
+        number(bottom - r + k),
+        number(x),
+        number(bottom - r),
+        number(x),
+        number(y + r),
+        number(x),
+        number(y + r - k),
+        number(x + r - k),
+        number(y),
+        number(x + r),
+        number(y)
+    );
+}
+
+/// The text as a literal string in WinAnsi, which matches Latin-1 for the
+/// characters used here.
+fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_pdf() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "R&D (café)").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        map.update_style(&a, |s| {
+            s.background_color = Some("#ffcc00".to_string());
+            s.bold = true;
+        })
+        .unwrap();
+        map.compute_layout();
+
+        let pdf = to_pdf(&map);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.contains("/MediaBox [0 0 842 595]"));
+        assert!(text.contains("(R&D \\(caf\\351\\)) Tj"));
+        assert!(text.contains("BT /F2 14 Tf 0 0 0 rg"));
+        assert!(text.contains("1 0.8 0 rg"));
+        let startxref: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|tail| tail.lines().next()?.parse().ok())
+            .unwrap();
+        assert!(pdf[startxref..].starts_with(b"xref\n0 9\n"));
+        assert!(text.contains("\n4 0 obj\n<< /Length "));
+
+        let options = PdfOptions {
+            page_size: PageSize::Letter,
+            landscape: false,
+            scale: PdfScale::Factor(1.0),
+            ..Default::default()
+        };
+        let text = String::from_utf8_lossy(&to_pdf_with(&map, &options)).into_owned();
+        assert!(text.contains("/MediaBox [0 0 612 792]"));
+        assert!(text.contains("1 0 0 -1 "));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::export::ExportOptions;
+use crate::formats::{self, Format};
+use crate::import::ImportOptions;
+use crate::{BrainCoreError, MindMap, storage};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+fn unsupported(path: &Path) -> BrainCoreError {
+    BrainCoreError::InvalidOperation(format!("Unsupported file type: {}", path.display()))
+}
+
+/// Writes the map in the format given by the file extension. Native `.mm`
+/// files keep archived nodes; other formats leave them out.
+pub fn save_to_path(map: &MindMap, path: impl AsRef<Path>) -> Result<(), BrainCoreError> {
+    let path = path.as_ref();
+    save_to_path_with(map, path, &save_options(path))
+}
+
+fn save_options(path: &Path) -> ExportOptions {
+    if Format::from_path(path) == Some(Format::FreeMind) {
+        storage::native_options()
+    } else {
+        ExportOptions::default()
+    }
+}
+
+/// Writes the map in the format given by the file extension, which must be
+/// one of the formats [`Format::can_export`] allows. The document
+/// goes to a temporary file next to `path` that then replaces it, so a
+/// crash mid-save leaves either the old file or the new one, never a mix.
+pub fn save_to_path_with(
+    map: &MindMap,
+    path: impl AsRef<Path>,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    let path = path.as_ref();
+    let format = Format::from_path(path)
+        .filter(|f| f.can_export())
+        .ok_or_else(|| unsupported(path))?;
+    let data = formats::export_with(map, format, options)?;
+    write_atomically(path, &data)
+}
+
+pub fn load_from_path(path: impl AsRef<Path>) -> Result<MindMap, BrainCoreError> {
+    load_from_path_with(path, &ImportOptions::default())
+}
+
+/// Reads a map in the format given by the file extension, or detected
+/// from the contents when the extension doesn't tell.
+pub fn load_from_path_with(
+    path: impl AsRef<Path>,
+    options: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    let path = path.as_ref();
+    let data = fs::read(path)?;
+    let format = Format::from_path(path)
+        .or_else(|| formats::detect_format(&data))
+        .ok_or_else(|| unsupported(path))?;
+    format.import(&data, options)
+}
+
+fn write_atomically(path: &Path, data: &[u8]) -> Result<(), BrainCoreError> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("map");
+    let temp = path.with_file_name(format!(".{name}.{}.tmp", Uuid::new_v4().simple()));
+    let written = File::create(&temp).and_then(|mut file| {
+        file.write_all(data)?;
+        file.sync_all()
+    });
+    match written.and_then(|_| fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(error) => {
+            let _ = fs::remove_file(&temp);
+            Err(error.into())
+        }
+    }
+}
+
+/// Saves a map to a file once edits have settled. Call [`Autosaver::poll`]
+/// after mutations or from a periodic timer: it notices changes through the
+/// map's revision and saves only when no further change has happened for
+/// the debounce delay, so a burst of edits costs a single write.
+#[derive(Debug, Clone)]
+pub struct Autosaver {
+    path: PathBuf,
+    delay: Duration,
+    options: ExportOptions,
+    saved_revision: u64,
+    seen_revision: u64,
+    changed_at: Option<Instant>,
+}
+
+impl Autosaver {
+    /// Starts tracking `map`, treating its current state as already saved.
+    /// Saves use the options [`save_to_path`] would.
+    pub fn new(map: &MindMap, path: impl Into<PathBuf>, delay: Duration) -> Self {
+        let path = path.into();
+        Self {
+            options: save_options(&path),
+            path,
+            delay,
+            saved_revision: map.revision,
+            seen_revision: map.revision,
+            changed_at: None,
+        }
+    }
+
+    pub fn with_options(mut self, options: ExportOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the map has changes that haven't been saved yet.
+    pub fn is_dirty(&self, map: &MindMap) -> bool {
+        map.revision != self.saved_revision
+    }
+
+    /// Saves if the map changed and then stayed unchanged for the delay.
+    /// Returns whether it wrote the file.
+    pub fn poll(&mut self, map: &MindMap) -> Result<bool, BrainCoreError> {
+        self.poll_at(map, Instant::now())
+    }
+
+    /// Saves now if there are unsaved changes, e.g. before closing.
+    pub fn flush(&mut self, map: &MindMap) -> Result<bool, BrainCoreError> {
+        if !self.is_dirty(map) {
+            return Ok(false);
+        }
+        self.save(map)?;
+        Ok(true)
+    }
+
+    fn poll_at(&mut self, map: &MindMap, now: Instant) -> Result<bool, BrainCoreError> {
+        if map.revision != self.seen_revision {
+            self.seen_revision = map.revision;
+            self.changed_at = Some(now);
+        }
+        let settled = self
+            .changed_at
+            .is_some_and(|changed| now.duration_since(changed) >= self.delay);
+        if !settled || !self.is_dirty(map) {
+            return Ok(false);
+        }
+        self.save(map)?;
+        Ok(true)
+    }
+
+    fn save(&mut self, map: &MindMap) -> Result<(), BrainCoreError> {
+        save_to_path_with(map, &self.path, &self.options)?;
+        self.saved_revision = map.revision;
+        self.seen_revision = map.revision;
+        self.changed_at = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load_by_extension() {
+        let dir = std::env::temp_dir().join(format!("brain_core-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.add_child(&root_id, "Child").unwrap();
+
+        for extension in ["mm", "opml", "xmind", "mindnode", "MMAP", "smmx", "md"] {
+            let path = dir.join(format!("map.{extension}"));
+            save_to_path(&map, &path).unwrap();
+            save_to_path(&map, &path).unwrap();
+            let loaded = load_from_path(&path).unwrap();
+            assert_eq!(loaded.nodes.len(), 2, "{extension}");
+        }
+        assert!(save_to_path(&map, dir.join("map.txt")).is_err());
+        assert!(save_to_path(&map, dir.join("map.enex")).is_err());
+        let files = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(files, 7);
+
+        // Archived work survives a native save but not an export.
+        let archived = map.add_child(&root_id, "Done").unwrap();
+        map.archive(&archived).unwrap();
+        save_to_path(&map, dir.join("map.mm")).unwrap();
+        let loaded = load_from_path(dir.join("map.mm")).unwrap();
+        assert!(loaded.nodes[&archived].archived);
+        save_to_path(&map, dir.join("map.opml")).unwrap();
+        assert_eq!(load_from_path(dir.join("map.opml")).unwrap().nodes.len(), 2);
+
+        // Unknown extensions fall back to sniffing the contents.
+        fs::copy(dir.join("map.mm"), dir.join("backup.bak")).unwrap();
+        assert_eq!(
+            load_from_path(dir.join("backup.bak")).unwrap().nodes.len(),
+            3
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_autosaver_debounces() {
+        let dir = std::env::temp_dir().join(format!("brain_core-{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("auto.mm");
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let delay = Duration::from_secs(2);
+        let mut saver = Autosaver::new(&map, &path, delay);
+        let start = Instant::now();
+
+        assert!(!saver.poll_at(&map, start).unwrap());
+        map.add_child(&root_id, "A").unwrap();
+        assert!(!saver.poll_at(&map, start).unwrap());
+        map.add_child(&root_id, "B").unwrap();
+        assert!(!saver.poll_at(&map, start + Duration::from_secs(1)).unwrap());
+        assert!(!saver.poll_at(&map, start + Duration::from_secs(2)).unwrap());
+        assert!(!path.exists());
+        assert!(saver.poll_at(&map, start + Duration::from_secs(3)).unwrap());
+        assert_eq!(load_from_path(&path).unwrap().nodes.len(), 3);
+        assert!(!saver.poll_at(&map, start + Duration::from_secs(9)).unwrap());
+
+        map.add_child(&root_id, "C").unwrap();
+        assert!(saver.is_dirty(&map));
+        assert!(saver.flush(&map).unwrap());
+        assert!(!saver.flush(&map).unwrap());
+        assert_eq!(load_from_path(&path).unwrap().nodes.len(), 4);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
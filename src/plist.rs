@@ -0,0 +1,137 @@
+//! Apple's XML property lists, read into and written from JSON values so
+//! importers can treat plist and JSON documents alike.
+
+use crate::html;
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use serde_json::{Map, Number, Value};
+
+enum Frame {
+    Dict(Map<String, Value>, Option<String>),
+    Array(Vec<Value>),
+}
+
+/// Parses the document's top-level value. `<data>` is kept as its base64
+/// text and `<date>` as its ISO 8601 text.
+pub(crate) fn from_xml(xml: &str) -> Result<Value, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut text = String::new();
+    let mut top = None;
+    loop {
+        let event = reader.read_event().map_err(|e| e.to_string())?;
+        let value = match event {
+            Event::Start(e) => {
+                match e.local_name().as_ref() {
+                    b"dict" => stack.push(Frame::Dict(Map::new(), None)),
+                    b"array" => stack.push(Frame::Array(Vec::new())),
+                    _ => text.clear(),
+                }
+                continue;
+            }
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"dict" => Value::Object(Map::new()),
+                b"array" => Value::Array(Vec::new()),
+                b"true" => Value::Bool(true),
+                b"false" => Value::Bool(false),
+                b"string" | b"data" | b"date" => Value::String(String::new()),
+                _ => continue,
+            },
+            Event::Text(e) => {
+                text.push_str(&e.decode().map_err(|e| e.to_string())?);
+                continue;
+            }
+            Event::CData(e) => {
+                text.push_str(&e.decode().map_err(|e| e.to_string())?);
+                continue;
+            }
+            Event::GeneralRef(e) => {
+                let name = e.decode().map_err(|e| e.to_string())?;
+                text.extend(html::entity(&name));
+                continue;
+            }
+            Event::End(e) => match e.local_name().as_ref() {
+                b"dict" => match stack.pop() {
+                    Some(Frame::Dict(map, _)) => Value::Object(map),
+                    _ => return Err("Unbalanced <dict>".to_string()),
+                },
+                b"array" => match stack.pop() {
+                    Some(Frame::Array(items)) => Value::Array(items),
+                    _ => return Err("Unbalanced <array>".to_string()),
+                },
+                b"key" => {
+                    if let Some(Frame::Dict(_, key)) = stack.last_mut() {
+                        *key = Some(std::mem::take(&mut text));
+                    }
+                    continue;
+                }
+                b"string" | b"date" => Value::String(std::mem::take(&mut text)),
+                b"data" => Value::String(text.split_whitespace().collect()),
+                b"integer" => Value::Number(
+                    text.trim()
+                        .parse::<i64>()
+                        .map_err(|e| e.to_string())?
+                        .into(),
+                ),
+                b"real" => {
+                    let real: f64 = text
+                        .trim()
+                        .parse()
+                        .map_err(|e: std::num::ParseFloatError| e.to_string())?;
+                    Number::from_f64(real).map_or(Value::Null, Value::Number)
+                }
+                _ => continue,
+            },
+            Event::Eof => break,
+            _ => continue,
+        };
+        match stack.last_mut() {
+            Some(Frame::Dict(map, key)) => {
+                if let Some(key) = key.take() {
+                    map.insert(key, value);
+                }
+            }
+            Some(Frame::Array(items)) => items.push(value),
+            None => top = top.or(Some(value)),
+        }
+    }
+    top.ok_or_else(|| "Property list is empty".to_string())
+}
+
+/// Writes `value` as a property list document. Nulls have no plist form
+/// and are left out.
+pub(crate) fn to_xml(value: &Value) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n<plist version=\"1.0\">",
+    );
+    write_value(value, &mut xml);
+    xml.push_str("</plist>\n");
+    xml
+}
+
+fn write_value(value: &Value, xml: &mut String) {
+    match value {
+        Value::Null => {}
+        Value::Bool(true) => xml.push_str("<true/>"),
+        Value::Bool(false) => xml.push_str("<false/>"),
+        Value::Number(n) if n.is_f64() => xml.push_str(&format!("<real>{n}</real>")),
+        Value::Number(n) => xml.push_str(&format!("<integer>{n}</integer>")),
+        Value::String(s) => xml.push_str(&format!("<string>{}</string>", escape(s.as_str()))),
+        Value::Array(items) => {
+            xml.push_str("<array>");
+            for item in items {
+                write_value(item, xml);
+            }
+            xml.push_str("</array>");
+        }
+        Value::Object(map) => {
+            xml.push_str("<dict>");
+            for (key, value) in map.iter().filter(|(_, v)| !v.is_null()) {
+                xml.push_str(&format!("<key>{}</key>", escape(key.as_str())));
+                write_value(value, xml);
+            }
+            xml.push_str("</dict>");
+        }
+    }
+}
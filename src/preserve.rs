@@ -0,0 +1,108 @@
+use crate::Node;
+use crate::import::ImportFormat;
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Attributes of an imported element that the crate doesn't understand,
+/// kept when [`ImportOptions::preserve_unknown`] is set. Only the exporter
+/// of the format they came from writes them back, as other formats would
+/// read them differently or not at all.
+///
+/// [`ImportOptions::preserve_unknown`]: crate::import::ImportOptions::preserve_unknown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnknownAttributes {
+    pub format: ImportFormat,
+    /// Names and values; FreeMind's are sorted by name.
+    pub attributes: Vec<(String, String)>,
+}
+
+impl UnknownAttributes {
+    /// Keeps `attributes` for `format`, or nothing when there are none.
+    pub(crate) fn new(
+        format: ImportFormat,
+        attributes: Vec<(String, String)>,
+    ) -> Option<UnknownAttributes> {
+        (!attributes.is_empty()).then_some(UnknownAttributes { format, attributes })
+    }
+}
+
+/// Unknown attributes to write for an element, serialized as `@name` map
+/// keys so they can be flattened into a quick-xml element.
+#[derive(Debug, Default, PartialEq)]
+pub struct XmlAttributes(pub Vec<(String, String)>);
+
+impl XmlAttributes {
+    /// The node's unknown attributes if they came from `format`.
+    pub(crate) fn of(node: &Node, format: ImportFormat) -> XmlAttributes {
+        XmlAttributes(
+            node.unknown_attributes
+                .as_ref()
+                .filter(|u| u.format == format)
+                .map(|u| u.attributes.clone())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+impl Serialize for XmlAttributes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, value) in &self.0 {
+            map.serialize_entry(&format!("@{name}"), value)?;
+        }
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::ImportOptions;
+    use crate::{opml, storage};
+
+    #[test]
+    fn test_preserve_unknown_attributes() {
+        let options = ImportOptions {
+            preserve_unknown: true,
+            ..ImportOptions::default()
+        };
+        let xml = r#"<map version="1.0.1"><node ID="root" TEXT="Root" STYLE="bubble"><node ID="a" TEXT="A" POSITION="left" VGAP="7"/></node></map>"#;
+        let map = storage::from_xml_with(xml, &options).unwrap();
+        assert_eq!(
+            map.nodes["a"]
+                .unknown_attributes
+                .as_ref()
+                .unwrap()
+                .attributes,
+            vec![("VGAP".to_string(), "7".to_string())]
+        );
+        let saved = storage::to_xml(&map).unwrap();
+        assert!(saved.contains(r#"STYLE="bubble""#));
+        assert!(saved.contains(r#"VGAP="7""#));
+        assert!(!opml::to_opml(&map).unwrap().contains("VGAP"));
+        assert!(
+            storage::from_xml(xml).unwrap().nodes["a"]
+                .unknown_attributes
+                .is_none()
+        );
+
+        let xml = r#"<opml version="2.0"><head><title>T</title></head><body>
+            <outline text="Root" icon="star"><outline text="A" _color="red"/></outline>
+            <outline text="B" type="link" isComment="true"/></body></opml>"#;
+        let map = opml::from_opml_with(xml, &options).unwrap();
+        let saved = opml::to_opml(&map).unwrap();
+        for attribute in [
+            r#"icon="star""#,
+            r#"_color="red""#,
+            r#"type="link""#,
+            r#"isComment="true""#,
+        ] {
+            assert!(
+                saved.contains(attribute),
+                "{attribute} missing from {saved}"
+            );
+        }
+        let root = &map.nodes[&map.root_id];
+        let b = &map.nodes[&root.children[1]];
+        assert_eq!(b.unknown_attributes.as_ref().unwrap().attributes.len(), 1);
+    }
+}
@@ -0,0 +1,240 @@
+use crate::BrainCoreError;
+use crate::limits::LimitError;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Phase of a conversion being reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Reading entries out of a zip package.
+    ReadingArchive,
+    /// Turning parsed document elements into nodes.
+    BuildingNodes,
+    /// Turning nodes into document elements.
+    WritingNodes,
+    /// Writing entries into a zip package.
+    WritingArchive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressEvent {
+    pub stage: Stage,
+    pub done: usize,
+    /// Known up front for most stages; `None` when the converter can't tell.
+    pub total: Option<usize>,
+}
+
+impl ProgressEvent {
+    /// Completion of the current stage in the range `0.0..=100.0`, if the
+    /// total is known.
+    pub fn percent(&self) -> Option<f32> {
+        self.total.map(|total| {
+            if total == 0 {
+                100.0
+            } else {
+                (self.done.min(total) as f32 / total as f32) * 100.0
+            }
+        })
+    }
+}
+
+/// Shared flag for aborting a running conversion from another thread.
+/// Clones observe the same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// Per-item events are coalesced so that huge maps don't flood the callback.
+const REPORT_EVERY: usize = 64;
+
+/// Observes and optionally aborts a long-running conversion. Pass one to
+/// the `*_monitored` variants of importers and exporters;
+/// `Monitor::default()` observes nothing and never cancels.
+#[derive(Default)]
+pub struct Monitor<'a> {
+    on_progress: Option<Box<dyn FnMut(ProgressEvent) + 'a>>,
+    cancel: Option<CancellationToken>,
+    stage: Option<Stage>,
+    done: usize,
+    total: Option<usize>,
+    max_nodes: Option<usize>,
+}
+
+impl<'a> Monitor<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on_progress(mut self, callback: impl FnMut(ProgressEvent) + 'a) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Aborts the conversion with an error once `token` is cancelled.
+    pub fn cancel_on(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.as_ref().is_some_and(|t| t.is_cancelled())
+    }
+
+    /// Makes an import give up once its building stage has produced more
+    /// than `max` nodes, before the rest of the document is turned into
+    /// nodes. The importer's final limit check still covers everything else.
+    pub(crate) fn limit_nodes(&mut self, max: Option<usize>) {
+        self.max_nodes = max;
+    }
+
+    fn exceeded(&self) -> Option<usize> {
+        self.max_nodes
+            .filter(|max| self.stage == Some(Stage::BuildingNodes) && self.done > *max)
+    }
+
+    /// Whether converters should stop their current stage early, because
+    /// of a cancellation or a node limit; `finish` then reports which.
+    pub(crate) fn should_stop(&self) -> bool {
+        self.is_cancelled() || self.exceeded().is_some()
+    }
+
+    /// Fails if the conversion has been cancelled or has gone over its node
+    /// limit. `begin` and `finish` check at stage boundaries; within a stage
+    /// converters stop early on [`Monitor::should_stop`] and let `finish`
+    /// report it.
+    pub(crate) fn check(&self) -> Result<(), BrainCoreError> {
+        if let Some(max) = self.exceeded() {
+            Err(LimitError::TooManyNodes { max }.into())
+        } else if self.is_cancelled() {
+            Err(BrainCoreError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Starts a new stage, reporting zero progress.
+    pub(crate) fn begin(
+        &mut self,
+        stage: Stage,
+        total: Option<usize>,
+    ) -> Result<(), BrainCoreError> {
+        self.check()?;
+        self.stage = Some(stage);
+        self.done = 0;
+        self.total = total;
+        self.emit();
+        Ok(())
+    }
+
+    /// Records one finished item in the current stage.
+    pub(crate) fn tick(&mut self) {
+        self.done += 1;
+        if self.done.is_multiple_of(REPORT_EVERY) || Some(self.done) == self.total {
+            self.emit();
+        }
+    }
+
+    /// Closes the current stage, reporting its final count unless `tick`
+    /// already did.
+    pub(crate) fn finish(&mut self) -> Result<(), BrainCoreError> {
+        self.check()?;
+        if !self.done.is_multiple_of(REPORT_EVERY) && Some(self.done) != self.total {
+            self.emit();
+        }
+        Ok(())
+    }
+
+    fn emit(&mut self) {
+        if let (Some(callback), Some(stage)) = (self.on_progress.as_mut(), self.stage) {
+            callback(ProgressEvent {
+                stage,
+                done: self.done,
+                total: self.total,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::ImportOptions;
+    use crate::testing::{GenerateSpec, generate};
+    use crate::xmind;
+
+    #[test]
+    fn test_xmind_round_trip_reports_progress() {
+        let spec = GenerateSpec {
+            node_count: 200,
+            ..Default::default()
+        };
+        let map = generate(5, &spec);
+
+        let mut events = Vec::new();
+        let data = {
+            let mut monitor = Monitor::new().on_progress(|e| events.push(e));
+            xmind::to_xmind_monitored(&map, &Default::default(), &mut monitor).unwrap()
+        };
+        let last_written = events
+            .iter()
+            .rfind(|e| e.stage == Stage::WritingNodes)
+            .unwrap();
+        assert_eq!(last_written.done, 200);
+        assert_eq!(last_written.percent(), Some(100.0));
+        assert!(events.iter().any(|e| e.stage == Stage::WritingArchive));
+
+        events.clear();
+        let mut monitor = Monitor::new().on_progress(|e| events.push(e));
+        xmind::from_xmind_monitored(&data, &ImportOptions::default(), &mut monitor).unwrap();
+        drop(monitor);
+        assert_eq!(events[0].stage, Stage::ReadingArchive);
+        let last = events.last().unwrap();
+        assert_eq!(last.stage, Stage::BuildingNodes);
+        assert_eq!((last.done, last.total), (200, Some(200)));
+    }
+
+    #[test]
+    fn test_cancellation_aborts_conversion() {
+        let spec = GenerateSpec {
+            node_count: 1_000,
+            ..Default::default()
+        };
+        let map = generate(9, &spec);
+        let data = xmind::to_xmind(&map).unwrap();
+
+        // Cancel from inside the progress callback, mid-way through building.
+        let token = CancellationToken::new();
+        let trigger = token.clone();
+        let mut built = 0;
+        let mut monitor = Monitor::new()
+            .on_progress(|e| {
+                if e.stage == Stage::BuildingNodes && e.done >= 128 {
+                    trigger.cancel();
+                }
+                built = e.done;
+            })
+            .cancel_on(token.clone());
+        assert!(matches!(
+            xmind::from_xmind_monitored(&data, &ImportOptions::default(), &mut monitor),
+            Err(BrainCoreError::Cancelled)
+        ));
+        drop(monitor);
+        assert!(built < 1_000);
+
+        let mut monitor = Monitor::new().cancel_on(token);
+        assert!(crate::storage::to_xml_monitored(&map, &Default::default(), &mut monitor).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::export::ExportOptions;
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A labelled arrow from one node to another, outside the tree structure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Relationship {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl MindMap {
+    /// Connects two distinct nodes and returns the relationship id.
+    pub fn add_relationship(
+        &mut self,
+        from: &str,
+        to: &str,
+        label: Option<&str>,
+    ) -> Result<String, BrainCoreError> {
+        for id in [from, to] {
+            if !self.nodes.contains_key(id) {
+                return Err(BrainCoreError::NodeNotFound(id.to_string()));
+            }
+        }
+        if from == to {
+            return Err(BrainCoreError::InvalidOperation(
+                "A relationship must connect two different nodes".to_string(),
+            ));
+        }
+        let id = Uuid::new_v4().to_string();
+        self.relationships.push(Relationship {
+            id: id.clone(),
+            from: from.to_string(),
+            to: to.to_string(),
+            label: label.map(str::to_string).filter(|l| !l.is_empty()),
+        });
+        self.bump_revision(from);
+        Ok(id)
+    }
+
+    pub fn remove_relationship(&mut self, id: &str) -> Result<Relationship, BrainCoreError> {
+        let pos = self
+            .relationships
+            .iter()
+            .position(|r| r.id == id)
+            .ok_or_else(|| BrainCoreError::RelationshipNotFound(id.to_string()))?;
+        let removed = self.relationships.remove(pos);
+        self.bump_revision(&removed.from);
+        Ok(removed)
+    }
+
+    /// Relationships starting or ending at `node_id`.
+    pub fn relationships_of<'a>(
+        &'a self,
+        node_id: &'a str,
+    ) -> impl Iterator<Item = &'a Relationship> + 'a {
+        self.relationships
+            .iter()
+            .filter(move |r| r.from == node_id || r.to == node_id)
+    }
+
+    /// Relationships whose ends are both written by an export. Relationships
+    /// touching trashed nodes are kept in the map so that restoring the node
+    /// brings them back, but they are never exported.
+    pub(crate) fn exported_relationships<'a>(
+        &'a self,
+        options: &'a ExportOptions,
+    ) -> impl Iterator<Item = &'a Relationship> + 'a {
+        let exported = move |id: &str| {
+            self.nodes.contains_key(id) && (options.include_archived || !self.is_archived(id))
+        };
+        self.relationships
+            .iter()
+            .filter(move |r| exported(&r.from) && exported(&r.to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mmap, storage, xmind};
+
+    #[test]
+    fn test_relationships_round_trip_through_formats() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "Cause").unwrap();
+        let b = map.add_child(&root_id, "Effect").unwrap();
+        let c = map.add_child(&root_id, "Gone").unwrap();
+        map.add_relationship(&a, &b, Some("leads to")).unwrap();
+        let dropped = map.add_relationship(&b, &a, None).unwrap();
+        map.add_relationship(&a, &c, None).unwrap();
+        map.trash_node(&c).unwrap();
+        assert!(map.add_relationship(&a, &a, None).is_err());
+        assert_eq!(map.remove_relationship(&dropped).unwrap().from, b);
+        assert!(matches!(
+            map.remove_relationship(&dropped),
+            Err(BrainCoreError::RelationshipNotFound(_))
+        ));
+        assert_eq!(map.relationships_of(&b).count(), 1);
+
+        for loaded in [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+            mmap::from_mmap(&mmap::to_mmap(&map).unwrap()).unwrap(),
+        ] {
+            assert_eq!(loaded.relationships.len(), 1);
+            let relationship = &loaded.relationships[0];
+            assert_eq!(loaded.nodes[&relationship.from].content, "Cause");
+            assert_eq!(loaded.nodes[&relationship.to].content, "Effect");
+            assert_eq!(relationship.label.as_deref(), Some("leads to"));
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::MindMap;
+use crate::events::MapEvent;
+
+impl MindMap {
+    /// Records a modification of `node_id`: its revision and the map's both
+    /// go up by one. Revisions only ever increase, so sync layers and caches
+    /// can compare them instead of timestamps. Observers are told the node
+    /// changed.
+    pub(crate) fn bump_revision(&mut self, node_id: &str) {
+        self.revision += 1;
+        if let Some(node) = self.nodes.get_mut(node_id) {
+            node.revision += 1;
+            self.emit(MapEvent::NodeChanged {
+                node_id: node_id.to_string(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{from_xml, to_xml};
+
+    #[test]
+    fn test_revisions_bump_and_persist() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = map.add_child(&root_id, "Draft").unwrap();
+        map.change_node(&child, "Final").unwrap();
+        map.change_node(&child, "Final").unwrap();
+        map.log_time(&child, 1000).unwrap();
+        map.archive(&child).unwrap();
+
+        assert_eq!(map.revision, 4);
+        assert_eq!(map.nodes[&root_id].revision, 1);
+        assert_eq!(map.nodes[&child].revision, 3);
+
+        map.trash_node(&child).unwrap();
+        assert_eq!(map.revision, 5);
+        assert_eq!(map.nodes[&root_id].revision, 2);
+
+        let entry = map.trash[0].id.clone();
+        map.restore(&entry).unwrap();
+        map.unarchive(&child).unwrap();
+        let loaded = from_xml(&to_xml(&map).unwrap()).unwrap();
+        assert_eq!(loaded.revision, 7);
+        assert_eq!(loaded.nodes[&root_id].revision, 3);
+        assert_eq!(loaded.nodes[&child].revision, 4);
+    }
+}
@@ -0,0 +1,462 @@
+use crate::{BrainCoreError, MindMap};
+use regex::{Regex, RegexBuilder};
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Skim-style scoring constants. A matched character is worth SCORE_MATCH plus
+// any positional bonus; every skipped character between two matches costs
+// PENALTY_GAP.
+const SCORE_MATCH: i64 = 16;
+const BONUS_FIRST_CHAR: i64 = 8;
+const BONUS_BOUNDARY: i64 = 10;
+const BONUS_CAMEL: i64 = 8;
+const BONUS_CONSECUTIVE: i64 = 12;
+const PENALTY_GAP: i64 = 1;
+
+/// A node matched by a fuzzy query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub node_id: String,
+    pub score: i64,
+    /// Byte ranges into the node content covering the matched characters,
+    /// merged so that consecutive matches form a single range.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A completion candidate drawn from existing map content.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    /// How many times the candidate occurs across the map.
+    pub count: usize,
+}
+
+/// What [`MindMap::search`] looks for. Both kinds of query ignore case.
+#[derive(Debug, Clone)]
+pub struct SearchQuery {
+    regex: Regex,
+}
+
+impl SearchQuery {
+    /// Matches `text` as a plain substring.
+    pub fn text(text: &str) -> Self {
+        Self {
+            regex: build(&regex::escape(text)).expect("escaped text is a valid pattern"),
+        }
+    }
+
+    /// Matches a regular expression in the syntax of the `regex` crate.
+    pub fn regex(pattern: &str) -> Result<Self, BrainCoreError> {
+        build(pattern)
+            .map(|regex| Self { regex })
+            .map_err(|e| BrainCoreError::InvalidOperation(format!("Invalid search pattern: {e}")))
+    }
+
+    /// Byte ranges of the non-empty matches in `text`.
+    fn ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.regex
+            .find_iter(text)
+            .map(|m| m.range())
+            .filter(|r| !r.is_empty())
+            .collect()
+    }
+}
+
+impl From<&str> for SearchQuery {
+    fn from(text: &str) -> Self {
+        Self::text(text)
+    }
+}
+
+fn build(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// Part of a node a search matched in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Content,
+    Note,
+    /// The icon at this index in [`Node::icons`](crate::Node::icons).
+    Icon(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    pub field: SearchField,
+    /// Byte ranges into the field's text.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A node matched by [`MindMap::search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchHit {
+    pub node_id: String,
+    /// Node ids from the root down to the matched node, inclusive.
+    pub path: Vec<String>,
+    pub matches: Vec<SearchMatch>,
+}
+
+/// Scores `text` against `pattern` fzf-style: every pattern character must
+/// appear in order, and the best alignment is chosen by rewarding consecutive
+/// runs and word boundaries while penalizing gaps.
+///
+/// Matching is case-insensitive unless the pattern contains an uppercase
+/// character ("smart case"). Returns `None` if the pattern doesn't match.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<(i64, Vec<Range<usize>>)> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+
+    let pat: Vec<char> = pattern.chars().map(fold).collect();
+    if pat.is_empty() {
+        return Some((0, Vec::new()));
+    }
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let folded: Vec<char> = chars.iter().map(|&(_, c)| fold(c)).collect();
+    let n = chars.len();
+    if pat.len() > n {
+        return None;
+    }
+
+    let bonus: Vec<i64> = (0..n)
+        .map(|j| {
+            let c = chars[j].1;
+            if j == 0 {
+                return BONUS_FIRST_CHAR + BONUS_BOUNDARY;
+            }
+            let prev = chars[j - 1].1;
+            if !prev.is_alphanumeric() && c.is_alphanumeric() {
+                BONUS_BOUNDARY
+            } else if prev.is_lowercase() && c.is_uppercase() {
+                BONUS_CAMEL
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    // score[i][j]: best score with pat[i] matched at text position j.
+    // from[i][j]: text position of pat[i - 1] in that alignment.
+    let mut score = vec![vec![None::<i64>; n]; pat.len()];
+    let mut from = vec![vec![0usize; n]; pat.len()];
+
+    for j in 0..n {
+        if folded[j] == pat[0] {
+            score[0][j] = Some(SCORE_MATCH + bonus[j]);
+        }
+    }
+
+    for i in 1..pat.len() {
+        // Running max of score[i - 1][k] + k * PENALTY_GAP over k < j - 1,
+        // so the gap-penalized best predecessor is found in O(1) per column.
+        let mut best_gapped: Option<(i64, usize)> = None;
+        for j in 1..n {
+            if j >= 2
+                && let Some(s) = score[i - 1][j - 2]
+            {
+                let key = s + (j - 2) as i64 * PENALTY_GAP;
+                if best_gapped.is_none_or(|(b, _)| key > b) {
+                    best_gapped = Some((key, j - 2));
+                }
+            }
+
+            if folded[j] != pat[i] {
+                continue;
+            }
+
+            let mut best: Option<(i64, usize)> = None;
+            if let Some(s) = score[i - 1][j - 1] {
+                best = Some((s + BONUS_CONSECUTIVE, j - 1));
+            }
+            if let Some((key, k)) = best_gapped {
+                let s = key - (j - 1) as i64 * PENALTY_GAP;
+                if best.is_none_or(|(b, _)| s > b) {
+                    best = Some((s, k));
+                }
+            }
+
+            if let Some((s, k)) = best {
+                score[i][j] = Some(s + SCORE_MATCH + bonus[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let last = pat.len() - 1;
+    let (best_score, mut j) = (0..n)
+        .filter_map(|j| score[last][j].map(|s| (s, j)))
+        .max_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)))?;
+
+    let mut positions = vec![0usize; pat.len()];
+    for i in (0..pat.len()).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    for &p in &positions {
+        let (start, c) = chars[p];
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(r) if r.end == start => r.end = end,
+            _ => ranges.push(start..end),
+        }
+    }
+
+    Some((best_score, ranges))
+}
+
+impl MindMap {
+    /// Fuzzy-matches `pattern` against the content of every node that isn't
+    /// archived and returns up to `limit` hits, best first. Ties are broken by
+    /// shorter content, then by node id, so results are stable between calls.
+    pub fn fuzzy_search(&self, pattern: &str, limit: usize) -> Vec<FuzzyMatch> {
+        let mut hits: Vec<(FuzzyMatch, usize)> = self
+            .nodes
+            .values()
+            .filter(|node| !self.is_archived(&node.id))
+            .filter_map(|node| {
+                let (score, ranges) = fuzzy_score(pattern, &node.content)?;
+                Some((
+                    FuzzyMatch {
+                        node_id: node.id.clone(),
+                        score,
+                        ranges,
+                    },
+                    node.content.len(),
+                ))
+            })
+            .collect();
+
+        hits.sort_by(|(a, a_len), (b, b_len)| {
+            b.score
+                .cmp(&a.score)
+                .then(a_len.cmp(b_len))
+                .then_with(|| a.node_id.cmp(&b.node_id))
+        });
+        hits.truncate(limit);
+        hits.into_iter().map(|(hit, _)| hit).collect()
+    }
+
+    /// Finds the nodes whose content, note or icons match `query`, in
+    /// depth-first order from the root. Archived nodes are skipped.
+    pub fn search(&self, query: impl Into<SearchQuery>) -> Vec<SearchHit> {
+        let query = query.into();
+        self.iter_dfs()
+            .filter(|node| !self.is_archived(&node.id))
+            .filter_map(|node| {
+                let fields = std::iter::once((SearchField::Content, node.content.as_str()))
+                    .chain(node.note.as_deref().map(|n| (SearchField::Note, n)))
+                    .chain(
+                        node.icons
+                            .iter()
+                            .enumerate()
+                            .map(|(i, icon)| (SearchField::Icon(i), icon.as_str())),
+                    );
+                let matches: Vec<SearchMatch> = fields
+                    .filter_map(|(field, text)| {
+                        let ranges = query.ranges(text);
+                        (!ranges.is_empty()).then_some(SearchMatch { field, ranges })
+                    })
+                    .collect();
+                if matches.is_empty() {
+                    return None;
+                }
+                let mut path: Vec<String> =
+                    self.ancestors(&node.id).map(|n| n.id.clone()).collect();
+                path.reverse();
+                path.push(node.id.clone());
+                Some(SearchHit {
+                    node_id: node.id.clone(),
+                    path,
+                    matches,
+                })
+            })
+            .collect()
+    }
+
+    /// Suggests completions for `prefix` from existing node contents, both
+    /// whole titles and individual words, and from tags, most frequent
+    /// first. Archived nodes are left out.
+    ///
+    /// Matching is case-insensitive; the most common spelling of each
+    /// candidate is returned. Candidates equal to the prefix itself are skipped.
+    pub fn suggest_completions<'a>(&'a self, prefix: &str, limit: usize) -> Vec<Completion> {
+        let prefix = prefix.trim().to_lowercase();
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        // Keyed by lowercase text so "Budget" and "budget" count together,
+        // with per-spelling counts to pick the variant to suggest.
+        let mut candidates: HashMap<String, HashMap<&str, usize>> = HashMap::new();
+        let mut record = |text: &'a str| {
+            let key = text.to_lowercase();
+            if key == prefix || !key.starts_with(&prefix) {
+                return;
+            }
+            *candidates.entry(key).or_default().entry(text).or_insert(0) += 1;
+        };
+
+        for node in self.nodes.values().filter(|n| !self.is_archived(&n.id)) {
+            for tag in &node.tags {
+                record(tag);
+            }
+            let content = node.content.trim();
+            let mut words = content
+                .split_whitespace()
+                .map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()))
+                .filter(|w| !w.is_empty())
+                .peekable();
+
+            let first = words.next();
+            if words.peek().is_some() {
+                record(content);
+            }
+            for word in first.into_iter().chain(words) {
+                record(word);
+            }
+        }
+
+        let mut completions: Vec<Completion> = candidates
+            .into_values()
+            .map(|spellings| {
+                let count = spellings.values().sum();
+                let (text, _) = spellings
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0)))
+                    .unwrap_or_default();
+                Completion {
+                    text: text.to_string(),
+                    count,
+                }
+            })
+            .collect();
+        completions.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then(a.text.len().cmp(&b.text.len()))
+                .then_with(|| a.text.cmp(&b.text))
+        });
+        completions.truncate(limit);
+        completions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzzy_score_prefers_boundaries_and_runs() {
+        let (boundary, ranges) = fuzzy_score("mm", "mind map").unwrap();
+        assert_eq!(ranges, vec![0..1, 5..6]);
+
+        let (inner, _) = fuzzy_score("mm", "hammock").unwrap();
+        assert!(boundary > inner);
+
+        assert!(fuzzy_score("xyz", "mind map").is_none());
+        assert!(fuzzy_score("Map", "mind map").is_none());
+    }
+
+    #[test]
+    fn test_fuzzy_search_ranking() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.nodes.get_mut(&root_id).unwrap().content = "Project plan".to_string();
+        let mut other = map.nodes[&root_id].clone();
+        other.id = "other".to_string();
+        other.content = "People and places".to_string();
+        map.nodes.insert(other.id.clone(), other);
+
+        let hits = map.fuzzy_search("pp", 10);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].node_id, root_id);
+        assert_eq!(hits[0].ranges, vec![0..1, 8..9]);
+
+        assert_eq!(map.fuzzy_search("pp", 1).len(), 1);
+    }
+
+    #[test]
+    fn test_search() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "Budget").unwrap();
+        let b = map.add_child(&a, "Q1 budget review").unwrap();
+        map.set_note(&b, "Ask about the BUDGET").unwrap();
+        map.nodes
+            .get_mut(&b)
+            .unwrap()
+            .icons
+            .push("budget".to_string());
+        let old = map.add_child(&root_id, "Old budget").unwrap();
+        map.archive(&old).unwrap();
+
+        let hits = map.search("budget");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].node_id, a);
+        assert_eq!(hits[1].path, vec![root_id.clone(), a.clone(), b.clone()]);
+        let matches: Vec<(SearchField, Range<usize>)> = hits[1]
+            .matches
+            .iter()
+            .map(|m| (m.field, m.ranges[0].clone()))
+            .collect();
+        assert_eq!(
+            matches,
+            vec![
+                (SearchField::Content, 3..9),
+                (SearchField::Note, 14..20),
+                (SearchField::Icon(0), 0..6),
+            ]
+        );
+
+        let hits = map.search(SearchQuery::regex(r"^q\d").unwrap());
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].matches[0].ranges[0], 0..2);
+        assert!(map.search("(").is_empty());
+        assert!(map.search("").is_empty());
+        assert!(SearchQuery::regex("(").is_err());
+    }
+
+    #[test]
+    fn test_suggest_completions() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        for (i, content) in ["Budget review", "budget", "Backlog", "Budget review"]
+            .iter()
+            .enumerate()
+        {
+            let mut node = map.nodes[&root_id].clone();
+            node.id = format!("node-{}", i);
+            node.content = content.to_string();
+            map.nodes.insert(node.id.clone(), node);
+        }
+        map.tag("node-2", "bugfix").unwrap();
+        let mut archived = map.nodes[&root_id].clone();
+        archived.id = "archived".to_string();
+        archived.content = "Burndown chart".to_string();
+        archived.archived = true;
+        map.nodes.insert(archived.id.clone(), archived);
+
+        let suggestions = map.suggest_completions("bu", 10);
+        assert_eq!(suggestions[0].text, "Budget");
+        assert_eq!(suggestions[0].count, 3);
+        assert_eq!(suggestions[1].text, "Budget review");
+        assert_eq!(suggestions[1].count, 2);
+        assert_eq!(suggestions[2].text, "bugfix");
+        assert_eq!(suggestions.len(), 3);
+
+        assert_eq!(
+            map.suggest_completions("budget", 10)[0].text,
+            "Budget review"
+        );
+        assert!(map.suggest_completions("", 10).is_empty());
+    }
+}
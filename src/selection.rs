@@ -0,0 +1,214 @@
+use crate::command::Command;
+use crate::transaction::TransactionResult;
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+
+/// The selected nodes: a primary node, which keyboard navigation, the
+/// selection history and single-node editing act on, and any number of
+/// others selected alongside it. The primary node always exists; the map
+/// falls back to a parent or the root when it goes away.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Selection {
+    #[serde(rename = "selected_node_id")]
+    pub primary: String,
+    /// In the order they were added, without the primary node.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional: Vec<String>,
+}
+
+impl Selection {
+    pub fn new(primary: String) -> Self {
+        Self {
+            primary,
+            additional: Vec::new(),
+        }
+    }
+
+    /// The primary node followed by the others.
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.primary.as_str()).chain(self.additional.iter().map(String::as_str))
+    }
+
+    pub fn contains(&self, node_id: &str) -> bool {
+        self.ids().any(|id| id == node_id)
+    }
+
+    /// Whether only the primary node is selected.
+    pub fn is_single(&self) -> bool {
+        self.additional.is_empty()
+    }
+}
+
+impl MindMap {
+    /// Adds a node to the selection, keeping the primary node, as a
+    /// modifier-click does.
+    pub fn select_add(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(BrainCoreError::NodeNotFound(node_id.to_string()));
+        }
+        if !self.selection.contains(node_id) {
+            self.selection.additional.push(node_id.to_string());
+        }
+        Ok(())
+    }
+
+    /// Adds the siblings from the primary node to `node_id`, both
+    /// included, as a shift-click does. Archived siblings are skipped.
+    /// Fails if `node_id` is not a sibling of the primary node.
+    pub fn select_range(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let primary = &self.selection.primary;
+        let siblings = match &node.parent {
+            Some(parent_id) => {
+                &self
+                    .nodes
+                    .get(parent_id)
+                    .ok_or_else(|| BrainCoreError::NodeNotFound(parent_id.clone()))?
+                    .children
+            }
+            None => &self.floating_roots,
+        };
+        let (Some(from), Some(to)) = (
+            siblings.iter().position(|id| id == primary),
+            siblings.iter().position(|id| id == node_id),
+        ) else {
+            if node_id == primary {
+                return Ok(());
+            }
+            return Err(BrainCoreError::InvalidOperation(
+                "Range selection needs siblings of the selected node".to_string(),
+            ));
+        };
+        let range: Vec<String> = siblings[from.min(to)..=from.max(to)]
+            .iter()
+            .filter(|id| !self.nodes.get(*id).is_some_and(|n| n.archived))
+            .cloned()
+            .collect();
+        for id in range {
+            if !self.selection.contains(&id) {
+                self.selection.additional.push(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Deselects everything but the primary node.
+    pub fn clear_selection(&mut self) {
+        self.selection.additional.clear();
+    }
+
+    /// Moves every selected node to the trash as one undoable step. The
+    /// root is kept, as are nodes whose ancestor is selected too, since
+    /// they go with it.
+    pub fn trash_selection(&mut self) -> Result<TransactionResult<()>, BrainCoreError> {
+        let ids: Vec<String> = self
+            .selection
+            .ids()
+            .filter(|id| *id != self.root_id)
+            .filter(|id| !self.ancestors(id).any(|a| self.selection.contains(&a.id)))
+            .map(str::to_string)
+            .collect();
+        self.transaction(|tx| {
+            for node_id in ids {
+                tx.apply(Command::Remove { node_id })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Gives every selected node the same icons as one undoable step.
+    pub fn set_selection_icons(
+        &mut self,
+        icons: Vec<String>,
+    ) -> Result<TransactionResult<()>, BrainCoreError> {
+        let ids: Vec<String> = self.selection.ids().map(str::to_string).collect();
+        self.transaction(|tx| {
+            for node_id in ids {
+                tx.apply(Command::SetIcons {
+                    node_id,
+                    icons: icons.clone(),
+                })?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Drops nodes that were removed, hidden or archived from the
+    /// additional selection.
+    pub(crate) fn prune_selection(&mut self) {
+        let mut additional = std::mem::take(&mut self.selection.additional);
+        additional.retain(|id| {
+            self.nodes.contains_key(id)
+                && *id != self.selection.primary
+                && !self.is_hidden(id)
+                && !self.is_archived(id)
+        });
+        self.selection.additional = additional;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_multi_select() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let ids: Vec<String> = (0..5)
+            .map(|i| map.add_child(&root_id, &format!("N{i}")).unwrap())
+            .collect();
+        let nested = map.add_child(&ids[1], "Nested").unwrap();
+
+        map.select(&ids[3]).unwrap();
+        map.select_range(&ids[1]).unwrap();
+        assert_eq!(map.selection.ids().count(), 3);
+        assert!(map.selection.contains(&ids[2]));
+        assert!(map.select_range(&nested).is_err());
+        map.select_add(&nested).unwrap();
+        map.select_add(&ids[3]).unwrap();
+        assert_eq!(map.selection.ids().count(), 4);
+
+        map.set_selection_icons(vec!["star".to_string()]).unwrap();
+        assert_eq!(map.nodes[&nested].icons, vec!["star"]);
+        assert!(map.nodes[&ids[0]].icons.is_empty());
+
+        let json = serde_json::to_string(&map).unwrap();
+        assert!(json.contains("\"selected_node_id\""));
+        let loaded: MindMap = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.selection, map.selection);
+
+        // The nested node goes with its selected parent, and the
+        // selection falls back to the root.
+        let result = map.trash_selection().unwrap();
+        assert_eq!(
+            map.nodes[&root_id].children,
+            vec![ids[0].clone(), ids[4].clone()]
+        );
+        assert_eq!(map.trash.len(), 3);
+        assert_eq!(map.selection, Selection::new(root_id.clone()));
+        map.apply(result.inverse).unwrap();
+        assert_eq!(map.nodes[&root_id].children, ids);
+
+        map.select_add(&ids[4]).unwrap();
+        map.set_folded(&root_id, true).unwrap();
+        assert!(map.selection.is_single());
+        map.select_add(&ids[0]).unwrap();
+        map.clear_selection();
+        assert!(map.selection.is_single());
+
+        // A node whose parent is gone is refused rather than panicking.
+        map.nodes.get_mut(&ids[4]).unwrap().parent = Some("gone".to_string());
+        assert!(matches!(
+            map.select_range(&ids[4]),
+            Err(BrainCoreError::NodeNotFound(id)) if id == "gone"
+        ));
+        assert!(matches!(
+            map.select_range("missing"),
+            Err(BrainCoreError::NodeNotFound(_))
+        ));
+    }
+}
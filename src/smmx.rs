@@ -1,9 +1,12 @@
-use crate::{MindMap, Node};
+use crate::export::{ExportOptions, TextWriter};
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node, html};
 use quick_xml::de::from_str;
-use quick_xml::se::to_string;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::io::Write;
 use uuid::Uuid;
 
 // SimpleMind XML Structure (Simplified)
@@ -33,6 +36,8 @@ pub struct SmmxTopic {
     pub id: String, // SimpleMind uses integer IDs usually, but string is safer for generic
     #[serde(rename = "@text")]
     pub text: String,
+    #[serde(rename = "note", default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
     #[serde(rename = "children", default, skip_serializing_if = "Option::is_none")]
     pub children: Option<SmmxChildren>,
 }
@@ -43,13 +48,60 @@ pub struct SmmxChildren {
     pub topics: SmmxTopics,
 }
 
-pub fn to_smmx(map: &MindMap) -> Result<String, String> {
-    let root_node = map.nodes.get(&map.root_id).ok_or("Root node not found")?;
+pub fn to_smmx(map: &MindMap) -> Result<String, BrainCoreError> {
+    to_smmx_with(map, &ExportOptions::default())
+}
+
+/// Topic IDs are the node IDs, so the output is always canonical.
+pub fn to_smmx_with(map: &MindMap, options: &ExportOptions) -> Result<String, BrainCoreError> {
+    let mut buf = Vec::new();
+    write_smmx_with(map, &mut buf, options)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+/// Streams the SimpleMind document to `writer` instead of building a string.
+pub fn write_smmx<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_smmx_with(map, writer, &ExportOptions::default())
+}
+
+pub fn write_smmx_with<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    write_smmx_monitored(map, writer, options, &mut Monitor::default())
+}
+
+pub fn to_smmx_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut buf = Vec::new();
+    write_smmx_monitored(map, &mut buf, options, monitor)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+pub fn write_smmx_monitored<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let root_node = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
 
     // SimpleMind IDs are usually integers. We might need to map UUIDs to integers if strict.
     // But let's try using UUIDs as strings first.
 
-    let smmx_root_topic = node_to_smmx_topic(root_node, map);
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
+    let smmx_root_topic = node_to_smmx_topic(root_node, map, options, monitor);
+    monitor.finish()?;
 
     let smmx_root = SmmxRoot {
         mindmap: SmmxMindMap {
@@ -59,16 +111,27 @@ pub fn to_smmx(map: &MindMap) -> Result<String, String> {
         },
     };
 
-    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
-    xml.push_str(&to_string(&smmx_root).map_err(|e| e.to_string())?);
-    Ok(xml)
+    writer.write_all(b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+    let mut text = TextWriter::new(&mut *writer);
+    let result = options.write_xml(&smmx_root, &mut html::AttributeEncoder::new(&mut text));
+    text.check(result)?;
+    Ok(writer.flush()?)
 }
 
-fn node_to_smmx_topic(node: &Node, map: &MindMap) -> SmmxTopic {
+fn node_to_smmx_topic(
+    node: &Node,
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> SmmxTopic {
+    monitor.tick();
     let mut children_vec = Vec::new();
     for child_id in &node.children {
-        if let Some(child) = map.nodes.get(child_id) {
-            children_vec.push(node_to_smmx_topic(child, map));
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            children_vec.push(node_to_smmx_topic(child, map, options, monitor));
         }
     }
 
@@ -85,12 +148,27 @@ fn node_to_smmx_topic(node: &Node, map: &MindMap) -> SmmxTopic {
     SmmxTopic {
         id: node.id.clone(),
         text: node.content.clone(),
+        note: node.note.clone(),
         children,
     }
 }
 
-pub fn from_smmx(xml: &str) -> Result<MindMap, String> {
-    let smmx_root: SmmxRoot = from_str(xml).map_err(|e| e.to_string())?;
+pub fn from_smmx(xml: &str) -> Result<MindMap, BrainCoreError> {
+    from_smmx_with(xml, &ImportOptions::default())
+}
+
+pub fn from_smmx_with(xml: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_smmx_monitored(xml, options, &mut Monitor::default())
+}
+
+pub fn from_smmx_monitored(
+    xml: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let smmx_root: SmmxRoot =
+        from_str(xml).map_err(|e| BrainCoreError::parse(ImportFormat::SimpleMind, e))?;
 
     let mut nodes = HashMap::new();
 
@@ -98,26 +176,41 @@ pub fn from_smmx(xml: &str) -> Result<MindMap, String> {
         return Ok(MindMap::new());
     }
 
-    let root_id = smmx_topic_to_node(&smmx_root.mindmap.topics.topic[0], None, &mut nodes);
+    let root_topic = &smmx_root.mindmap.topics.topic[0];
+    monitor.begin(Stage::BuildingNodes, Some(count_topics(root_topic)))?;
+    let root_id = smmx_topic_to_node(root_topic, None, &mut nodes, monitor);
+    monitor.finish()?;
 
-    Ok(MindMap {
-        nodes,
-        root_id: root_id.clone(),
-        selected_node_id: root_id,
-    })
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+fn count_topics(topic: &SmmxTopic) -> usize {
+    1 + topic
+        .children
+        .iter()
+        .flat_map(|c| &c.topics.topic)
+        .map(count_topics)
+        .sum::<usize>()
 }
 
 fn smmx_topic_to_node(
     topic: &SmmxTopic,
     parent_id: Option<&str>,
     nodes: &mut HashMap<String, Node>,
+    monitor: &mut Monitor,
 ) -> String {
+    monitor.tick();
     let id = Uuid::new_v4().to_string(); // Generate new UUIDs to avoid ID conflicts or format issues
 
     let mut children_ids = Vec::new();
     if let Some(children) = &topic.children {
         for child in &children.topics.topic {
-            children_ids.push(smmx_topic_to_node(child, Some(&id), nodes));
+            if monitor.should_stop() {
+                break;
+            }
+            children_ids.push(smmx_topic_to_node(child, Some(&id), nodes, monitor));
         }
     }
 
@@ -131,19 +224,14 @@ fn smmx_topic_to_node(
         created: now_millis(),
         modified: now_millis(),
         icons: Vec::new(),
+        note: topic.note.clone(),
+        ..Default::default()
     };
 
     nodes.insert(id.clone(), node);
     id
 }
 
-fn now_millis() -> u64 {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -161,6 +249,7 @@ mod tests {
             created: timestamp,
             modified: timestamp,
             icons: Vec::new(),
+            ..Default::default()
         };
         map.nodes.insert(id.clone(), node);
         if let Some(parent) = map.nodes.get_mut(parent_id) {
@@ -175,14 +264,16 @@ mod tests {
         let root_id = map.root_id.clone();
         map.nodes.get_mut(&root_id).unwrap().content = "Root Smmx".to_string();
 
-        add_child_for_test(&mut map, &root_id, "Child 1");
+        add_child_for_test(&mut map, &root_id, "Child 1\nsecond line");
 
         let xml = to_smmx(&map).unwrap();
-        assert!(!xml.is_empty());
+        assert!(xml.contains("text=\"Child 1&#xa;second line\""));
 
         let loaded_map = from_smmx(&xml).unwrap();
         let root = loaded_map.nodes.get(&loaded_map.root_id).unwrap();
         assert_eq!(root.content, "Root Smmx");
         assert_eq!(root.children.len(), 1);
+        let child = &loaded_map.nodes[&root.children[0]];
+        assert_eq!(child.content, "Child 1\nsecond line");
     }
 }
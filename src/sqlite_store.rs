@@ -0,0 +1,543 @@
+use crate::events::MapEvent;
+use crate::relationship::Relationship;
+use crate::trash::TrashEntry;
+use crate::{BrainCoreError, MindMap, Node};
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS nodes (
+        id TEXT PRIMARY KEY,
+        parent TEXT,
+        position INTEGER NOT NULL,
+        content TEXT NOT NULL,
+        created INTEGER NOT NULL,
+        modified INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS nodes_parent ON nodes (parent, position);
+    CREATE TABLE IF NOT EXISTS icons (
+        node_id TEXT NOT NULL REFERENCES nodes (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        icon TEXT NOT NULL,
+        PRIMARY KEY (node_id, position)
+    );
+    CREATE TABLE IF NOT EXISTS relationships (
+        id TEXT PRIMARY KEY,
+        from_id TEXT NOT NULL,
+        to_id TEXT NOT NULL,
+        label TEXT
+    );
+    CREATE TABLE IF NOT EXISTS resources (
+        data_ref TEXT PRIMARY KEY,
+        data BLOB NOT NULL
+    );
+";
+
+/// Node fields that have columns or tables of their own. The rest of a
+/// node is kept as JSON in `nodes.data`, so fields added later persist
+/// without a schema change.
+const COLUMNS: [&str; 7] = [
+    "id", "parent", "children", "content", "created", "modified", "icons",
+];
+
+/// A map kept in an SQLite database, one row per node, for maps too large
+/// to load and save as a whole document.
+///
+/// Besides loading and saving the whole map, parts can be loaded with
+/// [`SqliteStore::load_subtree`] and edits written as they happen with
+/// [`SqliteStore::write_events`] and [`SqliteStore::write_subtree_events`].
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+/// Part of a stored map, loaded with [`SqliteStore::load_subtree`]. Edit
+/// `map` and write the edits back with
+/// [`SqliteStore::write_subtree_events`].
+#[derive(Debug, Clone)]
+pub struct StoredSubtree {
+    /// The subtree, with its top node as the root.
+    pub map: MindMap,
+}
+
+impl SqliteStore {
+    /// Opens or creates a database file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BrainCoreError> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, BrainCoreError> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self, BrainCoreError> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(SqliteStore { conn })
+    }
+
+    /// Replaces whatever the database holds with `map`.
+    pub fn save(&mut self, map: &MindMap) -> Result<(), BrainCoreError> {
+        let tx = self.conn.transaction()?;
+        tx.execute_batch(
+            "DELETE FROM icons; DELETE FROM nodes; DELETE FROM meta; DELETE FROM resources;",
+        )?;
+        for node in map.nodes.values() {
+            write_node(&tx, map, node)?;
+        }
+        write_meta(&tx, map)?;
+        write_resources(&tx, map)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Loads the whole map.
+    pub fn load(&self) -> Result<MindMap, BrainCoreError> {
+        let root_id = self.meta("root_id")?.ok_or_else(|| {
+            BrainCoreError::InvalidOperation("The database holds no map".to_string())
+        })?;
+        let nodes = self.read_nodes("SELECT * FROM nodes", [])?;
+        let mut map = MindMap::from_nodes(nodes, root_id);
+        if let Some(id) = self.meta("selected_node_id")? {
+            map.selection.primary = id;
+        }
+        if let Some(revision) = self.meta("revision")? {
+            map.revision = revision.parse().unwrap_or_default();
+        }
+        if let Some(trash) = self.meta("trash")? {
+            map.trash = serde_json::from_str(&trash).map_err(corrupt)?;
+        }
+        if let Some(floating) = self.meta("floating_roots")? {
+            map.floating_roots = serde_json::from_str(&floating).map_err(corrupt)?;
+        }
+        if let Some(groups) = self.meta("groups")? {
+            map.groups = serde_json::from_str(&groups).map_err(corrupt)?;
+        }
+        if let Some(summaries) = self.meta("summaries")? {
+            map.summaries = serde_json::from_str(&summaries).map_err(corrupt)?;
+        }
+        if let Some(version) = self.meta("format_version")? {
+            map.format_version = serde_json::from_str(&version).map_err(corrupt)?;
+        }
+        if let Some(passthrough) = self.meta("passthrough")? {
+            map.passthrough = serde_json::from_str(&passthrough).map_err(corrupt)?;
+        }
+        map.relationships = self.read_relationships()?;
+        self.read_resources(&mut map)?;
+        Ok(map)
+    }
+
+    /// Loads the subtree below `node_id`, and nothing else, as a map with
+    /// that node as its root. Its relationships are the ones between nodes
+    /// of the subtree and its files the ones they are attached to; the
+    /// trash stays in the database.
+    pub fn load_subtree(&self, node_id: &str) -> Result<StoredSubtree, BrainCoreError> {
+        let mut nodes = self.read_nodes(
+            "WITH RECURSIVE subtree (id) AS (
+                 SELECT ?1
+                 UNION ALL
+                 SELECT nodes.id FROM nodes JOIN subtree ON nodes.parent = subtree.id
+             )
+             SELECT nodes.* FROM nodes JOIN subtree ON nodes.id = subtree.id",
+            [node_id],
+        )?;
+        nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?
+            .parent = None;
+        let mut map = MindMap::from_nodes(nodes, node_id.to_string());
+        map.relationships = self
+            .read_relationships()?
+            .into_iter()
+            .filter(|r| map.nodes.contains_key(&r.from) && map.nodes.contains_key(&r.to))
+            .collect();
+        self.read_attached_resources(&mut map)?;
+        Ok(StoredSubtree { map })
+    }
+
+    /// Writes the nodes the events name, as they now are in `map`, and
+    /// deletes removed subtrees. Register a channel with
+    /// [`MindMap::add_observer`] and pass what it received since the last
+    /// write; everything goes in one transaction.
+    ///
+    /// The selection, revision, trash, floating nodes, relationships,
+    /// groups and summaries are written too, as node events don't carry
+    /// them.
+    pub fn write_events<'a>(
+        &mut self,
+        map: &MindMap,
+        events: impl IntoIterator<Item = &'a MapEvent>,
+    ) -> Result<(), BrainCoreError> {
+        self.write(map, events, None)
+    }
+
+    /// Writes the edits to a loaded subtree as [`SqliteStore::write_events`]
+    /// does, leaving the rest of the map alone. Nodes trashed in the
+    /// subtree are added to the stored trash.
+    pub fn write_subtree_events<'a>(
+        &mut self,
+        subtree: &StoredSubtree,
+        events: impl IntoIterator<Item = &'a MapEvent>,
+    ) -> Result<(), BrainCoreError> {
+        let mut trash: Vec<TrashEntry> = match self.meta("trash")? {
+            Some(trash) => serde_json::from_str(&trash).map_err(corrupt)?,
+            None => Vec::new(),
+        };
+        let map = &subtree.map;
+        // Entries restored in the subtree leave the stored trash too.
+        trash.retain(|e| {
+            !map.nodes.contains_key(&e.node_id) && !map.trash.iter().any(|t| t.id == e.id)
+        });
+        trash.extend(map.trash.iter().cloned());
+        self.write(map, events, Some(trash))
+    }
+
+    /// Writes the whole map's meta data, or with `subtree_trash` only the
+    /// trash.
+    fn write<'a>(
+        &mut self,
+        map: &MindMap,
+        events: impl IntoIterator<Item = &'a MapEvent>,
+        subtree_trash: Option<Vec<TrashEntry>>,
+    ) -> Result<(), BrainCoreError> {
+        let mut dirty = BTreeSet::new();
+        let mut removed = BTreeSet::new();
+        for event in events.into_iter().flat_map(MapEvent::events) {
+            match event {
+                MapEvent::NodeAdded { node_id, .. } => {
+                    dirty.extend(map.descendants(node_id).map(|n| n.id.as_str()));
+                    dirty.insert(node_id.as_str());
+                }
+                MapEvent::NodeRemoved { node_id } => {
+                    removed.insert(node_id.as_str());
+                }
+                MapEvent::NodeChanged { node_id } | MapEvent::NodeMoved { node_id, .. } => {
+                    dirty.insert(node_id.as_str());
+                }
+                MapEvent::SelectionChanged { .. } | MapEvent::Batch { .. } => {}
+            }
+        }
+
+        let tx = self.conn.transaction()?;
+        for node_id in removed {
+            if !map.nodes.contains_key(node_id) {
+                tx.execute(
+                    "WITH RECURSIVE subtree (id) AS (
+                         SELECT ?1
+                         UNION ALL
+                         SELECT nodes.id FROM nodes JOIN subtree ON nodes.parent = subtree.id
+                     )
+                     DELETE FROM nodes WHERE id IN subtree",
+                    [node_id],
+                )?;
+            }
+        }
+        for node in dirty.into_iter().filter_map(|id| map.nodes.get(id)) {
+            write_node(&tx, map, node)?;
+        }
+        match subtree_trash {
+            Some(trash) => write_trash(&tx, &trash)?,
+            None => write_meta(&tx, map)?,
+        }
+        write_resources(&tx, map)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn meta(&self, key: &str) -> Result<Option<String>, BrainCoreError> {
+        Ok(self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    fn read_nodes(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<HashMap<String, Node>, BrainCoreError> {
+        let mut statement = self.conn.prepare(sql)?;
+        let mut rows = statement.query(params)?;
+        let mut fields: Vec<(String, Option<String>, i64, Value)> = Vec::new();
+        while let Some(row) = rows.next()? {
+            let mut data: Value =
+                serde_json::from_str(&row.get::<_, String>("data")?).map_err(corrupt)?;
+            let object = data.as_object_mut().ok_or_else(|| corrupt("node data"))?;
+            let id: String = row.get("id")?;
+            object.insert("id".into(), id.clone().into());
+            object.insert("content".into(), row.get::<_, String>("content")?.into());
+            object.insert("created".into(), row.get::<_, i64>("created")?.into());
+            object.insert("modified".into(), row.get::<_, i64>("modified")?.into());
+            object.insert("children".into(), Value::Array(Vec::new()));
+            object.insert("parent".into(), Value::Null);
+            fields.push((id, row.get("parent")?, row.get("position")?, data));
+        }
+
+        let mut icons = self
+            .conn
+            .prepare("SELECT icon FROM icons WHERE node_id = ?1 ORDER BY position")?;
+        let mut nodes = HashMap::new();
+        let mut children: HashMap<String, Vec<(i64, String)>> = HashMap::new();
+        for (id, parent, position, data) in fields {
+            let mut node: Node = serde_json::from_value(data).map_err(corrupt)?;
+            node.icons = icons
+                .query_map([&id], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            if let Some(parent) = &parent {
+                children
+                    .entry(parent.clone())
+                    .or_default()
+                    .push((position, id.clone()));
+            }
+            node.parent = parent;
+            nodes.insert(id, node);
+        }
+        for (parent, mut list) in children {
+            if let Some(node) = nodes.get_mut(&parent) {
+                list.sort();
+                node.children = list.into_iter().map(|(_, id)| id).collect();
+            }
+        }
+        Ok(nodes)
+    }
+
+    fn read_resources(&self, map: &mut MindMap) -> Result<(), BrainCoreError> {
+        let mut statement = self.conn.prepare("SELECT data_ref, data FROM resources")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let data_ref: String = row.get(0)?;
+            map.resources.restore(data_ref, row.get(1)?);
+        }
+        Ok(())
+    }
+
+    /// Reads only the files the map's nodes are attached to.
+    fn read_attached_resources(&self, map: &mut MindMap) -> Result<(), BrainCoreError> {
+        let refs: BTreeSet<String> = map
+            .nodes
+            .values()
+            .flat_map(|n| &n.attachments)
+            .map(|a| a.data_ref.clone())
+            .collect();
+        let mut statement = self
+            .conn
+            .prepare("SELECT data FROM resources WHERE data_ref = ?1")?;
+        for data_ref in refs {
+            if let Some(data) = statement
+                .query_row([&data_ref], |row| row.get(0))
+                .optional()?
+            {
+                map.resources.restore(data_ref, data);
+            }
+        }
+        Ok(())
+    }
+
+    fn read_relationships(&self) -> Result<Vec<Relationship>, BrainCoreError> {
+        let mut statement = self
+            .conn
+            .prepare("SELECT id, from_id, to_id, label FROM relationships ORDER BY rowid")?;
+        let relationships = statement
+            .query_map([], |row| {
+                Ok(Relationship {
+                    id: row.get(0)?,
+                    from: row.get(1)?,
+                    to: row.get(2)?,
+                    label: row.get(3)?,
+                })
+            })?
+            .collect::<Result<_, _>>()?;
+        Ok(relationships)
+    }
+}
+
+/// Writes a node and the order of its children. The root of `map` keeps
+/// its stored place, as it may be the top of a loaded subtree.
+fn write_node(tx: &Transaction, map: &MindMap, node: &Node) -> Result<(), BrainCoreError> {
+    let mut data = serde_json::to_value(node).map_err(BrainCoreError::export)?;
+    if let Some(object) = data.as_object_mut() {
+        for column in COLUMNS {
+            object.remove(column);
+        }
+    }
+    let parent = node.parent.as_deref();
+    let position = parent
+        .and_then(|p| map.nodes.get(p))
+        .and_then(|p| p.children.iter().position(|c| *c == node.id))
+        .unwrap_or_default() as i64;
+    tx.execute(
+        "INSERT INTO nodes (id, parent, position, content, created, modified, data)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT (id) DO UPDATE SET
+             parent = CASE WHEN ?8 THEN parent ELSE excluded.parent END,
+             position = CASE WHEN ?8 THEN position ELSE excluded.position END,
+             content = excluded.content,
+             created = excluded.created,
+             modified = excluded.modified,
+             data = excluded.data",
+        params![
+            node.id,
+            parent,
+            position,
+            node.content,
+            node.created as i64,
+            node.modified as i64,
+            data.to_string(),
+            node.id == map.root_id,
+        ],
+    )?;
+    tx.execute("DELETE FROM icons WHERE node_id = ?1", [&node.id])?;
+    for (position, icon) in node.icons.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO icons (node_id, position, icon) VALUES (?1, ?2, ?3)",
+            params![node.id, position as i64, icon],
+        )?;
+    }
+    for (position, child) in node.children.iter().enumerate() {
+        tx.execute(
+            "UPDATE nodes SET parent = ?1, position = ?2 WHERE id = ?3",
+            params![node.id, position as i64, child],
+        )?;
+    }
+    Ok(())
+}
+
+/// Adds the stored files the database lacks. Files are keyed by their
+/// contents, so existing rows never change.
+fn write_resources(tx: &Transaction, map: &MindMap) -> Result<(), BrainCoreError> {
+    for (data_ref, data) in map.resources.iter() {
+        tx.execute(
+            "INSERT OR IGNORE INTO resources (data_ref, data) VALUES (?1, ?2)",
+            params![data_ref, data],
+        )?;
+    }
+    Ok(())
+}
+
+fn write_meta(tx: &Transaction, map: &MindMap) -> Result<(), BrainCoreError> {
+    let floating = serde_json::to_string(&map.floating_roots).map_err(BrainCoreError::export)?;
+    let groups = serde_json::to_string(&map.groups).map_err(BrainCoreError::export)?;
+    let summaries = serde_json::to_string(&map.summaries).map_err(BrainCoreError::export)?;
+    let format_version =
+        serde_json::to_string(&map.format_version).map_err(BrainCoreError::export)?;
+    let passthrough = serde_json::to_string(&map.passthrough).map_err(BrainCoreError::export)?;
+    let entries = [
+        ("root_id", map.root_id.clone()),
+        ("selected_node_id", map.selection.primary.clone()),
+        ("revision", map.revision.to_string()),
+        ("floating_roots", floating),
+        ("groups", groups),
+        ("summaries", summaries),
+        ("format_version", format_version),
+        ("passthrough", passthrough),
+    ];
+    for (key, value) in entries {
+        write_meta_entry(tx, key, &value)?;
+    }
+    write_trash(tx, &map.trash)?;
+    tx.execute("DELETE FROM relationships", [])?;
+    for relationship in &map.relationships {
+        tx.execute(
+            "INSERT INTO relationships (id, from_id, to_id, label) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                relationship.id,
+                relationship.from,
+                relationship.to,
+                relationship.label
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+fn write_trash(tx: &Transaction, trash: &[TrashEntry]) -> Result<(), BrainCoreError> {
+    let trash = serde_json::to_string(trash).map_err(BrainCoreError::export)?;
+    write_meta_entry(tx, "trash", &trash)
+}
+
+fn write_meta_entry(tx: &Transaction, key: &str, value: &str) -> Result<(), BrainCoreError> {
+    tx.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+fn corrupt(error: impl std::fmt::Display) -> BrainCoreError {
+    BrainCoreError::InvalidOperation(format!("Corrupt map database: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_sqlite_store() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.set_icons(&a, vec!["idea".to_string(), "flag".to_string()])
+            .unwrap();
+        map.set_note(&a1, "Note").unwrap();
+        map.add_relationship(&a1, &b, Some("see")).unwrap();
+        map.add_group(&a, Some("First")).unwrap();
+        map.attach(&a1, "photo.png", vec![1, 2, 3]).unwrap();
+        map.attach(&b, "notes.txt", b"text".to_vec()).unwrap();
+
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.save(&map).unwrap();
+        let loaded = store.load().unwrap();
+        assert_eq!(
+            serde_json::to_value(&loaded).unwrap(),
+            serde_json::to_value(&map).unwrap()
+        );
+
+        // Edits to a loaded subtree are written back in place.
+        let mut part = store.load_subtree(&a).unwrap();
+        assert_eq!(part.map.nodes.len(), 2);
+        assert_eq!(part.map.resources.len(), 1);
+        let (sender, receiver) = channel();
+        part.map.add_observer(sender);
+        part.map.change_node(&a, "Renamed").unwrap();
+        let a2 = part.map.add_child(&a, "A2").unwrap();
+        part.map.trash_node(&a1).unwrap();
+        let events: Vec<MapEvent> = receiver.try_iter().collect();
+        store.write_subtree_events(&part, &events).unwrap();
+
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.nodes[&a].content, "Renamed");
+        assert_eq!(loaded.nodes[&a].parent.as_ref(), Some(&root_id));
+        assert_eq!(loaded.nodes[&a].children, vec![a2]);
+        assert_eq!(loaded.nodes[&root_id].children, vec![a.clone(), b]);
+        assert!(!loaded.nodes.contains_key(&a1));
+        assert_eq!(loaded.nodes[&a].icons, vec!["idea", "flag"]);
+        assert_eq!(loaded.trash.len(), 1);
+        assert_eq!(loaded.trash[0].node_id, a1);
+        assert_eq!(loaded.groups, map.groups);
+        assert_eq!(loaded.revision, map.revision);
+
+        // A subtree from the root leaves the map's meta data alone too.
+        let whole = store.load_subtree(&root_id).unwrap();
+        store.write_subtree_events(&whole, &[]).unwrap();
+        let reloaded = store.load().unwrap();
+        assert_eq!(reloaded.groups, map.groups);
+        assert_eq!(reloaded.trash.len(), 1);
+        assert_eq!(reloaded.relationships.len(), loaded.relationships.len());
+        assert!(matches!(
+            store.load_subtree("missing"),
+            Err(BrainCoreError::NodeNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{BrainCoreError, MindMap};
+use std::collections::HashMap;
+
+/// Counts and shape of a map, or of the subtree under one node.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MapStats {
+    /// Nodes in the tree, including its top node.
+    pub node_count: usize,
+    pub leaf_count: usize,
+    /// Levels below the top node; a lone node has depth 0.
+    pub max_depth: usize,
+    /// Mean number of children of the nodes that have any.
+    pub average_branching: f64,
+    /// Whitespace-separated words in node content; notes aren't counted.
+    pub word_count: usize,
+    /// Nodes at each depth, the top node at index 0.
+    pub nodes_per_depth: Vec<usize>,
+    /// The top node's child with the most nodes under it.
+    pub largest_subtree: Option<SubtreeSize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubtreeSize {
+    pub node_id: String,
+    /// Nodes in the subtree, including its top node.
+    pub size: usize,
+}
+
+impl MindMap {
+    /// Statistics of the root's tree. Floating trees aren't counted; pass
+    /// their roots to [`MindMap::subtree_stats`].
+    pub fn stats(&self) -> MapStats {
+        self.subtree_stats(&self.root_id).unwrap_or_default()
+    }
+
+    /// Statistics of the subtree under `node_id`, archived nodes included.
+    pub fn subtree_stats(&self, node_id: &str) -> Result<MapStats, BrainCoreError> {
+        if !self.nodes.contains_key(node_id) {
+            return Err(BrainCoreError::NodeNotFound(node_id.to_string()));
+        }
+        let mut stats = MapStats::default();
+        let mut parents = 0;
+        // Each node's child of the top node, to size the top's subtrees.
+        let mut branch_sizes: Vec<(String, usize)> = Vec::new();
+        let mut stack: Vec<(&str, usize, Option<usize>)> = vec![(node_id, 0, None)];
+        while let Some((id, depth, branch)) = stack.pop() {
+            let Some(node) = self.nodes.get(id) else {
+                continue;
+            };
+            stats.node_count += 1;
+            stats.word_count += node.content.split_whitespace().count();
+            stats.max_depth = stats.max_depth.max(depth);
+            if stats.nodes_per_depth.len() <= depth {
+                stats.nodes_per_depth.resize(depth + 1, 0);
+            }
+            stats.nodes_per_depth[depth] += 1;
+            if let Some(branch) = branch {
+                branch_sizes[branch].1 += 1;
+            }
+            if node.children.is_empty() {
+                stats.leaf_count += 1;
+            } else {
+                parents += 1;
+            }
+            for child in node.children.iter().rev() {
+                let branch = branch.or_else(|| {
+                    branch_sizes.push((child.clone(), 0));
+                    Some(branch_sizes.len() - 1)
+                });
+                stack.push((child, depth + 1, branch));
+            }
+        }
+        stats.average_branching = if parents == 0 {
+            0.0
+        } else {
+            (stats.node_count - 1) as f64 / parents as f64
+        };
+        // The first of equally large subtrees wins.
+        stats.largest_subtree = branch_sizes
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, size)| *size)
+            .map(|(node_id, size)| SubtreeSize { node_id, size });
+        Ok(stats)
+    }
+}
+
+/// Size and shape of one first-level branch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BranchBalance {
+    pub node_id: String,
+    /// Nodes in the branch, including its top node.
+    pub size: usize,
+    /// Levels in the branch; a single node has depth 1.
+    pub depth: usize,
+    /// Fraction of all non-root nodes that live in this branch.
+    pub share: f64,
+}
+
+/// A node whose subtree has grown large enough to be worth splitting.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitSuggestion {
+    pub node_id: String,
+    pub size: usize,
+    pub child_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceReport {
+    pub branches: Vec<BranchBalance>,
+    /// Coefficient of variation of branch sizes; 0 for perfectly even
+    /// branches (or fewer than two).
+    pub size_imbalance: f64,
+    /// Population variance of leaf depths.
+    pub depth_variance: f64,
+    /// `ln(1 + nodes) * (1 + sqrt(depth_variance)) * (1 + size_imbalance)`:
+    /// grows with size and with how lopsided the tree is.
+    pub complexity_score: f64,
+    /// Largest offenders first.
+    pub suggestions: Vec<SplitSuggestion>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceOptions {
+    /// Suggest splitting a non-root subtree holding more than this fraction
+    /// of all non-root nodes.
+    pub max_branch_share: f64,
+    /// Suggest splitting any node with more direct children than this.
+    pub max_children: usize,
+    /// Subtrees smaller than this are never flagged.
+    pub min_split_size: usize,
+}
+
+impl Default for BalanceOptions {
+    fn default() -> Self {
+        Self {
+            max_branch_share: 0.4,
+            max_children: 12,
+            min_split_size: 8,
+        }
+    }
+}
+
+pub fn balance(map: &MindMap) -> BalanceReport {
+    balance_with(map, &BalanceOptions::default())
+}
+
+pub fn balance_with(map: &MindMap, options: &BalanceOptions) -> BalanceReport {
+    // Pre-order walk recording depth; reversing it gives a post-order-safe
+    // sequence for accumulating subtree sizes and heights bottom-up.
+    let mut order: Vec<(&str, usize)> = Vec::new();
+    let mut stack = vec![(map.root_id.as_str(), 0)];
+    while let Some((id, depth)) = stack.pop() {
+        let Some(node) = map.nodes.get(id) else {
+            continue;
+        };
+        order.push((id, depth));
+        stack.extend(node.children.iter().map(|c| (c.as_str(), depth + 1)));
+    }
+
+    let mut size: HashMap<&str, usize> = HashMap::new();
+    let mut height: HashMap<&str, usize> = HashMap::new();
+    let mut leaf_depths = Vec::new();
+    for &(id, depth) in order.iter().rev() {
+        let node = &map.nodes[id];
+        let (mut s, mut h) = (1, 0);
+        for child in &node.children {
+            s += size.get(child.as_str()).copied().unwrap_or(0);
+            h = h.max(height.get(child.as_str()).copied().unwrap_or(0));
+        }
+        if node.children.is_empty() {
+            leaf_depths.push(depth as f64);
+        }
+        size.insert(id, s);
+        height.insert(id, h + 1);
+    }
+
+    let total = order.len().saturating_sub(1);
+    let branches: Vec<BranchBalance> = map
+        .nodes
+        .get(&map.root_id)
+        .map(|root| {
+            root.children
+                .iter()
+                .filter_map(|id| {
+                    Some(BranchBalance {
+                        node_id: id.clone(),
+                        size: *size.get(id.as_str())?,
+                        depth: height[id.as_str()],
+                        share: size[id.as_str()] as f64 / total.max(1) as f64,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let sizes: Vec<f64> = branches.iter().map(|b| b.size as f64).collect();
+    let size_imbalance = if sizes.len() < 2 {
+        0.0
+    } else {
+        variance(&sizes).sqrt() / mean(&sizes)
+    };
+    let depth_variance = variance(&leaf_depths);
+    let complexity_score =
+        (1.0 + order.len() as f64).ln() * (1.0 + depth_variance.sqrt()) * (1.0 + size_imbalance);
+
+    let mut suggestions: Vec<SplitSuggestion> = order
+        .iter()
+        .filter(|(id, _)| *id != map.root_id)
+        .filter_map(|&(id, _)| {
+            let s = size[id];
+            let child_count = map.nodes[id].children.len();
+            let too_big = s as f64 > options.max_branch_share * total as f64;
+            let too_wide = child_count > options.max_children;
+            (s >= options.min_split_size && (too_big || too_wide)).then(|| SplitSuggestion {
+                node_id: id.to_string(),
+                size: s,
+                child_count,
+            })
+        })
+        .collect();
+    suggestions.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.node_id.cmp(&b.node_id)));
+
+    BalanceReport {
+        branches,
+        size_imbalance,
+        depth_variance,
+        complexity_score,
+        suggestions,
+    }
+}
+
+fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+fn variance(values: &[f64]) -> f64 {
+    let m = mean(values);
+    mean(&values.iter().map(|v| (v - m).powi(2)).collect::<Vec<_>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Node;
+
+    fn add_child_for_test(map: &mut MindMap, parent_id: &str, content: &str) -> String {
+        let id = format!("node-{}", map.nodes.len());
+        let node = Node {
+            id: id.clone(),
+            content: content.to_string(),
+            parent: Some(parent_id.to_string()),
+            ..Default::default()
+        };
+        map.nodes.insert(id.clone(), node);
+        if let Some(parent) = map.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        id
+    }
+
+    #[test]
+    fn test_balance_flags_lopsided_branch() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let big = add_child_for_test(&mut map, &root_id, "Big");
+        let small = add_child_for_test(&mut map, &root_id, "Small");
+        for i in 0..9 {
+            add_child_for_test(&mut map, &big, &format!("Item {}", i));
+        }
+
+        let report = balance(&map);
+        assert_eq!(report.branches.len(), 2);
+        assert_eq!(report.branches[0].size, 10);
+        assert_eq!(report.branches[0].depth, 2);
+        assert_eq!(report.branches[1].node_id, small);
+        assert!(report.size_imbalance > 0.5);
+        assert!(report.depth_variance > 0.0);
+        assert_eq!(report.suggestions.len(), 1);
+        assert_eq!(report.suggestions[0].node_id, big);
+
+        let even = balance(&MindMap::new());
+        assert_eq!(even.size_imbalance, 0.0);
+        assert!(even.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_map_stats() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let small = add_child_for_test(&mut map, &root_id, "Small branch");
+        let big = add_child_for_test(&mut map, &root_id, "Big");
+        let middle = add_child_for_test(&mut map, &big, "Three words here");
+        add_child_for_test(&mut map, &middle, "Deep");
+        add_child_for_test(&mut map, &big, "Leaf");
+
+        let stats = map.stats();
+        assert_eq!(stats.node_count, 6);
+        assert_eq!(stats.leaf_count, 3);
+        assert_eq!(stats.max_depth, 3);
+        assert_eq!(stats.nodes_per_depth, [1, 2, 2, 1]);
+        assert_eq!(stats.word_count, 10);
+        assert_eq!(stats.average_branching, 5.0 / 3.0);
+        assert_eq!(
+            stats.largest_subtree,
+            Some(SubtreeSize {
+                node_id: big.clone(),
+                size: 4
+            })
+        );
+
+        let branch = map.subtree_stats(&small).unwrap();
+        assert_eq!((branch.node_count, branch.max_depth), (1, 0));
+        assert_eq!(branch.average_branching, 0.0);
+        assert!(branch.largest_subtree.is_none());
+        assert!(map.subtree_stats("missing").is_err());
+    }
+}
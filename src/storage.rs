@@ -1,13 +1,40 @@
-use crate::{MindMap, Node};
-use quick_xml::de::from_str;
-use quick_xml::se::to_string;
+use crate::export::{ExportOptions, TextWriter};
+use crate::groups::{Group, GroupStyle};
+use crate::html;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::layout::Offsets;
+use crate::preserve::{UnknownAttributes, XmlAttributes};
+use crate::progress::{Monitor, Stage};
+use crate::relationship::Relationship;
+use crate::style::NodeStyle;
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "map")]
 pub struct XmlMap {
     #[serde(rename = "@version")]
     pub version: String,
+    /// ID of the selected node. Not part of FreeMind's format, which
+    /// ignores unknown attributes; omitted when the root is selected.
+    #[serde(rename = "@SELECTED", default, skip_serializing_if = "Option::is_none")]
+    pub selected: Option<String>,
+    /// Map revision, another extension attribute; omitted while zero.
+    #[serde(rename = "@REVISION", default, skip_serializing_if = "is_zero")]
+    pub revision: u64,
+    /// Map-level elements kept from an imported file, written ahead of the
+    /// root as Freeplane does. Only written, like [`XmlNode::passthrough`].
+    #[serde(flatten, skip_deserializing)]
+    pub passthrough: XmlPassthrough,
     #[serde(rename = "node")]
     pub root: XmlNode,
 }
@@ -19,59 +46,449 @@ pub struct XmlIcon {
     pub builtin: String,
 }
 
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "edge")]
+pub struct XmlEdge {
+    #[serde(rename = "@COLOR", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// A width in pixels, or `thin`.
+    #[serde(rename = "@WIDTH", default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<String>,
+}
+
+/// A cloud drawn around the node and its descendants; the first of the
+/// node's groups. Clouds have no title or border color.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "cloud")]
+pub struct XmlCloud {
+    #[serde(rename = "@COLOR", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "font")]
+pub struct XmlFont {
+    #[serde(rename = "@NAME", default)]
+    pub name: String,
+    #[serde(rename = "@SIZE", default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u32>,
+    #[serde(rename = "@BOLD", default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<String>,
+    #[serde(rename = "@ITALIC", default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<String>,
+}
+
+/// A relationship starting at the enclosing node.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "arrowlink")]
+pub struct XmlArrowLink {
+    #[serde(rename = "@DESTINATION")]
+    pub destination: String,
+    #[serde(rename = "@ID", default)]
+    pub id: String,
+    #[serde(rename = "@ENDARROW", default, skip_serializing_if = "Option::is_none")]
+    pub end_arrow: Option<String>,
+    /// Freeplane's label attribute; FreeMind itself has no relationship
+    /// labels.
+    #[serde(
+        rename = "@MIDDLE_LABEL",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub label: Option<String>,
+}
+
+/// Name of the Freeplane attribute each of a node's tags is written as,
+/// one attribute per tag. FreeMind has no tags of its own.
+pub const TAG_ATTRIBUTE: &str = "tag";
+
+/// A Freeplane attribute, one name and value pair of a node.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "attribute")]
+pub struct XmlAttribute {
+    #[serde(rename = "@NAME")]
+    pub name: String,
+    #[serde(rename = "@VALUE", default)]
+    pub value: String,
+}
+
+/// Rich text attached to a node: `TYPE="NODE"` for the node text itself,
+/// `TYPE="NOTE"` for its note and Freeplane's `TYPE="DETAILS"` for its
+/// details. FreeMind stores it as XHTML; on import the markup is flattened
+/// to plain text before deserialization, and on export each line is written
+/// as a paragraph.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename = "richcontent")]
+pub struct XmlRichContent {
+    #[serde(rename = "@TYPE")]
+    pub kind: String,
+    #[serde(rename = "$text", default, skip_serializing)]
+    pub text: String,
+    #[serde(
+        rename = "html",
+        default,
+        skip_deserializing,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub html: Option<XmlHtml>,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct XmlHtml {
+    pub head: (),
+    pub body: XmlHtmlBody,
+}
+
+#[derive(Debug, Default, Serialize, PartialEq)]
+pub struct XmlHtmlBody {
+    #[serde(rename = "p")]
+    pub paragraphs: Vec<String>,
+}
+
+impl XmlRichContent {
+    fn paragraphs(kind: &str, text: &str) -> Self {
+        XmlRichContent {
+            kind: kind.to_string(),
+            text: String::new(),
+            html: Some(XmlHtml {
+                head: (),
+                body: XmlHtmlBody {
+                    paragraphs: text.lines().map(str::to_string).collect(),
+                },
+            }),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename = "node")]
 pub struct XmlNode {
-    #[serde(rename = "@ID")]
+    /// Missing in some hand-written and clipboard fragments; a fresh id is
+    /// generated on import.
+    #[serde(rename = "@ID", default)]
     pub id: String,
-    #[serde(rename = "@TEXT")]
+    #[serde(rename = "@TEXT", default)]
     pub text: String,
 
-    #[serde(rename = "@CREATED")]
-    pub created: u64,
-    #[serde(rename = "@MODIFIED")]
-    pub modified: u64,
+    #[serde(rename = "@CREATED", default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<u64>,
+    #[serde(rename = "@MODIFIED", default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+
+    #[serde(rename = "@FOLDED", default, skip_serializing_if = "Option::is_none")]
+    pub folded: Option<String>,
+
+    #[serde(rename = "@ARCHIVED", default, skip_serializing_if = "Option::is_none")]
+    pub archived: Option<String>,
+
+    #[serde(rename = "@COLOR", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(
+        rename = "@BACKGROUND_COLOR",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub background_color: Option<String>,
+
+    #[serde(rename = "@LINK", default, skip_serializing_if = "Option::is_none")]
+    pub link: Option<String>,
 
     #[serde(rename = "@POSITION", skip_serializing_if = "Option::is_none")]
     pub position: Option<String>,
 
+    /// Offset of a free node from the root, or of a node placed by hand
+    /// from its parent, in pixels. Other nodes leave these out: their
+    /// place comes from the layout.
+    #[serde(rename = "@HGAP", default, skip_serializing_if = "Option::is_none")]
+    pub hgap: Option<i32>,
+    #[serde(rename = "@VSHIFT", default, skip_serializing_if = "Option::is_none")]
+    pub vshift: Option<i32>,
+
+    #[serde(rename = "@REVISION", default, skip_serializing_if = "is_zero")]
+    pub revision: u64,
+
+    /// Attributes kept from an imported file. Only written, like
+    /// `passthrough`.
+    #[serde(flatten, skip_deserializing)]
+    pub unknown: XmlAttributes,
+
+    #[serde(rename = "edge", default, skip_serializing_if = "Option::is_none")]
+    pub edge: Option<XmlEdge>,
+
+    #[serde(rename = "cloud", default, skip_serializing_if = "Option::is_none")]
+    pub cloud: Option<XmlCloud>,
+
+    #[serde(rename = "font", default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<XmlFont>,
+
+    #[serde(rename = "arrowlink", default, skip_serializing_if = "Vec::is_empty")]
+    pub arrow_links: Vec<XmlArrowLink>,
+
     #[serde(rename = "icon", default)]
     pub icons: Vec<XmlIcon>,
 
+    #[serde(rename = "richcontent", default, skip_serializing_if = "Vec::is_empty")]
+    pub rich_content: Vec<XmlRichContent>,
+
+    #[serde(rename = "attribute", default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<XmlAttribute>,
+
+    /// Elements kept from an imported file. Only written: the importer
+    /// reads them from the event stream as raw markup.
+    #[serde(flatten, skip_deserializing)]
+    pub passthrough: XmlPassthrough,
+
     #[serde(rename = "node", default)]
     pub children: Vec<XmlNode>,
 }
 
-pub fn to_xml(map: &MindMap) -> Result<String, String> {
-    let root = map.nodes.get(&map.root_id).ok_or("Root not found")?;
-    let xml_root = to_xml_node(root, map, true);
+/// Elements of unknown meaning, written back as they were read.
+#[derive(Debug, Default, PartialEq)]
+pub struct XmlPassthrough(pub Vec<XmlElement>);
 
+#[derive(Debug, PartialEq)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub content: Vec<XmlContent>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum XmlContent {
+    Element(XmlElement),
+    Text(String),
+}
+
+// Serialized as maps so that element names can be chosen at runtime;
+// quick-xml writes `@` keys as attributes and `$text` as text.
+impl Serialize for XmlPassthrough {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for element in &self.0 {
+            map.serialize_entry(&element.name, element)?;
+        }
+        map.end()
+    }
+}
+
+impl Serialize for XmlElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        for (name, value) in &self.attributes {
+            map.serialize_entry(&format!("@{name}"), value)?;
+        }
+        for content in &self.content {
+            match content {
+                XmlContent::Element(element) => map.serialize_entry(&element.name, element)?,
+                XmlContent::Text(text) => map.serialize_entry("$text", text)?,
+            }
+        }
+        map.end()
+    }
+}
+
+impl XmlPassthrough {
+    /// Parses markup stored in [`Node::passthrough`]. Anything after a
+    /// syntax error is dropped.
+    fn parse(markup: &str) -> Self {
+        let mut reader = Reader::from_str(markup);
+        reader.config_mut().check_end_names = false;
+        let mut top = Vec::new();
+        let mut open: Vec<XmlElement> = Vec::new();
+        loop {
+            let (e, empty) = match reader.read_event() {
+                Ok(Event::Start(e)) => (e, false),
+                Ok(Event::Empty(e)) => (e, true),
+                Ok(Event::End(_)) => {
+                    if let Some(element) = open.pop() {
+                        match open.last_mut() {
+                            Some(parent) => parent.content.push(XmlContent::Element(element)),
+                            None => top.push(element),
+                        }
+                    }
+                    continue;
+                }
+                // Indentation between elements.
+                Ok(Event::Text(e)) if e.iter().all(u8::is_ascii_whitespace) => continue,
+                Ok(Event::Text(e)) => {
+                    push_text(&mut open, &lossy(&e));
+                    continue;
+                }
+                Ok(Event::CData(e)) => {
+                    push_text(&mut open, &lossy(&e));
+                    continue;
+                }
+                Ok(Event::GeneralRef(e)) => {
+                    if let Some(c) = html::entity(&lossy(&e)) {
+                        push_text(&mut open, &c.to_string());
+                    }
+                    continue;
+                }
+                Ok(Event::Eof) | Err(_) => break,
+                Ok(_) => continue,
+            };
+            let mut attributes: Vec<(String, String)> = attributes(&e, &reader)
+                .map(|a| {
+                    a.into_iter()
+                        .map(|(k, v)| (lossy(&k).into_owned(), v))
+                        .collect()
+                })
+                .unwrap_or_default();
+            // Attributes come back unordered; sort them for stable output.
+            attributes.sort();
+            let element = XmlElement {
+                name: lossy(e.name().as_ref()).into_owned(),
+                attributes,
+                content: Vec::new(),
+            };
+            match open.last_mut() {
+                Some(parent) if empty => parent.content.push(XmlContent::Element(element)),
+                None if empty => top.push(element),
+                _ => open.push(element),
+            }
+        }
+        XmlPassthrough(top)
+    }
+}
+
+fn push_text(open: &mut [XmlElement], text: &str) {
+    let Some(element) = open.last_mut() else {
+        return;
+    };
+    match element.content.last_mut() {
+        Some(XmlContent::Text(last)) => last.push_str(text),
+        _ => element.content.push(XmlContent::Text(text.to_string())),
+    }
+}
+
+/// Saves the map in its native format, archived nodes included.
+pub fn to_xml(map: &MindMap) -> Result<String, BrainCoreError> {
+    to_xml_with(map, &native_options())
+}
+
+/// What a plain native save writes: everything, archived subtrees too, as
+/// they are part of the document rather than hidden for good.
+pub(crate) fn native_options() -> ExportOptions {
+    ExportOptions {
+        include_archived: true,
+        ..Default::default()
+    }
+}
+
+pub fn to_xml_with(map: &MindMap, options: &ExportOptions) -> Result<String, BrainCoreError> {
+    to_xml_monitored(map, options, &mut Monitor::default())
+}
+
+pub fn to_xml_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut buf = Vec::new();
+    write_xml_monitored(map, &mut buf, options, monitor)?;
+    String::from_utf8(buf).map_err(BrainCoreError::export)
+}
+
+/// Streams the FreeMind document to `writer` instead of building a string.
+pub fn write_xml<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_xml_with(map, writer, &native_options())
+}
+
+pub fn write_xml_with<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+) -> Result<(), BrainCoreError> {
+    write_xml_monitored(map, writer, options, &mut Monitor::default())
+}
+
+pub fn write_xml_monitored<W: Write>(
+    map: &MindMap,
+    writer: &mut W,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<(), BrainCoreError> {
+    let root = map
+        .nodes
+        .get(&map.root_id)
+        .ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+    monitor.begin(Stage::WritingNodes, Some(options.exported_count(map, true)))?;
+    let offsets = map.slot_offsets();
+    let mut xml_root = to_xml_node(root, map, &offsets, options, monitor);
+    // Floating trees become free nodes: root children marked by a hook.
+    for node in options.exported_floating(map) {
+        let mut free = to_xml_node(node, map, &offsets, options, monitor);
+        free.position = Some("right".to_string());
+        free.hgap = Some((node.x - root.x).round() as i32);
+        free.vshift = Some((node.y - root.y).round() as i32);
+        free.passthrough.0.push(XmlElement {
+            name: "hook".to_string(),
+            attributes: vec![("NAME".to_string(), FREE_NODE_HOOK.to_string())],
+            content: Vec::new(),
+        });
+        xml_root.children.push(free);
+    }
+    monitor.finish()?;
+
+    let selected = &map.selection.primary;
     let xml_map = XmlMap {
-        version: "1.0.1".to_string(),
+        version: map
+            .format_version
+            .clone()
+            .unwrap_or_else(|| "1.0.1".to_string()),
+        selected: (*selected != map.root_id
+            && map.nodes.contains_key(selected)
+            && (options.include_archived || !map.is_archived(selected)))
+        .then(|| selected.clone()),
+        revision: map.revision,
+        passthrough: map
+            .passthrough
+            .as_deref()
+            .map(XmlPassthrough::parse)
+            .unwrap_or_default(),
         root: xml_root,
     };
 
-    let mut xml = String::from(
-        "<!-- To view this file, download free mind mapping software FreeMind from http://freemind.sourceforge.net -->\n",
+    writer
+        .write_all(
+            b"<!-- To view this file, download free mind mapping software FreeMind from http://freemind.sourceforge.net -->\n",
+        )?;
+    let mut text = TextWriter::new(&mut *writer);
+    let result = options.write_xml_as(
+        &xml_map,
+        Some("map"),
+        &mut html::AttributeEncoder::new(&mut text),
     );
-    xml.push_str(&to_string(&xml_map).map_err(|e| e.to_string())?);
-    Ok(xml)
+    text.check(result)?;
+    Ok(writer.flush()?)
 }
 
-fn to_xml_node(node: &Node, map: &MindMap, _is_root: bool) -> XmlNode {
+fn to_xml_node(
+    node: &Node,
+    map: &MindMap,
+    offsets: &HashMap<String, (f32, f32)>,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> XmlNode {
+    monitor.tick();
     let mut children = Vec::new();
     for child_id in &node.children {
-        if let Some(child_node) = map.nodes.get(child_id) {
-            children.push(to_xml_node(child_node, map, false));
+        if monitor.should_stop() {
+            break;
+        }
+        if let Some(child_node) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+            children.push(to_xml_node(child_node, map, offsets, options, monitor));
         }
     }
 
     let mut icons = Vec::new();
-    for icon_name in &node.icons {
+    let task_icons = node.task.as_ref().map(|t| t.icons(&node.icons));
+    for icon_name in node.icons.iter().chain(task_icons.iter().flatten()) {
         icons.push(XmlIcon {
             builtin: icon_name.clone(),
         });
     }
+    let style = &node.style;
 
     let position = if let Some(parent_id) = &node.parent {
         if parent_id == &map.root_id {
@@ -83,85 +500,531 @@ fn to_xml_node(node: &Node, map: &MindMap, _is_root: bool) -> XmlNode {
         None
     };
 
+    // A node placed by hand keeps its offset from its parent's right edge
+    // and from its place in the layout.
+    let offset = offsets
+        .get(&node.id)
+        .map(|(x, y)| (x.round() as i32, y.round() as i32));
+
     XmlNode {
         id: node.id.clone(),
         text: node.content.clone(),
-        created: node.created,
-        modified: node.modified,
+        created: (!options.suppress_timestamps).then_some(node.created),
+        modified: (!options.suppress_timestamps).then_some(node.modified),
+        folded: node.folded.then(|| "true".to_string()),
+        archived: node.archived.then(|| "true".to_string()),
+        color: style.text_color.clone(),
+        background_color: style.background_color.clone(),
+        link: node.link.clone(),
         position,
+        hgap: offset.map(|(x, _)| x),
+        vshift: offset.map(|(_, y)| y),
+        revision: node.revision,
+        unknown: XmlAttributes::of(node, ImportFormat::FreeMind),
+        edge: (style.edge_color.is_some() || style.edge_width.is_some()).then(|| XmlEdge {
+            color: style.edge_color.clone(),
+            width: style.edge_width.map(|w| w.to_string()),
+        }),
+        cloud: map.groups_of(&node.id).next().map(|group| XmlCloud {
+            color: group.style.fill_color.clone(),
+        }),
+        font: (style.font_size.is_some() || style.bold || style.italic).then(|| XmlFont {
+            name: "SansSerif".to_string(),
+            size: style.font_size,
+            bold: style.bold.then(|| "true".to_string()),
+            italic: style.italic.then(|| "true".to_string()),
+        }),
+        arrow_links: map
+            .exported_relationships(options)
+            .filter(|r| r.from == node.id)
+            .map(|r| XmlArrowLink {
+                destination: r.to.clone(),
+                id: r.id.clone(),
+                end_arrow: Some("Default".to_string()),
+                label: r.label.clone(),
+            })
+            .collect(),
         icons,
+        rich_content: [("NOTE", &node.note), ("DETAILS", &node.details)]
+            .into_iter()
+            .filter_map(|(kind, text)| Some(XmlRichContent::paragraphs(kind, text.as_deref()?)))
+            .collect(),
+        attributes: node
+            .attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value))
+            .chain(node.tags.iter().map(|tag| (TAG_ATTRIBUTE, tag)))
+            .map(|(name, value)| XmlAttribute {
+                name: name.to_string(),
+                value: value.clone(),
+            })
+            .collect(),
+        passthrough: node
+            .passthrough
+            .as_deref()
+            .map(XmlPassthrough::parse)
+            .unwrap_or_default(),
         children,
     }
 }
 
-pub fn from_xml(xml: &str) -> Result<MindMap, String> {
-    let xml_map: XmlMap = from_str(xml).map_err(|e| e.to_string())?;
+pub fn from_xml(xml: &str) -> Result<MindMap, BrainCoreError> {
+    from_xml_with(xml, &ImportOptions::default())
+}
 
-    let mut nodes = std::collections::HashMap::new();
-    let root_id = xml_map.root.id.clone();
+pub fn from_xml_with(xml: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_xml_monitored(xml, options, &mut Monitor::default())
+}
 
-    helpers::flatten_nodes(xml_map.root, None, &mut nodes);
+pub fn from_xml_monitored(
+    xml: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    read_xml_monitored(xml.as_bytes(), options, monitor)
+}
 
-    Ok(MindMap {
-        nodes,
-        root_id: root_id.clone(),
-        selected_node_id: root_id,
-    })
+/// Reads a FreeMind document from `reader` without loading it whole. Nodes
+/// are built straight from the parser's events, so memory use stays close
+/// to the size of the resulting map even for very large files.
+pub fn read_xml<R: BufRead>(reader: R) -> Result<MindMap, BrainCoreError> {
+    read_xml_with(reader, &ImportOptions::default())
 }
 
-mod helpers {
-    use super::*;
-    use crate::Node;
+pub fn read_xml_with<R: BufRead>(
+    reader: R,
+    options: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    read_xml_monitored(reader, options, &mut Monitor::default())
+}
+
+/// The node count is not known up front, so progress events carry no total.
+pub fn read_xml_monitored<R: BufRead>(
+    reader: R,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let mut reader = Reader::from_reader(reader);
+    // Rich content is XHTML as written by hand or by old versions; tolerate
+    // mismatched tags inside it as browsers do.
+    reader.config_mut().check_end_names = false;
+
+    let mut nodes = HashMap::new();
+    let mut relationships = Vec::new();
+    let mut groups = Vec::new();
+    let mut open: Vec<OpenNode> = Vec::new();
+    let mut capture: Option<Capture> = None;
+    let mut root_id = None;
+    let mut floating = Vec::new();
+    let mut selected = None;
+    let mut revision = 0;
+    let mut format_version = None;
+    // Elements outside the nodes, such as Freeplane's attribute registry.
+    let mut map_passthrough: Option<String> = None;
 
-    pub fn flatten_nodes(
-        xml_node: XmlNode,
-        parent_id: Option<String>,
-        nodes: &mut std::collections::HashMap<String, Node>,
-    ) {
-        let node_id = xml_node.id.clone();
+    monitor.begin(Stage::BuildingNodes, None)?;
+    let mut buf = Vec::new();
+    while !monitor.should_stop() {
+        buf.clear();
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| parse_error(&reader, e))?;
 
-        let mut children_ids = Vec::new();
-        for child in &xml_node.children {
-            children_ids.push(child.id.clone());
+        if let Some(current) = &mut capture {
+            let markup = &mut current.markup;
+            match event {
+                Event::End(e) if current.depth == 0 => {
+                    if current.kind == CaptureKind::Unknown {
+                        markup.push_str(&format!("</{}>", lossy(&e)));
+                    }
+                    match (capture.take(), open.last_mut()) {
+                        (Some(current), Some(node)) => current.finish(node),
+                        (Some(current), None) if current.kind == CaptureKind::Unknown => {
+                            map_passthrough
+                                .get_or_insert_with(String::new)
+                                .push_str(&current.markup);
+                        }
+                        _ => {}
+                    }
+                }
+                Event::Start(e) => {
+                    current.depth += 1;
+                    markup.push_str(&format!("<{}>", lossy(&e)));
+                }
+                Event::End(e) => {
+                    current.depth -= 1;
+                    markup.push_str(&format!("</{}>", lossy(&e)));
+                }
+                Event::Empty(e) => markup.push_str(&format!("<{}/>", lossy(&e))),
+                Event::Text(e) => markup.push_str(&lossy(&e)),
+                Event::GeneralRef(e) => markup.push_str(&format!("&{};", lossy(&e))),
+                Event::CData(e) => markup.push_str(&escape(lossy(&e))),
+                Event::Eof => break,
+                _ => {}
+            }
+            continue;
+        }
+
+        let (e, empty) = match event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) if e.local_name().as_ref() == b"node" && !open.is_empty() => {
+                close_node(&mut open, &mut nodes, &mut root_id, &mut floating);
+                monitor.tick();
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        let mut attrs = attributes(&e, &reader)?;
+        let mut take = |key: &[u8]| attrs.remove(key);
+        match e.local_name().as_ref() {
+            b"map" => {
+                selected = take(b"SELECTED");
+                revision = take(b"REVISION").and_then(|r| r.parse().ok()).unwrap_or(0);
+                // FreeMind's own versions are left behind: the exporter
+                // writes the 1.0 format whatever version was read.
+                format_version = take(b"version").filter(|v| v.starts_with("freeplane"));
+            }
+            b"node" => {
+                let parent = open.last().map(|n| n.node.id.clone());
+                let node = OpenNode::new(attrs, parent, options.preserve_unknown);
+                open.push(node);
+                if empty {
+                    close_node(&mut open, &mut nodes, &mut root_id, &mut floating);
+                    monitor.tick();
+                }
+            }
+            b"richcontent" if !empty => {
+                capture = Some(Capture::new(CaptureKind::RichContent(
+                    take(b"TYPE").unwrap_or_default(),
+                )));
+            }
+            b"hook" if !empty && take(b"NAME").as_deref() == Some(NOTE_HOOK) => {
+                capture = Some(Capture::new(CaptureKind::NoteHook));
+            }
+            b"hook" if empty && take(b"NAME").as_deref() == Some(FREE_NODE_HOOK) => {
+                if let Some(node) = open.last_mut() {
+                    node.free = true;
+                }
+            }
+            _ if open.is_empty() => {
+                if empty {
+                    map_passthrough
+                        .get_or_insert_with(String::new)
+                        .push_str(&format!("<{}/>", lossy(&e)));
+                } else {
+                    let mut unknown = Capture::new(CaptureKind::Unknown);
+                    unknown.markup = format!("<{}>", lossy(&e));
+                    capture = Some(unknown);
+                }
+            }
+            name => {
+                let Some(node) = open.last_mut().map(|n| &mut n.node) else {
+                    continue;
+                };
+                let style = &mut node.style;
+                match name {
+                    b"icon" => node.icons.extend(take(b"BUILTIN")),
+                    b"edge" => {
+                        style.edge_color = take(b"COLOR");
+                        style.edge_width = take(b"WIDTH").and_then(|w| w.parse().ok());
+                    }
+                    b"font" => {
+                        style.font_size = take(b"SIZE").and_then(|s| s.parse().ok());
+                        style.bold = take(b"BOLD").as_deref() == Some("true");
+                        style.italic = take(b"ITALIC").as_deref() == Some("true");
+                    }
+                    b"arrowlink" => {
+                        if let Some(to) = take(b"DESTINATION") {
+                            relationships.push(Relationship {
+                                id: take(b"ID")
+                                    .filter(|id| !id.is_empty())
+                                    .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+                                from: node.id.clone(),
+                                to,
+                                label: take(b"MIDDLE_LABEL").filter(|l| !l.is_empty()),
+                            });
+                        }
+                    }
+                    b"cloud" => groups.push(Group {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        node_id: node.id.clone(),
+                        title: None,
+                        style: GroupStyle {
+                            fill_color: take(b"COLOR"),
+                            border_color: None,
+                        },
+                    }),
+                    b"richcontent" => {}
+                    b"attribute" => {
+                        let value = take(b"VALUE").unwrap_or_default();
+                        match take(b"NAME") {
+                            Some(name) if name == TAG_ATTRIBUTE => node.tags.push(value),
+                            Some(name) => node.attributes.push((name, value)),
+                            None => {}
+                        }
+                    }
+                    _ if empty => {
+                        node.passthrough
+                            .get_or_insert_with(String::new)
+                            .push_str(&format!("<{}/>", lossy(&e)));
+                    }
+                    _ => {
+                        let mut unknown = Capture::new(CaptureKind::Unknown);
+                        unknown.markup = format!("<{}>", lossy(&e));
+                        capture = Some(unknown);
+                    }
+                }
+            }
         }
+    }
+    monitor.finish()?;
+
+    let root_id = root_id.ok_or_else(|| {
+        BrainCoreError::parse(ImportFormat::FreeMind, "Document has no root node")
+    })?;
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    map.floating_roots = floating;
+    map.revision = revision;
+    map.format_version = format_version;
+    map.passthrough = map_passthrough;
+    if let Some(selected) = selected.filter(|id| map.nodes.contains_key(id)) {
+        map.selection.primary = selected;
+    }
+    map.relationships = relationships
+        .into_iter()
+        .filter(|r| map.nodes.contains_key(&r.to))
+        .collect();
+    map.groups = groups;
+    options.apply(&mut map)?;
+    map.place_pinned_offsets(Offsets::FromSlot);
+    Ok(map)
+}
+
+/// FreeMind 0.8 kept notes in a plugin hook: `<hook NAME="..."><text>`.
+const NOTE_HOOK: &str = "accessories/plugins/NodeNote.properties";
 
-        for child in xml_node.children {
-            flatten_nodes(child, Some(node_id.clone()), nodes);
+/// Freeplane marks root children that float freely with `<hook NAME=...>`.
+const FREE_NODE_HOOK: &str = "FreeNode";
+
+/// An element inside a node that is read whole before it is interpreted.
+struct Capture {
+    kind: CaptureKind,
+    /// Elements open inside the captured one.
+    depth: usize,
+    markup: String,
+}
+
+#[derive(PartialEq)]
+enum CaptureKind {
+    /// `<richcontent>` of the given `TYPE`; only its inner markup is kept.
+    RichContent(String),
+    NoteHook,
+    /// An element the crate doesn't know, kept with its own tags.
+    Unknown,
+}
+
+impl Capture {
+    fn new(kind: CaptureKind) -> Self {
+        Capture {
+            kind,
+            depth: 0,
+            markup: String::new(),
         }
+    }
 
-        let mut icons = Vec::new();
-        for icon in xml_node.icons {
-            icons.push(icon.builtin);
+    fn finish(self, open: &mut OpenNode) {
+        let node = &mut open.node;
+        match self.kind {
+            CaptureKind::RichContent(kind) => {
+                let text = Some(html::to_plain_text(&self.markup)).filter(|t| !t.is_empty());
+                match kind.as_str() {
+                    "NODE" => open.rich_text = text,
+                    "NOTE" => node.note = text,
+                    "DETAILS" => node.details = text,
+                    _ => {}
+                }
+            }
+            CaptureKind::NoteHook => {
+                let text = html::to_plain_text(&self.markup.replace('\n', "<br>"));
+                node.note = Some(text).filter(|t| !t.is_empty());
+            }
+            CaptureKind::Unknown => node
+                .passthrough
+                .get_or_insert_with(String::new)
+                .push_str(&self.markup),
         }
+    }
+}
 
+/// A `<node>` whose end tag has not been read yet.
+#[derive(Default)]
+struct OpenNode {
+    node: Node,
+    created: Option<u64>,
+    modified: Option<u64>,
+    /// `TEXT` attribute; empty in FreeMind 0.9+ when the text is rich.
+    text: String,
+    rich_text: Option<String>,
+    /// Carries the free node hook.
+    free: bool,
+}
+
+impl OpenNode {
+    fn new(mut attrs: HashMap<Vec<u8>, String>, parent: Option<String>, preserve: bool) -> Self {
+        let mut take = |key: &[u8]| attrs.remove(key);
+        // Freeplane writes the offsets with a unit, as "12.0 pt".
+        let mut offset = |key: &[u8], quantity: &[u8]| -> Option<f32> {
+            let value = take(key).or_else(|| take(quantity))?;
+            value.trim().trim_end_matches("pt").trim().parse().ok()
+        };
+        let (hgap, vshift) = (
+            offset(b"HGAP", b"HGAP_QUANTITY"),
+            offset(b"VSHIFT", b"VSHIFT_QUANTITY"),
+        );
         let node = Node {
-            id: node_id.clone(),
-            content: xml_node.text,
-            children: children_ids,
-            parent: parent_id,
-            x: 0.0,
-            y: 0.0,
-            created: xml_node.created,
-            modified: xml_node.modified,
-            icons,
+            id: take(b"ID")
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+            parent,
+            revision: take(b"REVISION").and_then(|r| r.parse().ok()).unwrap_or(0),
+            folded: take(b"FOLDED").as_deref() == Some("true"),
+            archived: take(b"ARCHIVED").as_deref() == Some("true"),
+            link: take(b"LINK").filter(|l| !l.is_empty()),
+            pinned: hgap.is_some() || vshift.is_some(),
+            x: hgap.unwrap_or(0.0),
+            y: vshift.unwrap_or(0.0),
+            style: NodeStyle {
+                text_color: take(b"COLOR"),
+                background_color: take(b"BACKGROUND_COLOR"),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let mut open = OpenNode {
+            node,
+            created: take(b"CREATED").and_then(|c| c.parse().ok()),
+            modified: take(b"MODIFIED").and_then(|m| m.parse().ok()),
+            text: take(b"TEXT").unwrap_or_default(),
+            rich_text: None,
+            free: false,
         };
+        // The exporter works out POSITION itself.
+        take(b"POSITION");
+        if preserve {
+            let mut unknown: Vec<(String, String)> = attrs
+                .into_iter()
+                .map(|(k, v)| (lossy(&k).into_owned(), v))
+                .collect();
+            unknown.sort();
+            open.node.unknown_attributes = UnknownAttributes::new(ImportFormat::FreeMind, unknown);
+        }
+        open
+    }
+}
+
+/// Finishes the innermost open node and links it to its parent. The node
+/// that closes with nothing left open is the root; free nodes directly
+/// under it are detached and listed in `floating`.
+fn close_node(
+    open: &mut Vec<OpenNode>,
+    nodes: &mut HashMap<String, Node>,
+    root_id: &mut Option<String>,
+    floating: &mut Vec<String>,
+) {
+    let Some(OpenNode {
+        mut node,
+        created,
+        modified,
+        text,
+        rich_text,
+        free,
+    }) = open.pop()
+    else {
+        return;
+    };
+
+    // FreeMind 0.9+ keeps formatted text in a richcontent element and
+    // leaves TEXT empty; 0.8 put the HTML straight into TEXT.
+    node.content = match rich_text {
+        Some(rich) if text.is_empty() => rich,
+        _ if html::is_html(&text) => html::to_plain_text(&text),
+        _ => text,
+    };
+    node.created = created.unwrap_or_else(now_millis);
+    node.modified = modified.unwrap_or_else(now_millis);
+    node.task = TaskInfo::from_icons(&mut node.icons);
+
+    if free && open.len() == 1 {
+        node.parent = None;
+        node.pinned = false;
+        floating.push(node.id.clone());
+    } else {
+        // Elsewhere HGAP and VSHIFT offset a node placed by hand from its
+        // parent's right edge and its place in the layout; see
+        // `MindMap::place_pinned_offsets`.
+        if open.is_empty() || !node.pinned {
+            (node.x, node.y, node.pinned) = (0.0, 0.0, false);
+        }
+        if free {
+            node.passthrough
+                .get_or_insert_with(String::new)
+                .push_str(&format!("<hook NAME=\"{FREE_NODE_HOOK}\"/>"));
+        }
+    }
+    match open.last_mut() {
+        Some(parent) if node.parent.is_some() => parent.node.children.push(node.id.clone()),
+        Some(_) => {}
+        None => {
+            root_id.get_or_insert_with(|| node.id.clone());
+        }
+    }
+    nodes.insert(node.id.clone(), node);
+}
+
+/// Decoded and unescaped attributes of an element, by name.
+fn attributes<R>(
+    e: &BytesStart,
+    reader: &Reader<R>,
+) -> Result<HashMap<Vec<u8>, String>, BrainCoreError> {
+    let mut attrs = HashMap::new();
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| parse_error(reader, e))?;
+        let value = attr
+            .decode_and_unescape_value(reader.decoder())
+            .map_err(|e| parse_error(reader, e))?;
+        attrs.insert(attr.key.as_ref().to_vec(), value.into_owned());
+    }
+    Ok(attrs)
+}
 
-        nodes.insert(node_id, node);
+fn parse_error<R>(reader: &Reader<R>, error: impl std::fmt::Display) -> BrainCoreError {
+    BrainCoreError::Parse {
+        format: ImportFormat::FreeMind,
+        offset: Some(reader.error_position() as usize),
+        message: error.to_string(),
     }
 }
 
+fn lossy(bytes: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(bytes)
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::MindMap;
-    use std::time::{SystemTime, UNIX_EPOCH};
+    use crate::import::ImportOptions;
 
     fn add_child_for_test(map: &mut MindMap, parent_id: &str, content: &str) -> String {
         let id = format!("node-{}", map.nodes.len());
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        let timestamp = now_millis();
         let node = Node {
             id: id.clone(),
             content: content.to_string(),
@@ -172,6 +1035,7 @@ mod tests {
             created: timestamp,
             modified: timestamp,
             icons: Vec::new(),
+            ..Default::default()
         };
         map.nodes.insert(id.clone(), node);
         if let Some(parent) = map.nodes.get_mut(parent_id) {
@@ -190,6 +1054,7 @@ mod tests {
         let _grand1 = add_child_for_test(&mut map, &child1, "Grand 1");
 
         let xml_output = to_xml(&map).expect("Failed to export to XML");
+        assert!(xml_output.contains(r#"<map version="1.0.1""#));
 
         let loaded_map = from_xml(&xml_output).expect("Failed to import from XML");
 
@@ -209,5 +1074,219 @@ mod tests {
         // But map currently has layout computed. loaded_map does NOT have layout computed yet (X/Y=0).
         assert_eq!(r_load.x, 0.0);
         assert_eq!(r_load.y, 0.0);
+
+        // The selection survives a round trip.
+        assert_eq!(loaded_map.selection.primary, root_id);
+        map.selection.primary = child1.clone();
+        let loaded_map = from_xml(&to_xml(&map).unwrap()).unwrap();
+        assert_eq!(loaded_map.selection.primary, child1);
+    }
+
+    #[test]
+    fn test_canonical_export() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        add_child_for_test(&mut map, &root_id, "Child 1");
+
+        let options = ExportOptions::canonical();
+        let first = to_xml_with(&map, &options).unwrap();
+        let second = to_xml_with(&map.clone(), &options).unwrap();
+        assert_eq!(first, second);
+        assert!(!first.contains("CREATED"));
+        assert!(to_xml(&map).unwrap().contains("CREATED"));
+
+        let loaded = from_xml(&first).unwrap();
+        assert_eq!(loaded.nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_hand_placed_offsets() {
+        let xml = |offsets: &str| {
+            format!(
+                r#"<map version="1.0.1"><node ID="root" TEXT="Quarterly planning overview"><node ID="a" TEXT="Budget" POSITION="right"{offsets}/></node></map>"#
+            )
+        };
+        let placed = from_xml(&xml(r#" HGAP="25" VSHIFT="-3""#)).unwrap();
+        let free = from_xml(&xml("")).unwrap();
+        let (root, a) = (&placed.nodes["root"], &placed.nodes["a"]);
+        assert_eq!(crate::layout::node_width(root), 236.0);
+        assert!(a.pinned);
+        assert_eq!(a.x, root.x + 236.0 + 25.0);
+        assert_eq!(a.y, free.nodes["a"].y - 3.0);
+
+        let saved = to_xml(&placed).unwrap();
+        assert!(saved.contains(r#"HGAP="25" VSHIFT="-3""#));
+        assert!(!to_xml(&free).unwrap().contains("HGAP"));
+    }
+
+    #[test]
+    fn test_free_nodes_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        add_child_for_test(&mut map, &root_id, "Child");
+        let idea = map.add_floating_node("Idea", 320.0, -40.0).unwrap();
+        let detail = map.add_child(&idea, "Detail").unwrap();
+
+        let xml = to_xml(&map).unwrap();
+        assert!(xml.contains("HGAP=\"320\" VSHIFT=\"-40\""));
+        assert!(xml.contains("<hook NAME=\"FreeNode\"/>"));
+
+        let loaded = from_xml(&xml).unwrap();
+        assert_eq!(loaded.floating_roots, vec![idea.clone()]);
+        assert_eq!(loaded.nodes[&root_id].children.len(), 1);
+        assert_eq!(
+            (loaded.nodes[&idea].x, loaded.nodes[&idea].y),
+            (320.0, -40.0)
+        );
+        assert_eq!(loaded.nodes[&detail].parent.as_ref(), Some(&idea));
+        assert!(loaded.nodes[&idea].passthrough.is_none());
+        assert!(loaded.validate().is_empty());
+    }
+
+    // Trimmed from a FreeMind 0.8.1 file: HTML nodes kept their markup,
+    // escaped, in the TEXT attribute.
+    const FREEMIND_0_8: &str = r#"<map version="0.8.1">
+<!-- To view this file, download free mind mapping software FreeMind from http://freemind.sourceforge.net -->
+<node CREATED="1180703395000" ID="Freemind_Link_1513112588" MODIFIED="1180703431312" TEXT="Tom &amp; Jerry&#xa0;&#x2014; notes">
+<node CREATED="1180703408734" ID="_" MODIFIED="1180703431312" POSITION="right" TEXT="&lt;html&gt;&#xa;  &lt;head&gt;&#xa;    &#xa;  &lt;/head&gt;&#xa;  &lt;body&gt;&#xa;    &lt;p&gt;&#xa;      Budget&amp;nbsp;&amp;amp; costs&#xa;    &lt;/p&gt;&#xa;    &lt;p&gt;&#xa;      Q1 &amp;lt; Q2&#xa;    &lt;/p&gt;&#xa;  &lt;/body&gt;&#xa;&lt;/html&gt;">
+<font NAME="SansSerif" SIZE="12"/>
+<icon BUILTIN="idea"/>
+</node>
+</node>
+</map>"#;
+
+    // Trimmed from a FreeMind 1.0.1 file: formatted text lives in a
+    // richcontent element and the node has no TEXT.
+    const FREEMIND_1_0: &str = r##"<map version="1.0.1">
+<!-- To view this file, download free mind mapping software FreeMind from http://freemind.sourceforge.net -->
+<node CREATED="1355239452401" ID="ID_1723255651" MODIFIED="1355239467437" TEXT="Project">
+<node CREATED="1355239468854" ID="ID_436389553" MODIFIED="1355239532013" POSITION="right">
+<richcontent TYPE="NODE"><html>
+  <head>
+    <style type="text/css">p { margin-top: 0 }</style>
+  </head>
+  <body>
+    <p>
+      Line&#160;one
+    </p>
+    <p>
+      <b>Line</b> two<br/>and three
+    </p>
+  </body>
+</html>
+</richcontent>
+<edge COLOR="#808080" STYLE="bezier" WIDTH="thin"/>
+</node>
+</node>
+</map>"##;
+
+    #[test]
+    fn test_freemind_entities_and_html() {
+        let options = ImportOptions::raw();
+        let map = from_xml_with(FREEMIND_0_8, &options).unwrap();
+        assert_eq!(
+            map.nodes[&map.root_id].content,
+            "Tom & Jerry\u{a0}\u{2014} notes"
+        );
+        let html_node = &map.nodes["_"];
+        assert_eq!(html_node.content, "Budget\u{a0}& costs\nQ1 < Q2");
+        assert_eq!(html_node.icons, vec!["idea".to_string()]);
+
+        let map = from_xml_with(FREEMIND_1_0, &options).unwrap();
+        assert_eq!(
+            map.nodes["ID_436389553"].content,
+            "Line\u{a0}one\nLine two\nand three"
+        );
+
+        let xml = to_xml(&map).unwrap();
+        assert!(xml.contains(r#"TEXT="Line&#xa0;one&#xa;Line two&#xa;and three""#));
+        let reloaded = from_xml_with(&xml, &options).unwrap();
+        assert_eq!(
+            reloaded.nodes["ID_436389553"].content,
+            map.nodes["ID_436389553"].content
+        );
+    }
+
+    // Trimmed from a Freeplane 1.9 file.
+    const FREEPLANE: &str = r#"<map version="freeplane 1.9.13">
+<!--To view this file, download free mind mapping software Freeplane from https://www.freeplane.org -->
+<attribute_registry SHOW_ATTRIBUTES="hide"><attribute_name NAME="budget"/></attribute_registry>
+<node TEXT="Trip" FOLDED="false" ID="ID_1" LINK="https://example.com/?a=1&amp;b=2">
+<hook NAME="MapStyle">
+    <properties show_icon_for_attributes="true"/>
+    <map_styles><stylenode LOCALIZED_TEXT="styles.root_node">Default &amp; more</stylenode></map_styles>
+</hook>
+<attribute NAME="budget" VALUE="1200"/>
+<attribute NAME="currency" VALUE="EUR"/>
+<node TEXT="Hotel" FOLDED="true" ID="ID_2">
+<richcontent TYPE="DETAILS"><html><body><p>Near the station</p></body></html></richcontent>
+<hook NAME="accessories/plugins/NodeNote.properties"><text>Booked
+Paid</text></hook>
+<attribute_layout NAME_WIDTH="80"/>
+</node>
+</node>
+</map>"#;
+
+    #[test]
+    fn test_freeplane_round_trip() {
+        let map = from_xml_with(FREEPLANE, &ImportOptions::raw()).unwrap();
+        let root = &map.nodes["ID_1"];
+        assert_eq!(root.link.as_deref(), Some("https://example.com/?a=1&b=2"));
+        assert_eq!(
+            root.attributes,
+            vec![
+                ("budget".to_string(), "1200".to_string()),
+                ("currency".to_string(), "EUR".to_string()),
+            ]
+        );
+        let hotel = &map.nodes["ID_2"];
+        assert!(hotel.folded);
+        assert_eq!(hotel.details.as_deref(), Some("Near the station"));
+        assert_eq!(hotel.note.as_deref(), Some("Booked\nPaid"));
+        assert_eq!(
+            hotel.passthrough.as_deref(),
+            Some(r#"<attribute_layout NAME_WIDTH="80"/>"#)
+        );
+
+        assert_eq!(map.format_version.as_deref(), Some("freeplane 1.9.13"));
+
+        let xml = to_xml(&map).unwrap();
+        assert!(xml.contains(
+            r#"<map version="freeplane 1.9.13"><attribute_registry SHOW_ATTRIBUTES="hide"><attribute_name NAME="budget"/></attribute_registry><node "#
+        ));
+        assert!(xml.contains(
+            r#"<hook NAME="MapStyle"><properties show_icon_for_attributes="true"/><map_styles><stylenode LOCALIZED_TEXT="styles.root_node">Default &amp; more</stylenode></map_styles></hook>"#
+        ));
+        assert!(xml.contains(r#"<attribute NAME="budget" VALUE="1200"/>"#));
+        assert!(xml.contains(r#"<richcontent TYPE="DETAILS">"#));
+        let reloaded = from_xml_with(&xml, &ImportOptions::raw()).unwrap();
+        assert_eq!(reloaded.format_version, map.format_version);
+        assert_eq!(reloaded.passthrough, map.passthrough);
+        for id in ["ID_1", "ID_2"] {
+            let (before, after) = (&map.nodes[id], &reloaded.nodes[id]);
+            assert_eq!(before.attributes, after.attributes);
+            assert_eq!(before.details, after.details);
+            assert_eq!(before.note, after.note);
+            assert_eq!(
+                XmlPassthrough::parse(before.passthrough.as_deref().unwrap_or_default()),
+                XmlPassthrough::parse(after.passthrough.as_deref().unwrap_or_default())
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_xml_from_reader() {
+        let map = read_xml(FREEMIND_1_0.as_bytes()).unwrap();
+        assert_eq!(map.nodes.len(), 2);
+        assert_eq!(map.nodes[&map.root_id].children, vec!["ID_436389553"]);
+
+        let truncated = "<map version=\"1.0.1\">\n<node TEXT=\"A\" ID=\"a\"><node TEXT=\"B";
+        assert!(matches!(
+            from_xml(truncated),
+            Err(BrainCoreError::Parse {
+                offset: Some(_),
+                ..
+            })
+        ));
     }
 }
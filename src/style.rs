@@ -0,0 +1,96 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+
+/// Visual styling of a node and the edge leading to it. Unset fields fall
+/// back to the application's defaults. Colors are `#rrggbb` strings, sizes
+/// are in points.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeStyle {
+    #[serde(default)]
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub text_color: Option<String>,
+    #[serde(default)]
+    pub font_size: Option<u32>,
+    #[serde(default)]
+    pub bold: bool,
+    #[serde(default)]
+    pub italic: bool,
+    #[serde(default)]
+    pub edge_color: Option<String>,
+    #[serde(default)]
+    pub edge_width: Option<u32>,
+}
+
+impl NodeStyle {
+    pub fn is_default(&self) -> bool {
+        *self == NodeStyle::default()
+    }
+}
+
+impl MindMap {
+    /// Replaces a node's style.
+    pub fn set_style(&mut self, node_id: &str, style: NodeStyle) -> Result<(), BrainCoreError> {
+        self.update_style(node_id, |current| *current = style)
+    }
+
+    /// Changes part of a node's style in place, e.g.
+    /// `map.update_style(id, |s| s.bold = true)`.
+    pub fn update_style(
+        &mut self,
+        node_id: &str,
+        change: impl FnOnce(&mut NodeStyle),
+    ) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let mut style = node.style.clone();
+        change(&mut style);
+        if node.style != style {
+            node.style = style;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{storage, xmind};
+
+    #[test]
+    fn test_styles_round_trip_through_formats() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = map.add_child(&root_id, "Styled").unwrap();
+        let style = NodeStyle {
+            background_color: Some("#ffee00".to_string()),
+            text_color: Some("#202020".to_string()),
+            font_size: Some(16),
+            bold: true,
+            italic: true,
+            edge_color: Some("#3366cc".to_string()),
+            edge_width: Some(2),
+        };
+        map.set_style(&child, style.clone()).unwrap();
+        map.update_style(&root_id, |s| s.bold = true).unwrap();
+        map.update_style(&root_id, |s| s.bold = false).unwrap();
+        assert!(map.nodes[&root_id].style.is_default());
+
+        let xml = storage::to_xml(&map).unwrap();
+        assert!(xml.contains("<edge COLOR=\"#3366cc\" WIDTH=\"2\"/>"));
+        for loaded in [
+            storage::from_xml(&xml).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+        ] {
+            let root = &loaded.nodes[&loaded.root_id];
+            assert!(root.style.is_default());
+            assert_eq!(loaded.nodes[&root.children[0]].style, style);
+        }
+    }
+}
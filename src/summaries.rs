@@ -0,0 +1,107 @@
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A summary topic, as in XMind: a bracket over a run of siblings with a
+/// topic of its own beside them. The run is held by its first and last
+/// child, so it follows them when siblings are added or moved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub id: String,
+    pub parent_id: String,
+    pub first_child: String,
+    pub last_child: String,
+    pub content: String,
+}
+
+impl MindMap {
+    /// Summarizes the children of `parent_id` from index `from_idx` to
+    /// `to_idx`, both included, and returns the summary id.
+    pub fn add_summary(
+        &mut self,
+        parent_id: &str,
+        from_idx: usize,
+        to_idx: usize,
+        content: &str,
+    ) -> Result<String, BrainCoreError> {
+        let parent = self
+            .nodes
+            .get(parent_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(parent_id.to_string()))?;
+        if from_idx > to_idx || to_idx >= parent.children.len() {
+            return Err(BrainCoreError::InvalidOperation(format!(
+                "No children {from_idx} to {to_idx} to summarize"
+            )));
+        }
+        let id = Uuid::new_v4().to_string();
+        self.summaries.push(Summary {
+            id: id.clone(),
+            parent_id: parent_id.to_string(),
+            first_child: parent.children[from_idx].clone(),
+            last_child: parent.children[to_idx].clone(),
+            content: content.to_string(),
+        });
+        self.bump_revision(parent_id);
+        Ok(id)
+    }
+
+    pub fn remove_summary(&mut self, id: &str) -> Result<Summary, BrainCoreError> {
+        let pos = self
+            .summaries
+            .iter()
+            .position(|s| s.id == id)
+            .ok_or_else(|| BrainCoreError::SummaryNotFound(id.to_string()))?;
+        let removed = self.summaries.remove(pos);
+        self.bump_revision(&removed.parent_id);
+        Ok(removed)
+    }
+
+    /// The indices of the first and last summarized child, or `None` while
+    /// either is trashed or no longer a child of the summary's parent.
+    /// Summaries without a range are kept, so a restore brings them back,
+    /// but not exported.
+    pub fn summary_range(&self, summary: &Summary) -> Option<(usize, usize)> {
+        let children = &self.nodes.get(&summary.parent_id)?.children;
+        let first = children.iter().position(|c| *c == summary.first_child)?;
+        let last = children.iter().position(|c| *c == summary.last_child)?;
+        Some((first.min(last), first.max(last)))
+    }
+
+    /// Summaries over children of `parent_id`.
+    pub fn summaries_of<'a>(
+        &'a self,
+        parent_id: &'a str,
+    ) -> impl Iterator<Item = &'a Summary> + 'a {
+        self.summaries
+            .iter()
+            .filter(move |s| s.parent_id == parent_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::xmind;
+
+    #[test]
+    fn test_summaries() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "A").unwrap();
+        map.add_child(&root_id, "B").unwrap();
+        map.add_child(&root_id, "C").unwrap();
+        let summary = map.add_summary(&root_id, 1, 2, "B and C").unwrap();
+        assert!(map.add_summary(&root_id, 2, 3, "Too far").is_err());
+        map.trash_node(&a).unwrap();
+        assert_eq!(map.summary_range(&map.summaries[0]), Some((0, 1)));
+
+        let loaded = xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap();
+        assert_eq!(loaded.summaries.len(), 1);
+        assert_eq!(loaded.summaries[0].content, "B and C");
+        assert_eq!(loaded.summary_range(&loaded.summaries[0]), Some((0, 1)));
+        assert_eq!(loaded.nodes.len(), 3);
+
+        map.remove_summary(&summary).unwrap();
+        assert!(map.summaries.is_empty());
+    }
+}
@@ -0,0 +1,336 @@
+use crate::layout::{HeuristicMeasurer, TextMeasurer, font_size};
+use crate::{MindMap, Node};
+use quick_xml::escape::escape;
+use std::fmt::Write;
+
+const MARGIN: f32 = 20.0;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SvgOptions {
+    /// Background fill of the whole image; `None` leaves it transparent.
+    pub background: Option<String>,
+}
+
+pub fn to_svg(map: &MindMap) -> String {
+    to_svg_with(map, &SvgOptions::default())
+}
+
+/// Draws the visible nodes as rounded boxes joined by bezier connectors,
+/// at the positions stored on the nodes, floating trees included. Run
+/// [`MindMap::compute_layout`] first. Node colours, bold and italic text and
+/// edge colours and widths come from each node's style. Boxes are sized and content is broken into
+/// lines as [`MindMap::node_size`] does with [`HeuristicMeasurer`].
+pub fn to_svg_with(map: &MindMap, options: &SvgOptions) -> String {
+    to_svg_measured(map, options, &HeuristicMeasurer)
+}
+
+/// Draws the map as [`to_svg_with`] does, sizing boxes and breaking lines
+/// with `measurer`. Pass the one the map was laid out with.
+pub fn to_svg_measured(map: &MindMap, options: &SvgOptions, measurer: &dyn TextMeasurer) -> String {
+    let nodes = drawn_nodes(map);
+    let (left, top, right, bottom) = bounds(map, &nodes, measurer);
+    let size = |node: &Node| map.node_size(node, measurer);
+    let (width, height) = (right - left + 2.0 * MARGIN, bottom - top + 2.0 * MARGIN);
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         viewBox=\"{} {} {width} {height}\">",
+        left - MARGIN,
+        top - MARGIN
+    );
+    if let Some(background) = &options.background {
+        let _ = writeln!(
+            out,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{width}\" height=\"{height}\" fill=\"{}\"/>",
+            left - MARGIN,
+            top - MARGIN,
+            escape(background)
+        );
+    }
+
+    // Connectors first so that boxes are drawn over their ends.
+    for node in &nodes {
+        let children = map.laid_out_children(node);
+        let sides = connector_sides(node, &children, size);
+        for (child, sides) in children.into_iter().zip(sides) {
+            let [(x1, y1), (cx1, cy1), (cx2, cy2), (x2, y2)] =
+                connector_curve(node, child, sides, size);
+            let color = child.style.edge_color.as_deref().unwrap_or("#888888");
+            let stroke = child.style.edge_width.unwrap_or(1);
+            let _ = writeln!(
+                out,
+                "  <path d=\"M {x1} {y1} C {cx1} {cy1}, {cx2} {cy2}, {x2} {y2}\" \
+                 fill=\"none\" stroke=\"{}\" stroke-width=\"{stroke}\"/>",
+                escape(color)
+            );
+        }
+    }
+
+    for node in &nodes {
+        let style = &node.style;
+        let (width, height) = size(node);
+        let font_size = font_size(node);
+        let (center_x, center_y) = (node.x + width / 2.0, node.y + height / 2.0);
+        let fill = style.background_color.as_deref().unwrap_or("#ffffff");
+        let _ = writeln!(
+            out,
+            "  <rect x=\"{}\" y=\"{}\" width=\"{width}\" height=\"{height}\" rx=\"8\" ry=\"8\" \
+             fill=\"{}\" stroke=\"#555555\"/>",
+            node.x,
+            node.y,
+            escape(fill)
+        );
+        let _ = write!(
+            out,
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\" dominant-baseline=\"central\" \
+             font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\"",
+            center_x,
+            center_y,
+            font_size,
+            escape(style.text_color.as_deref().unwrap_or("#000000"))
+        );
+        if style.bold {
+            out.push_str(" font-weight=\"bold\"");
+        }
+        if style.italic {
+            out.push_str(" font-style=\"italic\"");
+        }
+        out.push('>');
+        let lines = map.node_lines(node, measurer);
+        if let [line] = lines.as_slice() {
+            out.push_str(&escape(line));
+        } else {
+            // One line height apart, centred as a block on the box.
+            let line_height = measurer.measure("", font_size).1;
+            let first = center_y - line_height * (lines.len() - 1) as f32 / 2.0;
+            for (i, line) in lines.iter().enumerate() {
+                let y = first + line_height * i as f32;
+                let _ = write!(
+                    out,
+                    "<tspan x=\"{center_x}\" y=\"{y}\">{}</tspan>",
+                    escape(line)
+                );
+            }
+        }
+        out.push_str("</text>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// The nodes a rendering draws: the root's tree and the floating trees,
+/// in depth-first order, without archived or folded-away nodes.
+pub(crate) fn drawn_nodes(map: &MindMap) -> Vec<&Node> {
+    let mut nodes: Vec<&Node> = Vec::new();
+    let mut stack: Vec<&Node> = map
+        .floating_roots
+        .iter()
+        .rev()
+        .filter_map(|id| map.nodes.get(id))
+        .filter(|n| !n.archived)
+        .collect();
+    stack.extend(map.nodes.get(&map.root_id));
+    while let Some(node) = stack.pop() {
+        nodes.push(node);
+        stack.extend(map.laid_out_children(node).into_iter().rev());
+    }
+    nodes
+}
+
+/// A side of a node's box, where connectors attach.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BoxSide {
+    Top,
+    Right,
+    Bottom,
+    Left,
+}
+
+impl BoxSide {
+    /// The side as JSON Canvas names it.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            BoxSide::Top => "top",
+            BoxSide::Right => "right",
+            BoxSide::Bottom => "bottom",
+            BoxSide::Left => "left",
+        }
+    }
+
+    /// Middle of this side of the node's box.
+    fn point(self, node: &Node, (width, height): (f32, f32)) -> (f32, f32) {
+        match self {
+            BoxSide::Top => (node.x + width / 2.0, node.y),
+            BoxSide::Right => (node.x + width, node.y + height / 2.0),
+            BoxSide::Bottom => (node.x + width / 2.0, node.y + height),
+            BoxSide::Left => (node.x, node.y + height / 2.0),
+        }
+    }
+}
+
+/// The sides of the boxes each connector from `node` to one of its
+/// `children` joins. When every child sits below the node, as in an org
+/// chart, they hang from its bottom edge; otherwise each leaves the side
+/// facing the child, so the left half of a balanced map joins on the left.
+pub(crate) fn connector_sides(
+    node: &Node,
+    children: &[&Node],
+    size: impl Fn(&Node) -> (f32, f32),
+) -> Vec<(BoxSide, BoxSide)> {
+    let (width, height) = size(node);
+    if children.iter().all(|c| c.y >= node.y + height) {
+        return vec![(BoxSide::Bottom, BoxSide::Top); children.len()];
+    }
+    children
+        .iter()
+        .map(|child| {
+            if child.x + size(child).0 / 2.0 < node.x + width / 2.0 {
+                (BoxSide::Left, BoxSide::Right)
+            } else {
+                (BoxSide::Right, BoxSide::Left)
+            }
+        })
+        .collect()
+}
+
+/// Start, control points and end of the bezier connector from `node` to
+/// `child`, leaving and entering at `sides` and bending halfway.
+pub(crate) fn connector_curve(
+    node: &Node,
+    child: &Node,
+    (from, to): (BoxSide, BoxSide),
+    size: impl Fn(&Node) -> (f32, f32),
+) -> [(f32, f32); 4] {
+    let (x1, y1) = from.point(node, size(node));
+    let (x2, y2) = to.point(child, size(child));
+    if matches!(from, BoxSide::Top | BoxSide::Bottom) {
+        let middle = (y1 + y2) / 2.0;
+        [(x1, y1), (x1, middle), (x2, middle), (x2, y2)]
+    } else {
+        let middle = (x1 + x2) / 2.0;
+        [(x1, y1), (middle, y1), (middle, y2), (x2, y2)]
+    }
+}
+
+/// Left, top, right and bottom edges of the boxes of `nodes`.
+pub(crate) fn bounds(
+    map: &MindMap,
+    nodes: &[&Node],
+    measurer: &dyn TextMeasurer,
+) -> (f32, f32, f32, f32) {
+    let (mut left, mut top, mut right, mut bottom) = (0.0f32, 0.0f32, 0.0f32, 0.0f32);
+    if let Some(first) = nodes.first() {
+        (left, top, right, bottom) = (first.x, first.y, first.x, first.y);
+    }
+    for node in nodes {
+        let (width, height) = map.node_size(node, measurer);
+        left = left.min(node.x);
+        top = top.min(node.y);
+        right = right.max(node.x + width);
+        bottom = bottom.max(node.y + height);
+    }
+    (left, top, right, bottom)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutKind;
+
+    #[test]
+    fn test_to_svg() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "R&D").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        map.update_style(&a, |s| {
+            s.background_color = Some("#ffcc00".to_string());
+            s.bold = true;
+            s.edge_color = Some("#ff0000".to_string());
+        })
+        .unwrap();
+        let old = map.add_child(&root_id, "Old").unwrap();
+        map.archive(&old).unwrap();
+        map.compute_layout();
+
+        let svg = to_svg(&map);
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"152\" height=\"90\" \
+             viewBox=\"-20 -20 152 90\">\n"
+        ));
+        assert!(svg.contains(
+            "  <path d=\"M 44 25 C 64 25, 64 25, 84 25\" fill=\"none\" stroke=\"#ff0000\""
+        ));
+        assert!(svg.contains(
+            "  <rect x=\"84\" y=\"0\" width=\"28\" height=\"50\" rx=\"8\" ry=\"8\" fill=\"#ffcc00\""
+        ));
+        assert!(svg.contains("font-weight=\"bold\">A</text>"));
+        assert!(svg.contains(">R&amp;D</text>"));
+        assert!(!svg.contains("Old"));
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_measurer() {
+        struct Wide;
+        impl TextMeasurer for Wide {
+            fn measure(&self, text: &str, font_size: f32) -> (f32, f32) {
+                (text.len() as f32 * 20.0, font_size * 1.2)
+            }
+        }
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Root").unwrap();
+        map.apply_layout(LayoutKind::RightTree.engine(), &Wide);
+
+        let svg = to_svg_measured(&map, &SvgOptions::default(), &Wide);
+        assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"100\" height=\"50\""));
+        let graphml = crate::graphml::to_graphml_measured(
+            &map,
+            &crate::graphml::GraphmlOptions {
+                geometry: true,
+                ..Default::default()
+            },
+            &Wide,
+        );
+        assert!(graphml.contains("<data key=\"width\">100</data>"));
+    }
+
+    #[test]
+    fn test_connector_sides() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.add_child(&root_id, "Right").unwrap();
+        map.add_child(&root_id, "Left").unwrap();
+        let mut balanced = map.clone();
+        balanced.compute_layout_with(LayoutKind::Balanced);
+        let mut org_chart = map;
+        org_chart.compute_layout_with(LayoutKind::OrgChart);
+        let size = |node: &Node| org_chart.node_size(node, &HeuristicMeasurer);
+        let sides = |map: &MindMap| {
+            let root = &map.nodes[&map.root_id];
+            connector_sides(root, &map.laid_out_children(root), size)
+        };
+
+        assert_eq!(
+            sides(&balanced),
+            [
+                (BoxSide::Right, BoxSide::Left),
+                (BoxSide::Left, BoxSide::Right)
+            ]
+        );
+        let root = &balanced.nodes[&root_id];
+        let left = &balanced.nodes[&root.children[1]];
+        let [start, .., end] = connector_curve(root, left, sides(&balanced)[1], size);
+        assert_eq!(start.0, root.x);
+        assert_eq!(end.0, left.x + size(left).0);
+
+        assert_eq!(sides(&org_chart), [(BoxSide::Bottom, BoxSide::Top); 2]);
+        let svg = to_svg(&org_chart);
+        let root = &org_chart.nodes[&root_id];
+        let (width, height) = size(root);
+        assert!(svg.contains(&format!("M {} {} C", root.x + width / 2.0, height)));
+    }
+}
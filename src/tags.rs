@@ -0,0 +1,120 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+
+impl MindMap {
+    /// Adds a tag to a node. A leading `#` is dropped, so `#urgent` and
+    /// `urgent` are the same tag. Tagging a node twice is a no-op.
+    pub fn tag(&mut self, node_id: &str, tag: &str) -> Result<(), BrainCoreError> {
+        let tag = normalize(tag)?;
+        self.update_tags(node_id, |tags| {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        })
+    }
+
+    /// Removes a tag from a node and returns whether it had it.
+    pub fn untag(&mut self, node_id: &str, tag: &str) -> Result<bool, BrainCoreError> {
+        let tag = normalize(tag)?;
+        let mut removed = false;
+        self.update_tags(node_id, |tags| {
+            let before = tags.len();
+            tags.retain(|t| t != tag);
+            removed = tags.len() != before;
+        })?;
+        Ok(removed)
+    }
+
+    /// Ids of the nodes carrying `tag`, ignoring case, in tree order with
+    /// floating trees last.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<String> {
+        let tag = tag.trim().trim_start_matches('#');
+        self.tree_nodes()
+            .into_iter()
+            .filter(|n| n.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+            .map(|n| n.id.clone())
+            .collect()
+    }
+
+    /// Every tag in use, sorted and without repeats.
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .nodes
+            .values()
+            .flat_map(|n| n.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn update_tags(
+        &mut self,
+        node_id: &str,
+        update: impl FnOnce(&mut Vec<String>),
+    ) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let mut tags = node.tags.clone();
+        update(&mut tags);
+        if node.tags != tags {
+            node.tags = tags;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+}
+
+// Tags are single words so they survive `#tag` syntax in text formats.
+fn normalize(tag: &str) -> Result<&str, BrainCoreError> {
+    let tag = tag.trim().trim_start_matches('#');
+    if tag.is_empty() || tag.contains(char::is_whitespace) {
+        return Err(BrainCoreError::InvalidOperation(format!(
+            "Invalid tag: {tag:?}"
+        )));
+    }
+    Ok(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{markdown, storage, xmind};
+
+    #[test]
+    fn test_tags() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = map.add_child(&root_id, "Launch").unwrap();
+        let b = map.add_child(&root_id, "Budget").unwrap();
+        map.tag(&a, "#q3").unwrap();
+        map.tag(&a, "urgent").unwrap();
+        map.tag(&a, "q3").unwrap();
+        map.tag(&b, "Q3").unwrap();
+        assert!(map.tag(&b, "two words").is_err());
+        assert_eq!(map.nodes[&a].tags, ["q3", "urgent"]);
+        assert_eq!(map.find_by_tag("q3"), [a.clone(), b.clone()]);
+        assert_eq!(map.all_tags(), ["Q3", "q3", "urgent"]);
+
+        let loaded = [
+            storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap(),
+            xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap(),
+            markdown::from_markdown(&markdown::to_markdown(&map)).unwrap(),
+        ];
+        for loaded in loaded {
+            let root = &loaded.nodes[&loaded.root_id];
+            let node = &loaded.nodes[&root.children[0]];
+            assert_eq!(node.content, "Launch");
+            assert_eq!(node.tags, map.nodes[&a].tags);
+            assert!(node.attributes.is_empty());
+        }
+
+        assert!(map.untag(&a, "urgent").unwrap());
+        assert!(!map.untag(&a, "urgent").unwrap());
+        assert_eq!(map.find_by_tag("urgent"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::{Deserialize, Serialize};
+
+/// Freeplane's progress icons, one per quarter.
+const PROGRESS_ICONS: [&str; 5] = ["0%", "25%", "50%", "75%", "100%"];
+
+/// Task metadata attached to a node.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TaskInfo {
+    #[serde(default)]
+    pub done: bool,
+    /// Completion from 0 to 100.
+    #[serde(default)]
+    pub progress_pct: u8,
+    /// Due date in Unix milliseconds.
+    #[serde(default)]
+    pub due: Option<u64>,
+    /// 1 is the most urgent.
+    #[serde(default)]
+    pub priority: Option<u8>,
+}
+
+impl TaskInfo {
+    /// Completion from 0 to 100, counting a done task as complete.
+    pub fn completion(&self) -> u8 {
+        if self.done {
+            100
+        } else {
+            self.progress_pct.min(100)
+        }
+    }
+
+    /// The FreeMind icons that stand for the task: Freeplane's progress
+    /// icon, rounded down to a quarter, and the priority as a numbered icon
+    /// unless the node already shows one.
+    pub(crate) fn icons(&self, node_icons: &[String]) -> Vec<String> {
+        let mut icons = vec![PROGRESS_ICONS[self.completion() as usize / 25].to_string()];
+        if let Some(priority) = self.priority.filter(|p| (1..=9).contains(p)) {
+            let icon = format!("full-{priority}");
+            if !node_icons.contains(&icon) {
+                icons.push(icon);
+            }
+        }
+        icons
+    }
+
+    /// Reads a task back from the icons [`TaskInfo::icons`] writes. The
+    /// progress icon is taken out; a numbered icon stays, and gives the
+    /// priority.
+    pub(crate) fn from_icons(icons: &mut Vec<String>) -> Option<TaskInfo> {
+        let at = icons
+            .iter()
+            .position(|i| PROGRESS_ICONS.contains(&i.as_str()))?;
+        let progress: u8 = icons.remove(at).trim_end_matches('%').parse().ok()?;
+        let priority = icons
+            .iter()
+            .find_map(|i| i.strip_prefix("full-")?.parse().ok())
+            .filter(|p| (1..=9).contains(p));
+        Some(TaskInfo {
+            done: progress == 100,
+            progress_pct: progress,
+            due: None,
+            priority,
+        })
+    }
+}
+
+impl MindMap {
+    /// Makes a node a task, or a plain node again with `None`.
+    pub fn set_task(
+        &mut self,
+        node_id: &str,
+        task: Option<TaskInfo>,
+    ) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.task != task {
+            node.task = task;
+            node.modified = now_millis();
+            node.modified_by = self.author.clone();
+            self.bump_revision(node_id);
+        }
+        Ok(())
+    }
+
+    /// Completion of the tasks in a subtree, from 0 to 100. A node that is
+    /// done counts as complete; otherwise a node whose subtree holds tasks
+    /// averages its children that do, and a task without any takes its
+    /// own progress. `None` when the subtree holds no task. Archived nodes
+    /// are left out.
+    pub fn task_progress(&self, node_id: &str) -> Result<Option<u8>, BrainCoreError> {
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        Ok(self.rolled_up_progress(node).map(|p| p.round() as u8))
+    }
+
+    fn rolled_up_progress(&self, node: &Node) -> Option<f32> {
+        let children: Vec<f32> = node
+            .children
+            .iter()
+            .filter_map(|id| self.nodes.get(id))
+            .filter(|c| !c.archived)
+            .filter_map(|c| self.rolled_up_progress(c))
+            .collect();
+        match &node.task {
+            Some(task) if task.done => Some(100.0),
+            _ if !children.is_empty() => Some(children.iter().sum::<f32>() / children.len() as f32),
+            Some(task) => Some(task.completion() as f32),
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{opml, storage, xmind};
+
+    #[test]
+    fn test_task_progress_and_formats() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let project = map.add_child(&root_id, "Project").unwrap();
+        let a = map.add_child(&project, "A").unwrap();
+        let b = map.add_child(&project, "B").unwrap();
+        let b1 = map.add_child(&b, "B1").unwrap();
+        let b2 = map.add_child(&b, "B2").unwrap();
+        map.add_child(&project, "Plain note").unwrap();
+        assert_eq!(map.task_progress(&root_id).unwrap(), None);
+
+        let task = |done, progress_pct| TaskInfo {
+            done,
+            progress_pct,
+            ..TaskInfo::default()
+        };
+        map.set_task(&a, Some(task(true, 100))).unwrap();
+        map.set_task(&b1, Some(task(false, 50))).unwrap();
+        map.set_task(&b2, Some(task(false, 0))).unwrap();
+        assert_eq!(map.task_progress(&b).unwrap(), Some(25));
+        assert_eq!(map.task_progress(&project).unwrap(), Some(63));
+        map.set_task(&b, Some(task(true, 100))).unwrap();
+        assert_eq!(map.task_progress(&root_id).unwrap(), Some(100));
+
+        map.set_task(
+            &b1,
+            Some(TaskInfo {
+                progress_pct: 75,
+                due: Some(1_714_521_600_000),
+                priority: Some(2),
+                ..TaskInfo::default()
+            }),
+        )
+        .unwrap();
+        let tasks = |map: &MindMap| -> Vec<Option<TaskInfo>> {
+            let mut tasks: Vec<(String, Option<TaskInfo>)> = map
+                .iter_dfs()
+                .map(|n| (n.content.clone(), n.task.clone()))
+                .collect();
+            tasks.sort_by(|x, y| x.0.cmp(&y.0));
+            tasks.into_iter().map(|(_, task)| task).collect()
+        };
+        let expected = tasks(&map);
+
+        let loaded = opml::from_opml(&opml::to_opml(&map).unwrap()).unwrap();
+        assert_eq!(tasks(&loaded), expected);
+        let loaded = xmind::from_xmind(&xmind::to_xmind(&map).unwrap()).unwrap();
+        assert_eq!(tasks(&loaded), expected);
+
+        // FreeMind has no due dates and keeps progress in quarters.
+        let without_due: Vec<Option<TaskInfo>> = expected
+            .iter()
+            .cloned()
+            .map(|t| t.map(|t| TaskInfo { due: None, ..t }))
+            .collect();
+        let loaded = storage::from_xml(&storage::to_xml(&map).unwrap()).unwrap();
+        assert_eq!(tasks(&loaded), without_due);
+        let b1 = loaded.nodes.values().find(|n| n.content == "B1").unwrap();
+        assert_eq!(b1.icons, vec!["full-2".to_string()]);
+    }
+}
@@ -0,0 +1,400 @@
+use crate::export::ExportOptions;
+use crate::import::ImportOptions;
+use crate::progress::{Monitor, Stage};
+use crate::timestamp::now_millis;
+use crate::views::TaskState;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// TaskPaper outlines: tab-indented lines where `Name:` is a project, `- Name`
+// a task and anything else a note on the item above. Note lines that would
+// read as a task or project are escaped with a leading backslash. Items carry
+// `@tag` or `@tag(value)` tags. The task icons map to the tags TaskPaper uses for
+// workflow state.
+
+/// The TaskPaper tag for a workflow state.
+fn state_tag(state: TaskState) -> Option<&'static str> {
+    match state {
+        TaskState::Todo => None,
+        TaskState::InProgress => Some("started"),
+        TaskState::Paused => Some("paused"),
+        TaskState::Done => Some("done"),
+    }
+}
+
+/// Whether a node is written as a project rather than a task: the root,
+/// and nodes with exported children that carry no task icon.
+fn is_project(node: &Node, has_children: bool, is_root: bool) -> bool {
+    is_root || (has_children && TaskState::of(node) == TaskState::Todo)
+}
+
+/// Whether a line of TaskPaper text is a project, i.e. ends in a colon
+/// once trailing tags are removed.
+pub(crate) fn is_project_line(line: &str) -> bool {
+    let line = line.trim();
+    !line.starts_with("- ") && split_tags(line).0.ends_with(':')
+}
+
+/// Backslash-escapes a note line that would read back as a task or
+/// project, and a leading backslash so that the escape itself survives.
+fn escape_note_line(line: &str) -> String {
+    if line.starts_with("- ") || line.starts_with('\\') || is_project_line(line) {
+        format!("\\{line}")
+    } else {
+        line.to_string()
+    }
+}
+
+fn included_children<'a>(map: &'a MindMap, node: &Node, options: &ExportOptions) -> Vec<&'a Node> {
+    node.children
+        .iter()
+        .filter_map(|id| map.nodes.get(id))
+        .filter(|c| options.includes(c))
+        .collect()
+}
+
+pub fn to_taskpaper(map: &MindMap) -> String {
+    to_taskpaper_with(map, &ExportOptions::default())
+}
+
+/// Writes the root and every node with children as a project, and the
+/// rest as tasks. `go`, `prepare` and `button_ok` icons become `@started`,
+/// `@paused` and `@done`, followed by the node's own tags; a node with
+/// one of those icons is always a task. Notes follow their item, one level
+/// deeper, without blank lines.
+pub fn to_taskpaper_with(map: &MindMap, options: &ExportOptions) -> String {
+    to_taskpaper_monitored(map, options, &mut Monitor::default()).unwrap_or_default()
+}
+
+pub fn to_taskpaper_monitored(
+    map: &MindMap,
+    options: &ExportOptions,
+    monitor: &mut Monitor,
+) -> Result<String, BrainCoreError> {
+    let mut out = String::new();
+    let mut stack: Vec<(&Node, usize)> = map
+        .nodes
+        .get(&map.root_id)
+        .map(|root| (root, 0))
+        .into_iter()
+        .collect();
+    monitor.begin(
+        Stage::WritingNodes,
+        Some(options.exported_count(map, false)),
+    )?;
+    while let Some((node, depth)) = stack.pop() {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let children = included_children(map, node, options);
+        let content = node.content.replace('\n', " ");
+        out.push_str(&"\t".repeat(depth));
+        if is_project(node, !children.is_empty(), node.id == map.root_id) {
+            out.push_str(&content);
+            out.push(':');
+        } else {
+            out.push_str("- ");
+            out.push_str(&content);
+        }
+        let state = state_tag(TaskState::of(node));
+        for tag in state
+            .into_iter()
+            .chain(node.tags.iter().map(String::as_str))
+        {
+            out.push_str(" @");
+            out.push_str(&tag.replace(char::is_whitespace, "_"));
+        }
+        out.push('\n');
+        for line in node.note.iter().flat_map(|n| n.lines()) {
+            if !line.trim().is_empty() {
+                out.push_str(&"\t".repeat(depth + 1));
+                out.push_str(&escape_note_line(line.trim()));
+                out.push('\n');
+            }
+        }
+        stack.extend(children.into_iter().rev().map(|c| (c, depth + 1)));
+    }
+    monitor.finish()?;
+    Ok(out)
+}
+
+pub fn to_todo_txt(map: &MindMap) -> String {
+    to_todo_txt_with(map, &ExportOptions::default())
+}
+
+/// Writes one todo.txt line per task, as [`to_taskpaper`] tells tasks from
+/// projects. Done tasks start with `x`; each task is tagged `+Project` with
+/// its nearest project, spaces removed, and `@tag` with its tags.
+pub fn to_todo_txt_with(map: &MindMap, options: &ExportOptions) -> String {
+    let mut out = String::new();
+    let mut stack: Vec<(&Node, Option<&str>)> = map
+        .nodes
+        .get(&map.root_id)
+        .map(|r| (r, None))
+        .into_iter()
+        .collect();
+    while let Some((node, project)) = stack.pop() {
+        let children = included_children(map, node, options);
+        let is_root = node.id == map.root_id;
+        let project = if is_project(node, !children.is_empty(), is_root) {
+            Some(node.content.as_str())
+        } else {
+            if TaskState::of(node) == TaskState::Done {
+                out.push_str("x ");
+            }
+            out.push_str(&node.content.replace('\n', " "));
+            if let Some(project) = project {
+                out.push_str(" +");
+                out.push_str(&project.replace(char::is_whitespace, ""));
+            }
+            for tag in &node.tags {
+                out.push_str(" @");
+                out.push_str(&tag.replace(char::is_whitespace, "_"));
+            }
+            out.push('\n');
+            project
+        };
+        stack.extend(children.into_iter().rev().map(|c| (c, project)));
+    }
+    out
+}
+
+pub fn from_taskpaper(text: &str) -> Result<MindMap, BrainCoreError> {
+    from_taskpaper_with(text, &ImportOptions::default())
+}
+
+/// Reads projects and tasks as nodes nested by indentation, with a tab
+/// counting as four spaces. Note lines are joined into the note of the
+/// item they are indented under. `@done`, `@started` and `@paused` become
+/// task icons and other tags node tags, with any value kept as
+/// `name(value)`. A single top-level item becomes the root; otherwise the
+/// top level is gathered under a "TaskPaper" root.
+pub fn from_taskpaper_with(text: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_taskpaper_monitored(text, options, &mut Monitor::default())
+}
+
+pub fn from_taskpaper_monitored(
+    text: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let now = now_millis();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        new_node(root_id.clone(), "TaskPaper", None, now),
+    );
+
+    // Open items with their indentation width.
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    monitor.begin(Stage::BuildingNodes, None)?;
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        if monitor.should_stop() {
+            break;
+        }
+        let body = line.trim();
+        if body.is_empty() {
+            continue;
+        }
+        let width: usize = line[..line.len() - line.trim_start().len()]
+            .chars()
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum();
+        while stack.last().is_some_and(|(open, _)| *open >= width) {
+            stack.pop();
+        }
+
+        let (content, tags) = if let Some(task) = body.strip_prefix("- ") {
+            split_tags(task)
+        } else if is_project_line(body) && !body.starts_with('\\') {
+            let (content, tags) = split_tags(body);
+            (content.trim_end_matches(':').trim_end().to_string(), tags)
+        } else if let Some((_, owner)) = stack.last() {
+            let body = body.strip_prefix('\\').unwrap_or(body);
+            let note = &mut nodes.get_mut(owner).unwrap().note;
+            match note {
+                Some(note) => {
+                    note.push('\n');
+                    note.push_str(body);
+                }
+                None => *note = Some(body.to_string()),
+            }
+            continue;
+        } else {
+            (body.to_string(), Vec::new())
+        };
+
+        let parent = stack.last().map_or(&root_id, |(_, id)| id).clone();
+        let id = Uuid::new_v4().to_string();
+        let mut node = new_node(id.clone(), &content, Some(parent.clone()), now);
+        for tag in tags {
+            let state = TaskState::ALL
+                .into_iter()
+                .find(|s| state_tag(*s) == Some(tag.as_str()));
+            match state.and_then(TaskState::icon) {
+                Some(icon) => node.icons.push(icon.to_string()),
+                None => node.tags.push(tag),
+            }
+        }
+        nodes.get_mut(&parent).unwrap().children.push(id.clone());
+        nodes.insert(id.clone(), node);
+        stack.push((width, id));
+        monitor.tick();
+    }
+    monitor.finish()?;
+
+    let root_id = match nodes[&root_id].children.as_slice() {
+        [only] => {
+            let only = only.clone();
+            nodes.remove(&root_id);
+            nodes.get_mut(&only).unwrap().parent = None;
+            only
+        }
+        _ => root_id,
+    };
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+/// Splits `@name` and `@name(value)` tags, which start a word, off the
+/// rest of the text. `@done(2024-05-01)` yields the tag `done(2024-05-01)`
+/// for the caller to interpret; state tags match on their name only.
+fn split_tags(text: &str) -> (String, Vec<String>) {
+    let mut content = String::new();
+    let mut tags = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let at_word = content.is_empty() || content.ends_with(char::is_whitespace);
+        let name_len = rest
+            .strip_prefix('@')
+            .filter(|_| at_word)
+            .map(|name| {
+                name.find(|c: char| c.is_whitespace() || c == '(')
+                    .unwrap_or(name.len())
+            })
+            .filter(|len| *len > 0);
+        let Some(name_len) = name_len else {
+            let c = rest.chars().next().unwrap();
+            content.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        };
+        let mut end = 1 + name_len;
+        if rest[end..].starts_with('(') {
+            end = rest[end..]
+                .find(')')
+                .map_or(rest.len(), |close| end + close + 1);
+        }
+        let tag = &rest[1..end];
+        let name = &tag[..name_len];
+        if TaskState::ALL
+            .into_iter()
+            .any(|s| state_tag(s) == Some(name))
+        {
+            tags.push(name.to_string());
+        } else {
+            tags.push(tag.to_string());
+        }
+        rest = &rest[end..];
+    }
+    (
+        content.split_whitespace().collect::<Vec<_>>().join(" "),
+        tags,
+    )
+}
+
+fn new_node(id: String, content: &str, parent: Option<String>, now: u64) -> Node {
+    Node {
+        id,
+        content: content.to_string(),
+        parent,
+        created: now,
+        modified: now,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taskpaper_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "Launch").unwrap();
+        let site = map.add_child(&root_id, "Website").unwrap();
+        let copy = map.add_child(&site, "Write copy").unwrap();
+        map.nodes
+            .get_mut(&copy)
+            .unwrap()
+            .icons
+            .push("button_ok".to_string());
+        let deploy = map.add_child(&site, "Deploy").unwrap();
+        map.nodes
+            .get_mut(&deploy)
+            .unwrap()
+            .icons
+            .push("go".to_string());
+        map.nodes
+            .get_mut(&deploy)
+            .unwrap()
+            .tags
+            .push("ops team".to_string());
+        map.set_note(
+            &deploy,
+            "Staging first\n\nThen production\n- not a task\nAgenda:\n\\kept",
+        )
+        .unwrap();
+
+        let text = to_taskpaper(&map);
+        assert_eq!(
+            text,
+            "Launch:\n\tWebsite:\n\t\t- Write copy @done\n\t\t- Deploy @started @ops_team\n\
+             \t\t\tStaging first\n\t\t\tThen production\n\t\t\t\\- not a task\n\
+             \t\t\t\\Agenda:\n\t\t\t\\\\kept\n"
+        );
+        assert_eq!(
+            to_todo_txt(&map),
+            "x Write copy +Website\nDeploy +Website @ops_team\n"
+        );
+
+        let loaded = from_taskpaper(&text).unwrap();
+        let root = &loaded.nodes[&loaded.root_id];
+        assert_eq!(root.content, "Launch");
+        let site = &loaded.nodes[&root.children[0]];
+        let copy = &loaded.nodes[&site.children[0]];
+        assert_eq!(copy.icons, ["button_ok"]);
+        let deploy = &loaded.nodes[&site.children[1]];
+        assert_eq!(deploy.content, "Deploy");
+        assert_eq!(deploy.icons, ["go"]);
+        assert_eq!(deploy.tags, ["ops_team"]);
+        assert_eq!(
+            deploy.note.as_deref(),
+            Some("Staging first\nThen production\n- not a task\nAgenda:\n\\kept")
+        );
+    }
+
+    #[test]
+    fn test_taskpaper_tags() {
+        let text = "Inbox: @home\n\t- Call bob@example.com @due(2024-05-01) @done(2024-04-30)\n\
+                    Errands:\n    - Groceries @paused\n";
+        let map = from_taskpaper(text).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "TaskPaper");
+        let inbox = &map.nodes[&root.children[0]];
+        assert_eq!(inbox.content, "Inbox");
+        assert_eq!(inbox.tags, ["home"]);
+        let call = &map.nodes[&inbox.children[0]];
+        assert_eq!(call.content, "Call bob@example.com");
+        assert_eq!(call.tags, ["due(2024-05-01)"]);
+        assert_eq!(call.icons, ["button_ok"]);
+        let errands = &map.nodes[&root.children[1]];
+        assert_eq!(map.nodes[&errands.children[0]].icons, ["prepare"]);
+    }
+}
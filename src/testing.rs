@@ -0,0 +1,320 @@
+use crate::{MindMap, Node};
+#[cfg(feature = "testing")]
+use arbitrary::{Arbitrary, Unstructured};
+#[cfg(feature = "testing")]
+use proptest::prelude::*;
+use std::collections::HashMap;
+use uuid::Builder;
+
+const WORDS: &[&str] = &[
+    "alpha", "budget", "client", "design", "review", "launch", "research", "team", "metric",
+    "goal", "risk", "plan", "idea", "draft", "scope", "market", "release", "feedback", "sprint",
+    "roadmap", "vision", "task", "owner", "deadline",
+];
+
+const ICONS: &[&str] = &[
+    "idea",
+    "help",
+    "yes",
+    "messagebox_warning",
+    "stop-sign",
+    "flag",
+    "button_ok",
+    "go",
+    "full-1",
+    "full-2",
+    "bookmark",
+];
+
+/// Shape of a generated map.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerateSpec {
+    /// Total nodes, including the root.
+    pub node_count: usize,
+    /// Deepest level below the root a node may be placed at.
+    pub max_depth: usize,
+    /// Probability of attaching a new node under the previously added one
+    /// instead of under a uniformly random node. Higher values give deeper,
+    /// narrower trees.
+    pub depth_bias: f64,
+    /// Inclusive range of words per node.
+    pub min_words: usize,
+    pub max_words: usize,
+    /// Probability that a node carries an icon.
+    pub icon_density: f64,
+}
+
+impl Default for GenerateSpec {
+    fn default() -> Self {
+        Self {
+            node_count: 100,
+            max_depth: 6,
+            depth_bias: 0.3,
+            min_words: 1,
+            max_words: 4,
+            icon_density: 0.1,
+        }
+    }
+}
+
+/// Generates a structurally valid map. The same seed and spec always
+/// produce the same map, down to node IDs and timestamps.
+pub fn generate(seed: u64, spec: &GenerateSpec) -> MindMap {
+    let mut rng = SplitMix64(seed);
+    let base_time = 1_700_000_000_000 + rng.below(1_000_000_000);
+
+    let root_id = rng.uuid();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: rng.sentence(spec),
+            created: base_time,
+            modified: base_time,
+            ..Default::default()
+        },
+    );
+
+    // (id, depth) of every node that may still receive children.
+    let mut open: Vec<(String, usize)> = Vec::new();
+    if spec.max_depth > 0 {
+        open.push((root_id.clone(), 0));
+    }
+    let mut last = 0;
+
+    for i in 1..spec.node_count {
+        if open.is_empty() {
+            break;
+        }
+        let slot = if rng.chance(spec.depth_bias) && last < open.len() {
+            last
+        } else {
+            rng.below(open.len() as u64) as usize
+        };
+        let (parent_id, depth) = open[slot].clone();
+
+        let id = rng.uuid();
+        let timestamp = base_time + i as u64 * 1_000;
+        let mut icons = Vec::new();
+        if rng.chance(spec.icon_density) {
+            icons.push(ICONS[rng.below(ICONS.len() as u64) as usize].to_string());
+        }
+        nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content: rng.sentence(spec),
+                parent: Some(parent_id.clone()),
+                created: timestamp,
+                modified: timestamp,
+                icons,
+                ..Default::default()
+            },
+        );
+        if let Some(parent) = nodes.get_mut(&parent_id) {
+            parent.children.push(id.clone());
+        }
+
+        last = slot;
+        if depth + 1 < spec.max_depth {
+            open.push((id, depth + 1));
+            last = open.len() - 1;
+        }
+    }
+
+    MindMap::from_nodes(nodes, root_id)
+}
+
+/// Proptest strategy for maps built by [`generate`] with up to `max_nodes`
+/// nodes. Content stays within a plain word list, so every format can
+/// represent it; use the [`Arbitrary`] impls to also cover odd text.
+#[cfg(feature = "testing")]
+pub fn arb_mind_map(max_nodes: usize) -> impl Strategy<Value = MindMap> {
+    (any::<u64>(), 1..=max_nodes.max(1), 1..8usize, 0.0..0.8f64).prop_map(
+        |(seed, node_count, max_depth, icon_density)| {
+            let spec = GenerateSpec {
+                node_count,
+                max_depth,
+                icon_density,
+                ..Default::default()
+            };
+            generate(seed, &spec)
+        },
+    )
+}
+
+/// A standalone node with no parent or children.
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for Node {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let created = u.arbitrary()?;
+        Ok(Node {
+            id: Builder::from_random_bytes(u.arbitrary()?)
+                .into_uuid()
+                .to_string(),
+            content: u.arbitrary()?,
+            created,
+            modified: created.saturating_add(u.arbitrary::<u32>()? as u64),
+            icons: arbitrary_icons(u)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// A structurally valid map: unique IDs, a single root, and parent/child
+/// links that agree with each other.
+#[cfg(feature = "testing")]
+impl<'a> Arbitrary<'a> for MindMap {
+    fn arbitrary(u: &mut Unstructured<'a>) -> arbitrary::Result<Self> {
+        let count = u.int_in_range(1..=64usize)?;
+        let mut ids: Vec<String> = Vec::with_capacity(count);
+        let mut nodes: HashMap<String, Node> = HashMap::new();
+
+        for i in 0..count {
+            // The index keeps IDs unique even when the input runs dry.
+            let mut bytes: [u8; 16] = u.arbitrary()?;
+            bytes[..8].copy_from_slice(&(i as u64).to_le_bytes());
+            let id = Builder::from_random_bytes(bytes).into_uuid().to_string();
+
+            let parent = if i == 0 {
+                None
+            } else {
+                Some(ids[u.choose_index(i)?].clone())
+            };
+            let mut node = Node::arbitrary(u)?;
+            node.id = id.clone();
+            node.parent = parent.clone();
+
+            if let Some(parent) = parent.and_then(|p| nodes.get_mut(&p)) {
+                parent.children.push(id.clone());
+            }
+            nodes.insert(id.clone(), node);
+            ids.push(id);
+        }
+
+        let mut map = MindMap::from_nodes(nodes, ids[0].clone());
+        map.selection.primary = ids[u.choose_index(count)?].clone();
+        Ok(map)
+    }
+}
+
+#[cfg(feature = "testing")]
+fn arbitrary_icons(u: &mut Unstructured) -> arbitrary::Result<Vec<String>> {
+    let count = u.int_in_range(0..=2)?;
+    (0..count)
+        .map(|_| u.choose(ICONS).map(|i| i.to_string()))
+        .collect()
+}
+
+// Small, dependency-free PRNG; quality is ample for test data.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 { 0 } else { self.next() % bound }
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        ((self.next() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    fn uuid(&mut self) -> String {
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&self.next().to_le_bytes());
+        bytes[8..].copy_from_slice(&self.next().to_le_bytes());
+        Builder::from_random_bytes(bytes).into_uuid().to_string()
+    }
+
+    fn sentence(&mut self, spec: &GenerateSpec) -> String {
+        let span = spec.max_words.saturating_sub(spec.min_words) as u64 + 1;
+        let count = spec.min_words + self.below(span) as usize;
+        let words: Vec<&str> = (0..count.max(1))
+            .map(|_| WORDS[self.below(WORDS.len() as u64) as usize])
+            .collect();
+        let mut sentence = words.join(" ");
+        if let Some(first) = sentence.get_mut(..1) {
+            first.make_ascii_uppercase();
+        }
+        sentence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_is_reproducible_and_valid() {
+        let spec = GenerateSpec {
+            node_count: 500,
+            max_depth: 4,
+            icon_density: 0.5,
+            ..Default::default()
+        };
+        let a = generate(42, &spec);
+        let b = generate(42, &spec);
+        assert_eq!(a.root_id, b.root_id);
+        assert_eq!(a.nodes.len(), 500);
+        for (id, node) in &a.nodes {
+            let other = &b.nodes[id];
+            assert_eq!(node.content, other.content);
+            assert_eq!(node.children, other.children);
+            assert_eq!(node.icons, other.icons);
+            if let Some(parent) = &node.parent {
+                assert!(a.nodes[parent].children.contains(id));
+            }
+        }
+
+        for node in a.nodes.values() {
+            let mut depth = 0;
+            let mut current = node;
+            while let Some(parent) = &current.parent {
+                depth += 1;
+                current = &a.nodes[parent];
+            }
+            assert!(depth <= 4);
+        }
+        assert!(a.nodes.values().any(|n| !n.icons.is_empty()));
+
+        assert_ne!(generate(7, &spec).root_id, a.root_id);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn test_arbitrary_map_is_structurally_valid() {
+        let data: Vec<u8> = (0..4096u32).map(|i| (i * 7919 % 251) as u8).collect();
+        let map = MindMap::arbitrary(&mut Unstructured::new(&data)).unwrap();
+        assert!(map.nodes.contains_key(&map.root_id));
+        assert!(map.nodes.contains_key(&map.selection.primary));
+        for (id, node) in &map.nodes {
+            match &node.parent {
+                Some(parent) => assert!(map.nodes[parent].children.contains(id)),
+                None => assert_eq!(id, &map.root_id),
+            }
+        }
+    }
+
+    #[cfg(feature = "testing")]
+    proptest! {
+        #[test]
+        fn prop_freemind_round_trip(map in arb_mind_map(64)) {
+            let xml = crate::storage::to_xml(&map).unwrap();
+            let loaded = crate::storage::from_xml(&xml).unwrap();
+            prop_assert_eq!(loaded.nodes.len(), map.nodes.len());
+            for (id, node) in &map.nodes {
+                prop_assert_eq!(&loaded.nodes[id].content, &node.content);
+                prop_assert_eq!(&loaded.nodes[id].children, &node.children);
+                prop_assert_eq!(&loaded.nodes[id].icons, &node.icons);
+            }
+        }
+    }
+}
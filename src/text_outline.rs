@@ -0,0 +1,171 @@
+use crate::export::ExportOptions;
+use crate::import::ImportOptions;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+// Plain-text outlines: one node per line, nested by indentation, as typed
+// in any text editor or copied from an outliner.
+
+/// Indentation of one outline level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Indent {
+    #[default]
+    Tab,
+    Spaces(usize),
+}
+
+impl Indent {
+    fn unit(self) -> String {
+        match self {
+            Indent::Tab => "\t".to_string(),
+            Indent::Spaces(width) => " ".repeat(width),
+        }
+    }
+
+    /// Columns a tab counts as when measuring indentation.
+    fn tab_width(self) -> usize {
+        match self {
+            Indent::Tab => 4,
+            Indent::Spaces(width) => width.max(1),
+        }
+    }
+}
+
+pub fn to_text_outline(map: &MindMap) -> String {
+    to_text_outline_with(map, Indent::default(), &ExportOptions::default())
+}
+
+/// Writes the root on the first line and each descendant one `indent`
+/// deeper than its parent. Multi-line content is joined with spaces.
+pub fn to_text_outline_with(map: &MindMap, indent: Indent, options: &ExportOptions) -> String {
+    let mut out = String::new();
+    let unit = indent.unit();
+    let mut stack: Vec<(&Node, usize)> = map
+        .nodes
+        .get(&map.root_id)
+        .map(|root| (root, 0))
+        .into_iter()
+        .collect();
+    while let Some((node, depth)) = stack.pop() {
+        out.push_str(&unit.repeat(depth));
+        out.push_str(&node.content.replace('\n', " "));
+        out.push('\n');
+        for child_id in node.children.iter().rev() {
+            if let Some(child) = map.nodes.get(child_id).filter(|c| options.includes(c)) {
+                stack.push((child, depth + 1));
+            }
+        }
+    }
+    out
+}
+
+pub fn from_text_outline(text: &str) -> Result<MindMap, BrainCoreError> {
+    from_text_outline_with(text, Indent::default(), &ImportOptions::default())
+}
+
+/// Reads one node per non-blank line. A line indented deeper than the one
+/// above is its child; otherwise it closes levels until it finds a
+/// shallower line. Tabs and spaces may be mixed, with a tab counting as
+/// `indent`'s width. A single top-level line becomes the root; otherwise
+/// the top level is gathered under an "Outline" root.
+pub fn from_text_outline_with(
+    text: &str,
+    indent: Indent,
+    options: &ImportOptions,
+) -> Result<MindMap, BrainCoreError> {
+    let now = now_millis();
+    let tab_width = indent.tab_width();
+    let root_id = Uuid::new_v4().to_string();
+    let mut nodes = HashMap::new();
+    nodes.insert(
+        root_id.clone(),
+        Node {
+            id: root_id.clone(),
+            content: "Outline".to_string(),
+            created: now,
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    // Open ancestors with their indentation width.
+    let mut stack: Vec<(usize, String)> = Vec::new();
+    for line in text.trim_start_matches('\u{feff}').lines() {
+        let body = line.trim_start();
+        if body.is_empty() {
+            continue;
+        }
+        let width: usize = line[..line.len() - body.len()]
+            .chars()
+            .map(|c| if c == '\t' { tab_width } else { 1 })
+            .sum();
+        while stack.last().is_some_and(|(open, _)| *open >= width) {
+            stack.pop();
+        }
+        let parent = stack.last().map_or(&root_id, |(_, id)| id).clone();
+        let id = Uuid::new_v4().to_string();
+        nodes.get_mut(&parent).unwrap().children.push(id.clone());
+        nodes.insert(
+            id.clone(),
+            Node {
+                id: id.clone(),
+                content: body.trim_end().to_string(),
+                parent: Some(parent),
+                created: now,
+                modified: now,
+                ..Default::default()
+            },
+        );
+        stack.push((width, id));
+    }
+
+    let root_id = match nodes[&root_id].children.as_slice() {
+        [only] => {
+            let only = only.clone();
+            nodes.remove(&root_id);
+            nodes.get_mut(&only).unwrap().parent = None;
+            only
+        }
+        _ => root_id,
+    };
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text_outline_round_trip() {
+        let text = "Plan\n  Research\n    Papers\n\n  Build\n\tShip\n";
+        let map =
+            from_text_outline_with(text, Indent::Spaces(2), &ImportOptions::default()).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "Plan");
+        let contents: Vec<&str> = root
+            .children
+            .iter()
+            .map(|id| map.nodes[id].content.as_str())
+            .collect();
+        // The tab counts as two spaces, making "Ship" a sibling of "Build".
+        assert_eq!(contents, ["Research", "Build", "Ship"]);
+
+        let written = to_text_outline_with(&map, Indent::Spaces(2), &ExportOptions::default());
+        assert_eq!(written, "Plan\n  Research\n    Papers\n  Build\n  Ship\n");
+        let tabbed = to_text_outline(&map);
+        assert_eq!(tabbed, "Plan\n\tResearch\n\t\tPapers\n\tBuild\n\tShip\n");
+        assert_eq!(
+            to_text_outline(&from_text_outline(&tabbed).unwrap()),
+            tabbed
+        );
+
+        let loose = from_text_outline("A\nB\n  B1\n").unwrap();
+        assert_eq!(loose.nodes[&loose.root_id].content, "Outline");
+        assert_eq!(loose.nodes.len(), 4);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+
+/// A span of time logged against a node, in milliseconds since the epoch.
+/// An entry without `end` is a running timer.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeEntry {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+impl TimeEntry {
+    /// Duration of the entry; running timers are measured up to `now`.
+    pub fn duration(&self, now: u64) -> u64 {
+        self.end.unwrap_or(now).saturating_sub(self.start)
+    }
+}
+
+impl MindMap {
+    /// Starts a timer on the node. Fails if one is already running.
+    pub fn start_timer(&mut self, node_id: &str) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        if node.time_entries.iter().any(|e| e.end.is_none()) {
+            return Err(BrainCoreError::InvalidOperation(
+                "Timer already running".to_string(),
+            ));
+        }
+        let now = now_millis();
+        node.time_entries.push(TimeEntry {
+            start: now,
+            end: None,
+        });
+        node.modified = now;
+        node.modified_by = self.author.clone();
+        self.bump_revision(node_id);
+        Ok(())
+    }
+
+    /// Stops the running timer on the node and returns the elapsed time.
+    pub fn stop_timer(&mut self, node_id: &str) -> Result<u64, BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let entry = node
+            .time_entries
+            .iter_mut()
+            .find(|e| e.end.is_none())
+            .ok_or_else(|| BrainCoreError::InvalidOperation("No timer running".to_string()))?;
+        let now = now_millis();
+        entry.end = Some(now.max(entry.start));
+        let elapsed = entry.duration(now);
+        node.modified = now;
+        node.modified_by = self.author.clone();
+        self.bump_revision(node_id);
+        Ok(elapsed)
+    }
+
+    /// Records `duration_ms` of already-spent time, ending now.
+    pub fn log_time(&mut self, node_id: &str, duration_ms: u64) -> Result<(), BrainCoreError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let now = now_millis();
+        node.time_entries.push(TimeEntry {
+            start: now.saturating_sub(duration_ms),
+            end: Some(now),
+        });
+        node.modified = now;
+        node.modified_by = self.author.clone();
+        self.bump_revision(node_id);
+        Ok(())
+    }
+
+    /// Total time logged directly on the node, including any running timer.
+    pub fn time_spent(&self, node_id: &str) -> u64 {
+        let now = now_millis();
+        self.nodes
+            .get(node_id)
+            .map(|node| node.time_entries.iter().map(|e| e.duration(now)).sum())
+            .unwrap_or(0)
+    }
+
+    /// Total time logged on the node and all of its descendants.
+    pub fn subtree_time(&self, node_id: &str) -> u64 {
+        let mut total = 0;
+        let mut stack = vec![node_id];
+        while let Some(id) = stack.pop() {
+            total += self.time_spent(id);
+            if let Some(node) = self.nodes.get(id) {
+                stack.extend(node.children.iter().map(String::as_str));
+            }
+        }
+        total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_time_aggregation() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let mut child = map.nodes[&root_id].clone();
+        child.id = "child".to_string();
+        child.parent = Some(root_id.clone());
+        map.nodes.insert(child.id.clone(), child);
+        map.nodes
+            .get_mut(&root_id)
+            .unwrap()
+            .children
+            .push("child".to_string());
+
+        map.set_author(Some("ada".to_string()));
+        map.log_time(&root_id, 1_000).unwrap();
+        let revision = map.nodes["child"].revision;
+        map.log_time("child", 2_500).unwrap();
+        let child = &map.nodes["child"];
+        assert_eq!(child.modified, child.time_entries[0].end.unwrap());
+        assert_eq!(child.modified_by.as_deref(), Some("ada"));
+        assert_eq!(child.revision, revision + 1);
+        map.log_time("child", 500).unwrap();
+
+        assert_eq!(map.time_spent("child"), 3_000);
+        assert_eq!(map.subtree_time(&root_id), 4_000);
+
+        map.start_timer("child").unwrap();
+        assert!(map.start_timer("child").is_err());
+        map.stop_timer("child").unwrap();
+        assert!(map.stop_timer("child").is_err());
+        assert_eq!(map.nodes["child"].time_entries.len(), 3);
+        assert!(map.log_time("missing", 1).is_err());
+    }
+}
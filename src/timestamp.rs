@@ -0,0 +1,239 @@
+// Clock and date parsing shared by the modules, kept small to avoid a
+// date crate.
+
+/// The current time in Unix milliseconds. In the browser the standard
+/// library has no clock, so the `wasm` feature asks JavaScript instead.
+pub(crate) fn now_millis() -> u64 {
+    #[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+    return js_sys::Date::now() as u64;
+    #[cfg(not(all(feature = "wasm", target_arch = "wasm32")))]
+    return std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+}
+
+/// Parses an ISO 8601 date-time into Unix milliseconds. Accepts the
+/// extended form (`2024-01-31T12:00:00.000Z`, `2024-01-31T12:00:00+01:00`),
+/// the basic form Evernote uses (`20240131T120000Z`) and bare dates, which
+/// are taken as midnight UTC. Times without an offset are treated as UTC.
+pub(crate) fn parse_iso8601(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (date, time) = match value.split_once(['T', ' ']) {
+        Some((date, time)) => (date, time),
+        None => (value, ""),
+    };
+
+    let date: String = date.chars().filter(|c| *c != '-').collect();
+    if date.len() != 8 || !date.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let year: i64 = date[..4].parse().ok()?;
+    let month: i64 = date[4..6].parse().ok()?;
+    let day: i64 = date[6..].parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let (clock, offset_minutes) = match time.find(['Z', 'z', '+', '-']) {
+        Some(at) => (&time[..at], parse_offset(&time[at..])?),
+        None => (time, 0),
+    };
+    let (clock, fraction) = clock.split_once(['.', ',']).unwrap_or((clock, ""));
+    let clock: String = clock.chars().filter(|c| *c != ':').collect();
+    if !clock.len().is_multiple_of(2)
+        || clock.len() > 6
+        || !clock.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let field = |i: usize| {
+        clock
+            .get(i..i + 2)
+            .map_or(Some(0), |s| s.parse::<i64>().ok())
+    };
+    let (hours, minutes, seconds) = (field(0)?, field(2)?, field(4)?);
+    let millis: i64 = format!("{:0<3}", &fraction[..fraction.len().min(3)])
+        .parse()
+        .ok()?;
+
+    let days = days_from_civil(year, month, day);
+    let total = ((days * 24 + hours) * 60 + minutes - offset_minutes) * 60 + seconds;
+    u64::try_from(total * 1000 + millis).ok()
+}
+
+/// Parses an RFC 2822 date such as `Mon, 1 Jan 2024 10:00:00 +0000`, as
+/// found in RSS feeds, into Unix milliseconds. Fields out of their
+/// calendar range, such as a five-digit year or hour 99, give `None`.
+pub(crate) fn parse_rfc2822(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let value = value.split_once(',').map_or(value, |(_, rest)| rest);
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [day, month, year, clock, rest @ ..] = parts.as_slice() else {
+        return None;
+    };
+
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let month = MONTHS
+        .iter()
+        .position(|m| month.get(..3).is_some_and(|p| p.eq_ignore_ascii_case(m)))?
+        as i64
+        + 1;
+    let day: i64 = day.parse().ok()?;
+    let year: i64 = year.parse().ok()?;
+    let mut clock = clock.split(':').map(|f| f.parse::<i64>().ok());
+    let hours = clock.next()??;
+    let minutes = clock.next()??;
+    let seconds = clock.next().unwrap_or(Some(0))?;
+    let offset_minutes = match rest.first() {
+        None => 0,
+        Some(zone) if ["GMT", "UT", "UTC", "Z"].contains(zone) => 0,
+        Some(zone) => parse_offset(zone)?,
+    };
+    if !(1..=31).contains(&day)
+        || !(0..=9999).contains(&year)
+        || !(0..24).contains(&hours)
+        || !(0..60).contains(&minutes)
+        || !(0..=60).contains(&seconds)
+    {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    let total = ((days * 24 + hours) * 60 + minutes - offset_minutes) * 60 + seconds;
+    u64::try_from(total * 1000).ok()
+}
+
+/// Formats Unix milliseconds as an extended ISO 8601 date-time in UTC,
+/// such as `2024-01-31T12:00:00.000Z`, the form [`parse_iso8601`] reads
+/// back exactly.
+pub(crate) fn format_iso8601(millis: u64) -> String {
+    let seconds = (millis / 1000) as i64;
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let clock = seconds.rem_euclid(86_400);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        clock / 3600,
+        clock / 60 % 60,
+        clock % 60,
+        millis % 1000
+    )
+}
+
+/// Formats Unix milliseconds as an RFC 2822 date in GMT, such as
+/// `Wed, 31 Jan 2024 12:00:00 GMT`, the form OPML and RSS use. The
+/// milliseconds are dropped.
+pub(crate) fn format_rfc2822(millis: u64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let seconds = (millis / 1000) as i64;
+    let days = seconds.div_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    let clock = seconds.rem_euclid(86_400);
+    format!(
+        "{}, {day:02} {} {year:04} {:02}:{:02}:{:02} GMT",
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        MONTHS[month as usize - 1],
+        clock / 3600,
+        clock / 60 % 60,
+        clock % 60
+    )
+}
+
+// `Z`, `+hh`, `+hhmm` or `+hh:mm`, returned in minutes east of UTC.
+fn parse_offset(offset: &str) -> Option<i64> {
+    let sign = match offset.chars().next()? {
+        'Z' | 'z' => return (offset.len() == 1).then_some(0),
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = offset[1..].chars().filter(|c| *c != ':').collect();
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = match &digits[2..] {
+        "" => 0,
+        minutes => minutes.parse().ok()?,
+    };
+    (hours < 24 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+// Days since 1970-01-01 in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// The inverse of `days_from_civil`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_iso8601() {
+        assert_eq!(parse_iso8601("1970-01-01"), Some(0));
+        assert_eq!(parse_iso8601("20240131T120000Z"), Some(1_706_702_400_000));
+        assert_eq!(
+            parse_iso8601("2024-01-31T12:00:00.250Z"),
+            Some(1_706_702_400_250)
+        );
+        assert_eq!(
+            parse_iso8601("2024-01-31T13:30:00+01:30"),
+            Some(1_706_702_400_000)
+        );
+        assert_eq!(
+            parse_iso8601("2024-01-31T07:00:00.000-0500"),
+            Some(1_706_702_400_000)
+        );
+        assert_eq!(parse_iso8601("2024-02-30x"), None);
+        assert_eq!(
+            parse_rfc2822("Wed, 31 Jan 2024 13:00:00 +0100"),
+            Some(1_706_702_400_000)
+        );
+        assert_eq!(parse_iso8601("yesterday"), None);
+        for overflowing in [
+            "Wed, 31 Jan 9223372036854775807 13:00:00 +0100",
+            "Wed, 31 Jan 2024 9223372036854775807:00:00 GMT",
+            "Wed, 31 Jan 2024 13:00:00 +00:9223372036854775807",
+        ] {
+            assert_eq!(parse_rfc2822(overflowing), None);
+        }
+        assert_eq!(
+            format_iso8601(1_706_702_400_250),
+            "2024-01-31T12:00:00.250Z"
+        );
+        assert_eq!(format_iso8601(951_782_400_000), "2000-02-29T00:00:00.000Z");
+        assert_eq!(
+            format_rfc2822(1_706_702_400_250),
+            "Wed, 31 Jan 2024 12:00:00 GMT"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::command::{Command, CommandResult};
+use crate::events::MapEvent;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+use std::collections::HashSet;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+/// The edits of a [`MindMap::transaction`]. Changes go through commands so
+/// the whole transaction can be undone as one.
+pub struct Transaction<'a> {
+    map: &'a mut MindMap,
+    inverses: Vec<Command>,
+    created: Vec<String>,
+}
+
+impl Transaction<'_> {
+    /// The map as the transaction has left it so far.
+    pub fn map(&self) -> &MindMap {
+        self.map
+    }
+
+    pub fn apply(&mut self, command: Command) -> Result<CommandResult, BrainCoreError> {
+        let result = self.map.apply(command)?;
+        self.inverses.push(result.inverse.clone());
+        self.created.extend(result.node_id.clone());
+        Ok(result)
+    }
+
+    pub fn add_child(&mut self, parent_id: &str, content: &str) -> Result<String, BrainCoreError> {
+        let result = self.apply(Command::AddChild {
+            parent_id: parent_id.to_string(),
+            content: content.to_string(),
+        })?;
+        Ok(result.node_id.unwrap_or_default())
+    }
+}
+
+/// What [`MindMap::transaction`] returned and how to take it back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionResult<T> {
+    pub value: T,
+    /// A `Batch` undoing the whole transaction in one step.
+    pub inverse: Command,
+}
+
+impl MindMap {
+    /// Runs `edit` as one change. Observers get a single
+    /// [`MapEvent::Batch`] at the end instead of an event per edit, every
+    /// node touched gets the same modification time, and nodes created get
+    /// it as their creation time too. If `edit` fails, what it did is
+    /// undone and its error returned; nodes it added are purged rather than
+    /// trashed. The same happens before a panic in `edit` carries on.
+    /// Revisions keep going up through the undoing, so none is reused.
+    pub fn transaction<T>(
+        &mut self,
+        edit: impl FnOnce(&mut Transaction) -> Result<T, BrainCoreError>,
+    ) -> Result<TransactionResult<T>, BrainCoreError> {
+        let observers = std::mem::take(&mut self.observers);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let log = events.clone();
+        self.add_observer(move |event: &MapEvent| log.lock().unwrap().push(event.clone()));
+
+        let mut tx = Transaction {
+            map: self,
+            inverses: Vec::new(),
+            created: Vec::new(),
+        };
+        let outcome = panic::catch_unwind(AssertUnwindSafe(|| edit(&mut tx)));
+        let Transaction {
+            mut inverses,
+            created,
+            ..
+        } = tx;
+        let value = match outcome {
+            Ok(Ok(value)) => value,
+            Ok(Err(e)) => {
+                self.rollback(inverses);
+                self.observers = observers;
+                return Err(e);
+            }
+            Err(payload) => {
+                self.rollback(inverses);
+                self.observers = observers;
+                panic::resume_unwind(payload);
+            }
+        };
+        self.observers = observers;
+
+        let events = std::mem::take(&mut *events.lock().unwrap_or_else(|e| e.into_inner()));
+        let now = now_millis();
+        let touched: HashSet<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                MapEvent::NodeAdded { node_id, .. }
+                | MapEvent::NodeChanged { node_id }
+                | MapEvent::NodeMoved { node_id, .. } => Some(node_id.as_str()),
+                _ => None,
+            })
+            .collect();
+        for node_id in touched {
+            if let Some(node) = self.nodes.get_mut(node_id) {
+                node.modified = now;
+            }
+        }
+        for node_id in &created {
+            if let Some(node) = self.nodes.get_mut(node_id) {
+                node.created = now;
+            }
+        }
+        if !events.is_empty() {
+            self.emit(MapEvent::Batch { events });
+        }
+        inverses.reverse();
+        Ok(TransactionResult {
+            value,
+            inverse: Command::Batch { commands: inverses },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transaction() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        map.add_observer(move |event: &MapEvent| log.lock().unwrap().push(event.clone()));
+
+        let result = map
+            .transaction(|tx| {
+                let parent = tx.add_child(&root_id, "Imported")?;
+                for i in 0..100 {
+                    tx.add_child(&parent, &format!("Item {i}"))?;
+                }
+                Ok(parent)
+            })
+            .unwrap();
+        let parent = result.value;
+        assert_eq!(map.nodes[&parent].children.len(), 100);
+        let events = seen.lock().unwrap().clone();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].events().len(), 2 * 101);
+        let modified = map.nodes[&parent].modified;
+        assert!(map.descendants(&parent).all(|n| n.modified == modified));
+        assert_eq!(map.nodes[&root_id].modified, modified);
+
+        // One step undoes all of it.
+        let redo = map.apply(result.inverse).unwrap().inverse;
+        assert!(map.nodes[&root_id].children.is_empty());
+        assert_eq!(map.nodes.len(), 1);
+        assert!(map.trash.is_empty());
+        map.apply(redo).unwrap();
+        assert_eq!(map.nodes[&parent].children.len(), 100);
+
+        // A failing transaction leaves the map as it was and says nothing.
+        let count = seen.lock().unwrap().len();
+        let revision = map.revision;
+        let root_revision = map.nodes[&root_id].revision;
+        let failed = map.transaction(|tx| {
+            tx.add_child(&root_id, "Partial")?;
+            tx.add_child("missing", "Orphan")
+        });
+        assert!(matches!(failed, Err(BrainCoreError::NodeNotFound(_))));
+        assert_eq!(map.nodes[&root_id].children, vec![parent.clone()]);
+        assert_eq!(seen.lock().unwrap().len(), count);
+        assert!(map.trash.is_empty());
+        // Revisions are never handed out twice.
+        assert!(map.revision > revision);
+        assert!(map.nodes[&root_id].revision > root_revision);
+
+        // Nor does a panicking one, and the map keeps its observers.
+        let nodes = map.nodes.len();
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.transaction(|tx| -> Result<(), BrainCoreError> {
+                tx.add_child(&root_id, "Partial")?;
+                panic!("edit gave up")
+            })
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(map.nodes.len(), nodes);
+        assert_eq!(map.nodes[&root_id].children, vec![parent]);
+        assert_eq!(seen.lock().unwrap().len(), count);
+        map.add_child(&root_id, "After").unwrap();
+        assert_eq!(seen.lock().unwrap().len(), count + 2);
+    }
+}
@@ -0,0 +1,231 @@
+use crate::events::MapEvent;
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// A subtree removed with [`MindMap::trash_node`], kept with enough context
+/// to put it back where it was. Trash is part of the serde representation
+/// of `MindMap`, so it persists wherever the map itself is serialized.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrashEntry {
+    pub id: String,
+    /// Top node of the trashed subtree.
+    pub node_id: String,
+    pub parent_id: String,
+    /// Position among the parent's children at deletion time.
+    pub index: usize,
+    pub deleted_at: u64,
+    pub nodes: Vec<Node>,
+}
+
+impl MindMap {
+    /// Moves a node and its descendants into the trash and returns the trash
+    /// entry id. If the selection was inside the subtree, the parent becomes
+    /// selected.
+    pub fn trash_node(&mut self, node_id: &str) -> Result<String, BrainCoreError> {
+        let entry = self.take_subtree(node_id)?;
+        let id = entry.id.clone();
+        self.trash.push(entry);
+        Ok(id)
+    }
+
+    /// Deletes a node and its descendants for good, skipping the trash, and
+    /// returns them as an entry that was never put in it, for
+    /// [`MindMap::reinsert`].
+    pub fn purge_node(&mut self, node_id: &str) -> Result<TrashEntry, BrainCoreError> {
+        self.take_subtree(node_id)
+    }
+
+    fn take_subtree(&mut self, node_id: &str) -> Result<TrashEntry, BrainCoreError> {
+        if node_id == self.root_id {
+            return Err(BrainCoreError::RootImmutable);
+        }
+        let node = self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        let parent_id = node
+            .parent
+            .clone()
+            .ok_or_else(|| BrainCoreError::InvalidOperation("Node has no parent".to_string()))?;
+
+        let mut index = 0;
+        if let Some(parent) = self.nodes.get_mut(&parent_id) {
+            index = parent
+                .children
+                .iter()
+                .position(|c| c == node_id)
+                .unwrap_or(parent.children.len());
+            parent.children.retain(|c| c != node_id);
+        }
+
+        let mut removed = Vec::new();
+        let mut stack = vec![node_id.to_string()];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.nodes.remove(&id) {
+                stack.extend(node.children.iter().cloned());
+                removed.push(node);
+            }
+        }
+
+        if !self.nodes.contains_key(&self.selection.primary) {
+            self.set_selected(parent_id.clone());
+        }
+        self.prune_selection();
+
+        self.bump_revision(&parent_id);
+        self.emit(MapEvent::NodeRemoved {
+            node_id: node_id.to_string(),
+        });
+        Ok(TrashEntry {
+            id: Uuid::new_v4().to_string(),
+            node_id: node_id.to_string(),
+            parent_id,
+            index,
+            deleted_at: now_millis(),
+            nodes: removed,
+        })
+    }
+
+    /// Puts a trashed subtree back at its original position. If the original
+    /// parent no longer exists the subtree is attached to the root instead.
+    pub fn restore(&mut self, trash_id: &str) -> Result<(), BrainCoreError> {
+        let pos = self
+            .trash
+            .iter()
+            .position(|e| e.id == trash_id)
+            .ok_or_else(|| BrainCoreError::TrashEntryNotFound(trash_id.to_string()))?;
+        self.check_reinsert(&self.trash[pos])?;
+        let entry = self.trash.remove(pos);
+        self.reinsert(entry)
+    }
+
+    /// Puts back a subtree taken out with [`MindMap::purge_node`], as
+    /// [`MindMap::restore`] does for the trash.
+    pub fn reinsert(&mut self, entry: TrashEntry) -> Result<(), BrainCoreError> {
+        self.check_reinsert(&entry)?;
+        let parent_id = if self.nodes.contains_key(&entry.parent_id) {
+            entry.parent_id
+        } else {
+            self.root_id.clone()
+        };
+
+        for mut node in entry.nodes {
+            if node.id == entry.node_id {
+                node.parent = Some(parent_id.clone());
+            }
+            self.nodes.insert(node.id.clone(), node);
+        }
+        if let Some(parent) = self.nodes.get_mut(&parent_id) {
+            let index = entry.index.min(parent.children.len());
+            parent.children.insert(index, entry.node_id.clone());
+        }
+        self.bump_revision(&parent_id);
+        self.emit(MapEvent::NodeAdded {
+            node_id: entry.node_id,
+            parent_id: Some(parent_id),
+        });
+        Ok(())
+    }
+
+    fn check_reinsert(&self, entry: &TrashEntry) -> Result<(), BrainCoreError> {
+        if entry.nodes.iter().any(|n| self.nodes.contains_key(&n.id)) {
+            return Err(BrainCoreError::InvalidOperation(
+                "Restoring would overwrite existing nodes".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Permanently drops everything in the trash, with the relationships,
+    /// groups and summaries that pointed into it and the files only its
+    /// nodes used.
+    pub fn empty_trash(&mut self) {
+        if !self.trash.is_empty() {
+            let purged: HashSet<String> = self
+                .trash
+                .drain(..)
+                .flat_map(|entry| entry.nodes)
+                .map(|node| node.id)
+                .collect();
+            self.drop_references(&purged);
+            self.prune_resources();
+            self.revision += 1;
+        }
+    }
+
+    /// Drops the relationships, groups and summaries that mention any of
+    /// `removed`.
+    pub(crate) fn drop_references(&mut self, removed: &HashSet<String>) {
+        self.relationships
+            .retain(|r| !removed.contains(&r.from) && !removed.contains(&r.to));
+        self.groups.retain(|g| !removed.contains(&g.node_id));
+        self.summaries.retain(|s| {
+            ![&s.parent_id, &s.first_child, &s.last_child]
+                .into_iter()
+                .any(|id| removed.contains(id))
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_child_for_test(map: &mut MindMap, parent_id: &str, content: &str) -> String {
+        let id = format!("node-{}", map.nodes.len());
+        let node = Node {
+            id: id.clone(),
+            content: content.to_string(),
+            parent: Some(parent_id.to_string()),
+            ..Default::default()
+        };
+        map.nodes.insert(id.clone(), node);
+        if let Some(parent) = map.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        id
+    }
+
+    #[test]
+    fn test_trash_and_restore() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = add_child_for_test(&mut map, &root_id, "A");
+        let b = add_child_for_test(&mut map, &root_id, "B");
+        let grand = add_child_for_test(&mut map, &a, "Grand");
+        map.selection.primary = grand.clone();
+
+        let trash_id = map.trash_node(&a).unwrap();
+        assert_eq!(map.nodes.len(), 2);
+        assert_eq!(map.nodes[&root_id].children, vec![b.clone()]);
+        assert_eq!(map.selection.primary, root_id);
+        assert_eq!(map.trash[0].nodes.len(), 2);
+
+        // Trash survives serialization of the map.
+        let json = serde_json::to_string(&map).unwrap();
+        let mut map: MindMap = serde_json::from_str(&json).unwrap();
+
+        map.restore(&trash_id).unwrap();
+        assert_eq!(map.nodes.len(), 4);
+        assert_eq!(map.nodes[&root_id].children, vec![a.clone(), b.clone()]);
+        assert_eq!(map.nodes[&grand].parent.as_deref(), Some(a.as_str()));
+        assert!(map.trash.is_empty());
+        assert!(map.restore(&trash_id).is_err());
+        assert!(map.trash_node(&root_id).is_err());
+
+        // Emptying the trash takes what pointed into it along.
+        map.add_relationship(&grand, &b, None).unwrap();
+        map.add_group(&grand, None).unwrap();
+        map.add_summary(&a, 0, 0, "Sum").unwrap();
+        map.trash_node(&a).unwrap();
+        assert_eq!(map.relationships.len(), 1);
+        map.empty_trash();
+        assert!(map.trash.is_empty());
+        assert!(map.relationships.is_empty());
+        assert!(map.groups.is_empty());
+        assert!(map.summaries.is_empty());
+    }
+}
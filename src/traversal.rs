@@ -0,0 +1,170 @@
+use crate::{MindMap, Node};
+use std::collections::VecDeque;
+
+/// Depth-first, pre-order walk over nodes. Children are visited in order.
+pub struct Dfs<'a> {
+    map: &'a MindMap,
+    stack: Vec<&'a str>,
+}
+
+impl<'a> Iterator for Dfs<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        while let Some(id) = self.stack.pop() {
+            if let Some(node) = self.map.nodes.get(id) {
+                self.stack
+                    .extend(node.children.iter().rev().map(String::as_str));
+                return Some(node);
+            }
+        }
+        None
+    }
+}
+
+/// Breadth-first walk over nodes, level by level.
+pub struct Bfs<'a> {
+    map: &'a MindMap,
+    queue: VecDeque<&'a str>,
+}
+
+impl<'a> Iterator for Bfs<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        while let Some(id) = self.queue.pop_front() {
+            if let Some(node) = self.map.nodes.get(id) {
+                self.queue.extend(node.children.iter().map(String::as_str));
+                return Some(node);
+            }
+        }
+        None
+    }
+}
+
+/// Walk from a node's parent up to the root.
+pub struct Ancestors<'a> {
+    map: &'a MindMap,
+    next: Option<&'a str>,
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<&'a Node> {
+        let node = self.map.nodes.get(self.next?)?;
+        self.next = node.parent.as_deref();
+        Some(node)
+    }
+}
+
+impl MindMap {
+    /// Every node reachable from the root, depth-first, starting with the
+    /// root.
+    pub fn iter_dfs(&self) -> Dfs<'_> {
+        Dfs {
+            map: self,
+            stack: vec![self.root_id.as_str()],
+        }
+    }
+
+    /// Every node reachable from the root, breadth-first, starting with the
+    /// root.
+    pub fn iter_bfs(&self) -> Bfs<'_> {
+        Bfs {
+            map: self,
+            queue: VecDeque::from([self.root_id.as_str()]),
+        }
+    }
+
+    /// The subtree below `node_id`, depth-first, without the node itself.
+    /// Empty if the node doesn't exist.
+    pub fn descendants(&self, node_id: &str) -> Dfs<'_> {
+        let stack = self
+            .nodes
+            .get(node_id)
+            .map(|n| n.children.iter().rev().map(String::as_str).collect())
+            .unwrap_or_default();
+        Dfs { map: self, stack }
+    }
+
+    /// The parent of `node_id`, its parent, and so on up to the root.
+    pub fn ancestors(&self, node_id: &str) -> Ancestors<'_> {
+        Ancestors {
+            map: self,
+            next: self.nodes.get(node_id).and_then(|n| n.parent.as_deref()),
+        }
+    }
+
+    /// The nodes from the root down to `node_id`, inclusive. Empty if the
+    /// node doesn't exist.
+    pub fn path_to(&self, node_id: &str) -> Vec<&Node> {
+        let mut path: Vec<&Node> = self.nodes.get(node_id).into_iter().collect();
+        path.extend(self.ancestors(node_id));
+        path.reverse();
+        path
+    }
+
+    /// The contents along [`MindMap::path_to`] joined by `separator`, as in
+    /// "Root > Project > Task". Line breaks in content become spaces.
+    pub fn breadcrumb_string(&self, node_id: &str, separator: &str) -> String {
+        self.path_to(node_id)
+            .iter()
+            .map(|n| n.content.replace('\n', " "))
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+
+    /// The other children of `node_id`'s parent, in order. The root has no
+    /// siblings.
+    pub fn siblings<'a>(&'a self, node_id: &'a str) -> impl Iterator<Item = &'a Node> + 'a {
+        self.nodes
+            .get(node_id)
+            .and_then(|n| n.parent.as_deref())
+            .and_then(|p| self.nodes.get(p))
+            .into_iter()
+            .flat_map(|p| &p.children)
+            .filter(move |c| *c != node_id)
+            .filter_map(|c| self.nodes.get(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traversal_iterators() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.change_node(&root_id, "R").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        let a1 = map.add_child(&a, "A1").unwrap();
+        map.add_child(&a1, "A1x").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.add_child(&b, "B1").unwrap();
+        map.add_child(&root_id, "C").unwrap();
+
+        let contents = |nodes: Vec<&Node>| -> Vec<String> {
+            nodes.into_iter().map(|n| n.content.clone()).collect()
+        };
+        assert_eq!(
+            contents(map.iter_dfs().collect()),
+            ["R", "A", "A1", "A1x", "B", "B1", "C"]
+        );
+        assert_eq!(
+            contents(map.iter_bfs().collect()),
+            ["R", "A", "B", "C", "A1", "B1", "A1x"]
+        );
+        assert_eq!(contents(map.descendants(&a).collect()), ["A1", "A1x"]);
+        assert_eq!(contents(map.ancestors(&a1).collect()), ["A", "R"]);
+        assert_eq!(contents(map.siblings(&b).collect()), ["A", "C"]);
+        assert_eq!(map.siblings(&root_id).count(), 0);
+        assert_eq!(map.descendants("missing").count(), 0);
+        assert_eq!(contents(map.path_to(&a1)), ["R", "A", "A1"]);
+        assert!(map.path_to("missing").is_empty());
+        map.change_node(&a, "Two\nlines").unwrap();
+        assert_eq!(map.breadcrumb_string(&a1, " > "), "R > Two lines > A1");
+        assert_eq!(map.breadcrumb_string(&root_id, " / "), "R");
+    }
+}
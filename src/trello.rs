@@ -0,0 +1,353 @@
+use crate::import::{ImportFormat, ImportOptions};
+use crate::progress::{Monitor, Stage};
+use crate::task::TaskInfo;
+use crate::timestamp::now_millis;
+use crate::timestamp::parse_iso8601;
+use crate::{BrainCoreError, MindMap, Node};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Trello board export ("Print and export" → JSON). Only the fields that map
+// onto nodes are read.
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloBoard {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub desc: String,
+    #[serde(default)]
+    pub lists: Vec<TrelloList>,
+    #[serde(default)]
+    pub cards: Vec<TrelloCard>,
+    #[serde(default)]
+    pub checklists: Vec<TrelloChecklist>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrelloList {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub closed: bool,
+    #[serde(default)]
+    pub pos: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloCard {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub desc: String,
+    #[serde(default)]
+    pub closed: bool,
+    pub id_list: String,
+    #[serde(default)]
+    pub pos: f64,
+    #[serde(default)]
+    pub due: Option<String>,
+    #[serde(default)]
+    pub due_complete: bool,
+    #[serde(default)]
+    pub labels: Vec<TrelloLabel>,
+    #[serde(default)]
+    pub date_last_activity: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrelloLabel {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloChecklist {
+    pub id: String,
+    pub name: String,
+    pub id_card: String,
+    #[serde(default)]
+    pub pos: f64,
+    #[serde(default)]
+    pub check_items: Vec<TrelloCheckItem>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrelloCheckItem {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub state: String,
+    #[serde(default)]
+    pub pos: f64,
+}
+
+pub fn from_trello(json: &str) -> Result<MindMap, BrainCoreError> {
+    from_trello_with(json, &ImportOptions::default())
+}
+
+/// The board becomes the root, lists its branches and cards their children.
+/// Card labels become tags, due dates and completion become task info and
+/// checklists become child nodes with one task per item. Closed lists and
+/// cards are imported as archived nodes. Node IDs are Trello's own.
+pub fn from_trello_with(json: &str, options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_trello_monitored(json, options, &mut Monitor::default())
+}
+
+pub fn from_trello_monitored(
+    json: &str,
+    options: &ImportOptions,
+    monitor: &mut Monitor,
+) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
+    let board: TrelloBoard = serde_json::from_str(json)
+        .map_err(|e| BrainCoreError::json(ImportFormat::Trello, json, e))?;
+    let now = now_millis();
+
+    let mut nodes = HashMap::new();
+    let root_id = board.id.clone();
+    insert(
+        &mut nodes,
+        Node {
+            id: root_id.clone(),
+            content: board.name.clone(),
+            note: non_empty(&board.desc),
+            created: created_at(&board.id).unwrap_or(now),
+            modified: now,
+            ..Default::default()
+        },
+    );
+
+    let total = board.lists.len()
+        + board.cards.len()
+        + board
+            .checklists
+            .iter()
+            .map(|c| 1 + c.check_items.len())
+            .sum::<usize>();
+    monitor.begin(Stage::BuildingNodes, Some(total))?;
+
+    let mut lists: Vec<&TrelloList> = board.lists.iter().collect();
+    lists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    for list in lists {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        let created = created_at(&list.id).unwrap_or(now);
+        insert(
+            &mut nodes,
+            Node {
+                id: list.id.clone(),
+                content: list.name.clone(),
+                parent: Some(root_id.clone()),
+                created,
+                modified: created,
+                archived: list.closed,
+                ..Default::default()
+            },
+        );
+    }
+
+    let mut cards: Vec<&TrelloCard> = board.cards.iter().collect();
+    cards.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    for card in cards {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        // Cards of lists missing from the export still land on the board.
+        let parent = if nodes.contains_key(&card.id_list) {
+            card.id_list.clone()
+        } else {
+            root_id.clone()
+        };
+        let created = created_at(&card.id).unwrap_or(now);
+        let due = card.due.as_deref().and_then(parse_iso8601);
+        insert(
+            &mut nodes,
+            Node {
+                id: card.id.clone(),
+                content: card.name.clone(),
+                parent: Some(parent),
+                created,
+                modified: card
+                    .date_last_activity
+                    .as_deref()
+                    .and_then(parse_iso8601)
+                    .unwrap_or(created),
+                note: non_empty(&card.desc),
+                tags: card
+                    .labels
+                    .iter()
+                    .filter_map(|l| non_empty(&l.name).or_else(|| l.color.clone()))
+                    .collect(),
+                task: (due.is_some() || card.due_complete).then(|| TaskInfo {
+                    done: card.due_complete,
+                    progress_pct: if card.due_complete { 100 } else { 0 },
+                    due,
+                    ..Default::default()
+                }),
+                archived: card.closed,
+                ..Default::default()
+            },
+        );
+    }
+
+    let mut checklists: Vec<&TrelloChecklist> = board.checklists.iter().collect();
+    checklists.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+    for checklist in checklists {
+        if monitor.should_stop() {
+            break;
+        }
+        monitor.tick();
+        if !nodes.contains_key(&checklist.id_card) {
+            continue;
+        }
+        let created = created_at(&checklist.id).unwrap_or(now);
+        let done = checklist
+            .check_items
+            .iter()
+            .filter(|i| i.state == "complete")
+            .count();
+        let total = checklist.check_items.len();
+        insert(
+            &mut nodes,
+            Node {
+                id: checklist.id.clone(),
+                content: checklist.name.clone(),
+                parent: Some(checklist.id_card.clone()),
+                created,
+                modified: created,
+                task: Some(TaskInfo {
+                    done: total > 0 && done == total,
+                    progress_pct: (done * 100).checked_div(total).unwrap_or(0) as u8,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+
+        let mut items: Vec<&TrelloCheckItem> = checklist.check_items.iter().collect();
+        items.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+        for item in items {
+            monitor.tick();
+            let complete = item.state == "complete";
+            insert(
+                &mut nodes,
+                Node {
+                    id: item.id.clone(),
+                    content: item.name.clone(),
+                    parent: Some(checklist.id.clone()),
+                    created: created_at(&item.id).unwrap_or(created),
+                    modified: created,
+                    task: Some(TaskInfo {
+                        done: complete,
+                        progress_pct: if complete { 100 } else { 0 },
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+    monitor.finish()?;
+
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    options.apply(&mut map)?;
+    Ok(map)
+}
+
+// Adds `node` and links it into its parent's children.
+fn insert(nodes: &mut HashMap<String, Node>, node: Node) {
+    if let Some(parent) = node.parent.as_ref().and_then(|p| nodes.get_mut(p)) {
+        parent.children.push(node.id.clone());
+    }
+    nodes.insert(node.id.clone(), node);
+}
+
+// Trello IDs are Mongo ObjectIds, whose first 4 bytes are the creation time
+// in Unix seconds.
+fn created_at(id: &str) -> Option<u64> {
+    let seconds = u64::from_str_radix(id.get(..8)?, 16).ok()?;
+    Some(seconds * 1000)
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOARD: &str = r#"{
+      "id": "65a1b2c3d4e5f60718293a4b",
+      "name": "Release 2.0",
+      "desc": "Everything for the launch",
+      "closed": false,
+      "labels": [{"id": "l1", "name": "backend", "color": "green"}],
+      "lists": [
+        {"id": "65a1b2c3d4e5f60718293a50", "name": "Done", "closed": false, "pos": 32768},
+        {"id": "65a1b2c3d4e5f60718293a4f", "name": "To do", "closed": false, "pos": 16384},
+        {"id": "65a1b2c3d4e5f60718293a51", "name": "Icebox", "closed": true, "pos": 65536}
+      ],
+      "cards": [
+        {"id": "65a1b2c3d4e5f60718293a61", "name": "Write changelog", "desc": "", "closed": false,
+         "idList": "65a1b2c3d4e5f60718293a4f", "pos": 2, "due": null, "dueComplete": false,
+         "labels": [], "idChecklists": []},
+        {"id": "65a1b2c3d4e5f60718293a60", "name": "Migrate database", "desc": "Run on staging first",
+         "closed": false, "idList": "65a1b2c3d4e5f60718293a4f", "pos": 1,
+         "due": "2024-03-01T17:00:00.000Z", "dueComplete": false,
+         "labels": [{"id": "l1", "name": "backend", "color": "green"}, {"id": "l2", "name": "", "color": "red"}],
+         "idChecklists": ["65a1b2c3d4e5f60718293a70"], "dateLastActivity": "2024-02-20T09:30:00.000Z"},
+        {"id": "65a1b2c3d4e5f60718293a62", "name": "Old idea", "closed": true,
+         "idList": "65a1b2c3d4e5f60718293a51", "pos": 1}
+      ],
+      "checklists": [
+        {"id": "65a1b2c3d4e5f60718293a70", "name": "Steps", "idCard": "65a1b2c3d4e5f60718293a60", "pos": 1,
+         "checkItems": [
+           {"id": "i2", "name": "Run migration", "state": "incomplete", "pos": 2},
+           {"id": "i1", "name": "Back up", "state": "complete", "pos": 1}
+         ]}
+      ],
+      "actions": []
+    }"#;
+
+    #[test]
+    fn test_trello_import() {
+        let map = from_trello(BOARD).unwrap();
+        let root = &map.nodes[&map.root_id];
+        assert_eq!(root.content, "Release 2.0");
+        assert_eq!(root.note.as_deref(), Some("Everything for the launch"));
+        let lists: Vec<&str> = root
+            .children
+            .iter()
+            .map(|id| map.nodes[id].content.as_str())
+            .collect();
+        assert_eq!(lists, ["To do", "Done", "Icebox"]);
+        assert!(map.nodes["65a1b2c3d4e5f60718293a51"].archived);
+
+        let todo = &map.nodes["65a1b2c3d4e5f60718293a4f"];
+        let card = &map.nodes[&todo.children[0]];
+        assert_eq!(card.content, "Migrate database");
+        assert_eq!(card.tags, ["backend", "red"]);
+        assert_eq!(card.created, 0x65a1b2c3 * 1000);
+        let task = card.task.as_ref().unwrap();
+        assert_eq!(task.due, Some(1_709_312_400_000));
+        assert!(!task.done);
+        assert!(map.nodes[&todo.children[1]].task.is_none());
+
+        let steps = &map.nodes[&card.children[0]];
+        assert_eq!(steps.task.as_ref().unwrap().progress_pct, 50);
+        let first = &map.nodes[&steps.children[0]];
+        assert_eq!(first.content, "Back up");
+        assert!(first.task.as_ref().unwrap().done);
+    }
+}
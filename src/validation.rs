@@ -0,0 +1,170 @@
+use crate::{BrainCoreError, MindMap};
+use std::fmt;
+use std::sync::Arc;
+
+/// A pending content change, as seen by a [`Validator`].
+pub struct Change<'a> {
+    pub map: &'a MindMap,
+    /// The node being edited, or `None` for a node about to be added.
+    pub node_id: Option<&'a str>,
+    pub parent_id: Option<&'a str>,
+}
+
+/// Outcome of validating content.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Validation {
+    Valid,
+    /// Store this repaired content instead.
+    Sanitized(String),
+    /// Refuse the change with this message.
+    Invalid(String),
+}
+
+/// What a built-in validator does with content that breaks its rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    Reject,
+    Sanitize,
+}
+
+/// A content rule enforced by [`MindMap::add_child`] and
+/// [`MindMap::change_node`]. Closures taking `(&Change, &str)` and returning
+/// a [`Validation`] implement it, which covers most business rules.
+pub trait Validator: Send + Sync {
+    fn validate(&self, change: &Change, content: &str) -> Validation;
+}
+
+impl<F> Validator for F
+where
+    F: Fn(&Change, &str) -> Validation + Send + Sync,
+{
+    fn validate(&self, change: &Change, content: &str) -> Validation {
+        self(change, content)
+    }
+}
+
+/// Limits content to `max` characters; sanitizing truncates.
+#[derive(Debug, Clone)]
+pub struct MaxLength {
+    pub max: usize,
+    pub policy: Policy,
+}
+
+impl Validator for MaxLength {
+    fn validate(&self, _change: &Change, content: &str) -> Validation {
+        if content.chars().count() <= self.max {
+            return Validation::Valid;
+        }
+        match self.policy {
+            Policy::Reject => {
+                Validation::Invalid(format!("Content is longer than {} characters", self.max))
+            }
+            Policy::Sanitize => Validation::Sanitized(content.chars().take(self.max).collect()),
+        }
+    }
+}
+
+/// Disallows the given characters; sanitizing removes them.
+#[derive(Debug, Clone)]
+pub struct ForbiddenChars {
+    pub chars: Vec<char>,
+    pub policy: Policy,
+}
+
+impl Validator for ForbiddenChars {
+    fn validate(&self, _change: &Change, content: &str) -> Validation {
+        let Some(found) = content.chars().find(|c| self.chars.contains(c)) else {
+            return Validation::Valid;
+        };
+        match self.policy {
+            Policy::Reject => Validation::Invalid(format!("Content contains {found:?}")),
+            Policy::Sanitize => Validation::Sanitized(
+                content
+                    .chars()
+                    .filter(|c| !self.chars.contains(c))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// The validators registered on a map. They are configuration, not
+/// content, so they are skipped by serde and start out empty.
+#[derive(Clone, Default)]
+pub struct Validators(Vec<Arc<dyn Validator>>);
+
+impl fmt::Debug for Validators {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Validators({})", self.0.len())
+    }
+}
+
+impl MindMap {
+    /// Registers a validator. Validators run in registration order, each
+    /// seeing the content as repaired by the ones before it.
+    pub fn add_validator(&mut self, validator: impl Validator + 'static) {
+        self.validators.0.push(Arc::new(validator));
+    }
+
+    pub fn clear_validators(&mut self) {
+        self.validators.0.clear();
+    }
+
+    /// Runs the registered validators and returns the content to store.
+    pub(crate) fn validate_content(
+        &self,
+        change: &Change,
+        content: &str,
+    ) -> Result<String, BrainCoreError> {
+        let mut content = content.to_string();
+        for validator in &self.validators.0 {
+            match validator.validate(change, &content) {
+                Validation::Valid => {}
+                Validation::Sanitized(repaired) => content = repaired,
+                Validation::Invalid(message) => return Err(BrainCoreError::Validation(message)),
+            }
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validators_reject_and_sanitize() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        map.add_validator(ForbiddenChars {
+            chars: vec!['<', '>'],
+            policy: Policy::Sanitize,
+        });
+        map.add_validator(MaxLength {
+            max: 10,
+            policy: Policy::Reject,
+        });
+        // Only one level of nesting below the root.
+        map.add_validator(|change: &Change, _: &str| match change.parent_id {
+            Some(parent) if change.node_id.is_none() && parent != change.map.root_id => {
+                Validation::Invalid("Maps are limited to one level".to_string())
+            }
+            _ => Validation::Valid,
+        });
+
+        let child = map.add_child(&root_id, "<b>Plan</b>").unwrap();
+        assert_eq!(map.nodes[&child].content, "bPlan/b");
+        assert!(matches!(
+            map.change_node(&child, "Much too long"),
+            Err(BrainCoreError::Validation(message))
+                if message == "Content is longer than 10 characters"
+        ));
+        assert_eq!(map.nodes[&child].content, "bPlan/b");
+        assert!(map.add_child(&child, "Deeper").is_err());
+        assert_eq!(map.nodes.len(), 2);
+
+        map.clear_validators();
+        map.change_node(&child, "Much too long").unwrap();
+        assert!(map.clone().add_child(&child, "Deeper").is_ok());
+    }
+}
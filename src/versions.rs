@@ -0,0 +1,231 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read, Write};
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const SNAPSHOT_ENTRY: &str = "map.json";
+
+/// Which versions a [`VersionStore`] drops as new ones come in. `Default`
+/// keeps everything. The newest version is never dropped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PruningPolicy {
+    /// Keep at most this many versions, dropping the oldest first.
+    pub max_versions: Option<usize>,
+    /// Drop versions taken longer ago than this many milliseconds.
+    pub max_age_millis: Option<u64>,
+}
+
+/// What [`VersionStore::list_versions`] tells about a snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Version {
+    pub id: String,
+    pub label: String,
+    /// Milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    /// The map's revision when the snapshot was taken.
+    pub revision: u64,
+    pub node_count: usize,
+    /// Size of the compressed snapshot in bytes.
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Snapshot {
+    version: Version,
+    data: Vec<u8>,
+}
+
+/// Labelled, timestamped snapshots of one map for browsing and restoring
+/// its history. Each snapshot is the whole document, deflated; settings
+/// such as validators and observers are not part of it. The store is
+/// serializable so it can be saved next to the map.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VersionStore {
+    snapshots: Vec<Snapshot>,
+    #[serde(default)]
+    policy: PruningPolicy,
+}
+
+impl VersionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn policy(&self) -> PruningPolicy {
+        self.policy
+    }
+
+    /// Sets the pruning policy and applies it right away.
+    pub fn set_policy(&mut self, policy: PruningPolicy) {
+        self.policy = policy;
+        self.prune();
+    }
+
+    /// Stores the current state of `map` and returns the new version's id.
+    /// Older versions are then pruned according to the policy.
+    pub fn snapshot(&mut self, map: &MindMap, label: &str) -> Result<String, BrainCoreError> {
+        let json = serde_json::to_vec(map).map_err(BrainCoreError::export)?;
+        let mut data = Vec::new();
+        let mut zip = ZipWriter::new(Cursor::new(&mut data));
+        zip.start_file(
+            SNAPSHOT_ENTRY,
+            SimpleFileOptions::default()
+                .compression_method(CompressionMethod::Deflated)
+                .last_modified_time(zip::DateTime::default()),
+        )?;
+        zip.write_all(&json)?;
+        zip.finish()?;
+
+        let version = Version {
+            id: Uuid::new_v4().to_string(),
+            label: label.to_string(),
+            timestamp: now_millis(),
+            revision: map.revision,
+            node_count: map.nodes.len(),
+            size: data.len(),
+        };
+        let id = version.id.clone();
+        self.snapshots.push(Snapshot { version, data });
+        self.prune();
+        Ok(id)
+    }
+
+    /// Stored versions, oldest first.
+    pub fn list_versions(&self) -> Vec<&Version> {
+        self.snapshots.iter().map(|s| &s.version).collect()
+    }
+
+    /// Replaces the document in `map` with a stored version. The map keeps
+    /// its settings and observers, and its revision moves forward so that
+    /// caches keyed on it are not mistaken for the old state. Fails, leaving
+    /// the map alone, if the version doesn't fit the map's limits.
+    pub fn restore(&self, version_id: &str, map: &mut MindMap) -> Result<(), BrainCoreError> {
+        let restored = self.load(version_id)?;
+        map.limits.check(&restored)?;
+        let revision = map.revision.max(restored.revision) + 1;
+        let selection = restored.selection.clone();
+        // Swap in the whole document, then hand back the settings, which
+        // snapshots leave out.
+        let previous = std::mem::replace(map, restored);
+        map.revision = revision;
+        map.selection = previous.selection;
+        map.validators = previous.validators;
+        map.observers = previous.observers;
+        map.limits = previous.limits;
+        map.layout = previous.layout;
+        map.history = previous.history;
+        map.author = previous.author;
+        map.set_selected(selection.primary);
+        map.selection.additional = selection.additional;
+        map.prune_selection();
+        Ok(())
+    }
+
+    /// A stored version as a map of its own, with default settings.
+    pub fn load(&self, version_id: &str) -> Result<MindMap, BrainCoreError> {
+        let snapshot = self
+            .snapshots
+            .iter()
+            .find(|s| s.version.id == version_id)
+            .ok_or_else(|| BrainCoreError::VersionNotFound(version_id.to_string()))?;
+        let mut archive = ZipArchive::new(Cursor::new(&snapshot.data))?;
+        let mut json = Vec::new();
+        archive.by_name(SNAPSHOT_ENTRY)?.read_to_end(&mut json)?;
+        serde_json::from_slice(&json).map_err(|e| {
+            BrainCoreError::InvalidOperation(format!("Corrupt snapshot {version_id}: {e}"))
+        })
+    }
+
+    pub fn remove_version(&mut self, version_id: &str) -> Result<Version, BrainCoreError> {
+        let index = self
+            .snapshots
+            .iter()
+            .position(|s| s.version.id == version_id)
+            .ok_or_else(|| BrainCoreError::VersionNotFound(version_id.to_string()))?;
+        Ok(self.snapshots.remove(index).version)
+    }
+
+    /// Applies the pruning policy. Snapshots also prune, but versions only
+    /// age out while time passes.
+    pub fn prune(&mut self) {
+        let Some(newest) = self.snapshots.pop() else {
+            return;
+        };
+        if let Some(age) = self.policy.max_age_millis {
+            let cutoff = now_millis().saturating_sub(age);
+            self.snapshots.retain(|s| s.version.timestamp >= cutoff);
+        }
+        self.snapshots.push(newest);
+        if let Some(max) = self.policy.max_versions {
+            let excess = self.snapshots.len().saturating_sub(max.max(1));
+            self.snapshots.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::MapLimits;
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let mut store = VersionStore::new();
+        let empty = store.snapshot(&map, "Empty").unwrap();
+        let a = map.add_child(&root_id, "A").unwrap();
+        map.selection.primary = a.clone();
+        let one = store.snapshot(&map, "One child").unwrap();
+        map.change_node(&a, "Renamed").unwrap();
+        let b = map.add_child(&root_id, "B").unwrap();
+        map.add_group(&b, None).unwrap();
+        map.add_summary(&root_id, 0, 1, "Both").unwrap();
+        let revision = map.revision;
+
+        let labels: Vec<&str> = store
+            .list_versions()
+            .iter()
+            .map(|v| v.label.as_str())
+            .collect();
+        assert_eq!(labels, vec!["Empty", "One child"]);
+        assert_eq!(store.list_versions()[1].node_count, 2);
+
+        store.restore(&one, &mut map).unwrap();
+        assert_eq!(map.nodes.len(), 2);
+        assert_eq!(map.nodes[&a].content, "A");
+        assert_eq!(map.selection.primary, a);
+        assert!(map.groups.is_empty());
+        assert!(map.summaries.is_empty());
+        assert!(map.revision > revision);
+        assert_eq!(store.load(&empty).unwrap().nodes.len(), 1);
+
+        let mut small = MindMap::new();
+        small.set_limits(MapLimits {
+            max_nodes: Some(1),
+            ..Default::default()
+        });
+        assert!(matches!(
+            store.restore(&one, &mut small),
+            Err(BrainCoreError::Limit(_))
+        ));
+        assert_eq!(small.nodes.len(), 1);
+
+        store.set_policy(PruningPolicy {
+            max_versions: Some(1),
+            ..PruningPolicy::default()
+        });
+        assert_eq!(store.list_versions().len(), 1);
+        assert!(matches!(
+            store.restore(&empty, &mut map),
+            Err(BrainCoreError::VersionNotFound(_))
+        ));
+
+        let json = serde_json::to_string(&store).unwrap();
+        let store: VersionStore = serde_json::from_str(&json).unwrap();
+        assert_eq!(store.load(&one).unwrap().nodes[&a].content, "A");
+    }
+}
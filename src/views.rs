@@ -0,0 +1,346 @@
+use crate::timestamp::now_millis;
+use crate::{BrainCoreError, MindMap, Node};
+
+/// Workflow state of a node, derived from its task and task icons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskState {
+    Todo,
+    InProgress,
+    Paused,
+    Done,
+}
+
+impl TaskState {
+    pub const ALL: [TaskState; 4] = [
+        TaskState::Todo,
+        TaskState::InProgress,
+        TaskState::Paused,
+        TaskState::Done,
+    ];
+
+    /// The FreeMind icon representing this state, if any.
+    pub fn icon(self) -> Option<&'static str> {
+        match self {
+            TaskState::Todo => None,
+            TaskState::InProgress => Some("go"),
+            TaskState::Paused => Some("prepare"),
+            TaskState::Done => Some("button_ok"),
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            TaskState::Todo => "To do",
+            TaskState::InProgress => "In progress",
+            TaskState::Paused => "Paused",
+            TaskState::Done => "Done",
+        }
+    }
+
+    /// A done [`TaskInfo`](crate::task::TaskInfo) makes the node done;
+    /// otherwise its state icon decides, and a task with some progress but
+    /// no icon is in progress.
+    pub fn of(node: &Node) -> TaskState {
+        if node.task.as_ref().is_some_and(|t| t.done) {
+            return TaskState::Done;
+        }
+        TaskState::ALL
+            .into_iter()
+            .rev()
+            .find(|s| s.icon().is_some_and(|i| node.icons.iter().any(|n| n == i)))
+            .or_else(|| {
+                let task = node.task.as_ref()?;
+                (task.completion() > 0).then_some(TaskState::InProgress)
+            })
+            .unwrap_or(TaskState::Todo)
+    }
+}
+
+/// How nodes are distributed across board columns.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GroupBy {
+    /// One column per listed icon, plus a leading column for nodes carrying
+    /// none of them. A node goes to the column of its first matching icon.
+    Icon(Vec<String>),
+    /// One column per listed tag, plus a leading column for nodes carrying
+    /// none of them. Tags match ignoring case, and a node goes to the
+    /// column of its first matching tag.
+    Tag(Vec<String>),
+    /// One column per [`TaskState`].
+    TaskState,
+}
+
+/// What a column stands for; moving a card into it applies this value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnKey {
+    Icon(Option<String>),
+    Tag(Option<String>),
+    TaskState(TaskState),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub node_id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub key: ColumnKey,
+    pub title: String,
+    pub cards: Vec<Card>,
+}
+
+/// Options shared by view projections.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ViewOptions {
+    /// Show archived nodes and their subtrees, which are hidden by default.
+    pub include_archived: bool,
+}
+
+/// A board projection of a map. Cards reference nodes by id, so the board
+/// stays valid as a view while the map is edited through [`Board::apply_move`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Board {
+    pub group_by: GroupBy,
+    pub columns: Vec<Column>,
+}
+
+/// Projects every non-root node onto board columns, in tree order.
+pub fn kanban(map: &MindMap, group_by: GroupBy) -> Board {
+    kanban_with(map, group_by, &ViewOptions::default())
+}
+
+pub fn kanban_with(map: &MindMap, group_by: GroupBy, options: &ViewOptions) -> Board {
+    let mut columns: Vec<Column> = match &group_by {
+        GroupBy::Icon(icons) => std::iter::once(ColumnKey::Icon(None))
+            .chain(icons.iter().map(|i| ColumnKey::Icon(Some(i.clone()))))
+            .map(new_column)
+            .collect(),
+        GroupBy::Tag(tags) => std::iter::once(ColumnKey::Tag(None))
+            .chain(tags.iter().map(|t| ColumnKey::Tag(Some(t.clone()))))
+            .map(new_column)
+            .collect(),
+        GroupBy::TaskState => TaskState::ALL
+            .into_iter()
+            .map(|s| new_column(ColumnKey::TaskState(s)))
+            .collect(),
+    };
+
+    let mut stack: Vec<&str> = match map.nodes.get(&map.root_id) {
+        Some(root) => root.children.iter().rev().map(String::as_str).collect(),
+        None => Vec::new(),
+    };
+    while let Some(id) = stack.pop() {
+        let Some(node) = map.nodes.get(id) else {
+            continue;
+        };
+        if node.archived && !options.include_archived {
+            continue;
+        }
+        let index = match &group_by {
+            GroupBy::Icon(icons) => icons
+                .iter()
+                .position(|i| node.icons.contains(i))
+                .map_or(0, |p| p + 1),
+            GroupBy::Tag(tags) => tags
+                .iter()
+                .position(|t| has_tag(node, t))
+                .map_or(0, |p| p + 1),
+            GroupBy::TaskState => TaskState::ALL
+                .iter()
+                .position(|s| *s == TaskState::of(node))
+                .unwrap_or(0),
+        };
+        columns[index].cards.push(Card {
+            node_id: node.id.clone(),
+            title: node.content.clone(),
+        });
+        stack.extend(node.children.iter().rev().map(String::as_str));
+    }
+
+    Board { group_by, columns }
+}
+
+impl Board {
+    /// Moves a card into `column` by updating the underlying node so that the
+    /// next [`kanban`] projection places it there. The board itself is not
+    /// updated; re-project to refresh it.
+    pub fn apply_move(
+        &self,
+        map: &mut MindMap,
+        card: &Card,
+        column: &Column,
+    ) -> Result<(), BrainCoreError> {
+        let node = map
+            .nodes
+            .get_mut(&card.node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(card.node_id.clone()))?;
+        match (&self.group_by, &column.key) {
+            (GroupBy::Icon(icons), ColumnKey::Icon(target)) => {
+                node.icons.retain(|i| !icons.contains(i));
+                if let Some(icon) = target {
+                    node.icons.push(icon.clone());
+                }
+            }
+            (GroupBy::Tag(tags), ColumnKey::Tag(target)) => {
+                node.tags
+                    .retain(|t| !tags.iter().any(|listed| t.eq_ignore_ascii_case(listed)));
+                if let Some(tag) = target {
+                    node.tags.push(tag.clone());
+                }
+            }
+            (GroupBy::TaskState, ColumnKey::TaskState(state)) => {
+                node.icons
+                    .retain(|i| !TaskState::ALL.iter().any(|s| s.icon() == Some(i.as_str())));
+                if let Some(icon) = state.icon() {
+                    node.icons.push(icon.to_string());
+                }
+                if let Some(task) = &mut node.task {
+                    task.done = *state == TaskState::Done;
+                    if *state == TaskState::Todo {
+                        task.progress_pct = 0;
+                    }
+                }
+            }
+            _ => {
+                return Err(BrainCoreError::InvalidOperation(
+                    "Column does not belong to this board".to_string(),
+                ));
+            }
+        }
+        node.modified = now_millis();
+        node.modified_by = map.author.clone();
+        map.bump_revision(&card.node_id);
+        Ok(())
+    }
+}
+
+fn has_tag(node: &Node, tag: &str) -> bool {
+    node.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+}
+
+fn new_column(key: ColumnKey) -> Column {
+    let title = match &key {
+        ColumnKey::Icon(Some(icon)) => icon.clone(),
+        ColumnKey::Tag(Some(tag)) => tag.clone(),
+        ColumnKey::Icon(None) | ColumnKey::Tag(None) => "Other".to_string(),
+        ColumnKey::TaskState(state) => state.title().to_string(),
+    };
+    Column {
+        key,
+        title,
+        cards: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::task::TaskInfo;
+
+    fn add_child_for_test(map: &mut MindMap, parent_id: &str, content: &str) -> String {
+        let id = format!("node-{}", map.nodes.len());
+        let node = Node {
+            id: id.clone(),
+            content: content.to_string(),
+            parent: Some(parent_id.to_string()),
+            ..Default::default()
+        };
+        map.nodes.insert(id.clone(), node);
+        if let Some(parent) = map.nodes.get_mut(parent_id) {
+            parent.children.push(id.clone());
+        }
+        id
+    }
+
+    #[test]
+    fn test_kanban_by_task_state() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = add_child_for_test(&mut map, &root_id, "Write spec");
+        let b = add_child_for_test(&mut map, &root_id, "Ship it");
+        map.nodes.get_mut(&b).unwrap().icons.push("go".to_string());
+
+        let board = kanban(&map, GroupBy::TaskState);
+        assert_eq!(board.columns.len(), 4);
+        assert_eq!(board.columns[0].cards[0].node_id, a);
+        assert_eq!(board.columns[1].cards[0].node_id, b);
+
+        let card = board.columns[1].cards[0].clone();
+        board
+            .apply_move(&mut map, &card, &board.columns[3])
+            .unwrap();
+        assert_eq!(map.nodes[&b].icons, vec!["button_ok".to_string()]);
+
+        let board = kanban(&map, GroupBy::TaskState);
+        assert_eq!(board.columns[3].cards[0].node_id, b);
+        assert!(board.columns[1].cards.is_empty());
+
+        // Tasks set through the task API land where their state says.
+        let started = add_child_for_test(&mut map, &root_id, "Started");
+        let finished = add_child_for_test(&mut map, &root_id, "Finished");
+        let task = |done, progress_pct| TaskInfo {
+            done,
+            progress_pct,
+            ..Default::default()
+        };
+        map.set_task(&started, Some(task(false, 40))).unwrap();
+        map.set_task(&finished, Some(task(true, 0))).unwrap();
+        let board = kanban(&map, GroupBy::TaskState);
+        assert_eq!(board.columns[1].cards[0].node_id, started);
+        assert_eq!(board.columns[3].cards[1].node_id, finished);
+
+        let card = board.columns[3].cards[1].clone();
+        board
+            .apply_move(&mut map, &card, &board.columns[0])
+            .unwrap();
+        assert_eq!(map.nodes[&finished].task, Some(task(false, 0)));
+        let board = kanban(&map, GroupBy::TaskState);
+        assert_eq!(board.columns[0].cards[1].node_id, finished);
+    }
+
+    #[test]
+    fn test_kanban_by_tag() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = add_child_for_test(&mut map, &root_id, "Bug");
+        let b = add_child_for_test(&mut map, &root_id, "Chore");
+        map.tag(&a, "Urgent").unwrap();
+
+        let group_by = GroupBy::Tag(vec!["urgent".to_string(), "later".to_string()]);
+        let board = kanban(&map, group_by);
+        assert_eq!(board.columns.len(), 3);
+        assert_eq!(board.columns[0].cards[0].node_id, b);
+        assert_eq!(board.columns[1].cards[0].node_id, a);
+        assert_eq!(board.columns[2].title, "later");
+
+        let card = board.columns[1].cards[0].clone();
+        board
+            .apply_move(&mut map, &card, &board.columns[2])
+            .unwrap();
+        assert_eq!(map.nodes[&a].tags, vec!["later".to_string()]);
+    }
+
+    #[test]
+    fn test_kanban_by_icon() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let a = add_child_for_test(&mut map, &root_id, "Idea");
+        map.nodes
+            .get_mut(&a)
+            .unwrap()
+            .icons
+            .push("idea".to_string());
+
+        let group_by = GroupBy::Icon(vec!["idea".to_string(), "flag".to_string()]);
+        let board = kanban(&map, group_by);
+        assert_eq!(board.columns[1].cards[0].node_id, a);
+
+        let card = board.columns[1].cards[0].clone();
+        board
+            .apply_move(&mut map, &card, &board.columns[2])
+            .unwrap();
+        assert_eq!(map.nodes[&a].icons, vec!["flag".to_string()]);
+    }
+}
@@ -0,0 +1,276 @@
+use crate::command::Command;
+use crate::formats::{self, Format};
+use crate::import::ImportOptions;
+use crate::navigation::Direction;
+use crate::{BrainCoreError, MindMap, json, svg};
+use wasm_bindgen::prelude::*;
+
+/// A map for JavaScript, exported as `MindMap`. Methods take and return
+/// node ids as strings, documents as `Uint8Array` or strings, and
+/// structured values such as nodes and commands as JSON text. Errors are
+/// thrown as `Error`s carrying the message of the [`BrainCoreError`].
+///
+/// Formats are named by file extension (`"mm"`, `"opml"`, `"xmind"`,
+/// ...), plus `"json"` for the crate's own format.
+#[wasm_bindgen(js_name = MindMap)]
+pub struct WasmMindMap {
+    map: MindMap,
+}
+
+#[wasm_bindgen(js_class = MindMap)]
+impl WasmMindMap {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmMindMap {
+        WasmMindMap {
+            map: MindMap::new(),
+        }
+    }
+
+    /// Reads a document in any supported format, detected from its
+    /// contents.
+    pub fn import(data: &[u8]) -> Result<WasmMindMap, JsError> {
+        Ok(WasmMindMap {
+            map: formats::import(data)?,
+        })
+    }
+
+    #[wasm_bindgen(js_name = importAs)]
+    pub fn import_as(data: &[u8], format: &str) -> Result<WasmMindMap, JsError> {
+        let map = format_named(format)?.import(data, &ImportOptions::default())?;
+        Ok(WasmMindMap { map })
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(text: &str) -> Result<WasmMindMap, JsError> {
+        Ok(WasmMindMap {
+            map: json::from_json(text)?,
+        })
+    }
+
+    pub fn export(&self, format: &str) -> Result<Vec<u8>, JsError> {
+        Ok(formats::export(&self.map, format_named(format)?)?)
+    }
+
+    /// [`WasmMindMap::export`] for text formats, decoded as UTF-8.
+    #[wasm_bindgen(js_name = exportText)]
+    pub fn export_text(&self, format: &str) -> Result<String, JsError> {
+        let data = self.export(format)?;
+        String::from_utf8(data).map_err(|_| JsError::new("The format is not a text format"))
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(json::to_json(&self.map)?)
+    }
+
+    #[wasm_bindgen(js_name = toSvg)]
+    pub fn to_svg(&self) -> String {
+        svg::to_svg(&self.map)
+    }
+
+    #[wasm_bindgen(getter, js_name = rootId)]
+    pub fn root_id(&self) -> String {
+        self.map.root_id.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = selectedNodeId)]
+    pub fn selected_node_id(&self) -> String {
+        self.map.selection.primary.clone()
+    }
+
+    /// The primary node first.
+    #[wasm_bindgen(getter, js_name = selectedNodeIds)]
+    pub fn selected_node_ids(&self) -> Vec<String> {
+        self.map.selection.ids().map(str::to_string).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn revision(&self) -> u64 {
+        self.map.revision
+    }
+
+    /// The node as JSON, with its children as a list of ids.
+    pub fn node(&self, node_id: &str) -> Result<String, JsError> {
+        let node = self
+            .map
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        Ok(serde_json::to_string(node)?)
+    }
+
+    pub fn children(&self, node_id: &str) -> Result<Vec<String>, JsError> {
+        let node = self
+            .map
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| BrainCoreError::NodeNotFound(node_id.to_string()))?;
+        Ok(node.children.clone())
+    }
+
+    #[wasm_bindgen(js_name = addChild)]
+    pub fn add_child(&mut self, parent_id: &str, content: &str) -> Result<String, JsError> {
+        Ok(self.map.add_child(parent_id, content)?)
+    }
+
+    #[wasm_bindgen(js_name = changeNode)]
+    pub fn change_node(&mut self, node_id: &str, content: &str) -> Result<(), JsError> {
+        Ok(self.map.change_node(node_id, content)?)
+    }
+
+    /// Moves the subtree to the trash and returns the trash entry id.
+    #[wasm_bindgen(js_name = trashNode)]
+    pub fn trash_node(&mut self, node_id: &str) -> Result<String, JsError> {
+        Ok(self.map.trash_node(node_id)?)
+    }
+
+    pub fn restore(&mut self, trash_id: &str) -> Result<(), JsError> {
+        self.map.restore(trash_id)?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = moveNode)]
+    pub fn move_node(
+        &mut self,
+        node_id: &str,
+        parent_id: &str,
+        position: usize,
+    ) -> Result<(), JsError> {
+        Ok(self.map.move_node(node_id, parent_id, position)?)
+    }
+
+    #[wasm_bindgen(js_name = setNote)]
+    pub fn set_note(&mut self, node_id: &str, note: Option<String>) -> Result<(), JsError> {
+        match note {
+            Some(note) => self.map.set_note(node_id, &note)?,
+            None => self.map.clear_note(node_id)?,
+        }
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = setIcons)]
+    pub fn set_icons(&mut self, node_id: &str, icons: Vec<String>) -> Result<(), JsError> {
+        Ok(self.map.set_icons(node_id, icons)?)
+    }
+
+    #[wasm_bindgen(js_name = setFolded)]
+    pub fn set_folded(&mut self, node_id: &str, folded: bool) -> Result<(), JsError> {
+        Ok(self.map.set_folded(node_id, folded)?)
+    }
+
+    /// Runs a [`Command`] given as JSON and returns its inverse as JSON,
+    /// for undo and redo.
+    pub fn apply(&mut self, command: &str) -> Result<String, JsError> {
+        let command: Command = serde_json::from_str(command)?;
+        let result = self.map.apply(command)?;
+        Ok(serde_json::to_string(&result.inverse)?)
+    }
+
+    #[wasm_bindgen(js_name = computeLayout)]
+    pub fn compute_layout(&mut self) {
+        self.map.compute_layout();
+    }
+
+    #[wasm_bindgen(js_name = setPosition)]
+    pub fn set_position(&mut self, node_id: &str, x: f32, y: f32) -> Result<(), JsError> {
+        Ok(self.map.set_position(node_id, x, y)?)
+    }
+
+    pub fn select(&mut self, node_id: &str) -> Result<(), JsError> {
+        Ok(self.map.select(node_id)?)
+    }
+
+    #[wasm_bindgen(js_name = selectAdd)]
+    pub fn select_add(&mut self, node_id: &str) -> Result<(), JsError> {
+        Ok(self.map.select_add(node_id)?)
+    }
+
+    #[wasm_bindgen(js_name = selectRange)]
+    pub fn select_range(&mut self, node_id: &str) -> Result<(), JsError> {
+        Ok(self.map.select_range(node_id)?)
+    }
+
+    #[wasm_bindgen(js_name = clearSelection)]
+    pub fn clear_selection(&mut self) {
+        self.map.clear_selection();
+    }
+
+    /// Moves the selection towards `"up"`, `"down"`, `"left"` or
+    /// `"right"`. Returns whether it moved.
+    pub fn navigate(&mut self, direction: &str) -> Result<bool, JsError> {
+        let direction = match direction {
+            "up" => Direction::Up,
+            "down" => Direction::Down,
+            "left" => Direction::Left,
+            "right" => Direction::Right,
+            _ => return Err(JsError::new(&format!("Unknown direction: {direction}"))),
+        };
+        Ok(self.map.navigate_spatial(direction))
+    }
+
+    #[wasm_bindgen(js_name = goBack)]
+    pub fn go_back(&mut self) -> bool {
+        self.map.go_back()
+    }
+
+    #[wasm_bindgen(js_name = goForward)]
+    pub fn go_forward(&mut self) -> bool {
+        self.map.go_forward()
+    }
+
+    /// Ids of the nodes whose text contains `text`, in tree order.
+    pub fn search(&self, text: &str) -> Vec<String> {
+        self.map
+            .search(text)
+            .into_iter()
+            .map(|hit| hit.node_id)
+            .collect()
+    }
+}
+
+impl Default for WasmMindMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn format_named(name: &str) -> Result<Format, BrainCoreError> {
+    match name {
+        "json" => Ok(Format::Json),
+        name => Format::from_extension(name)
+            .ok_or_else(|| BrainCoreError::InvalidOperation(format!("Unknown format: {name}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_map() {
+        let mut map = WasmMindMap::new();
+        let root_id = map.root_id();
+        let a = map.add_child(&root_id, "Alpha").unwrap();
+        map.set_icons(&a, vec!["idea".to_string()]).unwrap();
+        map.compute_layout();
+        assert!(map.navigate("right").unwrap());
+        assert_eq!(map.selected_node_id(), a);
+        assert_eq!(map.search("alp"), vec![a.clone()]);
+
+        let inverse = map
+            .apply(
+                r#"{"Rename": {"node_id": "ROOT", "content": "Home"}}"#
+                    .replace("ROOT", &root_id)
+                    .as_str(),
+            )
+            .unwrap();
+        assert!(inverse.contains("Central Node"));
+
+        let opml = map.export("opml").unwrap();
+        let copy = WasmMindMap::import(&opml).unwrap();
+        assert_eq!(copy.map.nodes.len(), 2);
+        let text = map.export_text("md").unwrap();
+        assert!(text.contains("Alpha"));
+        assert!(map.to_svg().starts_with("<svg"));
+    }
+}
@@ -1,7 +1,17 @@
 use serde::{Deserialize, Serialize};
-use crate::{MindMap, Node};
-use std::io::{Read, Write, Cursor};
-use zip::write::SimpleFileOptions;
+use crate::attachments::{self, Attachment};
+use crate::export::ExportOptions;
+use crate::groups::{Group, GroupStyle};
+use crate::html;
+use crate::import::{ImportFormat, ImportOptions};
+use crate::layout::Offsets;
+use crate::progress::{Monitor, Stage};
+use crate::relationship::Relationship;
+use crate::style::NodeStyle;
+use crate::summaries::Summary;
+use crate::task::TaskInfo;
+use crate::{BrainCoreError, MindMap, Node};
+use std::io::{Read, Write, Seek, Cursor};
 use zip::{ZipArchive, ZipWriter};
 
 // XMind JSON structures
@@ -13,6 +23,19 @@ pub struct XmindSheet {
     #[serde(rename = "rootTopic")]
     pub root_topic: XmindTopic,
     pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relationships: Vec<XmindRelationship>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindRelationship {
+    pub id: String,
+    #[serde(rename = "end1Id")]
+    pub end1_id: String,
+    #[serde(rename = "end2Id")]
+    pub end2_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,6 +48,100 @@ pub struct XmindTopic {
     pub markers: Vec<XmindMarker>,
     #[serde(default)]
     pub children: Option<XmindChildren>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<XmindNotes>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    /// `"folded"` when the topic's children are collapsed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<XmindStyle>,
+    // XMind records who last edited a topic, not who created it.
+    #[serde(rename = "modifiedBy", default, skip_serializing_if = "Option::is_none")]
+    pub modified_by: Option<String>,
+    /// Relative to the root topic on detached topics, and to the parent on
+    /// topics placed by hand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<XmindPosition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<XmindImage>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub boundaries: Vec<XmindBoundary>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summaries: Vec<XmindSummary>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<XmindExtension>,
+}
+
+/// A frame around part of a topic's subtree. `range` is `"master"` for the
+/// topic and all below it, or `"(first,last)"` for a run of its children.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindBoundary {
+    pub id: String,
+    pub range: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub style: Option<XmindStyle>,
+}
+
+/// A bracket over the run of children `range`, `"(first,last)"`, next to
+/// the topic `topic_id` from the parent's summary children.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindSummary {
+    pub id: String,
+    pub range: String,
+    #[serde(rename = "topicId")]
+    pub topic_id: String,
+}
+
+/// An image shown in a topic. `src` points into the package as
+/// `xap:resources/<file>`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindImage {
+    pub src: String,
+}
+
+/// Provider of the topic extension that holds node attributes. XMind has no
+/// attribute table of its own; it keeps extensions it doesn't know.
+pub const ATTRIBUTES_PROVIDER: &str = "brain_core.attributes";
+
+/// Provider of the topic extension that holds the node's task as JSON. The
+/// task also shows as a marker, which files from XMind itself only have.
+pub const TASK_PROVIDER: &str = "brain_core.task";
+
+/// Provider of the topic extension that lists every attachment of the
+/// node. XMind itself shows one image per topic and links one file.
+pub const ATTACHMENTS_PROVIDER: &str = "brain_core.attachments";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindExtension {
+    pub provider: String,
+    #[serde(default)]
+    pub content: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindAttribute {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindAttachment {
+    pub name: String,
+    pub mime: String,
+    pub src: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindPosition {
+    pub x: f32,
+    pub y: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,10 +150,102 @@ pub struct XmindMarker {
     pub marker_id: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindNotes {
+    #[serde(default)]
+    pub plain: Option<XmindPlainNote>,
+    /// Formatted copy of the note; only read when there is no plain one.
+    #[serde(rename = "realHTML", default, skip_serializing)]
+    pub real_html: Option<XmindPlainNote>,
+}
+
+impl XmindNotes {
+    fn text(&self) -> Option<String> {
+        match (&self.plain, &self.real_html) {
+            (Some(plain), _) => Some(plain.content.clone()),
+            (None, Some(html)) => Some(html::to_plain_text(&html.content)),
+            (None, None) => None,
+        }
+        .filter(|text| !text.is_empty())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindPlainNote {
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct XmindStyle {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub properties: XmindStyleProperties,
+}
+
+// Sizes are written with a unit, e.g. "14pt"; only the number is kept.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct XmindStyleProperties {
+    #[serde(rename = "svg:fill", default, skip_serializing_if = "Option::is_none")]
+    pub fill: Option<String>,
+    #[serde(rename = "fo:color", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(rename = "fo:font-size", default, skip_serializing_if = "Option::is_none")]
+    pub font_size: Option<String>,
+    #[serde(rename = "fo:font-weight", default, skip_serializing_if = "Option::is_none")]
+    pub font_weight: Option<String>,
+    #[serde(rename = "fo:font-style", default, skip_serializing_if = "Option::is_none")]
+    pub font_style: Option<String>,
+    #[serde(rename = "line-color", default, skip_serializing_if = "Option::is_none")]
+    pub line_color: Option<String>,
+    #[serde(rename = "line-width", default, skip_serializing_if = "Option::is_none")]
+    pub line_width: Option<String>,
+}
+
+impl XmindStyle {
+    /// Style ids are derived from the topic id so canonical exports stay stable.
+    fn from_node(node: &Node) -> Option<Self> {
+        let style = &node.style;
+        if style.is_default() {
+            return None;
+        }
+        let properties = XmindStyleProperties {
+            fill: style.background_color.clone(),
+            color: style.text_color.clone(),
+            font_size: style.font_size.map(|s| format!("{s}pt")),
+            font_weight: style.bold.then(|| "bold".to_string()),
+            font_style: style.italic.then(|| "italic".to_string()),
+            line_color: style.edge_color.clone(),
+            line_width: style.edge_width.map(|w| format!("{w}pt")),
+        };
+        Some(XmindStyle { id: format!("{}-style", node.id), properties })
+    }
+
+    fn to_node_style(&self) -> NodeStyle {
+        let size = |value: &Option<String>| value.as_deref().and_then(|v| v.trim_end_matches(|c: char| c.is_ascii_alphabetic()).parse().ok());
+        let p = &self.properties;
+        NodeStyle {
+            background_color: p.fill.clone(),
+            text_color: p.color.clone(),
+            font_size: size(&p.font_size),
+            bold: p.font_weight.as_deref() == Some("bold"),
+            italic: p.font_style.as_deref() == Some("italic"),
+            edge_color: p.line_color.clone(),
+            edge_width: size(&p.line_width),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct XmindChildren {
     #[serde(default)]
     pub attached: Vec<XmindTopic>,
+    /// Floating topics; XMind keeps them under the root topic.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub detached: Vec<XmindTopic>,
+    /// The topics of the parent's summaries.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub summary: Vec<XmindTopic>,
 }
 
 // Marker ID to FreeMind icon name mapping
@@ -136,21 +345,76 @@ fn icon_to_marker(icon: &str) -> String {
     }.to_string()
 }
 
-pub fn from_xmind(data: &[u8]) -> Result<MindMap, String> {
+// XMind's task markers, from not started to done in eighths. A topic shows
+// at most one of them, so a task's marker replaces any task icons.
+const TASK_MARKERS: [&str; 9] = [
+    "task-start", "task-oct", "task-quarter", "task-3oct", "task-half",
+    "task-5oct", "task-3quar", "task-7oct", "task-done",
+];
+
+// The progress marker for a task, rounded down to an eighth, and its
+// priority marker unless the node's icons already carry one.
+fn task_markers(node: &Node) -> Vec<XmindMarker> {
+    let Some(task) = &node.task else {
+        return Vec::new();
+    };
+    let mut markers = vec![TASK_MARKERS[task.completion() as usize * 8 / 100].to_string()];
+    if let Some(priority) = task.priority.filter(|p| (1..=9).contains(p))
+        && !node.icons.contains(&format!("full-{priority}"))
+    {
+        markers.push(format!("priority-{priority}"));
+    }
+    markers.into_iter().map(|marker_id| XmindMarker { marker_id }).collect()
+}
+
+// The task from our extension or, in files from XMind, from a marker
+// between not started and done; those two stay icons there.
+fn topic_task(topic: &XmindTopic) -> Option<TaskInfo> {
+    let stored = topic.extensions.iter()
+        .filter(|e| e.provider == TASK_PROVIDER)
+        .find_map(|e| serde_json::from_value(e.content.clone()).ok());
+    if stored.is_some() {
+        return stored;
+    }
+    let eighths = topic.markers.iter()
+        .find_map(|m| TASK_MARKERS.iter().position(|t| *t == m.marker_id))
+        .filter(|eighths| (1..8).contains(eighths))?;
+    let priority = topic.markers.iter()
+        .find_map(|m| m.marker_id.strip_prefix("priority-")?.parse().ok());
+    Some(TaskInfo {
+        done: eighths == 8,
+        progress_pct: (eighths * 100 / 8) as u8,
+        due: None,
+        priority,
+    })
+}
+
+pub fn from_xmind(data: &[u8]) -> Result<MindMap, BrainCoreError> {
+    from_xmind_with(data, &ImportOptions::default())
+}
+
+pub fn from_xmind_with(data: &[u8], options: &ImportOptions) -> Result<MindMap, BrainCoreError> {
+    from_xmind_monitored(data, options, &mut Monitor::default())
+}
+
+pub fn from_xmind_monitored(data: &[u8], options: &ImportOptions, monitor: &mut Monitor) -> Result<MindMap, BrainCoreError> {
+    monitor.limit_nodes(options.limits.max_nodes);
     let cursor = Cursor::new(data);
-    let mut archive = ZipArchive::new(cursor).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(cursor)?;
     
     // Find and read content.json
+    monitor.begin(Stage::ReadingArchive, Some(1))?;
     let mut content_json = String::new();
     {
-        let mut file = archive.by_name("content.json").map_err(|e| e.to_string())?;
-        file.read_to_string(&mut content_json).map_err(|e| e.to_string())?;
+        let mut file = archive.by_name("content.json")?;
+        file.read_to_string(&mut content_json)?;
     }
+    monitor.tick();
     
-    let sheets: Vec<XmindSheet> = serde_json::from_str(&content_json).map_err(|e| e.to_string())?;
+    let sheets: Vec<XmindSheet> = serde_json::from_str(&content_json).map_err(|e| BrainCoreError::json(ImportFormat::Xmind, &content_json, e))?;
     
     if sheets.is_empty() {
-        return Err("No sheets found in XMind file".to_string());
+        return Err(BrainCoreError::parse(ImportFormat::Xmind, "No sheets found in XMind file"));
     }
     
     // Use first sheet
@@ -158,16 +422,120 @@ pub fn from_xmind(data: &[u8]) -> Result<MindMap, String> {
     let mut nodes = std::collections::HashMap::new();
     let root_id = sheet.root_topic.id.clone();
     
-    flatten_xmind_topic(&sheet.root_topic, None, &mut nodes);
+    let detached = sheet.root_topic.children.as_ref().map_or(&[][..], |c| &c.detached[..]);
+    monitor.begin(Stage::BuildingNodes, Some(count_topics(&sheet.root_topic)))?;
+    flatten_xmind_topic(&sheet.root_topic, None, &mut nodes, monitor);
+    for topic in detached {
+        flatten_xmind_topic(topic, None, &mut nodes, monitor);
+    }
+    monitor.finish()?;
     
-    Ok(MindMap {
-        nodes,
-        root_id: root_id.clone(),
-        selected_node_id: root_id,
-    })
+    let mut map = MindMap::from_nodes(nodes, root_id);
+    map.floating_roots = detached.iter().map(|t| t.id.clone()).collect();
+    map.relationships = sheet.relationships.iter()
+        .filter(|r| map.nodes.contains_key(&r.end1_id) && map.nodes.contains_key(&r.end2_id))
+        .map(|r| Relationship {
+            id: r.id.clone(),
+            from: r.end1_id.clone(),
+            to: r.end2_id.clone(),
+            label: r.title.clone().filter(|t| !t.is_empty()),
+        })
+        .collect();
+    collect_groups(&sheet.root_topic, &mut map.groups);
+    for topic in detached {
+        collect_groups(topic, &mut map.groups);
+    }
+    collect_summaries(&sheet.root_topic, &mut map.summaries);
+    attachments::read_package_files(&mut map, &mut archive)?;
+    options.apply(&mut map)?;
+    map.place_pinned_offsets(Offsets::FromParent);
+    Ok(map)
+}
+
+// Boundaries over a run of children become one group per child.
+fn collect_groups(topic: &XmindTopic, groups: &mut Vec<Group>) {
+    let attached = topic.children.as_ref().map_or(&[][..], |c| &c.attached[..]);
+    for boundary in &topic.boundaries {
+        let grouped: Vec<&str> = match boundary.range.trim() {
+            "master" => vec![topic.id.as_str()],
+            range => match parse_range(range) {
+                Some((first, last)) => attached.iter().skip(first).take((last + 1).saturating_sub(first)).map(|t| t.id.as_str()).collect(),
+                None => Vec::new(),
+            },
+        };
+        let style = boundary.style.as_ref().map(|s| GroupStyle {
+            fill_color: s.properties.fill.clone(),
+            border_color: s.properties.line_color.clone(),
+        }).unwrap_or_default();
+        for (i, node_id) in grouped.iter().enumerate() {
+            groups.push(Group {
+                id: if i == 0 { boundary.id.clone() } else { format!("{}-{i}", boundary.id) },
+                node_id: node_id.to_string(),
+                title: boundary.title.clone().filter(|t| !t.is_empty()),
+                style: style.clone(),
+            });
+        }
+    }
+    if let Some(children) = &topic.children {
+        for child in &children.attached {
+            collect_groups(child, groups);
+        }
+    }
+}
+
+// A summary keeps the ends of its run, so it follows them when the
+// children change.
+fn collect_summaries(topic: &XmindTopic, summaries: &mut Vec<Summary>) {
+    let Some(children) = &topic.children else {
+        return;
+    };
+    for summary in &topic.summaries {
+        let Some((first, last)) = parse_range(&summary.range) else {
+            continue;
+        };
+        let (Some(first_child), Some(last_child)) = (children.attached.get(first), children.attached.get(last)) else {
+            continue;
+        };
+        summaries.push(Summary {
+            id: summary.id.clone(),
+            parent_id: topic.id.clone(),
+            first_child: first_child.id.clone(),
+            last_child: last_child.id.clone(),
+            content: children.summary.iter().find(|t| t.id == summary.topic_id).map(|t| t.title.clone()).unwrap_or_default(),
+        });
+    }
+    for child in children.attached.iter().chain(&children.detached) {
+        collect_summaries(child, summaries);
+    }
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let (first, last) = range.trim().trim_start_matches('(').trim_end_matches(')').split_once(',')?;
+    Some((first.trim().parse().ok()?, last.trim().parse().ok()?))
+}
+
+fn boundary(group: &Group) -> XmindBoundary {
+    let style = &group.style;
+    XmindBoundary {
+        id: group.id.clone(),
+        range: "master".to_string(),
+        title: group.title.clone(),
+        style: (*style != GroupStyle::default()).then(|| XmindStyle {
+            id: format!("{}-style", group.id),
+            properties: XmindStyleProperties {
+                fill: style.fill_color.clone(),
+                line_color: style.border_color.clone(),
+                ..Default::default()
+            },
+        }),
+    }
 }
 
-fn flatten_xmind_topic(topic: &XmindTopic, parent_id: Option<String>, nodes: &mut std::collections::HashMap<String, Node>) {
+fn count_topics(topic: &XmindTopic) -> usize {
+    1 + topic.children.as_ref().map_or(0, |c| c.attached.iter().chain(&c.detached).map(count_topics).sum())
+}
+
+fn flatten_xmind_topic(topic: &XmindTopic, parent_id: Option<String>, nodes: &mut std::collections::HashMap<String, Node>, monitor: &mut Monitor) {
     let node_id = topic.id.clone();
     
     // Collect children IDs
@@ -178,50 +546,128 @@ fn flatten_xmind_topic(topic: &XmindTopic, parent_id: Option<String>, nodes: &mu
     };
     
     // Convert markers to icons
+    let task = topic_task(topic);
     let icons: Vec<String> = topic.markers.iter()
+        .filter(|m| task.is_none() || !TASK_MARKERS.contains(&m.marker_id.as_str()))
         .filter_map(|m| marker_to_icon(&m.marker_id))
         .collect();
     
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
+    let now = crate::timestamp::now_millis();
+    // Attached topics with a position were placed by hand.
+    let pinned = parent_id.is_some() && topic.position.is_some();
     
     let node = Node {
         id: node_id.clone(),
         content: topic.title.clone(),
         children: children_ids,
         parent: parent_id,
-        x: 0.0,
-        y: 0.0,
+        x: topic.position.as_ref().map_or(0.0, |p| p.x),
+        y: topic.position.as_ref().map_or(0.0, |p| p.y),
+        pinned,
         created: now,
         modified: now,
         icons,
+        modified_by: topic.modified_by.clone(),
+        note: topic.notes.as_ref().and_then(XmindNotes::text),
+        tags: topic.labels.clone(),
+        link: topic.href.clone().filter(|h| !h.is_empty() && !h.starts_with("xap:")),
+        folded: topic.branch.as_deref() == Some("folded"),
+        style: topic.style.as_ref().map(XmindStyle::to_node_style).unwrap_or_default(),
+        attributes: topic_attributes(topic),
+        attachments: topic_attachments(topic),
+        task,
+        ..Default::default()
     };
     
     nodes.insert(node_id.clone(), node);
+    monitor.tick();
     
     // Recurse into children
     if let Some(children) = &topic.children {
         for child in &children.attached {
-            flatten_xmind_topic(child, Some(node_id.clone()), nodes);
+            if monitor.should_stop() {
+                break;
+            }
+            flatten_xmind_topic(child, Some(node_id.clone()), nodes, monitor);
         }
     }
 }
 
-pub fn to_xmind(map: &MindMap) -> Result<Vec<u8>, String> {
-    let root = map.nodes.get(&map.root_id).ok_or("Root not found")?;
-    let root_topic = build_xmind_topic(root, map);
+pub fn to_xmind(map: &MindMap) -> Result<Vec<u8>, BrainCoreError> {
+    to_xmind_with(map, &ExportOptions::default())
+}
+
+pub fn to_xmind_with(map: &MindMap, options: &ExportOptions) -> Result<Vec<u8>, BrainCoreError> {
+    to_xmind_monitored(map, options, &mut Monitor::default())
+}
+
+pub fn to_xmind_monitored(map: &MindMap, options: &ExportOptions, monitor: &mut Monitor) -> Result<Vec<u8>, BrainCoreError> {
+    let mut buffer = Vec::new();
+    {
+        let cursor = Cursor::new(&mut buffer);
+        let mut zip = ZipWriter::new(cursor);
+        write_archive(map, &mut zip, options, monitor)?;
+        zip.finish()?;
+    }
     
+    Ok(buffer)
+}
+
+/// Streams the XMind archive to `writer`. Each JSON document is serialized
+/// straight into its zip entry, so no copy of the file is held in memory.
+pub fn write_xmind<W: Write>(map: &MindMap, writer: &mut W) -> Result<(), BrainCoreError> {
+    write_xmind_with(map, writer, &ExportOptions::default())
+}
+
+pub fn write_xmind_with<W: Write>(map: &MindMap, writer: &mut W, options: &ExportOptions) -> Result<(), BrainCoreError> {
+    write_xmind_monitored(map, writer, options, &mut Monitor::default())
+}
+
+pub fn write_xmind_monitored<W: Write>(map: &MindMap, writer: &mut W, options: &ExportOptions, monitor: &mut Monitor) -> Result<(), BrainCoreError> {
+    let mut zip = ZipWriter::new_stream(&mut *writer);
+    write_archive(map, &mut zip, options, monitor)?;
+    zip.finish()?;
+    Ok(writer.flush()?)
+}
+
+fn write_archive<W: Write + Seek>(map: &MindMap, zip: &mut ZipWriter<W>, options: &ExportOptions, monitor: &mut Monitor) -> Result<(), BrainCoreError> {
+    let root = map.nodes.get(&map.root_id).ok_or_else(|| BrainCoreError::NodeNotFound(map.root_id.clone()))?;
+    monitor.begin(Stage::WritingNodes, Some(options.exported_count(map, true)))?;
+    let mut root_topic = build_xmind_topic(root, map, options, monitor);
+    let detached: Vec<XmindTopic> = options.exported_floating(map).into_iter()
+        .map(|node| XmindTopic {
+            position: Some(XmindPosition { x: node.x - root.x, y: node.y - root.y }),
+            ..build_xmind_topic(node, map, options, monitor)
+        })
+        .collect();
+    if !detached.is_empty() {
+        root_topic.children.get_or_insert_with(|| XmindChildren { attached: Vec::new(), detached: Vec::new(), summary: Vec::new() }).detached = detached;
+    }
+    monitor.finish()?;
+    
+    // The sheet has no counterpart in the model; canonical output derives its
+    // id from the root so re-exports stay byte-identical.
+    let sheet_id = if options.canonical {
+        format!("sheet-{}", root.id)
+    } else {
+        uuid::Uuid::new_v4().to_string()
+    };
     let sheet = XmindSheet {
-        id: uuid::Uuid::new_v4().to_string(),
+        id: sheet_id,
         class_name: Some("sheet".to_string()),
         root_topic,
         title: Some(root.content.clone()),
+        relationships: map.exported_relationships(options)
+            .map(|r| XmindRelationship {
+                id: r.id.clone(),
+                end1_id: r.from.clone(),
+                end2_id: r.to.clone(),
+                title: r.label.clone(),
+            })
+            .collect(),
     };
     
     let sheets = vec![sheet];
-    let content_json = serde_json::to_string(&sheets).map_err(|e| e.to_string())?;
     
     let metadata = serde_json::json!({
         "dataStructureVersion": "2",
@@ -230,53 +676,58 @@ pub fn to_xmind(map: &MindMap) -> Result<Vec<u8>, String> {
             "version": "0.1.0"
         }
     });
-    let metadata_json = serde_json::to_string(&metadata).map_err(|e| e.to_string())?;
     
-    let manifest = serde_json::json!({
-        "file-entries": {
-            "content.json": {},
-            "metadata.json": {}
-        }
+    let files = attachments::package_files(map, options);
+    let mut file_entries = serde_json::json!({
+        "content.json": {},
+        "metadata.json": {}
     });
-    let manifest_json = serde_json::to_string(&manifest).map_err(|e| e.to_string())?;
-    
-    // Create ZIP
-    let mut buffer = Vec::new();
-    {
-        let cursor = Cursor::new(&mut buffer);
-        let mut zip = ZipWriter::new(cursor);
-        let options = SimpleFileOptions::default()
-            .compression_method(zip::CompressionMethod::Deflated);
-        
-        zip.start_file("content.json", options).map_err(|e| e.to_string())?;
-        zip.write_all(content_json.as_bytes()).map_err(|e| e.to_string())?;
-        
-        zip.start_file("metadata.json", options).map_err(|e| e.to_string())?;
-        zip.write_all(metadata_json.as_bytes()).map_err(|e| e.to_string())?;
-        
-        zip.start_file("manifest.json", options).map_err(|e| e.to_string())?;
-        zip.write_all(manifest_json.as_bytes()).map_err(|e| e.to_string())?;
-        
-        zip.finish().map_err(|e| e.to_string())?;
+    for path in files.keys() {
+        file_entries[path] = serde_json::json!({});
     }
+    let manifest = serde_json::json!({ "file-entries": file_entries });
     
-    Ok(buffer)
+    let zip_options = options.zip_options(zip::CompressionMethod::Deflated);
+    monitor.begin(Stage::WritingArchive, Some(4))?;
+    
+    zip.start_file("content.json", zip_options)?;
+    options.write_json(&sheets, &mut *zip)?;
+    monitor.tick();
+    
+    for (name, contents) in [("metadata.json", metadata), ("manifest.json", manifest)] {
+        zip.start_file(name, zip_options)?;
+        options.write_json(&contents, &mut *zip)?;
+        monitor.tick();
+    }
+    attachments::write_package_files(&files, zip, zip_options)?;
+    monitor.tick();
+    monitor.finish()
 }
 
-fn build_xmind_topic(node: &Node, map: &MindMap) -> XmindTopic {
-    let markers: Vec<XmindMarker> = node.icons.iter()
+fn build_xmind_topic(node: &Node, map: &MindMap, options: &ExportOptions, monitor: &mut Monitor) -> XmindTopic {
+    monitor.tick();
+
+    let mut markers: Vec<XmindMarker> = node.icons.iter()
         .map(|icon| XmindMarker { marker_id: icon_to_marker(icon) })
+        .filter(|m| node.task.is_none() || !TASK_MARKERS.contains(&m.marker_id.as_str()))
         .collect();
+    markers.extend(task_markers(node));
     
-    let children: Vec<XmindTopic> = node.children.iter()
-        .filter_map(|child_id| map.nodes.get(child_id))
-        .map(|child| build_xmind_topic(child, map))
-        .collect();
+    let mut children: Vec<XmindTopic> = Vec::new();
+    for child in node.children.iter().filter_map(|child_id| map.nodes.get(child_id)) {
+        if monitor.should_stop() {
+            break;
+        }
+        if options.includes(child) {
+            children.push(build_xmind_topic(child, map, options, monitor));
+        }
+    }
     
+    let (summaries, summary_topics) = summaries(node, map, &children);
     let children_obj = if children.is_empty() {
         None
     } else {
-        Some(XmindChildren { attached: children })
+        Some(XmindChildren { attached: children, detached: Vec::new(), summary: summary_topics })
     };
     
     XmindTopic {
@@ -285,5 +736,171 @@ fn build_xmind_topic(node: &Node, map: &MindMap) -> XmindTopic {
         title: node.content.clone(),
         markers,
         children: children_obj,
+        notes: node.note.as_ref().map(|note| XmindNotes { plain: Some(XmindPlainNote { content: note.clone() }), real_html: None }),
+        href: node.link.clone(),
+        labels: node.tags.clone(),
+        branch: node.folded.then(|| "folded".to_string()),
+        style: XmindStyle::from_node(node),
+        modified_by: node.modified_by.clone(),
+        position: node.parent.as_ref().and_then(|p| map.nodes.get(p)).filter(|_| node.pinned)
+            .map(|p| XmindPosition { x: node.x - p.x, y: node.y - p.y }),
+        boundaries: map.groups_of(&node.id).map(boundary).collect(),
+        summaries,
+        image: node.attachments.iter().find(|a| a.is_image())
+            .map(|a| XmindImage { src: format!("xap:{}", attachments::package_path(a)) }),
+        extensions: attribute_extension(node).into_iter()
+            .chain(task_extension(node))
+            .chain(attachment_extension(node))
+            .collect(),
+    }
+}
+
+// The node's summaries over exported children, with their topics. Ranges
+// count exported children only.
+fn summaries(node: &Node, map: &MindMap, children: &[XmindTopic]) -> (Vec<XmindSummary>, Vec<XmindTopic>) {
+    let index = |id: &str| children.iter().position(|t| t.id == id);
+    map.summaries_of(&node.id)
+        .filter_map(|s| {
+            let (first, last) = (index(&s.first_child)?, index(&s.last_child)?);
+            let topic_id = format!("{}-topic", s.id);
+            let summary = XmindSummary { id: s.id.clone(), range: format!("({},{})", first.min(last), first.max(last)), topic_id: topic_id.clone() };
+            Some((summary, summary_topic(topic_id, &s.content)))
+        })
+        .unzip()
+}
+
+fn summary_topic(id: String, title: &str) -> XmindTopic {
+    XmindTopic {
+        id,
+        class_name: Some("topic".to_string()),
+        title: title.to_string(),
+        markers: Vec::new(),
+        children: None,
+        notes: None,
+        href: None,
+        labels: Vec::new(),
+        branch: None,
+        style: None,
+        modified_by: None,
+        position: None,
+        image: None,
+        boundaries: Vec::new(),
+        summaries: Vec::new(),
+        extensions: Vec::new(),
+    }
+}
+
+fn attribute_extension(node: &Node) -> Option<XmindExtension> {
+    if node.attributes.is_empty() {
+        return None;
+    }
+    let attributes: Vec<XmindAttribute> = node.attributes.iter()
+        .map(|(name, value)| XmindAttribute { name: name.clone(), value: value.clone() })
+        .collect();
+    Some(XmindExtension {
+        provider: ATTRIBUTES_PROVIDER.to_string(),
+        content: serde_json::to_value(attributes).unwrap_or_default(),
+    })
+}
+
+fn task_extension(node: &Node) -> Option<XmindExtension> {
+    let task = node.task.as_ref()?;
+    Some(XmindExtension {
+        provider: TASK_PROVIDER.to_string(),
+        content: serde_json::to_value(task).unwrap_or_default(),
+    })
+}
+
+fn attachment_extension(node: &Node) -> Option<XmindExtension> {
+    if node.attachments.is_empty() {
+        return None;
+    }
+    let attachments: Vec<XmindAttachment> = node.attachments.iter()
+        .map(|a| XmindAttachment {
+            name: a.name.clone(),
+            mime: a.mime.clone(),
+            src: format!("xap:{}", attachments::package_path(a)),
+        })
+        .collect();
+    Some(XmindExtension {
+        provider: ATTACHMENTS_PROVIDER.to_string(),
+        content: serde_json::to_value(attachments).unwrap_or_default(),
+    })
+}
+
+// Attachments from our extension or, in files from XMind, from the topic's
+// image and a link into the package. `data_ref` holds the package path
+// until the files are read.
+fn topic_attachments(topic: &XmindTopic) -> Vec<Attachment> {
+    let listed = topic.extensions.iter()
+        .filter(|e| e.provider == ATTACHMENTS_PROVIDER)
+        .find_map(|e| serde_json::from_value::<Vec<XmindAttachment>>(e.content.clone()).ok());
+    if let Some(listed) = listed {
+        return listed.into_iter()
+            .filter_map(|a| Some(Attachment {
+                name: a.name,
+                mime: a.mime,
+                data_ref: a.src.strip_prefix("xap:")?.to_string(),
+            }))
+            .collect();
+    }
+    topic.image.iter().map(|i| i.src.as_str())
+        .chain(topic.href.as_deref())
+        .filter_map(|src| src.strip_prefix("xap:"))
+        .map(attachments::package_attachment)
+        .collect()
+}
+
+fn topic_attributes(topic: &XmindTopic) -> Vec<(String, String)> {
+    topic.extensions.iter()
+        .filter(|e| e.provider == ATTRIBUTES_PROVIDER)
+        .filter_map(|e| serde_json::from_value::<Vec<XmindAttribute>>(e.content.clone()).ok())
+        .flatten()
+        .map(|a| (a.name, a.value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notes_labels_and_links_round_trip() {
+        let mut map = MindMap::new();
+        let root_id = map.root_id.clone();
+        let child = map.add_child(&root_id, "Release").unwrap();
+        map.set_note(&child, "Ship it\nthen celebrate").unwrap();
+        map.set_link(&child, "https://example.com/release").unwrap();
+        map.nodes.get_mut(&child).unwrap().tags = vec!["q3".to_string(), "launch".to_string()];
+
+        let loaded = from_xmind(&to_xmind(&map).unwrap()).unwrap();
+        let node = &loaded.nodes[&loaded.nodes[&loaded.root_id].children[0]];
+        assert_eq!(node.note.as_deref(), Some("Ship it\nthen celebrate"));
+        assert_eq!(node.link.as_deref(), Some("https://example.com/release"));
+        assert_eq!(node.tags, vec!["q3".to_string(), "launch".to_string()]);
+
+        let topic: XmindTopic = serde_json::from_str(
+            r#"{"id": "t1", "title": "Topic", "labels": ["a"],
+                "notes": {"realHTML": {"content": "<div>First</div><div>Second</div>"}}}"#,
+        )
+        .unwrap();
+        let mut nodes = std::collections::HashMap::new();
+        flatten_xmind_topic(&topic, None, &mut nodes, &mut Monitor::default());
+        assert_eq!(nodes["t1"].note.as_deref(), Some("First\nSecond"));
+        assert_eq!(nodes["t1"].tags, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn test_detached_topics() {
+        let mut map = MindMap::new();
+        let idea = map.add_floating_node("Idea", 300.0, -80.0).unwrap();
+        let detail = map.add_child(&idea, "Detail").unwrap();
+
+        let loaded = from_xmind(&to_xmind(&map).unwrap()).unwrap();
+        assert_eq!(loaded.floating_roots, vec![idea.clone()]);
+        assert_eq!((loaded.nodes[&idea].x, loaded.nodes[&idea].y), (300.0, -80.0));
+        assert_eq!(loaded.nodes[&detail].parent.as_ref(), Some(&idea));
+        assert!(loaded.nodes[&loaded.root_id].children.is_empty());
+        assert!(loaded.validate().is_empty());
     }
 }